@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTest;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+use vice_city_fuzz::RSA_FIXTURE;
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*RSA_FIXTURE;
+    if let Some(party_two_message) = mutate(&fixture.party_two_biprimality_message, data) {
+        let _ = PartyOneBiprimalityTest::verify_party_two_first_message_and_conclude_biprimality(
+            &fixture.joint_modulus.n,
+            &FIXTURE.party_one_keys,
+            &fixture.p_party_two_message,
+            &fixture.q_party_two_message,
+            &fixture.party_one_biprimality_message,
+            &party_two_message,
+        );
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGeneration;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*FIXTURE;
+    if let Some(party_two_message) = mutate(&fixture.party_two_first_message, data) {
+        let _ =
+            PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                &fixture.party_one_keys,
+                &fixture.party_one_first_message,
+                &party_two_message,
+            );
+    }
+});
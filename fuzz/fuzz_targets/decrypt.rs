@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneDecrypt;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::RSA_FIXTURE;
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*RSA_FIXTURE;
+    if let Some(party_two_message) = mutate(&fixture.party_two_decrypt_message, data) {
+        let _ = PartyOneDecrypt::verify_party_two_first_message_and_decrypt(
+            &fixture.key_share,
+            &fixture.ciphertext,
+            &fixture.label,
+            &fixture.party_one_decrypt_message,
+            &party_two_message,
+        );
+    }
+});
@@ -0,0 +1,63 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulus;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+use vice_city_fuzz::RSA_FIXTURE;
+
+// the first byte selects the first, second or third message of party two
+fuzz_target!(|data: &[u8]| {
+    let keys = &FIXTURE.party_one_keys;
+    let fixture = &*RSA_FIXTURE;
+    let (selector, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    match selector % 3 {
+        0 => {
+            if let Some(party_two_message) =
+                mutate(&fixture.party_two_joint_modulus_first_message, data)
+            {
+                let _ =
+                    PartyOneJointModulus::verify_party_two_first_message_and_compute_cross_terms(
+                        keys,
+                        &fixture.p_witness,
+                        &fixture.q_witness,
+                        &fixture.joint_modulus_witness,
+                        &fixture.p_ciphertexts,
+                        &fixture.q_ciphertexts,
+                        &fixture.party_one_joint_modulus_first_message,
+                        &party_two_message,
+                    );
+            }
+        }
+        1 => {
+            if let Some(party_two_message) =
+                mutate(&fixture.party_two_joint_modulus_second_message, data)
+            {
+                let _ = PartyOneJointModulus::verify_party_two_second_message_and_decrypt_modulus(
+                    keys,
+                    &fixture.p_ciphertexts,
+                    &fixture.q_ciphertexts,
+                    &fixture.party_one_joint_modulus_first_message,
+                    &fixture.party_two_joint_modulus_first_message,
+                    &party_two_message,
+                );
+            }
+        }
+        _ => {
+            if let Some(party_two_message) =
+                mutate(&fixture.party_two_joint_modulus_third_message, data)
+            {
+                let _ =
+                    PartyOneJointModulus::verify_party_two_third_message_and_output_joint_modulus(
+                        keys,
+                        &fixture.party_one_joint_modulus_second_message,
+                        &fixture.party_one_joint_modulus_third_message,
+                        &fixture.party_two_joint_modulus_first_message,
+                        &party_two_message,
+                    );
+            }
+        }
+    }
+});
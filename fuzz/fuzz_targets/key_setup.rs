@@ -0,0 +1,17 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetup;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*FIXTURE;
+    if let Some(party_two_message) = mutate(&fixture.party_two_key_setup_message, data) {
+        let _ = PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &fixture.params,
+            &fixture.party_one_key_setup_message,
+            &party_two_message,
+            fixture.party_one_private.clone(),
+        );
+    }
+});
@@ -0,0 +1,52 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGeneration;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+
+// the first byte selects the first, second or third message of party two
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*FIXTURE;
+    let (selector, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    match selector % 3 {
+        0 => {
+            if let Some(party_two_message) = mutate(&fixture.party_two_paillier_first_message, data)
+            {
+                let _ = PartyOnePaillierCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                    &fixture.party_one_keys,
+                    &fixture.party_one_paillier_first_message,
+                    &party_two_message,
+                );
+            }
+        }
+        1 => {
+            if let Some(party_two_message) =
+                mutate(&fixture.party_two_paillier_second_message, data)
+            {
+                let _ = PartyOnePaillierCandidateGeneration::verify_party_two_second_message_and_mask_residues(
+                    &fixture.party_one_paillier_second_message,
+                    &party_two_message,
+                    &fixture.alpha,
+                    &fixture.party_one_keys,
+                    &fixture.paillier_ciphertext_pair,
+                    &fixture.party_one_paillier_witness,
+                );
+            }
+        }
+        _ => {
+            if let Some(party_two_message) = mutate(&fixture.party_two_paillier_third_message, data)
+            {
+                let _ = PartyOnePaillierCandidateGeneration::verify_party_two_third_message_and_conclude_division(
+                    &fixture.party_one_paillier_second_message,
+                    &fixture.party_two_paillier_second_message,
+                    &party_two_message,
+                    &fixture.alpha,
+                    &fixture.party_one_keys,
+                );
+            }
+        }
+    }
+});
@@ -0,0 +1,39 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShare;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+use vice_city_fuzz::RSA_FIXTURE;
+
+// the first byte selects the first (exponent share) or second (verification key) message of party two
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*RSA_FIXTURE;
+    let (selector, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    if selector % 2 == 0 {
+        if let Some(party_two_message) = mutate(&fixture.party_two_exponent_first_message, data) {
+            let _ = PartyOnePrivateExponentShare::verify_party_two_first_message_and_compute_exponent_share(
+                &fixture.e,
+                &fixture.joint_modulus,
+                &FIXTURE.party_one_keys,
+                &fixture.p_witness,
+                &fixture.q_witness,
+                &fixture.p_ciphertexts,
+                &fixture.q_ciphertexts,
+                &fixture.party_one_exponent_first_message,
+                &party_two_message,
+            );
+        }
+    } else if let Some(party_two_message) = mutate(&fixture.party_two_exponent_second_message, data)
+    {
+        let _ = PartyOnePrivateExponentShare::verify_party_two_second_message_and_output_key_share(
+            &fixture.e,
+            &fixture.joint_modulus,
+            fixture.exponent_witness.clone(),
+            &fixture.party_one_exponent_second_message,
+            &party_two_message,
+        );
+    }
+});
@@ -0,0 +1,31 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGeneration;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+
+// the first byte selects the sieving message or the sieved first message of party two
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*FIXTURE;
+    let (selector, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    if selector % 2 == 0 {
+        if let Some(party_two_message) = mutate(&fixture.party_two_sieving_message, data) {
+            let _ = PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+                &fixture.party_one_keys,
+                &fixture.party_one_sieving_message,
+                &party_two_message,
+                &fixture.party_two_sieved_first_message,
+            );
+        }
+    } else if let Some(party_two_message) = mutate(&fixture.party_two_sieved_first_message, data) {
+        let _ = PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+            &fixture.party_one_keys,
+            &fixture.party_one_sieving_message,
+            &fixture.party_two_sieving_message,
+            &party_two_message,
+        );
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneSign;
+use vice_city::protocols::two_party_rsa::padding::SignatureEncoding;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::RSA_FIXTURE;
+
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*RSA_FIXTURE;
+    if let Some(party_two_message) = mutate(&fixture.party_two_sign_message, data) {
+        let _ = PartyOneSign::verify_party_two_first_message_and_output_signature(
+            &fixture.key_share,
+            &fixture.message,
+            &SignatureEncoding::Pkcs1v15,
+            &fixture.party_one_sign_message,
+            &party_two_message,
+        );
+    }
+});
@@ -0,0 +1,34 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGeneration;
+use vice_city_fuzz::mutate;
+use vice_city_fuzz::FIXTURE;
+
+// the first byte selects the second (mod proof) or third (decryption) message of party two
+fuzz_target!(|data: &[u8]| {
+    let fixture = &*FIXTURE;
+    let (selector, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    if selector % 2 == 0 {
+        if let Some(party_two_message) = mutate(&fixture.party_two_second_message, data) {
+            let _ =
+                PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+                    &fixture.party_one_second_message,
+                    &party_two_message,
+                    &fixture.alpha,
+                    &fixture.party_one_keys,
+                    &fixture.ciphertext_pair,
+                );
+        }
+    } else if let Some(party_two_message) = mutate(&fixture.party_two_third_message, data) {
+        let _ = PartyOneCandidateGeneration::verify_party_two_third_message_full_decrypt_and_conclude_division(
+            &fixture.c_alpha,
+            &fixture.c_alpha_tilde,
+            &party_two_message,
+            &fixture.alpha,
+            &fixture.party_one_keys,
+        );
+    }
+});
@@ -0,0 +1,715 @@
+//! Fuzzing harness for the verifiers of HMRT key generation.
+//!
+//! Random bytes almost never deserialize to a message, so every target starts from an honest
+//! transcript (computed once, see `Fixture` and `RsaFixture`) and lets the fuzzer mutate the
+//! message of the counterparty: the input selects a path in the json tree of the message and the
+//! malformed value put there, e.g. a truncated vector, a zero or negative number or a ciphertext
+//! of another group. A verifier may reject the message but must never panic.
+
+use curv::arithmetic::traits::Modulo;
+use curv::BigInt;
+use elgamal::prime::is_prime;
+use elgamal::ElGamalCiphertext;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use vice_city::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTest;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTestFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGeneration;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationBatchSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateSievingFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateWitness;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneDecrypt;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneDecryptFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulus;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusThirdMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusWitness;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetup;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetupFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGeneration;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGenerationFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGenerationSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateWitness;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivate;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShare;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareWitness;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneRSAKeyShare;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneSign;
+use vice_city::protocols::two_party_rsa::hmrt::party_one::PartyOneSignFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::KeySetupFirstMsg as PartyTwoKeySetupFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoBiprimalityTest;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoBiprimalityTestFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGeneration;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationBatchSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationBatchThirdMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationThirdMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateSievingFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateWitness;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoDecrypt;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoDecryptFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulus;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusThirdMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoKeySetup;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGeneration;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGenerationFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGenerationSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGenerationThirdMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShare;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareSecondMsg;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoSign;
+use vice_city::protocols::two_party_rsa::hmrt::party_two::PartyTwoSignFirstMsg;
+use vice_city::protocols::two_party_rsa::hmrt::rsa_oaep_encrypt;
+use vice_city::protocols::two_party_rsa::hmrt::CiphertextPair;
+use vice_city::protocols::two_party_rsa::hmrt::HmrtParams;
+use vice_city::protocols::two_party_rsa::hmrt::JointModulus;
+use vice_city::protocols::two_party_rsa::hmrt::PaillierCiphertextPair;
+use vice_city::protocols::two_party_rsa::padding::SignatureEncoding;
+use vice_city::protocols::two_party_rsa::DEFAULT_PUBLIC_EXPONENT;
+use vice_city::utlities::SMALL_PRIMES;
+
+/// Honest transcript of key setup, candidate generation and trial division, seen from party one
+pub struct Fixture {
+    pub params: HmrtParams,
+    pub party_one_key_setup_message: PartyOneKeySetupFirstMsg,
+    pub party_one_private: PartyOnePrivate,
+    pub party_two_key_setup_message: PartyTwoKeySetupFirstMsg,
+    pub party_one_keys: PartyOneKeySetup,
+    pub party_two_keys: PartyTwoKeySetup,
+    pub party_one_first_message: PartyOneCandidateGenerationFirstMsg,
+    pub party_two_first_message: PartyTwoCandidateGenerationFirstMsg,
+    pub ciphertext_pair: CiphertextPair,
+    pub alpha: BigInt,
+    pub party_one_second_message: PartyOneCandidateGenerationSecondMsg,
+    pub party_two_second_message: PartyTwoCandidateGenerationSecondMsg,
+    pub c_alpha: ElGamalCiphertext,
+    pub c_alpha_tilde: ElGamalCiphertext,
+    pub party_two_third_message: PartyTwoCandidateGenerationThirdMsg,
+    // batched trial division of the same candidate
+    pub alpha_vec: Vec<BigInt>,
+    pub party_one_batch_second_message: PartyOneCandidateGenerationBatchSecondMsg,
+    pub party_two_batch_second_message: PartyTwoCandidateGenerationBatchSecondMsg,
+    pub c_alpha_vec: Vec<ElGamalCiphertext>,
+    pub c_alpha_tilde_vec: Vec<ElGamalCiphertext>,
+    pub party_two_batch_third_message: PartyTwoCandidateGenerationBatchThirdMsg,
+    // distributed sieving of another candidate
+    pub party_one_sieving_message: PartyOneCandidateSievingFirstMsg,
+    pub party_two_sieving_message: PartyTwoCandidateSievingFirstMsg,
+    pub party_two_sieved_first_message: PartyTwoCandidateGenerationFirstMsg,
+    // trial division of the Paillier variant
+    pub party_one_paillier_witness: PartyOnePaillierCandidateWitness,
+    pub party_one_paillier_first_message: PartyOnePaillierCandidateGenerationFirstMsg,
+    pub party_two_paillier_first_message: PartyTwoPaillierCandidateGenerationFirstMsg,
+    pub paillier_ciphertext_pair: PaillierCiphertextPair,
+    pub party_one_paillier_second_message: PartyOnePaillierCandidateGenerationSecondMsg,
+    pub party_two_paillier_second_message: PartyTwoPaillierCandidateGenerationSecondMsg,
+    pub party_two_paillier_third_message: PartyTwoPaillierCandidateGenerationThirdMsg,
+}
+
+pub static FIXTURE: Lazy<Fixture> = Lazy::new(Fixture::new);
+
+impl Fixture {
+    fn new() -> Self {
+        let params = HmrtParams::default();
+        let session_id = SessionId::random();
+        let (party_one_key_setup_message, party_one_private) =
+            PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(&params, &session_id)
+                .expect("honest key setup");
+        let (party_two_key_setup_message, party_two_private) =
+            PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+                &params,
+                party_one_key_setup_message.group,
+                &session_id,
+            )
+            .expect("honest key setup");
+        let party_one_keys =
+            PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+                &params,
+                &party_one_key_setup_message,
+                &party_two_key_setup_message,
+                party_one_private.clone(),
+            )
+            .expect("honest key setup");
+        let party_two_keys =
+            PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+                &params,
+                &party_one_key_setup_message,
+                &party_two_key_setup_message,
+                party_two_private,
+            )
+            .expect("honest key setup");
+        let (party_one_witness, party_one_first_message) =
+            PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys)
+                .expect("honest candidate generation");
+        let (party_two_witness, party_two_first_message) =
+            PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys)
+                .expect("honest candidate generation");
+        let ciphertext_pair =
+            PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                &party_one_keys,
+                &party_one_first_message,
+                &party_two_first_message,
+            )
+            .expect("honest candidate generation");
+        let party_two_ciphertext_pair =
+            PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+                &party_two_keys,
+                &party_one_first_message,
+                &party_two_first_message,
+            )
+            .expect("honest candidate generation");
+
+        let alpha = BigInt::from(SMALL_PRIMES[1]);
+        let party_one_second_message = PartyOneCandidateGeneration::trial_division_prepare_c_alpha(
+            &alpha,
+            &party_one_keys,
+            &ciphertext_pair,
+            &party_one_witness,
+        )
+        .expect("honest trial division");
+        let party_two_second_message = PartyTwoCandidateGeneration::trial_division_prepare_c_alpha(
+            &alpha,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+            &party_two_witness,
+        )
+        .expect("honest trial division");
+        let (_, c_alpha, c_alpha_tilde) =
+            PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+                &party_one_second_message,
+                &party_two_second_message,
+                &alpha,
+                &party_one_keys,
+                &ciphertext_pair,
+            )
+            .expect("honest trial division");
+        let (party_two_third_message, _, _) =
+            PartyTwoCandidateGeneration::verify_party_one_second_message_and_partial_decrypt(
+                &party_one_second_message,
+                &party_two_second_message,
+                &alpha,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+            )
+            .expect("honest trial division");
+
+        let alpha_vec = SMALL_PRIMES[1..4]
+            .iter()
+            .map(|alpha| BigInt::from(*alpha))
+            .collect::<Vec<_>>();
+        let party_one_batch_second_message =
+            PartyOneCandidateGeneration::trial_division_prepare_c_alpha_batch(
+                &alpha_vec,
+                &party_one_keys,
+                &ciphertext_pair,
+                &party_one_witness,
+            )
+            .expect("honest trial division");
+        let party_two_batch_second_message =
+            PartyTwoCandidateGeneration::trial_division_prepare_c_alpha_batch(
+                &alpha_vec,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+                &party_two_witness,
+            )
+            .expect("honest trial division");
+        let (_, c_alpha_vec, c_alpha_tilde_vec) =
+            PartyOneCandidateGeneration::verify_party_two_batch_second_message_and_partial_decrypt(
+                &party_one_batch_second_message,
+                &party_two_batch_second_message,
+                &alpha_vec,
+                &party_one_keys,
+                &ciphertext_pair,
+            )
+            .expect("honest trial division");
+        let (party_two_batch_third_message, _, _) =
+            PartyTwoCandidateGeneration::verify_party_one_batch_second_message_and_partial_decrypt(
+                &party_one_batch_second_message,
+                &party_two_batch_second_message,
+                &alpha_vec,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+            )
+            .expect("honest trial division");
+
+        let party_one_sieving_message =
+            PartyOneCandidateGeneration::sample_multiplicative_share_and_sieving_message(
+                &party_one_keys,
+            )
+            .expect("honest sieving");
+        let (party_two_sieving_message, _, party_two_sieved_first_message) =
+            PartyTwoCandidateGeneration::verify_party_one_sieving_message_and_generate_sieved_candidate(
+                &party_two_keys,
+                &party_one_sieving_message,
+            )
+            .expect("honest sieving");
+
+        let (party_one_paillier_witness, party_one_paillier_first_message) =
+            PartyOnePaillierCandidateGeneration::generate_shares_of_candidate(&party_one_keys)
+                .expect("honest candidate generation");
+        let (party_two_paillier_witness, party_two_paillier_first_message) =
+            PartyTwoPaillierCandidateGeneration::generate_shares_of_candidate(&party_two_keys)
+                .expect("honest candidate generation");
+        let paillier_ciphertext_pair =
+            PartyOnePaillierCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                &party_one_keys,
+                &party_one_paillier_first_message,
+                &party_two_paillier_first_message,
+            )
+            .expect("honest candidate generation");
+        let party_two_paillier_ciphertext_pair =
+            PartyTwoPaillierCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+                &party_two_keys,
+                &party_one_paillier_first_message,
+                &party_two_paillier_first_message,
+            )
+            .expect("honest candidate generation");
+        let party_one_paillier_second_message =
+            PartyOnePaillierCandidateGeneration::trial_division_prepare_c_alpha(
+                &alpha,
+                &party_one_keys,
+                &paillier_ciphertext_pair,
+                &party_one_paillier_witness,
+            )
+            .expect("honest trial division");
+        let party_two_paillier_second_message =
+            PartyTwoPaillierCandidateGeneration::trial_division_prepare_c_alpha(
+                &alpha,
+                &party_two_keys,
+                &party_two_paillier_ciphertext_pair,
+                &party_two_paillier_witness,
+            )
+            .expect("honest trial division");
+        let party_two_paillier_third_message =
+            PartyTwoPaillierCandidateGeneration::verify_party_one_second_message_and_mask_residues(
+                &party_two_paillier_second_message,
+                &party_one_paillier_second_message,
+                &alpha,
+                &party_two_keys,
+                &party_two_paillier_ciphertext_pair,
+                &party_two_paillier_witness,
+            )
+            .expect("honest trial division");
+
+        Fixture {
+            params,
+            party_one_key_setup_message,
+            party_one_private,
+            party_two_key_setup_message,
+            party_one_keys,
+            party_two_keys,
+            party_one_first_message,
+            party_two_first_message,
+            ciphertext_pair,
+            alpha,
+            party_one_second_message,
+            party_two_second_message,
+            c_alpha,
+            c_alpha_tilde,
+            party_two_third_message,
+            alpha_vec,
+            party_one_batch_second_message,
+            party_two_batch_second_message,
+            c_alpha_vec,
+            c_alpha_tilde_vec,
+            party_two_batch_third_message,
+            party_one_sieving_message,
+            party_two_sieving_message,
+            party_two_sieved_first_message,
+            party_one_paillier_witness,
+            party_one_paillier_first_message,
+            party_two_paillier_first_message,
+            paillier_ciphertext_pair,
+            party_one_paillier_second_message,
+            party_two_paillier_second_message,
+            party_two_paillier_third_message,
+        }
+    }
+}
+
+/// Honest transcript of the stages after trial division, seen from party one: joint modulus,
+/// biprimality test and private exponent share on two prime candidates, then signing and
+/// decryption with the resulting key shares. Built on the keys of `FIXTURE`.
+pub struct RsaFixture {
+    pub e: BigInt,
+    pub p_witness: PartyOneCandidateWitness,
+    pub q_witness: PartyOneCandidateWitness,
+    pub p_party_one_message: PartyOneCandidateGenerationFirstMsg,
+    pub q_party_one_message: PartyOneCandidateGenerationFirstMsg,
+    pub p_party_two_message: PartyTwoCandidateGenerationFirstMsg,
+    pub q_party_two_message: PartyTwoCandidateGenerationFirstMsg,
+    pub p_ciphertexts: CiphertextPair,
+    pub q_ciphertexts: CiphertextPair,
+    pub joint_modulus_witness: PartyOneJointModulusWitness,
+    pub party_one_joint_modulus_first_message: PartyOneJointModulusFirstMsg,
+    pub party_two_joint_modulus_first_message: PartyTwoJointModulusFirstMsg,
+    pub party_one_joint_modulus_second_message: PartyOneJointModulusSecondMsg,
+    pub party_two_joint_modulus_second_message: PartyTwoJointModulusSecondMsg,
+    pub party_one_joint_modulus_third_message: PartyOneJointModulusThirdMsg,
+    pub party_two_joint_modulus_third_message: PartyTwoJointModulusThirdMsg,
+    pub joint_modulus: JointModulus,
+    pub party_one_biprimality_message: PartyOneBiprimalityTestFirstMsg,
+    pub party_two_biprimality_message: PartyTwoBiprimalityTestFirstMsg,
+    pub party_one_exponent_first_message: PartyOnePrivateExponentShareFirstMsg,
+    pub party_two_exponent_first_message: PartyTwoPrivateExponentShareFirstMsg,
+    pub exponent_witness: PartyOnePrivateExponentShareWitness,
+    pub party_one_exponent_second_message: PartyOnePrivateExponentShareSecondMsg,
+    pub party_two_exponent_second_message: PartyTwoPrivateExponentShareSecondMsg,
+    pub key_share: PartyOneRSAKeyShare,
+    pub message: Vec<u8>,
+    pub party_one_sign_message: PartyOneSignFirstMsg,
+    pub party_two_sign_message: PartyTwoSignFirstMsg,
+    pub ciphertext: BigInt,
+    pub label: Vec<u8>,
+    pub party_one_decrypt_message: PartyOneDecryptFirstMsg,
+    pub party_two_decrypt_message: PartyTwoDecryptFirstMsg,
+}
+
+pub static RSA_FIXTURE: Lazy<RsaFixture> = Lazy::new(RsaFixture::new);
+
+impl RsaFixture {
+    fn new() -> Self {
+        let fixture = &*FIXTURE;
+        let party_one_keys = &fixture.party_one_keys;
+        let party_two_keys = &fixture.party_two_keys;
+        let e = BigInt::from(DEFAULT_PUBLIC_EXPONENT);
+        let (p_witness, p_party_one_message, p_party_two_witness, p_party_two_message) =
+            prime_candidate(party_one_keys, party_two_keys, &e);
+        let (q_witness, q_party_one_message, q_party_two_witness, q_party_two_message) =
+            prime_candidate(party_one_keys, party_two_keys, &e);
+        let p_ciphertexts =
+            PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                party_one_keys,
+                &p_party_one_message,
+                &p_party_two_message,
+            )
+            .expect("honest candidate generation");
+        let q_ciphertexts =
+            PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                party_one_keys,
+                &q_party_one_message,
+                &q_party_two_message,
+            )
+            .expect("honest candidate generation");
+
+        let (joint_modulus_witness, party_one_joint_modulus_first_message) =
+            PartyOneJointModulus::encrypt_shares_and_first_message(
+                party_one_keys,
+                &p_witness,
+                &q_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+            )
+            .expect("honest joint modulus");
+        let (party_two_joint_modulus_witness, party_two_joint_modulus_first_message) =
+            PartyTwoJointModulus::encrypt_shares_and_first_message(
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+            )
+            .expect("honest joint modulus");
+        let party_one_joint_modulus_second_message =
+            PartyOneJointModulus::verify_party_two_first_message_and_compute_cross_terms(
+                party_one_keys,
+                &p_witness,
+                &q_witness,
+                &joint_modulus_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_one_joint_modulus_first_message,
+                &party_two_joint_modulus_first_message,
+            )
+            .expect("honest joint modulus");
+        let party_two_joint_modulus_second_message =
+            PartyTwoJointModulus::verify_party_one_first_message_and_compute_cross_terms(
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &party_two_joint_modulus_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_two_joint_modulus_first_message,
+                &party_one_joint_modulus_first_message,
+            )
+            .expect("honest joint modulus");
+        let party_one_joint_modulus_third_message =
+            PartyOneJointModulus::verify_party_two_second_message_and_decrypt_modulus(
+                party_one_keys,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_one_joint_modulus_first_message,
+                &party_two_joint_modulus_first_message,
+                &party_two_joint_modulus_second_message,
+            )
+            .expect("honest joint modulus");
+        let party_two_joint_modulus_third_message =
+            PartyTwoJointModulus::verify_party_one_second_message_and_decrypt_modulus(
+                party_two_keys,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_two_joint_modulus_first_message,
+                &party_one_joint_modulus_first_message,
+                &party_one_joint_modulus_second_message,
+            )
+            .expect("honest joint modulus");
+        let joint_modulus =
+            PartyOneJointModulus::verify_party_two_third_message_and_output_joint_modulus(
+                party_one_keys,
+                &party_one_joint_modulus_second_message,
+                &party_one_joint_modulus_third_message,
+                &party_two_joint_modulus_first_message,
+                &party_two_joint_modulus_third_message,
+            )
+            .expect("honest joint modulus");
+
+        let party_one_biprimality_message =
+            PartyOneBiprimalityTest::compute_exponentiations_and_first_message(
+                &joint_modulus.n,
+                party_one_keys,
+                &p_witness,
+                &q_witness,
+                &p_party_one_message,
+                &q_party_one_message,
+            )
+            .expect("honest biprimality test");
+        let party_two_biprimality_message =
+            PartyTwoBiprimalityTest::compute_exponentiations_and_first_message(
+                &joint_modulus.n,
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &p_party_two_message,
+                &q_party_two_message,
+            )
+            .expect("honest biprimality test");
+
+        let party_one_exponent_first_message =
+            PartyOnePrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+                &e,
+                party_one_keys,
+                &p_witness,
+                &q_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+            )
+            .expect("honest private exponent share");
+        let party_two_exponent_first_message =
+            PartyTwoPrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+                &e,
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+            )
+            .expect("honest private exponent share");
+        let (exponent_witness, party_one_exponent_second_message) =
+            PartyOnePrivateExponentShare::verify_party_two_first_message_and_compute_exponent_share(
+                &e,
+                &joint_modulus,
+                party_one_keys,
+                &p_witness,
+                &q_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_one_exponent_first_message,
+                &party_two_exponent_first_message,
+            )
+            .expect("honest private exponent share");
+        let (party_two_exponent_witness, party_two_exponent_second_message) =
+            PartyTwoPrivateExponentShare::verify_party_one_first_message_and_compute_exponent_share(
+                &e,
+                &joint_modulus,
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_two_exponent_first_message,
+                &party_one_exponent_first_message,
+            )
+            .expect("honest private exponent share");
+        let key_share =
+            PartyOnePrivateExponentShare::verify_party_two_second_message_and_output_key_share(
+                &e,
+                &joint_modulus,
+                exponent_witness.clone(),
+                &party_one_exponent_second_message,
+                &party_two_exponent_second_message,
+            )
+            .expect("honest private exponent share");
+        let party_two_key_share =
+            PartyTwoPrivateExponentShare::verify_party_one_second_message_and_output_key_share(
+                &e,
+                &joint_modulus,
+                party_two_exponent_witness,
+                &party_two_exponent_second_message,
+                &party_one_exponent_second_message,
+            )
+            .expect("honest private exponent share");
+
+        let message = b"vice city".to_vec();
+        let party_one_sign_message = PartyOneSign::encode_message_and_partial_sign(
+            &key_share,
+            &message,
+            &SignatureEncoding::Pkcs1v15,
+        )
+        .expect("honest signing");
+        let party_two_sign_message = PartyTwoSign::verify_party_one_first_message_and_partial_sign(
+            &party_two_key_share,
+            &message,
+            &SignatureEncoding::Pkcs1v15,
+            &party_one_sign_message,
+        )
+        .expect("honest signing");
+
+        let label = b"label".to_vec();
+        let ciphertext = rsa_oaep_encrypt(&key_share.n, &key_share.e, &message, &label)
+            .expect("honest encryption");
+        let party_one_decrypt_message =
+            PartyOneDecrypt::partial_decrypt_and_first_message(&key_share, &ciphertext)
+                .expect("honest decryption");
+        let party_two_decrypt_message =
+            PartyTwoDecrypt::partial_decrypt_and_first_message(&party_two_key_share, &ciphertext)
+                .expect("honest decryption");
+
+        RsaFixture {
+            e,
+            p_witness,
+            q_witness,
+            p_party_one_message,
+            q_party_one_message,
+            p_party_two_message,
+            q_party_two_message,
+            p_ciphertexts,
+            q_ciphertexts,
+            joint_modulus_witness,
+            party_one_joint_modulus_first_message,
+            party_two_joint_modulus_first_message,
+            party_one_joint_modulus_second_message,
+            party_two_joint_modulus_second_message,
+            party_one_joint_modulus_third_message,
+            party_two_joint_modulus_third_message,
+            joint_modulus,
+            party_one_biprimality_message,
+            party_two_biprimality_message,
+            party_one_exponent_first_message,
+            party_two_exponent_first_message,
+            exponent_witness,
+            party_one_exponent_second_message,
+            party_two_exponent_second_message,
+            key_share,
+            message,
+            party_one_sign_message,
+            party_two_sign_message,
+            ciphertext,
+            label,
+            party_one_decrypt_message,
+            party_two_decrypt_message,
+        }
+    }
+}
+
+// samples shares until the candidate p = 4(p_0 + p_1) + 3 is a prime with e coprime to p - 1,
+// skipping trial division
+fn prime_candidate(
+    party_one_keys: &PartyOneKeySetup,
+    party_two_keys: &PartyTwoKeySetup,
+    e: &BigInt,
+) -> (
+    PartyOneCandidateWitness,
+    PartyOneCandidateGenerationFirstMsg,
+    PartyTwoCandidateWitness,
+    PartyTwoCandidateGenerationFirstMsg,
+) {
+    loop {
+        let (party_one_witness, party_one_first_message) =
+            PartyOneCandidateGeneration::generate_shares_of_candidate(party_one_keys)
+                .expect("honest candidate generation");
+        let (party_two_witness, party_two_first_message) =
+            PartyTwoCandidateGeneration::generate_shares_of_candidate(party_two_keys)
+                .expect("honest candidate generation");
+        let p =
+            (&party_one_witness.p_0 + &party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+        if is_prime(&p) && (&p - BigInt::one()).mod_floor(e) != BigInt::zero() {
+            return (
+                party_one_witness,
+                party_one_first_message,
+                party_two_witness,
+                party_two_first_message,
+            );
+        }
+    }
+}
+
+/// Mutates an honest message as selected by data, None if the result is not a message of
+/// type T anymore
+pub fn mutate<T: Serialize + DeserializeOwned>(message: &T, data: &[u8]) -> Option<T> {
+    let mut value = serde_json::to_value(message).ok()?;
+    mutate_value(&mut value, data);
+    serde_json::from_value(value).ok()
+}
+
+// every byte descends one level of the tree, a byte that is 0 mod 4 stops at the current node
+fn mutate_value(value: &mut Value, data: &[u8]) {
+    let (byte, rest) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    match value {
+        Value::Array(vec) if !vec.is_empty() && byte % 4 != 0 => {
+            let index = *byte as usize % vec.len();
+            mutate_value(&mut vec[index], rest)
+        }
+        Value::Object(map) if !map.is_empty() && byte % 4 != 0 => {
+            let index = *byte as usize % map.len();
+            if let Some(field) = map.values_mut().nth(index) {
+                mutate_value(field, rest)
+            }
+        }
+        _ => *value = malformed(value, *byte / 4, rest),
+    }
+}
+
+// BigInt is serialized as a hex string, vectors as arrays
+fn malformed(value: &Value, selector: u8, rest: &[u8]) -> Value {
+    match selector % 7 {
+        0 => Value::from("0"),
+        1 => Value::from("-1"),
+        2 => Value::from(
+            rest.iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>(),
+        ),
+        3 => match value {
+            Value::Array(vec) => Value::Array(vec.iter().skip(1).cloned().collect()),
+            _ => Value::Array(Vec::new()),
+        },
+        4 => match value {
+            Value::Array(vec) => {
+                let mut vec = vec.clone();
+                if let Some(last) = vec.last().cloned() {
+                    vec.push(last);
+                }
+                Value::Array(vec)
+            }
+            value => value.clone(),
+        },
+        5 => Value::from(u64::max_value()),
+        _ => Value::Null,
+    }
+}
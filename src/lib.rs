@@ -3,10 +3,14 @@ extern crate serde_derive;
 extern crate serde;
 extern crate serde_json;
 
+use curv::BigInt;
+use std::error::Error;
+use std::fmt;
+
 pub mod protocols;
 pub mod utlities;
 
-#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub enum TwoPartyRSAError {
     GeneralError,
     InvalidPaillierKey,
@@ -16,9 +20,57 @@ pub enum TwoPartyRSAError {
     CandidateGenerationEncError,
     CandidateGenerationDecError,
     InvalidModProof,
+    InvalidBiprimalityProof,
+    InvalidAffineProof,
+    JointModulusError,
+    InvalidPublicExponent,
+    PrivateExponentShareError,
+    MessageEncodingError,
+    InvalidPartialSignature,
+    InvalidSignature,
+    InvalidCiphertext,
+    InvalidPartialDecryption,
+    UnexpectedMessage,
+    WrongSession,
+    UnexpectedSender,
+    ReplayedMessage,
+    OutOfOrderMessage,
+    KeyGenAborted,
+    InvalidHmrtParams,
+    ElGamalGroupTooSmall,
+    ProtocolAbort(ProtocolAbort),
+    /// one of our own proofs could not be generated
+    ProofGenerationError(ProofError),
 }
 
-#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+/// Evidence that the counterparty deviated from the protocol: a proof in one of its messages
+/// failed to verify. The offending message is kept serialized so the abort can be audited.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct ProtocolAbort {
+    pub round: ProtocolRound,
+    /// field of the message holding the proof, e.g. zero_test_alpha_tilde.pi_random
+    pub proof: String,
+    /// the small prime of trial division the proof refers to
+    pub alpha: Option<BigInt>,
+    /// the offending message, serialized to json
+    pub message: String,
+    /// why the proof was rejected, None for checks that are not a proof (e.g. an opening)
+    pub proof_error: Option<ProofError>,
+}
+
+#[derive(Copy, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum ProtocolRound {
+    KeySetup,
+    CandidateGeneration,
+    TrialDivision,
+    BiprimalityTest,
+    JointModulus,
+    PrivateExponentShare,
+    Sign,
+    Decrypt,
+}
+
+#[derive(Copy, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub enum ProofError {
     DlogProofError,
     ElGamalProofError,
@@ -26,11 +78,242 @@ pub enum ProofError {
     RangeProofError,
     ModProofError,
     DHProofError,
+    AffineProofError,
+    /// repetition `index` of the cut-and-choose range proof failed
+    RangeProofRepetitionError { index: usize },
+    /// repetition `index` of the cut-and-choose equal secret proof failed
+    EqRepetitionError { index: usize },
+    /// the OR-proof of bit `index` of the tight range proof failed
+    RangeProofBitError { index: usize },
+    /// range proof `statement` of a mod proof failed: 1 bounds c', 2 bounds c''. `bit` is the
+    /// failing bit of the tight range proof, if it got that far.
+    ModRangeProofError {
+        statement: usize,
+        bit: Option<usize>,
+    },
+    /// the scheme rejected a value of the statement or the proof, e.g. a group element outside
+    /// of the prime order subgroup or a ciphertext of another group
+    SchemeError(HomomorphicSchemeError),
+    /// the bulletproof behind a range statement failed
+    BulletproofError(BulletproofError),
 }
 
-#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+#[derive(Copy, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum HomomorphicSchemeError {
+    EncryptionError,
+    MismatchedGroups,
+    PointAtInfinity,
+    InvalidDecryptionShares,
+    InvalidCiphertext,
+    /// not an element of the prime order subgroup of the ElGamal group
+    NotInSubgroup,
+}
+
+#[derive(Copy, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub enum BulletproofError {
     SetupError,
     InnerProductError,
     RangeProofError,
-}
\ No newline at end of file
+    /// the assignment does not satisfy the constraint system, or the circuit proof failed
+    R1CSError,
+}
+
+#[derive(Copy, PartialEq, Eq, Clone, Debug)]
+pub enum SealedShareError {
+    UnsupportedVersion(u8),
+    /// the envelope holds another type, e.g. a party two share opened as party one's
+    WrongContent,
+    /// a password was given for an envelope sealed with a raw key or vice versa
+    KeyTypeMismatch,
+    DecryptionFailed,
+    /// the Argon2id parameters of the envelope are outside of the window `open` accepts
+    KdfParametersOutOfRange,
+    KeyDerivationError,
+    EncodingError,
+}
+
+impl fmt::Display for TwoPartyRSAError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TwoPartyRSAError::GeneralError => write!(f, "general error"),
+            TwoPartyRSAError::InvalidPaillierKey => write!(f, "invalid Paillier key"),
+            TwoPartyRSAError::InvalidElGamalKey => write!(f, "invalid ElGamal key"),
+            TwoPartyRSAError::InvalidDlogProof => write!(f, "invalid dlog proof"),
+            TwoPartyRSAError::InvalidCom => write!(f, "invalid commitment"),
+            TwoPartyRSAError::CandidateGenerationEncError => {
+                write!(f, "failed to encrypt a candidate share")
+            }
+            TwoPartyRSAError::CandidateGenerationDecError => {
+                write!(f, "failed to decrypt in trial division")
+            }
+            TwoPartyRSAError::InvalidModProof => write!(f, "invalid mod proof"),
+            TwoPartyRSAError::InvalidBiprimalityProof => write!(f, "invalid biprimality proof"),
+            TwoPartyRSAError::InvalidAffineProof => write!(f, "invalid affine proof"),
+            TwoPartyRSAError::JointModulusError => write!(f, "the parties disagree on N"),
+            TwoPartyRSAError::InvalidPublicExponent => {
+                write!(f, "the public exponent is not invertible mod phi(N)")
+            }
+            TwoPartyRSAError::PrivateExponentShareError => {
+                write!(f, "invalid private exponent share")
+            }
+            TwoPartyRSAError::MessageEncodingError => write!(f, "invalid message encoding"),
+            TwoPartyRSAError::InvalidPartialSignature => write!(f, "invalid partial signature"),
+            TwoPartyRSAError::InvalidSignature => write!(f, "invalid signature"),
+            TwoPartyRSAError::InvalidCiphertext => write!(f, "invalid ciphertext"),
+            TwoPartyRSAError::InvalidPartialDecryption => write!(f, "invalid partial decryption"),
+            TwoPartyRSAError::UnexpectedMessage => write!(f, "unexpected message"),
+            TwoPartyRSAError::WrongSession => write!(f, "message of another session"),
+            TwoPartyRSAError::UnexpectedSender => write!(f, "unexpected sender"),
+            TwoPartyRSAError::ReplayedMessage => write!(f, "replayed message"),
+            TwoPartyRSAError::OutOfOrderMessage => write!(f, "out of order message"),
+            TwoPartyRSAError::KeyGenAborted => write!(f, "key generation was aborted"),
+            TwoPartyRSAError::InvalidHmrtParams => write!(f, "invalid HMRT parameters"),
+            TwoPartyRSAError::ElGamalGroupTooSmall => write!(f, "ElGamal group is too small"),
+            TwoPartyRSAError::ProtocolAbort(abort) => write!(f, "{}", abort),
+            TwoPartyRSAError::ProofGenerationError(err) => {
+                write!(f, "failed to generate a proof: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for TwoPartyRSAError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            // the abort is displayed as the error itself, its source is the proof error
+            TwoPartyRSAError::ProtocolAbort(abort) => abort.source(),
+            TwoPartyRSAError::ProofGenerationError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProtocolAbort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "aborting in round {:?}: counterparty's {} failed to verify",
+            self.round, self.proof
+        )?;
+        if let Some(alpha) = &self.alpha {
+            write!(f, " for alpha = {}", alpha)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ProtocolAbort {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.proof_error
+            .as_ref()
+            .map(|err| err as &(dyn Error + 'static))
+    }
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProofError::DlogProofError => write!(f, "dlog proof failed"),
+            ProofError::ElGamalProofError => write!(f, "encryption proof failed"),
+            ProofError::EqError => write!(f, "equal secret proof failed"),
+            ProofError::RangeProofError => write!(f, "range proof failed"),
+            ProofError::ModProofError => write!(f, "mod proof failed"),
+            ProofError::DHProofError => write!(f, "DDH proof failed"),
+            ProofError::AffineProofError => write!(f, "affine proof failed"),
+            ProofError::RangeProofRepetitionError { index } => {
+                write!(f, "repetition {} of the range proof failed", index)
+            }
+            ProofError::EqRepetitionError { index } => {
+                write!(f, "repetition {} of the equal secret proof failed", index)
+            }
+            ProofError::RangeProofBitError { index } => {
+                write!(f, "bit {} of the range proof failed", index)
+            }
+            ProofError::ModRangeProofError {
+                statement,
+                bit: Some(bit),
+            } => write!(
+                f,
+                "bit {} of range proof {} of the mod proof failed",
+                bit, statement
+            ),
+            ProofError::ModRangeProofError {
+                statement,
+                bit: None,
+            } => write!(f, "range proof {} of the mod proof failed", statement),
+            ProofError::SchemeError(err) => write!(f, "invalid proof input: {}", err),
+            ProofError::BulletproofError(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ProofError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProofError::SchemeError(err) => Some(err),
+            ProofError::BulletproofError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HomomorphicSchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HomomorphicSchemeError::EncryptionError => write!(f, "encryption failed"),
+            HomomorphicSchemeError::MismatchedGroups => {
+                write!(f, "ciphertexts or keys of different groups")
+            }
+            HomomorphicSchemeError::PointAtInfinity => write!(f, "point at infinity"),
+            HomomorphicSchemeError::InvalidDecryptionShares => {
+                write!(f, "invalid decryption shares")
+            }
+            HomomorphicSchemeError::InvalidCiphertext => {
+                write!(f, "not a ciphertext under this public key")
+            }
+            HomomorphicSchemeError::NotInSubgroup => {
+                write!(f, "not an element of the prime order subgroup")
+            }
+        }
+    }
+}
+
+impl Error for HomomorphicSchemeError {}
+
+impl fmt::Display for BulletproofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BulletproofError::SetupError => write!(f, "invalid bulletproof setup"),
+            BulletproofError::InnerProductError => write!(f, "inner product argument failed"),
+            BulletproofError::RangeProofError => write!(f, "bulletproof range proof failed"),
+            BulletproofError::R1CSError => write!(f, "arithmetic circuit proof failed"),
+        }
+    }
+}
+
+impl Error for BulletproofError {}
+
+impl fmt::Display for SealedShareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SealedShareError::UnsupportedVersion(version) => {
+                write!(f, "unsupported sealed share version {}", version)
+            }
+            SealedShareError::WrongContent => write!(f, "the sealed share holds another type"),
+            SealedShareError::KeyTypeMismatch => {
+                write!(f, "the share was sealed with another kind of key")
+            }
+            SealedShareError::DecryptionFailed => write!(
+                f,
+                "wrong key or password, or the sealed share was tampered with"
+            ),
+            SealedShareError::KdfParametersOutOfRange => {
+                write!(f, "the key derivation parameters are out of range")
+            }
+            SealedShareError::KeyDerivationError => write!(f, "key derivation failed"),
+            SealedShareError::EncodingError => write!(f, "invalid sealed share encoding"),
+        }
+    }
+}
+
+impl Error for SealedShareError {}
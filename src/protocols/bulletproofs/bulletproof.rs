@@ -0,0 +1,956 @@
+// based on the paper: https://eprint.iacr.org/2017/1066.pdf
+
+#![allow(non_snake_case)]
+
+use crate::protocols::bulletproofs::inner_product::is_scalar;
+use crate::protocols::bulletproofs::inner_product::multiexponentiation;
+use crate::protocols::bulletproofs::inner_product::InnerProductArg;
+use crate::protocols::bulletproofs::Field;
+use crate::protocols::bulletproofs::Group;
+use crate::utlities::elgamal_enc_proof::HomoELGamalProof;
+use crate::utlities::elgamal_enc_proof::HomoElGamalStatement;
+use crate::utlities::elgamal_enc_proof::HomoElGamalWitness;
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::group_element::validate_group_elements;
+use crate::utlities::group_element::GroupElement;
+use crate::utlities::hash_to_zn;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::mod_proof::ModRangeProof;
+use crate::utlities::tight_range_proof::Statement;
+use crate::utlities::tight_range_proof::Witness;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::BulletproofError::{self, InnerProductError, RangeProofError, SetupError};
+use crate::ProofError;
+use curv::arithmetic::traits::{Modulo, Samplable, ZeroizeBN};
+use curv::BigInt;
+use elgamal::{ElGamalCiphertext, ElGamalPP, ElGamalPublicKey};
+use std::slice;
+
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/bulletproof-range-proof";
+
+/// Bulletproofs range proof (sections 4.2 and 4.3 of the paper) for exponent ElGamal over the
+/// prime order subgroup of an RFC 7919 group.
+/// For c = Enc(x; r) = (g^r, g^x * h^r), V = g^x * h^r is a Pedersen commitment to x with
+/// blinding r, for bases g and the public key h, and the proof shows that 0 <= x < 2^k for
+/// k = |range - 1|: exactly x < range when range is a power of two, and x < 2 * range otherwise.
+/// The commitment is binding for a prover that does not know log_g(h), in HMRT h is the joint
+/// key and each party only knows a share of it. A proof of knowledge of the plaintext and
+/// randomness of c ties V to c1 = g^r, otherwise c could decrypt to another value than the one
+/// committed in V.
+///
+/// The proof aggregates m ciphertexts c_j with bit_length bits each: value j gets the block
+/// j * n, ..., (j + 1) * n - 1 of vectors of length m * n, weighted by z^(2 + j), where n is
+/// bit_length and m the number of values rounded up to powers of two. Bits of the padding get
+/// weight 0 (they are still proven to be bits, and are 0 for an honest prover). The knowledge of
+/// the plaintexts is proven once, for c = sum lambda^j * c_j with lambda a hash of the
+/// ciphertexts: its randomness is sum lambda^j * r_j for the blindings r_j of the commitments
+/// only if all of them are the randomness of c1_j as well, but with probability m / q.
+/// The generators g_vec, h_vec and u of the inner product argument are hashed into the subgroup.
+/// All challenges, including those of the inner product argument, come from a single transcript.
+///
+/// For a single value, statement and witness are those of the tight range proof, and the proof
+/// has 2 * log(m * n) + 4 group elements instead of k ciphertexts per value. It only bounds x by
+/// 2^k, `BulletproofRangeProof` builds the exact range proof on top of it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RangeProof {
+    pub(super) A: Group,
+    pub(super) S: Group,
+    pub(super) T1: Group,
+    pub(super) T2: Group,
+    pub(super) tau_x: Field,
+    pub(super) miu: Field,
+    pub(super) tx: Field,
+    pub(super) inner_product_proof: InnerProductArg,
+    /// knowledge of the plaintext and randomness of sum lambda^j * c_j
+    pub(super) pi_enc: HomoELGamalProof,
+}
+
+// bases and dimensions of a statement
+struct Setup {
+    pp: ElGamalPP,
+    // number of values and bits of each
+    m: usize,
+    bit_length: usize,
+    // length of the block of a value, bit_length rounded up to a power of two
+    n: usize,
+    g: BigInt,
+    h: BigInt,
+    g_vec: Vec<Group>,
+    h_vec: Vec<Group>,
+    u: Group,
+}
+
+impl Setup {
+    fn new(
+        pk: &ElGamalPublicKey,
+        ciphertexts: &[ElGamalCiphertext],
+        bit_length: usize,
+    ) -> Result<Self, BulletproofError> {
+        let pp = &pk.pp;
+        // every value, a sum of bits weighted by powers of two, must not wrap around modulo q
+        if ciphertexts.is_empty() || bit_length == 0 || bit_length >= pp.q.bit_length() {
+            return Err(SetupError);
+        }
+        validate_statement(pk, ciphertexts)?;
+
+        let m = ciphertexts.len();
+        let n = bit_length.next_power_of_two();
+        let len = m.next_power_of_two() * n;
+        Ok(Setup {
+            pp: pp.clone(),
+            m,
+            bit_length,
+            n,
+            g: pp.g.clone(),
+            h: pk.h.clone(),
+            g_vec: (0..len).map(|i| hash_to_group(pp, 0, i)).collect(),
+            h_vec: (0..len).map(|i| hash_to_group(pp, 1, i)).collect(),
+            u: hash_to_group(pp, 2, 0),
+        })
+    }
+
+    // length of the vectors
+    fn len(&self) -> usize {
+        self.g_vec.len()
+    }
+
+    fn field(&self, x: BigInt) -> Field {
+        Field {
+            pp: self.pp.clone(),
+            x,
+        }
+    }
+
+    fn group(&self, g: BigInt) -> Group {
+        Group {
+            pp: self.pp.clone(),
+            g,
+        }
+    }
+
+    // h_vec[i]^(y^-i), the bases of the right vector after the challenge y
+    fn h_tag(&self, y_inv: &BigInt) -> Vec<Group> {
+        let y_inv_pow = powers(y_inv, self.len(), &self.pp.q);
+        (0..self.len())
+            .map(|i| self.group(BigInt::mod_pow(&self.h_vec[i].g, &y_inv_pow[i], &self.pp.p)))
+            .collect()
+    }
+
+    // z^(2 + j) * 2^i at index j * n + i for bit i < bit_length of value j < m, 0 for the padding
+    fn z_weights(&self, z: &BigInt) -> Vec<BigInt> {
+        let q = &self.pp.q;
+        let z_pow = powers(z, self.m + 2, q);
+        (0..self.len())
+            .map(|index| {
+                let (j, i) = (index / self.n, index % self.n);
+                if j < self.m && i < self.bit_length {
+                    BigInt::mod_mul(&z_pow[j + 2], &(BigInt::one() << i), q)
+                } else {
+                    BigInt::zero()
+                }
+            })
+            .collect()
+    }
+}
+
+// the public key and the ciphertexts carrying the commitments V_j = c2_j
+pub(super) fn validate_statement(
+    pk: &ElGamalPublicKey,
+    ciphertexts: &[ElGamalCiphertext],
+) -> Result<(), BulletproofError> {
+    GroupElement::from_bigint(&pk.h, &pk.pp).map_err(|_| SetupError)?;
+    for c in ciphertexts {
+        FFDHEExponentElGamal::validate_ciphertext(pk, c).map_err(|_| SetupError)?;
+    }
+    Ok(())
+}
+
+// an element of the subgroup of order q with unknown discrete log to g and to the other
+// generators: a hash of (p, g, label, index) into Z_p^*, raised to the cofactor (p - 1) / q
+pub(super) fn hash_to_group(pp: &ElGamalPP, label: u64, index: usize) -> Group {
+    let cofactor = (&pp.p - BigInt::one()).div_floor(&pp.q);
+    let mut counter: u64 = 0;
+    loop {
+        let x = hash_to_zn(
+            &[
+                &pp.p,
+                &pp.g,
+                &BigInt::from(label),
+                &BigInt::from(index as u64),
+                &BigInt::from(counter),
+            ],
+            &pp.p,
+        );
+        let g = BigInt::mod_pow(&x, &cofactor, &pp.p);
+        if g > BigInt::one() {
+            return Group { pp: pp.clone(), g };
+        }
+        counter += 1;
+    }
+}
+
+// 1, y, y^2, ..., y^(n-1) mod q
+pub(super) fn powers(y: &BigInt, n: usize, q: &BigInt) -> Vec<BigInt> {
+    let mut y_pow = Vec::with_capacity(n);
+    let mut acc = BigInt::one();
+    for _ in 0..n {
+        y_pow.push(acc.clone());
+        acc = BigInt::mod_mul(&acc, y, q);
+    }
+    y_pow
+}
+
+pub(super) fn inner_product(a: &[BigInt], b: &[BigInt], q: &BigInt) -> BigInt {
+    a.iter().zip(b).fold(BigInt::zero(), |acc, (a_i, b_i)| {
+        BigInt::mod_add(&acc, &BigInt::mod_mul(a_i, b_i, q), q)
+    })
+}
+
+pub(super) fn append_statement(
+    transcript: &mut Transcript,
+    pk: &ElGamalPublicKey,
+    ciphertexts: &[ElGamalCiphertext],
+) {
+    transcript.append_bigints(b"pp", &[pk.pp.p.clone(), pk.pp.g.clone()]);
+    transcript.append_bigint(b"h", &pk.h);
+    transcript.append_u64(b"m", ciphertexts.len() as u64);
+    for c in ciphertexts {
+        transcript.append_bigints(b"c", &FFDHEExponentElGamal::ciphertext_to_hash_input(c));
+    }
+}
+
+// the transcript of a proof starts with the statement and the bit length
+fn statement_transcript(
+    pk: &ElGamalPublicKey,
+    ciphertexts: &[ElGamalCiphertext],
+    bit_length: usize,
+    context: &ProofContext,
+) -> Transcript {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    append_statement(&mut transcript, pk, ciphertexts);
+    transcript.append_u64(b"bit-length", bit_length as u64);
+    transcript
+}
+
+fn challenges_y_z(
+    transcript: &mut Transcript,
+    q: &BigInt,
+    A: &BigInt,
+    S: &BigInt,
+) -> (BigInt, BigInt) {
+    transcript.append_bigint(b"A", A);
+    transcript.append_bigint(b"S", S);
+    let y = transcript.challenge_zn(b"y", q);
+    let z = transcript.challenge_zn(b"z", q);
+    (y, z)
+}
+
+// x after T1, T2 and w after tau_x, miu and tx
+fn challenge_x(transcript: &mut Transcript, q: &BigInt, T: &[&BigInt]) -> BigInt {
+    for T_i in T {
+        transcript.append_bigint(b"T", T_i);
+    }
+    transcript.challenge_zn(b"x", q)
+}
+
+fn challenge_w(
+    transcript: &mut Transcript,
+    q: &BigInt,
+    tau_x: &BigInt,
+    miu: &BigInt,
+    tx: &BigInt,
+) -> BigInt {
+    transcript.append_bigint(b"tau_x", tau_x);
+    transcript.append_bigint(b"miu", miu);
+    transcript.append_bigint(b"tx", tx);
+    transcript.challenge_zn(b"w", q)
+}
+
+// lambda is drawn from the statement, returns sum lambda^j * c_j and the powers of lambda
+fn combined_ciphertext(
+    pk: &ElGamalPublicKey,
+    ciphertexts: &[ElGamalCiphertext],
+    context: &ProofContext,
+) -> Result<(ElGamalCiphertext, Vec<BigInt>), BulletproofError> {
+    let mut transcript = Transcript::new(b"vice-city/ciphertext-combination", context);
+    append_statement(&mut transcript, pk, ciphertexts);
+    let lambda = transcript.challenge_zn(b"lambda", &pk.pp.q);
+    let lambda_pow = powers(&lambda, ciphertexts.len(), &pk.pp.q);
+    let combined = ciphertexts[1..]
+        .iter()
+        .zip(&lambda_pow[1..])
+        .try_fold(ciphertexts[0].clone(), |acc, (c, lambda_j)| {
+            FFDHEExponentElGamal::add(pk, &acc, &FFDHEExponentElGamal::mul(pk, c, lambda_j)?)
+        })
+        .map_err(|_| RangeProofError)?;
+    Ok((combined, lambda_pow))
+}
+
+// knowledge of the plaintext and randomness of sum lambda^j * c_j
+pub(super) fn prove_opening(
+    pk: &ElGamalPublicKey,
+    witnesses: &[Witness],
+    ciphertexts: &[ElGamalCiphertext],
+    context: &ProofContext,
+) -> Result<HomoELGamalProof, BulletproofError> {
+    let q = &pk.pp.q;
+    let (combined, lambda_pow) = combined_ciphertext(pk, ciphertexts, context)?;
+    let combine = |values: Vec<&BigInt>| {
+        values
+            .iter()
+            .zip(&lambda_pow)
+            .fold(BigInt::zero(), |acc, (v, lambda_j)| {
+                BigInt::mod_add(&acc, &BigInt::mod_mul(v, lambda_j, q), q)
+            })
+    };
+    HomoELGamalProof::prove(
+        &HomoElGamalWitness {
+            r: combine(witnesses.iter().map(|w| &w.r).collect()),
+            m: combine(witnesses.iter().map(|w| &w.x).collect()),
+        },
+        &HomoElGamalStatement {
+            pk: pk.clone(),
+            ciphertext: combined,
+        },
+        context,
+    )
+    .map_err(|_| RangeProofError)
+}
+
+pub(super) fn verify_opening(
+    proof: &HomoELGamalProof,
+    pk: &ElGamalPublicKey,
+    ciphertexts: &[ElGamalCiphertext],
+    context: &ProofContext,
+) -> Result<(), BulletproofError> {
+    let (combined, _) = combined_ciphertext(pk, ciphertexts, context)?;
+    proof
+        .verify(
+            &HomoElGamalStatement {
+                pk: pk.clone(),
+                ciphertext: combined,
+            },
+            context,
+        )
+        .map_err(|_| RangeProofError)
+}
+
+// k = |range - 1|, the bit length proven for a range statement
+fn range_bit_length(range: &BigInt) -> Result<usize, BulletproofError> {
+    if range <= &BigInt::one() {
+        return Err(SetupError);
+    }
+    Ok((range - BigInt::one()).bit_length())
+}
+
+impl RangeProof {
+    pub fn prove(
+        witness: &Witness,
+        statement: &Statement,
+        context: &ProofContext,
+    ) -> Result<Self, BulletproofError> {
+        RangeProof::prove_aggregated(
+            &statement.pk,
+            slice::from_ref(witness),
+            slice::from_ref(&statement.ciphertext),
+            range_bit_length(&statement.range)?,
+            context,
+        )
+    }
+
+    pub fn verify(
+        &self,
+        statement: &Statement,
+        context: &ProofContext,
+    ) -> Result<(), BulletproofError> {
+        self.verify_aggregated(
+            &statement.pk,
+            slice::from_ref(&statement.ciphertext),
+            range_bit_length(&statement.range)?,
+            context,
+        )
+    }
+
+    /// Proves 0 <= x_j < 2^bit_length for the plaintexts x_j of all ciphertexts, witnesses[j] is
+    /// the plaintext and randomness of ciphertexts[j]
+    pub fn prove_aggregated(
+        pk: &ElGamalPublicKey,
+        witnesses: &[Witness],
+        ciphertexts: &[ElGamalCiphertext],
+        bit_length: usize,
+        context: &ProofContext,
+    ) -> Result<Self, BulletproofError> {
+        let setup = Setup::new(pk, ciphertexts, bit_length)?;
+        let len = setup.len();
+        let (p, q) = (&setup.pp.p, &setup.pp.q);
+        if witnesses.len() != ciphertexts.len() {
+            return Err(SetupError);
+        }
+        if witnesses
+            .iter()
+            .any(|w| w.x < BigInt::zero() || w.x.bit_length() > bit_length)
+        {
+            return Err(RangeProofError);
+        }
+
+        let pi_enc = prove_opening(pk, witnesses, ciphertexts, context)?;
+
+        // a_L are the bits of the values and a_R = a_L - 1
+        let mut a_L = (0..len)
+            .map(|index| match witnesses.get(index / setup.n) {
+                Some(w) => (&w.x >> (index % setup.n)).mod_floor(&BigInt::from(2)),
+                None => BigInt::zero(),
+            })
+            .collect::<Vec<BigInt>>();
+        let mut a_R = a_L
+            .iter()
+            .map(|a| BigInt::mod_sub(a, &BigInt::one(), q))
+            .collect::<Vec<BigInt>>();
+        let mut s_L = (0..len)
+            .map(|_| BigInt::sample_below(q))
+            .collect::<Vec<_>>();
+        let mut s_R = (0..len)
+            .map(|_| BigInt::sample_below(q))
+            .collect::<Vec<_>>();
+        let mut alpha = BigInt::sample_below(q);
+        let mut rho = BigInt::sample_below(q);
+
+        let g_h_vec = [&setup.g_vec[..], &setup.h_vec[..]].concat();
+        let to_field = |v: &[BigInt]| v.iter().map(|x| setup.field(x.clone())).collect::<Vec<_>>();
+        let A = BigInt::mod_mul(
+            &BigInt::mod_pow(&setup.h, &alpha, p),
+            &multiexponentiation(&g_h_vec, &to_field(&[&a_L[..], &a_R[..]].concat())).g,
+            p,
+        );
+        let S = BigInt::mod_mul(
+            &BigInt::mod_pow(&setup.h, &rho, p),
+            &multiexponentiation(&g_h_vec, &to_field(&[&s_L[..], &s_R[..]].concat())).g,
+            p,
+        );
+
+        let mut transcript = statement_transcript(pk, ciphertexts, bit_length, context);
+        let (y, z) = challenges_y_z(&mut transcript, q, &A, &S);
+        if y == BigInt::zero() {
+            return Err(RangeProofError);
+        }
+        let y_pow = powers(&y, len, q);
+        let z_weights = setup.z_weights(&z);
+
+        // l(X) = l0 + l1 * X and r(X) = r0 + r1 * X
+        let l0 = a_L
+            .iter()
+            .map(|a| BigInt::mod_sub(a, &z, q))
+            .collect::<Vec<_>>();
+        let r0 = (0..len)
+            .map(|i| {
+                let a_z = BigInt::mod_add(&a_R[i], &z, q);
+                BigInt::mod_add(&BigInt::mod_mul(&y_pow[i], &a_z, q), &z_weights[i], q)
+            })
+            .collect::<Vec<_>>();
+        let r1 = (0..len)
+            .map(|i| BigInt::mod_mul(&y_pow[i], &s_R[i], q))
+            .collect::<Vec<_>>();
+
+        // t(X) = <l(X), r(X)> = t0 + t1 * X + t2 * X^2
+        let t1 = BigInt::mod_add(&inner_product(&l0, &r1, q), &inner_product(&s_L, &r0, q), q);
+        let t2 = inner_product(&s_L, &r1, q);
+        let mut tau1 = BigInt::sample_below(q);
+        let mut tau2 = BigInt::sample_below(q);
+        let commit = |t: &BigInt, tau: &BigInt| {
+            BigInt::mod_mul(
+                &BigInt::mod_pow(&setup.g, t, p),
+                &BigInt::mod_pow(&setup.h, tau, p),
+                p,
+            )
+        };
+        let T1 = commit(&t1, &tau1);
+        let T2 = commit(&t2, &tau2);
+
+        let x = challenge_x(&mut transcript, q, &[&T1, &T2]);
+        let x_sq = BigInt::mod_mul(&x, &x, q);
+        let l = (0..len)
+            .map(|i| BigInt::mod_add(&l0[i], &BigInt::mod_mul(&s_L[i], &x, q), q))
+            .collect::<Vec<_>>();
+        let r = (0..len)
+            .map(|i| BigInt::mod_add(&r0[i], &BigInt::mod_mul(&r1[i], &x, q), q))
+            .collect::<Vec<_>>();
+        let tx = inner_product(&l, &r, q);
+        // tau_x = tau2 * x^2 + tau1 * x + sum z^(2 + j) * r_j
+        let z_pow = powers(&z, setup.m + 2, q);
+        let tau_x = witnesses.iter().zip(&z_pow[2..]).fold(
+            BigInt::mod_add(
+                &BigInt::mod_mul(&tau2, &x_sq, q),
+                &BigInt::mod_mul(&tau1, &x, q),
+                q,
+            ),
+            |acc, (w, z_j)| BigInt::mod_add(&acc, &BigInt::mod_mul(z_j, &w.r, q), q),
+        );
+        let miu = BigInt::mod_add(&alpha, &BigInt::mod_mul(&rho, &x, q), q);
+
+        // P = g_vec^l * h_tag^r * ux^tx, the verifier gets it from A, S and the challenges
+        let w = challenge_w(&mut transcript, q, &tau_x, &miu, &tx);
+        let ux = setup.group(BigInt::mod_pow(&setup.u.g, &w, p));
+        let h_tag = setup.h_tag(&BigInt::mod_inv(&y, q));
+        let l = to_field(&l);
+        let r = to_field(&r);
+        let P = multiexponentiation(
+            &[&setup.g_vec[..], &h_tag[..], &[ux.clone()][..]].concat(),
+            &[&l[..], &r[..], &[setup.field(tx.clone())][..]].concat(),
+        );
+        let inner_product_proof = InnerProductArg::prove(
+            &mut transcript,
+            &setup.g_vec,
+            &h_tag,
+            &ux,
+            &P,
+            &l,
+            &r,
+            Vec::with_capacity(len),
+            Vec::with_capacity(len),
+        );
+
+        for secret in a_L
+            .iter_mut()
+            .chain(a_R.iter_mut())
+            .chain(s_L.iter_mut())
+            .chain(s_R.iter_mut())
+        {
+            secret.zeroize_bn();
+        }
+        alpha.zeroize_bn();
+        rho.zeroize_bn();
+        tau1.zeroize_bn();
+        tau2.zeroize_bn();
+
+        Ok(RangeProof {
+            A: setup.group(A),
+            S: setup.group(S),
+            T1: setup.group(T1),
+            T2: setup.group(T2),
+            tau_x: setup.field(tau_x),
+            miu: setup.field(miu),
+            tx: setup.field(tx),
+            inner_product_proof,
+            pi_enc,
+        })
+    }
+
+    pub fn verify_aggregated(
+        &self,
+        pk: &ElGamalPublicKey,
+        ciphertexts: &[ElGamalCiphertext],
+        bit_length: usize,
+        context: &ProofContext,
+    ) -> Result<(), BulletproofError> {
+        let setup = Setup::new(pk, ciphertexts, bit_length)?;
+        let len = setup.len();
+        let (p, q) = (&setup.pp.p, &setup.pp.q);
+        validate_group_elements(&[&self.A.g, &self.S.g, &self.T1.g, &self.T2.g], &setup.pp)
+            .map_err(|_| RangeProofError)?;
+        if !is_scalar(&self.tau_x, q) || !is_scalar(&self.miu, q) || !is_scalar(&self.tx, q) {
+            return Err(RangeProofError);
+        }
+        verify_opening(&self.pi_enc, pk, ciphertexts, context)?;
+
+        let mut transcript = statement_transcript(pk, ciphertexts, bit_length, context);
+        let (y, z) = challenges_y_z(&mut transcript, q, &self.A.g, &self.S.g);
+        if y == BigInt::zero() {
+            return Err(RangeProofError);
+        }
+        let x = challenge_x(&mut transcript, q, &[&self.T1.g, &self.T2.g]);
+        let w = challenge_w(&mut transcript, q, &self.tau_x.x, &self.miu.x, &self.tx.x);
+        let y_pow = powers(&y, len, q);
+        let z_pow = powers(&z, setup.m + 2, q);
+        let z_weights = setup.z_weights(&z);
+        let z_sq = &z_pow[2];
+        let x_sq = BigInt::mod_mul(&x, &x, q);
+
+        // g^tx * h^tau_x = prod V_j^(z^(2 + j)) * g^delta * T1^x * T2^(x^2) for V_j = c2_j and
+        // delta = (z - z^2) * <1, y^(m * n)> - z * <1, z_weights>
+        let sum = |v: &[BigInt]| {
+            v.iter()
+                .fold(BigInt::zero(), |acc, v_i| BigInt::mod_add(&acc, v_i, q))
+        };
+        let delta = BigInt::mod_sub(
+            &BigInt::mod_mul(&BigInt::mod_sub(&z, z_sq, q), &sum(&y_pow), q),
+            &BigInt::mod_mul(&z, &sum(&z_weights), q),
+            q,
+        );
+        let lhs = BigInt::mod_mul(
+            &BigInt::mod_pow(&setup.g, &self.tx.x, p),
+            &BigInt::mod_pow(&setup.h, &self.tau_x.x, p),
+            p,
+        );
+        let rhs = ciphertexts
+            .iter()
+            .zip(&z_pow[2..])
+            .map(|(c, z_j)| BigInt::mod_pow(&c.c2, z_j, p))
+            .chain(vec![
+                BigInt::mod_pow(&setup.g, &delta, p),
+                BigInt::mod_pow(&self.T1.g, &x, p),
+                BigInt::mod_pow(&self.T2.g, &x_sq, p),
+            ])
+            .fold(BigInt::one(), |acc, a| BigInt::mod_mul(&acc, &a, p));
+        if lhs != rhs {
+            return Err(RangeProofError);
+        }
+
+        // P = A * S^x * h^(-miu) * g_vec^(-z) * h_tag^(z * y^(m * n) + z_weights) * ux^tx
+        let ux = setup.group(BigInt::mod_pow(&setup.u.g, &w, p));
+        let h_tag = setup.h_tag(&BigInt::mod_inv(&y, q));
+        let minus_z = BigInt::mod_sub(&BigInt::zero(), &z, q);
+        let h_tag_exponents =
+            (0..len).map(|i| BigInt::mod_add(&BigInt::mod_mul(&z, &y_pow[i], q), &z_weights[i], q));
+        let mut scalars = vec![
+            BigInt::one(),
+            x,
+            BigInt::mod_sub(&BigInt::zero(), &self.miu.x, q),
+            self.tx.x.clone(),
+        ];
+        scalars.extend((0..len).map(|_| minus_z.clone()));
+        scalars.extend(h_tag_exponents);
+        let mut elements = vec![
+            self.A.clone(),
+            self.S.clone(),
+            setup.group(setup.h.clone()),
+            ux.clone(),
+        ];
+        elements.extend_from_slice(&setup.g_vec);
+        elements.extend_from_slice(&h_tag);
+        let P = multiexponentiation(
+            &elements,
+            &scalars
+                .into_iter()
+                .map(|s| setup.field(s))
+                .collect::<Vec<_>>(),
+        );
+
+        self.inner_product_proof
+            .fast_verify(&mut transcript, &setup.g_vec, &h_tag, &ux, &P)
+            .map_err(|_| InnerProductError)
+    }
+}
+
+/// Exact range proof 0 <= x < range with the statement, witness and errors of `TightRangeProof`,
+/// so that it can replace it in a `ModProof`. For k = |range - 1| it proves x < 2^k and
+/// range - 1 - x < 2^k in one aggregated proof, the latter for Enc(range - 1 - x) = Enc(range - 1)
+/// - c with randomness -r. When range is 2^k the first bound is exact on its own.
+/// Unlike the tight range proof it is only sound while log_g(h) is unknown to the prover.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BulletproofRangeProof {
+    pub(super) proof: RangeProof,
+}
+
+// c, followed by Enc(range - 1) - c unless range is a power of two, and k = |range - 1|
+fn exact_range_ciphertexts(
+    statement: &Statement,
+) -> Result<(Vec<ElGamalCiphertext>, usize), BulletproofError> {
+    let bit_length = range_bit_length(&statement.range)?;
+    if statement.range == BigInt::one() << bit_length {
+        return Ok((vec![statement.ciphertext.clone()], bit_length));
+    }
+    let pk = &statement.pk;
+    let range_max = &statement.range - BigInt::one();
+    let complement = FFDHEExponentElGamal::mul(pk, &statement.ciphertext, &-BigInt::one())
+        .and_then(|minus_c| {
+            let c_max =
+                FFDHEExponentElGamal::encrypt_with_randomness(pk, &range_max, &BigInt::zero())?;
+            FFDHEExponentElGamal::add(pk, &c_max, &minus_c)
+        })
+        .map_err(|_| SetupError)?;
+    Ok((vec![statement.ciphertext.clone(), complement], bit_length))
+}
+
+impl BulletproofRangeProof {
+    pub fn prove(
+        witness: &Witness,
+        statement: &Statement,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        let (ciphertexts, bit_length) =
+            exact_range_ciphertexts(statement).map_err(ProofError::BulletproofError)?;
+        let mut witnesses = vec![witness.clone()];
+        if ciphertexts.len() == 2 {
+            witnesses.push(Witness {
+                x: &statement.range - BigInt::one() - &witness.x,
+                r: BigInt::mod_sub(&BigInt::zero(), &witness.r, &statement.pk.pp.q),
+            });
+        }
+        let proof = RangeProof::prove_aggregated(
+            &statement.pk,
+            &witnesses,
+            &ciphertexts,
+            bit_length,
+            context,
+        )
+        .map_err(ProofError::BulletproofError)?;
+        Ok(BulletproofRangeProof { proof })
+    }
+
+    pub fn verify(&self, statement: &Statement, context: &ProofContext) -> Result<(), ProofError> {
+        let (ciphertexts, bit_length) =
+            exact_range_ciphertexts(statement).map_err(ProofError::BulletproofError)?;
+        self.proof
+            .verify_aggregated(&statement.pk, &ciphertexts, bit_length, context)
+            .map_err(ProofError::BulletproofError)
+    }
+}
+
+impl ModRangeProof<FFDHEExponentElGamal> for BulletproofRangeProof {
+    fn prove(
+        witness: &Witness,
+        statement: &Statement,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        BulletproofRangeProof::prove(witness, statement, context)
+    }
+
+    fn verify(&self, statement: &Statement, context: &ProofContext) -> Result<(), ProofError> {
+        BulletproofRangeProof::verify(self, statement, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocols::bulletproofs::bulletproof::hash_to_group;
+    use crate::protocols::bulletproofs::bulletproof::BulletproofRangeProof;
+    use crate::protocols::bulletproofs::bulletproof::RangeProof;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::tight_range_proof::Statement;
+    use crate::utlities::tight_range_proof::Witness;
+    use crate::utlities::transcript::ProofContext;
+    use crate::BulletproofError;
+    use crate::ProofError;
+    use curv::arithmetic::traits::Modulo;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalCiphertext;
+    use elgamal::ElGamalKeyPair;
+    use elgamal::ElGamalPP;
+    use elgamal::ElGamalPublicKey;
+
+    fn ffdhe_statement(x: &BigInt, range: &BigInt) -> (Witness, Statement) {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let r = BigInt::sample_below(&pp.q);
+        let ciphertext = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, x, &r).unwrap();
+        (
+            Witness { x: x.clone(), r },
+            Statement {
+                pk: keypair.pk,
+                range: range.clone(),
+                ciphertext,
+            },
+        )
+    }
+
+    fn ffdhe_ciphertexts(
+        values: &[BigInt],
+    ) -> (ElGamalPublicKey, Vec<Witness>, Vec<ElGamalCiphertext>) {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let witnesses = values
+            .iter()
+            .map(|x| Witness {
+                x: x.clone(),
+                r: BigInt::sample_below(&pp.q),
+            })
+            .collect::<Vec<_>>();
+        let ciphertexts = witnesses
+            .iter()
+            .map(|w| {
+                FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &w.x, &w.r).unwrap()
+            })
+            .collect();
+        (keypair.pk, witnesses, ciphertexts)
+    }
+
+    #[test]
+    fn test_hash_to_group() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let g = hash_to_group(&pp, 0, 0);
+        assert_eq!(BigInt::mod_pow(&g.g, &pp.q, &pp.p), BigInt::one());
+        assert_eq!(g, hash_to_group(&pp, 0, 0));
+        assert_ne!(g, hash_to_group(&pp, 0, 1));
+        assert_ne!(g, hash_to_group(&pp, 1, 0));
+    }
+
+    #[test]
+    fn test_bulletproof_range_proof() {
+        let range = BigInt::one() << 32;
+        for x in [
+            BigInt::zero(),
+            &range - BigInt::one(),
+            BigInt::sample_below(&range),
+        ]
+        .iter()
+        {
+            let (witness, statement) = ffdhe_statement(x, &range);
+            let proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+            assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_bulletproof_range_not_power_of_two() {
+        // k = 10 bits padded to vectors of length 16
+        let range = BigInt::from(1000);
+        let x = BigInt::from(999);
+        let (witness, statement) = ffdhe_statement(&x, &range);
+        let proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+
+        // the proof bounds x by 2^10
+        let (witness, statement) = ffdhe_statement(&BigInt::from(1024), &range);
+        assert_eq!(
+            RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap_err(),
+            BulletproofError::RangeProofError
+        );
+    }
+
+    #[test]
+    fn test_bulletproof_bad_proof() {
+        let context = ProofContext::default();
+        let range = BigInt::one() << 16;
+        let (witness, statement) = ffdhe_statement(&BigInt::from(12345), &range);
+        let proof = RangeProof::prove(&witness, &statement, &context).unwrap();
+
+        let mut bad_tx = proof.clone();
+        bad_tx.tx.x = BigInt::mod_add(&bad_tx.tx.x, &BigInt::one(), &statement.pk.pp.q);
+        assert!(bad_tx.verify(&statement, &context).is_err());
+
+        let mut bad_a = proof.clone();
+        bad_a.A = proof.S.clone();
+        assert!(bad_a.verify(&statement, &context).is_err());
+
+        let mut bad_ip = proof.clone();
+        bad_ip.inner_product_proof.a_tag.x = BigInt::mod_add(
+            &bad_ip.inner_product_proof.a_tag.x,
+            &BigInt::one(),
+            &statement.pk.pp.q,
+        );
+        assert_eq!(
+            bad_ip.verify(&statement, &context).unwrap_err(),
+            BulletproofError::InnerProductError
+        );
+
+        // another session
+        assert!(proof
+            .verify(&statement, &ProofContext::new(&[1u8; 32], 1))
+            .is_err());
+
+        // another ciphertext
+        let (_, other_statement) = ffdhe_statement(&BigInt::from(12345), &range);
+        assert!(proof.verify(&other_statement, &context).is_err());
+
+        // another range changes the length of the vectors
+        let mut smaller_range = statement.clone();
+        smaller_range.range = BigInt::one() << 8;
+        assert!(proof.verify(&smaller_range, &context).is_err());
+
+        let mut bad_setup = statement;
+        bad_setup.range = BigInt::one();
+        assert_eq!(
+            proof.verify(&bad_setup, &context).unwrap_err(),
+            BulletproofError::SetupError
+        );
+    }
+
+    #[test]
+    fn test_aggregated_range_proof() {
+        let context = ProofContext::default();
+        // 3 values of 12 bits, vectors of length 4 * 16
+        let values = [0, 4095, 1234]
+            .iter()
+            .map(|x| BigInt::from(*x))
+            .collect::<Vec<_>>();
+        let (pk, witnesses, ciphertexts) = ffdhe_ciphertexts(&values);
+        let proof =
+            RangeProof::prove_aggregated(&pk, &witnesses, &ciphertexts, 12, &context).unwrap();
+        assert!(proof
+            .verify_aggregated(&pk, &ciphertexts, 12, &context)
+            .is_ok());
+        assert_eq!(proof.inner_product_proof.L.len(), 6);
+
+        // the order and number of the ciphertexts and the bit length are part of the statement
+        let mut swapped = ciphertexts.clone();
+        swapped.swap(0, 2);
+        assert!(proof
+            .verify_aggregated(&pk, &swapped, 12, &context)
+            .is_err());
+        assert!(proof
+            .verify_aggregated(&pk, &ciphertexts[..2], 12, &context)
+            .is_err());
+        assert!(proof
+            .verify_aggregated(&pk, &ciphertexts, 11, &context)
+            .is_err());
+        assert_eq!(
+            proof.verify_aggregated(&pk, &[], 12, &context).unwrap_err(),
+            BulletproofError::SetupError
+        );
+
+        let (pk, witnesses, ciphertexts) =
+            ffdhe_ciphertexts(&[BigInt::from(5), BigInt::from(4096)]);
+        assert_eq!(
+            RangeProof::prove_aggregated(&pk, &witnesses, &ciphertexts, 12, &context).unwrap_err(),
+            BulletproofError::RangeProofError
+        );
+        assert_eq!(
+            RangeProof::prove_aggregated(&pk, &witnesses[..1], &ciphertexts, 12, &context)
+                .unwrap_err(),
+            BulletproofError::SetupError
+        );
+    }
+
+    #[test]
+    fn test_aggregated_range_proof_binds_c1() {
+        let context = ProofContext::default();
+        // c2 of the second ciphertext commits to 9 with randomness r, but c1 is not g^r so the
+        // ciphertext decrypts to another value
+        let (pk, witnesses, mut ciphertexts) =
+            ffdhe_ciphertexts(&[BigInt::from(7), BigInt::from(9)]);
+        ciphertexts[1].c1 = BigInt::mod_mul(&ciphertexts[1].c1, &pk.pp.g, &pk.pp.p);
+        let proof =
+            RangeProof::prove_aggregated(&pk, &witnesses, &ciphertexts, 8, &context).unwrap();
+        assert_eq!(
+            proof
+                .verify_aggregated(&pk, &ciphertexts, 8, &context)
+                .unwrap_err(),
+            BulletproofError::RangeProofError
+        );
+    }
+
+    #[test]
+    fn test_bulletproof_exact_range() {
+        let context = ProofContext::default();
+        let range = BigInt::from(1000);
+        for x in [BigInt::zero(), BigInt::from(999), BigInt::from(500)].iter() {
+            let (witness, statement) = ffdhe_statement(x, &range);
+            let proof = BulletproofRangeProof::prove(&witness, &statement, &context).unwrap();
+            assert!(proof.verify(&statement, &context).is_ok());
+            assert_eq!(proof.proof.inner_product_proof.L.len(), 5);
+        }
+
+        // 1000 <= x < 1024 passes the bound 2^10 but not the complement
+        let (witness, statement) = ffdhe_statement(&BigInt::from(1000), &range);
+        assert_eq!(
+            BulletproofRangeProof::prove(&witness, &statement, &context).unwrap_err(),
+            ProofError::BulletproofError(BulletproofError::RangeProofError)
+        );
+
+        // a power of two range needs no complement
+        let range = BigInt::one() << 16;
+        let (witness, statement) = ffdhe_statement(&(&range - BigInt::one()), &range);
+        let proof = BulletproofRangeProof::prove(&witness, &statement, &context).unwrap();
+        assert!(proof.verify(&statement, &context).is_ok());
+        assert_eq!(proof.proof.inner_product_proof.L.len(), 4);
+
+        // the proof for 2^16 does not pass for the range 2^16 - 1
+        let mut smaller_range = statement;
+        smaller_range.range = &range - BigInt::one();
+        assert!(proof.verify(&smaller_range, &context).is_err());
+    }
+}
@@ -13,12 +13,12 @@ Copyright information here.
 
 use crate::protocols::bulletproofs::Group;
 use crate::protocols::bulletproofs::Field;
+use crate::utlities::group_element::validate_group_elements;
+use crate::utlities::transcript::Transcript;
 use crate::BulletproofError::{self, InnerProductError};
 
 use curv::arithmetic::traits::Modulo;
 use curv::BigInt;
-use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
-use curv::cryptographic_primitives::hashing::traits::*;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InnerProductArg {
@@ -28,8 +28,18 @@ pub struct InnerProductArg {
     pub(super) b_tag: Field,
 }
 
+// the challenge of a round binds L, R and all previous rounds of the enclosing proof
+fn round_challenge(transcript: &mut Transcript, L: &Group, R: &Group, ux: &Group) -> BigInt {
+    transcript.append_bigint(b"L", &L.g);
+    transcript.append_bigint(b"R", &R.g);
+    transcript.append_bigint(b"ux", &ux.g);
+    transcript.challenge_zn(b"x", &ux.pp.q)
+}
+
 impl InnerProductArg {
+    #[allow(clippy::too_many_arguments)]
     pub fn prove(
+        transcript: &mut Transcript,
         G: &[Group],
         H: &[Group],
         ux: &Group,
@@ -41,6 +51,7 @@ impl InnerProductArg {
     ) -> InnerProductArg {
         let n = G.len();
         let order = a[0].pp.q.clone();
+        let modulus = a[0].pp.p.clone();
         let params = a[0].pp.clone();
 
         // All of the input vectors must have the same length.
@@ -85,7 +96,7 @@ impl InnerProductArg {
             R_vec.push(R.clone());
 
             // generate challenge
-            let x = HSha256::create_hash(&[&L.g, &R.g, &ux.g]);
+            let x = round_challenge(transcript, &L, &R, ux);
             let x_inv = BigInt::mod_inv(&x, &order);
         
             // update secret vectors for next round
@@ -110,25 +121,25 @@ impl InnerProductArg {
             // update generator vectors 
             let G_new = (0..n)
                 .map(|i| {
-                    let GLx_inv = BigInt::mod_pow(&G_L[i].g, &x_inv, &order);
-                    let GRx = BigInt::mod_pow(&G_R[i].g, &x, &order);
-                    let G_new_element = BigInt::mod_mul(&GRx, &GLx_inv, &order);
-                    println!("G[{}] = {}", i, G_new_element);
+                    let GLx_inv = BigInt::mod_pow(&G_L[i].g, &x_inv, &modulus);
+                    let GRx = BigInt::mod_pow(&G_R[i].g, &x, &modulus);
+                    let G_new_element = BigInt::mod_mul(&GRx, &GLx_inv, &modulus);
                     Group{ pp: params.clone(), g: G_new_element }
                 })
                 .collect::<Vec<Group>>();
             
             let H_new = (0..n)
                 .map(|i| {
-                    let HLx = BigInt::mod_pow(&H_L[i].g, &x, &order);
-                    let HRx_inv = BigInt::mod_pow(&H_R[i].g, &x_inv, &order);
-                    let H_new_element = BigInt::mod_mul(&HRx_inv, &HLx, &order);
-                    println!("H[{}] = {}", i, H_new_element);
+                    let HLx = BigInt::mod_pow(&H_L[i].g, &x, &modulus);
+                    let HRx_inv = BigInt::mod_pow(&H_R[i].g, &x_inv, &modulus);
+                    let H_new_element = BigInt::mod_mul(&HRx_inv, &HLx, &modulus);
                     Group{ pp: params.clone(), g: H_new_element }
                 })
                 .collect::<Vec<Group>>();
             
-            return InnerProductArg::prove(&G_new, &H_new, &ux, &P, &a_new, &b_new, L_vec, R_vec);
+            return InnerProductArg::prove(
+                transcript, &G_new, &H_new, &ux, &P, &a_new, &b_new, L_vec, R_vec,
+            );
         }
 
         InnerProductArg {
@@ -141,6 +152,7 @@ impl InnerProductArg {
 
     pub fn verify(
         &self, 
+        transcript: &mut Transcript,
         g_vec: &[Group],
         hi_tag: &[Group], 
         ux: &Group, 
@@ -151,19 +163,33 @@ impl InnerProductArg {
         let H = &hi_tag[..];
         let n = G.len();
         let order = ux.pp.q.clone();
+        let modulus = ux.pp.p.clone();
         let params = ux.pp.clone();
 
-        // All of the input vectors must have the same length.
-        assert_eq!(H.len(), n);
-        assert!(n.is_power_of_two());
+        // All of the input vectors must have the same length, the proof comes from the prover
+        // and has one round per halving of the vectors.
+        if H.len() != n
+            || !n.is_power_of_two()
+            || self.L.len() != self.R.len()
+            || n.trailing_zeros() as usize != self.L.len()
+            || !is_scalar(&self.a_tag, &order)
+            || !is_scalar(&self.b_tag, &order)
+        {
+            return Err(InnerProductError);
+        }
 
         if n != 1 {
             let n = n / 2;
             let (G_L, G_R) = G.split_at(n);
             let (H_L, H_R) = H.split_at(n);
+            validate_group_elements(&[&self.L[0].g, &self.R[0].g], &params)
+                .map_err(|_| InnerProductError)?;
 
             // generate challenge
-            let x = HSha256::create_hash(&[&self.L[0].g, &self.R[0].g, &ux.g]);
+            let x = round_challenge(transcript, &self.L[0], &self.R[0], ux);
+            if x == BigInt::zero() {
+                return Err(InnerProductError);
+            }
             let x_inv = BigInt::mod_inv(&x, &order);
             let x_sq = BigInt::mod_mul(&x, &x, &order);
             let x_inv_sq = BigInt::mod_mul(&x_inv, &x_inv, &order);
@@ -171,29 +197,27 @@ impl InnerProductArg {
             // update generator vectors 
             let G_new = (0..n)
                 .map(|i| {
-                    let GLx_inv = BigInt::mod_pow(&G_L[i].g, &x_inv, &order);
-                    let GRx = BigInt::mod_pow(&G_R[i].g, &x, &order);
-                    let G_new_element = BigInt::mod_mul(&GRx, &GLx_inv, &order);
-                    println!("G[{}] = {}", i, G_new_element);
+                    let GLx_inv = BigInt::mod_pow(&G_L[i].g, &x_inv, &modulus);
+                    let GRx = BigInt::mod_pow(&G_R[i].g, &x, &modulus);
+                    let G_new_element = BigInt::mod_mul(&GRx, &GLx_inv, &modulus);
                     Group{ pp: params.clone(), g: G_new_element }
                 })
                 .collect::<Vec<Group>>();
             
             let H_new = (0..n)
                 .map(|i| {
-                    let HLx = BigInt::mod_pow(&H_L[i].g, &x, &order);
-                    let HRx_inv = BigInt::mod_pow(&H_R[i].g, &x_inv, &order);
-                    let H_new_element = BigInt::mod_mul(&HRx_inv, &HLx, &order);
-                    println!("H[{}] = {}", i, H_new_element);
+                    let HLx = BigInt::mod_pow(&H_L[i].g, &x, &modulus);
+                    let HRx_inv = BigInt::mod_pow(&H_R[i].g, &x_inv, &modulus);
+                    let H_new_element = BigInt::mod_mul(&HRx_inv, &HLx, &modulus);
                     Group{ pp: params.clone(), g: H_new_element }
                 })
                 .collect::<Vec<Group>>();
                 
             // updating P
-            let Lx_sq = BigInt::mod_pow(&self.L[0].g, &x_sq, &order);
-            let Rx_inv_sq = BigInt::mod_pow(&self.R[0].g, &x_inv_sq, &order);
-            let Lx_Rx_inv = BigInt::mod_mul(&Lx_sq, &Rx_inv_sq, &order);
-            let P_new = Group{ pp: params.clone(), g: BigInt::mod_mul(&P.g, &Lx_Rx_inv, &order) };
+            let Lx_sq = BigInt::mod_pow(&self.L[0].g, &x_sq, &modulus);
+            let Rx_inv_sq = BigInt::mod_pow(&self.R[0].g, &x_inv_sq, &modulus);
+            let Lx_Rx_inv = BigInt::mod_mul(&Lx_sq, &Rx_inv_sq, &modulus);
+            let P_new = Group{ pp: params.clone(), g: BigInt::mod_mul(&P.g, &Lx_Rx_inv, &modulus) };
 
             // recursive computation
             let ip = InnerProductArg {
@@ -202,19 +226,16 @@ impl InnerProductArg {
                 a_tag: self.a_tag.clone(),
                 b_tag: self.b_tag.clone(),
             };
-            return ip.verify(&G_new, &H_new, ux, &P_new);
+            return ip.verify(transcript, &G_new, &H_new, ux, &P_new);
         }
 
         // final verification check
         let c = BigInt::mod_mul(&self.a_tag.x, &self.b_tag.x, &order);
-        let G_times_a = BigInt::mod_pow(&G[0].g, &self.a_tag.x, &order);
-        let H_times_b = BigInt::mod_pow(&H[0].g, &self.b_tag.x, &order);
-        let Ga_Hb = BigInt::mod_mul(&G_times_a, &H_times_b, &order);
-        let ux_c = BigInt::mod_pow(&ux.g, &c, &order);
-        let P_calc = BigInt::mod_mul(&Ga_Hb, &ux_c, &order);
-
-        // println!("c = {}\nGa = {}\nHb = {}", c, G_times_a, H_times_b);
-        // println!("P_ver = {}", P_calc);
+        let G_times_a = BigInt::mod_pow(&G[0].g, &self.a_tag.x, &modulus);
+        let H_times_b = BigInt::mod_pow(&H[0].g, &self.b_tag.x, &modulus);
+        let Ga_Hb = BigInt::mod_mul(&G_times_a, &H_times_b, &modulus);
+        let ux_c = BigInt::mod_pow(&ux.g, &c, &modulus);
+        let P_calc = BigInt::mod_mul(&Ga_Hb, &ux_c, &modulus);
 
         if P.g.clone() == P_calc {
             Ok(())
@@ -231,22 +252,34 @@ impl InnerProductArg {
     /// Uses a single multiexponentiation (multiscalar multiplication in additive notation)
     /// check to verify an inner product proof.
     ///
-    pub fn fast_verify(&self, g_vec: &[Group], hi_tag: &[Group], ux: &Group, P: &Group) -> Result<(), BulletproofError> {
+    pub fn fast_verify(
+        &self,
+        transcript: &mut Transcript,
+        g_vec: &[Group],
+        hi_tag: &[Group],
+        ux: &Group,
+        P: &Group,
+    ) -> Result<(), BulletproofError> {
         let G = &g_vec[..];
         let H = &hi_tag[..];
         let n = G.len();
         let order = ux.pp.q.clone();
-        // let params = ux.pp.clone();
-
-        // All of the input vectors must have the same length.
-        assert_eq!(H.len(), n);
-        assert!(n.is_power_of_two());
+        let modulus = ux.pp.p.clone();
 
+        // All of the input vectors must have the same length, the proof comes from the prover
+        // and has one round per halving of the vectors.
         let lg_n = self.L.len();
-        assert!(
-            lg_n <= 64,
-            "Not compatible for vector sizes greater than 2^64!"
-        );
+        if H.len() != n
+            || !n.is_power_of_two()
+            || self.R.len() != lg_n
+            || n.trailing_zeros() as usize != lg_n
+            || !is_scalar(&self.a_tag, &order)
+            || !is_scalar(&self.b_tag, &order)
+        {
+            return Err(InnerProductError);
+        }
+        let L_and_R = self.L.iter().chain(self.R.iter()).map(|X| &X.g).collect::<Vec<_>>();
+        validate_group_elements(&L_and_R, &ux.pp).map_err(|_| InnerProductError)?;
 
         let mut x_sq_vec: Vec<BigInt> = Vec::with_capacity(lg_n);
         let mut x_inv_sq_vec: Vec<BigInt> = Vec::with_capacity(lg_n);
@@ -254,7 +287,10 @@ impl InnerProductArg {
         let mut minus_x_inv_sq_vec: Vec<BigInt> = Vec::with_capacity(lg_n);
         let mut allinv = BigInt::one();
         for (Li, Ri) in self.L.iter().zip(self.R.iter()) {
-            let x = HSha256::create_hash(&[&Li.g, &Ri.g, &ux.g]);
+            let x = round_challenge(transcript, Li, Ri, ux);
+            if x == BigInt::zero() {
+                return Err(InnerProductError);
+            }
             let x_inv = BigInt::mod_inv(&x, &order);
             let x_sq = BigInt::mod_pow(&x, &BigInt::from(2), &order);
             let x_inv_sq = BigInt::mod_pow(&x_inv, &BigInt::from(2), &order);
@@ -275,7 +311,7 @@ impl InnerProductArg {
             // The challenges are stored in "creation order" as [x_k,...,x_1],
             // so u_{lg(i)+1} = is indexed by (lg_n-1) - lg_i
             let x_lg_i_sq = x_sq_vec[(lg_n - 1) - lg_i].clone();
-            s.push(s[i - k].clone() * x_lg_i_sq);
+            s.push(BigInt::mod_mul(&s[i - k], &x_lg_i_sq, &order));
         }
 
         let a_times_s: Vec<BigInt> = (0..n)
@@ -306,8 +342,8 @@ impl InnerProductArg {
 
         let tot_len = points.len();
         let expect_P = (0..tot_len)
-            .map(|i|  BigInt::mod_pow(&points[i].g, &scalars[i], &order))
-            .fold(BigInt::one(), |acc, x| BigInt::mod_mul(&acc, &x, &order));
+            .map(|i|  BigInt::mod_pow(&points[i].g, &scalars[i], &modulus))
+            .fold(BigInt::one(), |acc, x| BigInt::mod_mul(&acc, &x, &modulus));
 
         if P.g == expect_P {
             Ok(())
@@ -339,38 +375,38 @@ pub fn multiexponentiation(elements: &[Group], scalars: &[Field]) -> Group {
         "multiexponentiation(a,g): lengths of vectors do not match"
     );
     let out = BigInt::one();
-    let order = scalars[0].pp.q.clone();
+    let modulus = elements[0].pp.p.clone();
     let out = elements.iter().zip(scalars).fold(out, |acc, var| {
-        let temp = BigInt::mod_pow(&(var.0).g, &(var.1).x, &order);
-        BigInt::mod_mul(&acc, &temp, &order)
+        let temp = BigInt::mod_pow(&(var.0).g, &(var.1).x, &modulus);
+        BigInt::mod_mul(&acc, &temp, &modulus)
     });
-    return Group{ pp: scalars[0].pp.clone(), g: out };
+    return Group{ pp: elements[0].pp.clone(), g: out };
+}
+
+// scalars of a proof come from the prover and are used as exponents
+pub(super) fn is_scalar(a: &Field, order: &BigInt) -> bool {
+    a.x >= BigInt::zero() && &a.x < order
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::inner_product::*;
+    use crate::utlities::transcript::ProofContext;
     use curv::arithmetic::traits::Samplable;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalPP;
 
     fn test_helper(n: usize) {
         let params = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
-        let order = params.q.clone();
+        let modulus = params.p.clone();
+        let random_element = || {
+            let g = BigInt::mod_pow(&params.g, &BigInt::sample_below(&params.q), &modulus);
+            Group { pp: params.clone(), g }
+        };
 
-        let g_vec = (0..n)
-            .map(|_| {
-                Group { pp: params.clone(), g: BigInt::sample_below(&params.q)}
-            })
-            .collect::<Vec<Group>>();
-        
-        let h_vec = (0..n)
-            .map(|_| {
-                Group { pp: params.clone(), g: BigInt::sample_below(&params.q)}
-            })
-            .collect::<Vec<Group>>();
-
-        let Gx = Group{ pp: params.clone(), g: BigInt::sample_below(&params.q) };
+        let g_vec = (0..n).map(|_| random_element()).collect::<Vec<Group>>();
+        let h_vec = (0..n).map(|_| random_element()).collect::<Vec<Group>>();
+        let Gx = random_element();
 
         let a: Vec<_> = (0..n)
             .map(|_| {
@@ -389,26 +425,77 @@ mod tests {
         let y = Field { pp: params.clone(), x: BigInt::sample_below(&params.q)};
         let y_vec = (0..n).map(|_| y.clone()).collect::<Vec<Field>>();
         let hi_tag = (0..n).map(|i| {
-                let hi_yi = BigInt::mod_mul(&h_vec[i].g, &y_vec[i].x, &order);
+                let hi_yi = BigInt::mod_pow(&h_vec[i].g, &y_vec[i].x, &modulus);
                 Group{ pp: params.clone(), g: hi_yi}
             })
             .collect::<Vec<Group>>();
 
         // compute pedersen vector commitment P
-        let ux_c = BigInt::mod_pow(&Gx.g, &c.x, &order);
+        let ux_c = BigInt::mod_pow(&Gx.g, &c.x, &modulus);
         let G_pow_a = multiexponentiation(&g_vec, &a);
         let H_pow_b = multiexponentiation(&hi_tag, &b);
-        let Ga_Hb = BigInt::mod_mul(&G_pow_a.g, &H_pow_b.g, &order);
-        let P = Group { pp: params.clone(), g: BigInt::mod_mul(&Ga_Hb, &ux_c, &order)};
-
-        // println!("c = {}\nGa = {}\nHb = {}", c.x, G_pow_a.g, H_pow_b.g);
-        // println!("P_test = {}", P.g);
+        let Ga_Hb = BigInt::mod_mul(&G_pow_a.g, &H_pow_b.g, &modulus);
+        let P = Group { pp: params.clone(), g: BigInt::mod_mul(&Ga_Hb, &ux_c, &modulus)};
 
         let L_vec = Vec::with_capacity(n);
         let R_vec = Vec::with_capacity(n);
-        let ipp = InnerProductArg::prove(&g_vec, &hi_tag, &Gx, &P, &a, &b, L_vec, R_vec);
-        let verifier = ipp.fast_verify(&g_vec, &hi_tag, &Gx, &P);
-        assert!(verifier.is_ok())
+        let transcript = || Transcript::new(b"test", &ProofContext::default());
+        let ipp =
+            InnerProductArg::prove(&mut transcript(), &g_vec, &hi_tag, &Gx, &P, &a, &b, L_vec, R_vec);
+        assert!(ipp.verify(&mut transcript(), &g_vec, &hi_tag, &Gx, &P).is_ok());
+        assert!(ipp.fast_verify(&mut transcript(), &g_vec, &hi_tag, &Gx, &P).is_ok());
+
+        // a commitment to other vectors
+        let P_bad = Group { pp: params.clone(), g: BigInt::mod_mul(&P.g, &params.g, &modulus)};
+        assert!(ipp.verify(&mut transcript(), &g_vec, &hi_tag, &Gx, &P_bad).is_err());
+        assert!(ipp.fast_verify(&mut transcript(), &g_vec, &hi_tag, &Gx, &P_bad).is_err());
+
+        // the rounds are bound to the transcript of the enclosing proof
+        if n > 1 {
+            let mut other = Transcript::new(b"other", &ProofContext::default());
+            assert!(ipp.verify(&mut other, &g_vec, &hi_tag, &Gx, &P).is_err());
+        }
+    }
+
+    #[test]
+    fn test_malformed_inner_product_arg() {
+        let n = 8;
+        let params = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let g_vec = (0..n)
+            .map(|i| Group { pp: params.clone(), g: BigInt::mod_pow(&params.g, &BigInt::from(i as u32 + 2), &params.p)})
+            .collect::<Vec<Group>>();
+        let a = (0..n)
+            .map(|_| Field { pp: params.clone(), x: BigInt::sample_below(&params.q)})
+            .collect::<Vec<Field>>();
+        let c = scalar_inner_product(&a, &a);
+        let Gx = Group { pp: params.clone(), g: params.g.clone() };
+        let P = multiexponentiation(
+            &[&g_vec[..], &g_vec[..], &[Gx.clone()][..]].concat(),
+            &[&a[..], &a[..], &[c][..]].concat(),
+        );
+        let transcript = || Transcript::new(b"test", &ProofContext::default());
+        let ipp = InnerProductArg::prove(
+            &mut transcript(), &g_vec, &g_vec, &Gx, &P, &a, &a, Vec::new(), Vec::new(),
+        );
+        assert!(ipp.fast_verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_ok());
+
+        // a missing round
+        let mut short = ipp.clone();
+        short.L.pop();
+        short.R.pop();
+        assert!(short.verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_err());
+        assert!(short.fast_verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_err());
+
+        // an element of order 2
+        let mut small_order = ipp.clone();
+        small_order.L[0].g = &params.p - BigInt::one();
+        assert!(small_order.verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_err());
+        assert!(small_order.fast_verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_err());
+
+        let mut negative = ipp;
+        negative.a_tag.x = BigInt::zero() - &negative.a_tag.x;
+        assert!(negative.verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_err());
+        assert!(negative.fast_verify(&mut transcript(), &g_vec, &g_vec, &Gx, &P).is_err());
     }
 
     #[test]
@@ -446,7 +533,7 @@ mod tests {
         G.push(Group{ pp: params.clone(), g: BigInt::from(2)});
         G.push(Group{ pp: params.clone(), g: BigInt::from(9)});
 
-        let expected = BigInt::mod_mul(&BigInt::from(1451188224), &BigInt::one(), &params.q);
+        let expected = BigInt::mod_mul(&BigInt::from(1451188224), &BigInt::one(), &params.p);
         
         assert_eq!(expected, multiexponentiation(&G, &a).g)
     }
@@ -3,6 +3,7 @@ use elgamal::ElGamalPP;
 
 pub mod bulletproof;
 pub mod inner_product;
+pub mod r1cs;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Group {
@@ -0,0 +1,904 @@
+// based on the paper: https://eprint.iacr.org/2017/1066.pdf
+
+#![allow(non_snake_case)]
+
+use crate::protocols::bulletproofs::bulletproof::append_statement;
+use crate::protocols::bulletproofs::bulletproof::hash_to_group;
+use crate::protocols::bulletproofs::bulletproof::inner_product;
+use crate::protocols::bulletproofs::bulletproof::powers;
+use crate::protocols::bulletproofs::bulletproof::prove_opening;
+use crate::protocols::bulletproofs::bulletproof::validate_statement;
+use crate::protocols::bulletproofs::bulletproof::verify_opening;
+use crate::protocols::bulletproofs::inner_product::is_scalar;
+use crate::protocols::bulletproofs::inner_product::multiexponentiation;
+use crate::protocols::bulletproofs::inner_product::InnerProductArg;
+use crate::protocols::bulletproofs::Field;
+use crate::protocols::bulletproofs::Group;
+use crate::utlities::elgamal_enc_proof::HomoELGamalProof;
+use crate::utlities::group_element::validate_group_elements;
+use crate::utlities::tight_range_proof::Witness;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::BulletproofError::{self, InnerProductError, R1CSError, SetupError};
+use curv::arithmetic::traits::{Modulo, Samplable, ZeroizeBN};
+use curv::BigInt;
+use elgamal::{ElGamalCiphertext, ElGamalPP, ElGamalPublicKey};
+use zeroize::Zeroize;
+
+/// A variable of a constraint system
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Variable {
+    /// the constant 1
+    One,
+    /// the plaintext of ciphertext j of the statement
+    Committed(usize),
+    /// left input, right input and output of multiplication gate i
+    MultiplierLeft(usize),
+    MultiplierRight(usize),
+    MultiplierOutput(usize),
+}
+
+/// sum of coefficient * variable, the coefficients are taken modulo q
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinearCombination {
+    terms: Vec<(Variable, BigInt)>,
+}
+
+impl LinearCombination {
+    pub fn new() -> Self {
+        LinearCombination::default()
+    }
+
+    pub fn add_term(mut self, variable: Variable, coefficient: BigInt) -> Self {
+        self.terms.push((variable, coefficient));
+        self
+    }
+}
+
+impl From<Variable> for LinearCombination {
+    fn from(variable: Variable) -> Self {
+        LinearCombination::new().add_term(variable, BigInt::one())
+    }
+}
+
+// values of the variables, known to the prover only
+struct Assignment {
+    committed: Vec<BigInt>,
+    a_L: Vec<BigInt>,
+    a_R: Vec<BigInt>,
+    a_O: Vec<BigInt>,
+    // a gate was allocated without values
+    incomplete: bool,
+}
+
+impl Zeroize for Assignment {
+    fn zeroize(&mut self) {
+        for value in self
+            .committed
+            .iter_mut()
+            .chain(self.a_L.iter_mut())
+            .chain(self.a_R.iter_mut())
+            .chain(self.a_O.iter_mut())
+        {
+            value.zeroize_bn();
+        }
+    }
+}
+
+impl Drop for Assignment {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Builder of an arithmetic circuit over Z_q (section 5 of the paper): multiplication gates
+/// a_L[i] * a_R[i] = a_O[i] and linear constraints sum c * v = 0 over the variables of the gates,
+/// the plaintexts of the ciphertexts of the statement and the constant 1.
+/// Prover and verifier build the same system, e.g. with one gadget function taking the secret
+/// values as Option: the system of the prover (`with_values`) also evaluates the gates.
+/// A relation over the integers, like p = 3 mod 4, needs its variables bounded by the circuit
+/// (e.g. decomposed into bits), otherwise it holds modulo q only.
+pub struct ConstraintSystem {
+    q: BigInt,
+    num_committed: usize,
+    num_multipliers: usize,
+    constraints: Vec<LinearCombination>,
+    assignment: Option<Assignment>,
+}
+
+// z-weighted sums of the constraints: with w_V and w_c moved to the other side,
+// sum z^(k+1) * constraint_k = <w_L, a_L> + <w_R, a_R> + <w_O, a_O> - <w_V, v> - w_c
+struct Weights {
+    w_L: Vec<BigInt>,
+    w_R: Vec<BigInt>,
+    w_O: Vec<BigInt>,
+    w_V: Vec<BigInt>,
+    w_c: BigInt,
+}
+
+impl ConstraintSystem {
+    /// System of the verifier, over num_committed ciphertexts under pk
+    pub fn new(pk: &ElGamalPublicKey, num_committed: usize) -> Self {
+        ConstraintSystem {
+            q: pk.pp.q.clone(),
+            num_committed,
+            num_multipliers: 0,
+            constraints: Vec::new(),
+            assignment: None,
+        }
+    }
+
+    /// System of the prover, values are the plaintexts of the ciphertexts
+    pub fn with_values(pk: &ElGamalPublicKey, values: &[BigInt]) -> Self {
+        ConstraintSystem {
+            q: pk.pp.q.clone(),
+            num_committed: values.len(),
+            num_multipliers: 0,
+            constraints: Vec::new(),
+            assignment: Some(Assignment {
+                committed: values.iter().map(|v| v.mod_floor(&pk.pp.q)).collect(),
+                a_L: Vec::new(),
+                a_R: Vec::new(),
+                a_O: Vec::new(),
+                incomplete: false,
+            }),
+        }
+    }
+
+    pub fn committed_variables(&self) -> Vec<Variable> {
+        (0..self.num_committed).map(Variable::Committed).collect()
+    }
+
+    /// Adds a gate with inputs left and right, returns its left, right and output variables
+    pub fn multiply(
+        &mut self,
+        left: LinearCombination,
+        right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        let values = match (self.evaluate(&left), self.evaluate(&right)) {
+            (Some(l), Some(r)) => Some((l, r)),
+            _ => None,
+        };
+        let (l, r, o) = self.allocate_multiplier(values);
+        self.constrain(left.add_term(l, BigInt::from(-1)));
+        self.constrain(right.add_term(r, BigInt::from(-1)));
+        (l, r, o)
+    }
+
+    /// Adds a gate with free inputs, e.g. a bit or a quotient known to the prover only. The
+    /// prover passes the values of the inputs, the verifier None
+    pub fn allocate_multiplier(
+        &mut self,
+        values: Option<(BigInt, BigInt)>,
+    ) -> (Variable, Variable, Variable) {
+        let i = self.num_multipliers;
+        self.num_multipliers += 1;
+        let q = &self.q;
+        if let Some(assignment) = self.assignment.as_mut() {
+            let (l, r) = match values {
+                Some((l, r)) => (l.mod_floor(q), r.mod_floor(q)),
+                None => {
+                    assignment.incomplete = true;
+                    (BigInt::zero(), BigInt::zero())
+                }
+            };
+            assignment.a_O.push(BigInt::mod_mul(&l, &r, q));
+            assignment.a_L.push(l);
+            assignment.a_R.push(r);
+        }
+        (
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        )
+    }
+
+    /// Adds the constraint lc = 0
+    pub fn constrain(&mut self, lc: LinearCombination) {
+        self.constraints.push(lc);
+    }
+
+    /// Value of lc for the prover, None for the verifier or if lc has an unknown variable
+    pub fn evaluate(&self, lc: &LinearCombination) -> Option<BigInt> {
+        let assignment = self.assignment.as_ref()?;
+        let one = BigInt::one();
+        lc.terms
+            .iter()
+            .try_fold(BigInt::zero(), |acc, (variable, coefficient)| {
+                let value = match *variable {
+                    Variable::One => &one,
+                    Variable::Committed(j) => assignment.committed.get(j)?,
+                    Variable::MultiplierLeft(i) => assignment.a_L.get(i)?,
+                    Variable::MultiplierRight(i) => assignment.a_R.get(i)?,
+                    Variable::MultiplierOutput(i) => assignment.a_O.get(i)?,
+                };
+                let term = BigInt::mod_mul(value, &coefficient.mod_floor(&self.q), &self.q);
+                Some(BigInt::mod_add(&acc, &term, &self.q))
+            })
+    }
+
+    // number of gates rounded up to a power of two, the length of the vectors of the proof
+    fn padded_multipliers(&self) -> usize {
+        self.num_multipliers.next_power_of_two()
+    }
+
+    fn weights(&self, z: &BigInt) -> Result<Weights, BulletproofError> {
+        let q = &self.q;
+        let n = self.padded_multipliers();
+        let mut weights = Weights {
+            w_L: vec![BigInt::zero(); n],
+            w_R: vec![BigInt::zero(); n],
+            w_O: vec![BigInt::zero(); n],
+            w_V: vec![BigInt::zero(); self.num_committed],
+            w_c: BigInt::zero(),
+        };
+        let mut z_k = BigInt::one();
+        for constraint in &self.constraints {
+            z_k = BigInt::mod_mul(&z_k, z, q);
+            for (variable, coefficient) in &constraint.terms {
+                let weight = BigInt::mod_mul(&z_k, &coefficient.mod_floor(q), q);
+                let (entry, negate) = match *variable {
+                    Variable::One => (&mut weights.w_c, true),
+                    Variable::Committed(j) if j < self.num_committed => (&mut weights.w_V[j], true),
+                    Variable::MultiplierLeft(i) if i < self.num_multipliers => {
+                        (&mut weights.w_L[i], false)
+                    }
+                    Variable::MultiplierRight(i) if i < self.num_multipliers => {
+                        (&mut weights.w_R[i], false)
+                    }
+                    Variable::MultiplierOutput(i) if i < self.num_multipliers => {
+                        (&mut weights.w_O[i], false)
+                    }
+                    _ => return Err(SetupError),
+                };
+                let updated = if negate {
+                    BigInt::mod_sub(entry, &weight, q)
+                } else {
+                    BigInt::mod_add(entry, &weight, q)
+                };
+                *entry = updated;
+            }
+        }
+        Ok(weights)
+    }
+
+    // the sizes and every term of every constraint, so that a proof is bound to its circuit
+    fn to_hash_input(&self) -> Vec<BigInt> {
+        let mut input = vec![
+            BigInt::from(self.num_committed as u64),
+            BigInt::from(self.num_multipliers as u64),
+            BigInt::from(self.constraints.len() as u64),
+        ];
+        for constraint in &self.constraints {
+            input.push(BigInt::from(constraint.terms.len() as u64));
+            for (variable, coefficient) in &constraint.terms {
+                let (tag, index) = match *variable {
+                    Variable::One => (0, 0),
+                    Variable::Committed(j) => (1, j),
+                    Variable::MultiplierLeft(i) => (2, i),
+                    Variable::MultiplierRight(i) => (3, i),
+                    Variable::MultiplierOutput(i) => (4, i),
+                };
+                input.push(BigInt::from(tag as u64));
+                input.push(BigInt::from(index as u64));
+                input.push(coefficient.mod_floor(&self.q));
+            }
+        }
+        input
+    }
+}
+
+/// Arithmetic circuit proof (section 5.3 of the paper) that the plaintexts of ciphertexts of
+/// exponent ElGamal satisfy a constraint system. As in the range proof (see bulletproof.rs) the
+/// commitments are V_j = c2_j, tied to c1_j by a proof of knowledge of the plaintext and
+/// randomness of sum lambda^j * c_j.
+/// The prover commits to the gates in A_I = h^alpha * g_vec^a_L * h_vec^a_R and
+/// A_O = h^beta * g_vec^a_O, and to blinding vectors in S. After the challenges y and z,
+/// with w_L, w_R, w_O, w_V, w_c the constraints weighted by z, z^2, ...:
+/// l(X) = (a_L + y^-n o w_R) * X + a_O * X^2 + s_L * X^3
+/// r(X) = y^n o a_R * X - y^n + w_L * X + w_O + y^n o s_R * X^3
+/// and the coefficient t2 of t(X) = <l(X), r(X)> is <w_V, v> + w_c + <y^-n o w_R, w_L> if all
+/// constraints hold. The prover commits to the other coefficients in T_1, T_3, ..., T_6, and
+/// proves t(x) and <l(x), r(x)> = t(x) for the challenge x with the inner product argument.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct R1CSProof {
+    pub(super) A_I: Group,
+    pub(super) A_O: Group,
+    pub(super) S: Group,
+    pub(super) T_1: Group,
+    pub(super) T_3: Group,
+    pub(super) T_4: Group,
+    pub(super) T_5: Group,
+    pub(super) T_6: Group,
+    pub(super) tau_x: Field,
+    pub(super) miu: Field,
+    pub(super) tx: Field,
+    pub(super) inner_product_proof: InnerProductArg,
+    /// knowledge of the plaintext and randomness of sum lambda^j * c_j
+    pub(super) pi_enc: HomoELGamalProof,
+}
+
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/bulletproof-r1cs-proof";
+
+// the transcript of a proof starts with the statement and the circuit
+fn statement_transcript(
+    cs: &ConstraintSystem,
+    pk: &ElGamalPublicKey,
+    ciphertexts: &[ElGamalCiphertext],
+    context: &ProofContext,
+) -> Transcript {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    append_statement(&mut transcript, pk, ciphertexts);
+    transcript.append_bigints(b"circuit", &cs.to_hash_input());
+    transcript
+}
+
+// appends the messages of a round and draws its challenge
+fn challenge(
+    transcript: &mut Transcript,
+    messages: &[&BigInt],
+    label: &'static [u8],
+    q: &BigInt,
+) -> BigInt {
+    for message in messages {
+        transcript.append_bigint(b"message", message);
+    }
+    transcript.challenge_zn(label, q)
+}
+
+fn generators(pp: &ElGamalPP, n: usize) -> (Vec<Group>, Vec<Group>, Group) {
+    (
+        (0..n).map(|i| hash_to_group(pp, 0, i)).collect(),
+        (0..n).map(|i| hash_to_group(pp, 1, i)).collect(),
+        hash_to_group(pp, 2, 0),
+    )
+}
+
+fn group(pp: &ElGamalPP, g: BigInt) -> Group {
+    Group { pp: pp.clone(), g }
+}
+
+fn field(pp: &ElGamalPP, x: BigInt) -> Field {
+    Field { pp: pp.clone(), x }
+}
+
+// h_vec[i]^(y^-i)
+fn h_tag(pp: &ElGamalPP, h_vec: &[Group], y_inv_pow: &[BigInt]) -> Vec<Group> {
+    h_vec
+        .iter()
+        .zip(y_inv_pow)
+        .map(|(h_i, y_i)| group(pp, BigInt::mod_pow(&h_i.g, y_i, &pp.p)))
+        .collect()
+}
+
+impl R1CSProof {
+    /// witnesses[j] is the plaintext and randomness of ciphertexts[j], cs the system of the
+    /// prover for these plaintexts
+    pub fn prove(
+        cs: &ConstraintSystem,
+        pk: &ElGamalPublicKey,
+        witnesses: &[Witness],
+        ciphertexts: &[ElGamalCiphertext],
+        context: &ProofContext,
+    ) -> Result<Self, BulletproofError> {
+        let pp = &pk.pp;
+        let (p, q) = (&pp.p, &pp.q);
+        let assignment = cs.assignment.as_ref().ok_or(SetupError)?;
+        if assignment.incomplete
+            || cs.num_committed == 0
+            || witnesses.len() != cs.num_committed
+            || ciphertexts.len() != cs.num_committed
+            || witnesses
+                .iter()
+                .zip(&assignment.committed)
+                .any(|(w, v)| &w.x.mod_floor(q) != v)
+        {
+            return Err(SetupError);
+        }
+        validate_statement(pk, ciphertexts)?;
+        if cs
+            .constraints
+            .iter()
+            .any(|constraint| cs.evaluate(constraint) != Some(BigInt::zero()))
+        {
+            return Err(R1CSError);
+        }
+
+        let n = cs.padded_multipliers();
+        let (g_vec, h_vec, u) = generators(pp, n);
+        let pi_enc = prove_opening(pk, witnesses, ciphertexts, context).map_err(|_| R1CSError)?;
+
+        let padded = |v: &[BigInt]| {
+            let mut v = v.to_vec();
+            v.resize(n, BigInt::zero());
+            v
+        };
+        let mut a_L = padded(&assignment.a_L);
+        let mut a_R = padded(&assignment.a_R);
+        let mut a_O = padded(&assignment.a_O);
+        let mut s_L = (0..n).map(|_| BigInt::sample_below(q)).collect::<Vec<_>>();
+        let mut s_R = (0..n).map(|_| BigInt::sample_below(q)).collect::<Vec<_>>();
+        let mut alpha = BigInt::sample_below(q);
+        let mut beta = BigInt::sample_below(q);
+        let mut rho = BigInt::sample_below(q);
+
+        let to_field = |v: &[BigInt]| v.iter().map(|x| field(pp, x.clone())).collect::<Vec<_>>();
+        let g_h_vec = [&g_vec[..], &h_vec[..]].concat();
+        let blinded = |blinding: &BigInt, elements: &[Group], exponents: &[BigInt]| {
+            BigInt::mod_mul(
+                &BigInt::mod_pow(&pk.h, blinding, p),
+                &multiexponentiation(elements, &to_field(exponents)).g,
+                p,
+            )
+        };
+        let A_I = blinded(&alpha, &g_h_vec, &[&a_L[..], &a_R[..]].concat());
+        let A_O = blinded(&beta, &g_vec, &a_O);
+        let S = blinded(&rho, &g_h_vec, &[&s_L[..], &s_R[..]].concat());
+
+        let mut transcript = statement_transcript(cs, pk, ciphertexts, context);
+        let y = challenge(&mut transcript, &[&A_I, &A_O, &S], b"y", q);
+        let z = challenge(&mut transcript, &[], b"z", q);
+        if y == BigInt::zero() {
+            return Err(R1CSError);
+        }
+        let weights = cs.weights(&z)?;
+        let y_pow = powers(&y, n, q);
+        let y_inv_pow = powers(&BigInt::mod_inv(&y, q), n, q);
+
+        // l(X) = l_1 * X + l_2 * X^2 + l_3 * X^3 and r(X) = r_0 + r_1 * X + r_3 * X^3
+        let l_1 = (0..n)
+            .map(|i| {
+                let w_R_i = BigInt::mod_mul(&y_inv_pow[i], &weights.w_R[i], q);
+                BigInt::mod_add(&a_L[i], &w_R_i, q)
+            })
+            .collect::<Vec<_>>();
+        let r_0 = (0..n)
+            .map(|i| BigInt::mod_sub(&weights.w_O[i], &y_pow[i], q))
+            .collect::<Vec<_>>();
+        let r_1 = (0..n)
+            .map(|i| {
+                let a_R_i = BigInt::mod_mul(&y_pow[i], &a_R[i], q);
+                BigInt::mod_add(&a_R_i, &weights.w_L[i], q)
+            })
+            .collect::<Vec<_>>();
+        let r_3 = (0..n)
+            .map(|i| BigInt::mod_mul(&y_pow[i], &s_R[i], q))
+            .collect::<Vec<_>>();
+        let l_poly = [None, Some(&l_1), Some(&a_O), Some(&s_L)];
+        let r_poly = [Some(&r_0), Some(&r_1), None, Some(&r_3)];
+
+        // t(X) = <l(X), r(X)> = t_1 * X + ... + t_6 * X^6
+        let mut t = vec![BigInt::zero(); 7];
+        for (i, l_i) in l_poly.iter().enumerate() {
+            for (j, r_j) in r_poly.iter().enumerate() {
+                if let (Some(l_i), Some(r_j)) = (l_i, r_j) {
+                    t[i + j] = BigInt::mod_add(&t[i + j], &inner_product(l_i, r_j, q), q);
+                }
+            }
+        }
+        // tau[2] stays 0, t_2 is committed in the V_j
+        let mut tau = (0..7)
+            .map(|k| match k {
+                1 | 3 | 4 | 5 | 6 => BigInt::sample_below(q),
+                _ => BigInt::zero(),
+            })
+            .collect::<Vec<_>>();
+        let T = (0..7)
+            .map(|k| {
+                BigInt::mod_mul(
+                    &BigInt::mod_pow(&pp.g, &t[k], p),
+                    &BigInt::mod_pow(&pk.h, &tau[k], p),
+                    p,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let x = challenge(
+            &mut transcript,
+            &[&T[1], &T[3], &T[4], &T[5], &T[6]],
+            b"x",
+            q,
+        );
+        let x_pow = powers(&x, 7, q);
+        let evaluate = |poly: &[Option<&Vec<BigInt>>]| {
+            (0..n)
+                .map(|i| {
+                    poly.iter()
+                        .enumerate()
+                        .filter_map(|(k, coefficient)| coefficient.map(|c| (k, c)))
+                        .fold(BigInt::zero(), |acc, (k, c)| {
+                            BigInt::mod_add(&acc, &BigInt::mod_mul(&c[i], &x_pow[k], q), q)
+                        })
+                })
+                .collect::<Vec<_>>()
+        };
+        let l = evaluate(&l_poly);
+        let r = evaluate(&r_poly);
+        let tx = inner_product(&l, &r, q);
+        // tau_x = sum tau_k * x^k + x^2 * <w_V, gamma>
+        let w_V_gamma = weights
+            .w_V
+            .iter()
+            .zip(witnesses)
+            .fold(BigInt::zero(), |acc, (w_V_j, w)| {
+                BigInt::mod_add(&acc, &BigInt::mod_mul(w_V_j, &w.r, q), q)
+            });
+        let tau_x = (0..7).fold(BigInt::mod_mul(&x_pow[2], &w_V_gamma, q), |acc, k| {
+            BigInt::mod_add(&acc, &BigInt::mod_mul(&tau[k], &x_pow[k], q), q)
+        });
+        let miu = [&alpha, &beta, &rho]
+            .iter()
+            .zip(&x_pow[1..4])
+            .fold(BigInt::zero(), |acc, (blinding, x_k)| {
+                BigInt::mod_add(&acc, &BigInt::mod_mul(blinding, x_k, q), q)
+            });
+
+        // P = g_vec^l * h_tag^r * ux^tx, the verifier gets it from the commitments
+        let w = challenge(&mut transcript, &[&tau_x, &miu, &tx], b"w", q);
+        let ux = group(pp, BigInt::mod_pow(&u.g, &w, p));
+        let h_tag = h_tag(pp, &h_vec, &y_inv_pow);
+        let l = to_field(&l);
+        let r = to_field(&r);
+        let P = multiexponentiation(
+            &[&g_vec[..], &h_tag[..], &[ux.clone()][..]].concat(),
+            &[&l[..], &r[..], &[field(pp, tx.clone())][..]].concat(),
+        );
+        let inner_product_proof = InnerProductArg::prove(
+            &mut transcript,
+            &g_vec,
+            &h_tag,
+            &ux,
+            &P,
+            &l,
+            &r,
+            Vec::with_capacity(n),
+            Vec::with_capacity(n),
+        );
+
+        for secret in a_L
+            .iter_mut()
+            .chain(a_R.iter_mut())
+            .chain(a_O.iter_mut())
+            .chain(s_L.iter_mut())
+            .chain(s_R.iter_mut())
+            .chain(tau.iter_mut())
+        {
+            secret.zeroize_bn();
+        }
+        alpha.zeroize_bn();
+        beta.zeroize_bn();
+        rho.zeroize_bn();
+
+        Ok(R1CSProof {
+            A_I: group(pp, A_I),
+            A_O: group(pp, A_O),
+            S: group(pp, S),
+            T_1: group(pp, T[1].clone()),
+            T_3: group(pp, T[3].clone()),
+            T_4: group(pp, T[4].clone()),
+            T_5: group(pp, T[5].clone()),
+            T_6: group(pp, T[6].clone()),
+            tau_x: field(pp, tau_x),
+            miu: field(pp, miu),
+            tx: field(pp, tx),
+            inner_product_proof,
+            pi_enc,
+        })
+    }
+
+    /// cs is the system of the verifier, built as the prover's but without values
+    pub fn verify(
+        &self,
+        cs: &ConstraintSystem,
+        pk: &ElGamalPublicKey,
+        ciphertexts: &[ElGamalCiphertext],
+        context: &ProofContext,
+    ) -> Result<(), BulletproofError> {
+        let pp = &pk.pp;
+        let (p, q) = (&pp.p, &pp.q);
+        if cs.num_committed == 0 || ciphertexts.len() != cs.num_committed {
+            return Err(SetupError);
+        }
+        validate_statement(pk, ciphertexts)?;
+        let T = [
+            &self.T_1.g,
+            &self.T_3.g,
+            &self.T_4.g,
+            &self.T_5.g,
+            &self.T_6.g,
+        ];
+        let mut elements = vec![&self.A_I.g, &self.A_O.g, &self.S.g];
+        elements.extend_from_slice(&T);
+        validate_group_elements(&elements, pp).map_err(|_| R1CSError)?;
+        if !is_scalar(&self.tau_x, q) || !is_scalar(&self.miu, q) || !is_scalar(&self.tx, q) {
+            return Err(R1CSError);
+        }
+        verify_opening(&self.pi_enc, pk, ciphertexts, context).map_err(|_| R1CSError)?;
+
+        let mut transcript = statement_transcript(cs, pk, ciphertexts, context);
+        let y = challenge(
+            &mut transcript,
+            &[&self.A_I.g, &self.A_O.g, &self.S.g],
+            b"y",
+            q,
+        );
+        let z = challenge(&mut transcript, &[], b"z", q);
+        if y == BigInt::zero() {
+            return Err(R1CSError);
+        }
+        let weights = cs.weights(&z)?;
+        let n = cs.padded_multipliers();
+        let (g_vec, h_vec, u) = generators(pp, n);
+        let x = challenge(&mut transcript, &T, b"x", q);
+        let w = challenge(
+            &mut transcript,
+            &[&self.tau_x.x, &self.miu.x, &self.tx.x],
+            b"w",
+            q,
+        );
+        let x_pow = powers(&x, 7, q);
+        let y_pow = powers(&y, n, q);
+        let y_inv_pow = powers(&BigInt::mod_inv(&y, q), n, q);
+        let w_R_y_inv = (0..n)
+            .map(|i| BigInt::mod_mul(&y_inv_pow[i], &weights.w_R[i], q))
+            .collect::<Vec<_>>();
+
+        // g^tx * h^tau_x = g^(x^2 * (delta + w_c)) * prod V_j^(x^2 * w_V[j]) * T_1^x *
+        // prod_{k >= 3} T_k^(x^k) for V_j = c2_j and delta = <y^-n o w_R, w_L>
+        let delta = inner_product(&w_R_y_inv, &weights.w_L, q);
+        let lhs = BigInt::mod_mul(
+            &BigInt::mod_pow(&pp.g, &self.tx.x, p),
+            &BigInt::mod_pow(&pk.h, &self.tau_x.x, p),
+            p,
+        );
+        let g_exponent = BigInt::mod_mul(&x_pow[2], &BigInt::mod_add(&delta, &weights.w_c, q), q);
+        let rhs = ciphertexts
+            .iter()
+            .zip(&weights.w_V)
+            .map(|(c, w_V_j)| BigInt::mod_pow(&c.c2, &BigInt::mod_mul(&x_pow[2], w_V_j, q), p))
+            .chain(
+                T.iter()
+                    .zip([1, 3, 4, 5, 6].iter())
+                    .map(|(T_k, k)| BigInt::mod_pow(T_k, &x_pow[*k], p)),
+            )
+            .fold(BigInt::mod_pow(&pp.g, &g_exponent, p), |acc, a| {
+                BigInt::mod_mul(&acc, &a, p)
+            });
+        if lhs != rhs {
+            return Err(R1CSError);
+        }
+
+        // P = A_I^x * A_O^(x^2) * S^(x^3) * h^(-miu) * g_vec^(x * y^-n o w_R) *
+        // h_tag^(-y^n + x * w_L + w_O) * ux^tx
+        let ux = group(pp, BigInt::mod_pow(&u.g, &w, p));
+        let h_tag = h_tag(pp, &h_vec, &y_inv_pow);
+        let mut scalars = vec![
+            x_pow[1].clone(),
+            x_pow[2].clone(),
+            x_pow[3].clone(),
+            BigInt::mod_sub(&BigInt::zero(), &self.miu.x, q),
+            self.tx.x.clone(),
+        ];
+        scalars.extend(w_R_y_inv.iter().map(|w_i| BigInt::mod_mul(&x, w_i, q)));
+        scalars.extend((0..n).map(|i| {
+            let w_L_i = BigInt::mod_mul(&x, &weights.w_L[i], q);
+            BigInt::mod_sub(&BigInt::mod_add(&w_L_i, &weights.w_O[i], q), &y_pow[i], q)
+        }));
+        let mut elements = vec![
+            self.A_I.clone(),
+            self.A_O.clone(),
+            self.S.clone(),
+            group(pp, pk.h.clone()),
+            ux.clone(),
+        ];
+        elements.extend_from_slice(&g_vec);
+        elements.extend_from_slice(&h_tag);
+        let P = multiexponentiation(
+            &elements,
+            &scalars
+                .into_iter()
+                .map(|s| field(pp, s))
+                .collect::<Vec<_>>(),
+        );
+
+        self.inner_product_proof
+            .fast_verify(&mut transcript, &g_vec, &h_tag, &ux, &P)
+            .map_err(|_| InnerProductError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocols::bulletproofs::r1cs::ConstraintSystem;
+    use crate::protocols::bulletproofs::r1cs::LinearCombination;
+    use crate::protocols::bulletproofs::r1cs::R1CSProof;
+    use crate::protocols::bulletproofs::r1cs::Variable;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::tight_range_proof::Witness;
+    use crate::utlities::transcript::ProofContext;
+    use crate::BulletproofError;
+    use curv::arithmetic::traits::Modulo;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalCiphertext;
+    use elgamal::ElGamalKeyPair;
+    use elgamal::ElGamalPP;
+    use elgamal::ElGamalPublicKey;
+
+    fn ffdhe_ciphertexts(
+        values: &[BigInt],
+    ) -> (ElGamalPublicKey, Vec<Witness>, Vec<ElGamalCiphertext>) {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let witnesses = values
+            .iter()
+            .map(|x| Witness {
+                x: x.clone(),
+                r: BigInt::sample_below(&pp.q),
+            })
+            .collect::<Vec<_>>();
+        let ciphertexts = witnesses
+            .iter()
+            .map(|w| {
+                FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &w.x, &w.r).unwrap()
+            })
+            .collect();
+        (keypair.pk, witnesses, ciphertexts)
+    }
+
+    // the committed p is 3 mod 4 and smaller than 2^32: p = sum 2^i * b_i with b_i * (1 - b_i) = 0
+    // and b_0 = b_1 = 1. The prover passes p, the verifier None
+    fn blum_gadget(cs: &mut ConstraintSystem, p: Option<&BigInt>) {
+        let mut sum = LinearCombination::new();
+        let mut bits = Vec::new();
+        for i in 0..32 {
+            let values = p.map(|p| {
+                let b = (p >> i).mod_floor(&BigInt::from(2));
+                (b.clone(), BigInt::one() - b)
+            });
+            let (l, r, o) = cs.allocate_multiplier(values);
+            cs.constrain(
+                LinearCombination::from(l)
+                    .add_term(r, BigInt::one())
+                    .add_term(Variable::One, BigInt::from(-1)),
+            );
+            cs.constrain(LinearCombination::from(o));
+            sum = sum.add_term(l, BigInt::one() << i);
+            bits.push(l);
+        }
+        cs.constrain(sum.add_term(Variable::Committed(0), BigInt::from(-1)));
+        for bit in &bits[..2] {
+            cs.constrain(LinearCombination::from(*bit).add_term(Variable::One, BigInt::from(-1)));
+        }
+    }
+
+    // z = x^3 * y, three gates padded to four
+    fn cube_gadget(cs: &mut ConstraintSystem) {
+        let v = cs.committed_variables();
+        let (_, _, x2) = cs.multiply(v[0].into(), v[0].into());
+        let (_, _, x3) = cs.multiply(x2.into(), v[0].into());
+        let (_, _, x3y) = cs.multiply(x3.into(), v[1].into());
+        cs.constrain(LinearCombination::from(x3y).add_term(v[2], BigInt::from(-1)));
+    }
+
+    #[test]
+    fn test_r1cs_blum_prime() {
+        let context = ProofContext::default();
+        let p = BigInt::from(4294967291u64);
+        let (pk, witnesses, ciphertexts) = ffdhe_ciphertexts(&[p.clone()]);
+        let mut prover_cs = ConstraintSystem::with_values(&pk, &[p.clone()]);
+        blum_gadget(&mut prover_cs, Some(&p));
+        let proof = R1CSProof::prove(&prover_cs, &pk, &witnesses, &ciphertexts, &context).unwrap();
+
+        let mut verifier_cs = ConstraintSystem::new(&pk, 1);
+        blum_gadget(&mut verifier_cs, None);
+        assert!(proof
+            .verify(&verifier_cs, &pk, &ciphertexts, &context)
+            .is_ok());
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let proof: R1CSProof = serde_json::from_str(&json).unwrap();
+        assert!(proof
+            .verify(&verifier_cs, &pk, &ciphertexts, &context)
+            .is_ok());
+
+        // another session
+        let other_context = ProofContext::new(&[1u8; 32], 1);
+        assert!(proof
+            .verify(&verifier_cs, &pk, &ciphertexts, &other_context)
+            .is_err());
+    }
+
+    #[test]
+    fn test_r1cs_unsatisfied() {
+        let context = ProofContext::default();
+        let p = BigInt::from(13);
+        let (pk, witnesses, ciphertexts) = ffdhe_ciphertexts(&[p.clone()]);
+        let mut prover_cs = ConstraintSystem::with_values(&pk, &[p.clone()]);
+        blum_gadget(&mut prover_cs, Some(&p));
+        assert_eq!(
+            R1CSProof::prove(&prover_cs, &pk, &witnesses, &ciphertexts, &context).unwrap_err(),
+            BulletproofError::R1CSError
+        );
+
+        // the values of the system must be the plaintexts of the ciphertexts
+        let mut prover_cs = ConstraintSystem::with_values(&pk, &[BigInt::from(7)]);
+        blum_gadget(&mut prover_cs, Some(&BigInt::from(7)));
+        assert_eq!(
+            R1CSProof::prove(&prover_cs, &pk, &witnesses, &ciphertexts, &context).unwrap_err(),
+            BulletproofError::SetupError
+        );
+    }
+
+    #[test]
+    fn test_r1cs_product() {
+        let context = ProofContext::default();
+        let x = BigInt::sample(64);
+        let y = BigInt::sample(64);
+        let values = [x.clone(), y.clone(), &x * &x * &x * &y];
+        let (pk, witnesses, ciphertexts) = ffdhe_ciphertexts(&values);
+        let mut prover_cs = ConstraintSystem::with_values(&pk, &values);
+        cube_gadget(&mut prover_cs);
+        let proof = R1CSProof::prove(&prover_cs, &pk, &witnesses, &ciphertexts, &context).unwrap();
+
+        let mut verifier_cs = ConstraintSystem::new(&pk, 3);
+        cube_gadget(&mut verifier_cs);
+        assert!(proof
+            .verify(&verifier_cs, &pk, &ciphertexts, &context)
+            .is_ok());
+
+        // the proof is bound to its circuit
+        let mut other_cs = ConstraintSystem::new(&pk, 3);
+        cube_gadget(&mut other_cs);
+        other_cs.constrain(LinearCombination::new());
+        assert!(proof
+            .verify(&other_cs, &pk, &ciphertexts, &context)
+            .is_err());
+        assert_eq!(
+            proof
+                .verify(&ConstraintSystem::new(&pk, 2), &pk, &ciphertexts, &context)
+                .unwrap_err(),
+            BulletproofError::SetupError
+        );
+    }
+
+    #[test]
+    fn test_r1cs_bad_proof() {
+        let context = ProofContext::default();
+        let x = BigInt::sample(64);
+        let y = BigInt::sample(64);
+        let values = [x.clone(), y.clone(), &x * &x * &x * &y];
+        let (pk, witnesses, ciphertexts) = ffdhe_ciphertexts(&values);
+        let mut prover_cs = ConstraintSystem::with_values(&pk, &values);
+        cube_gadget(&mut prover_cs);
+        let proof = R1CSProof::prove(&prover_cs, &pk, &witnesses, &ciphertexts, &context).unwrap();
+        let mut verifier_cs = ConstraintSystem::new(&pk, 3);
+        cube_gadget(&mut verifier_cs);
+
+        let mut bad_proof = proof.clone();
+        bad_proof.tx.x = BigInt::mod_add(&bad_proof.tx.x, &BigInt::one(), &pk.pp.q);
+        assert!(bad_proof
+            .verify(&verifier_cs, &pk, &ciphertexts, &context)
+            .is_err());
+
+        let mut bad_proof = proof.clone();
+        bad_proof.T_3 = bad_proof.T_4.clone();
+        assert!(bad_proof
+            .verify(&verifier_cs, &pk, &ciphertexts, &context)
+            .is_err());
+
+        // same plaintexts, fresh randomness
+        let other_ciphertexts = values
+            .iter()
+            .map(|v| {
+                let r = BigInt::sample_below(&pk.pp.q);
+                FFDHEExponentElGamal::encrypt_with_randomness(&pk, v, &r).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(proof
+            .verify(&verifier_cs, &pk, &other_ciphertexts, &context)
+            .is_err());
+    }
+}
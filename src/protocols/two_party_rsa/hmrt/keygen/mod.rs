@@ -0,0 +1,136 @@
+use crate::protocols::two_party_rsa::hmrt::keygen::party_one::PartyOneKeyGenMsg;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_two::PartyTwoKeyGenMsg;
+use crate::protocols::two_party_rsa::hmrt::sieving_modulus;
+use crate::protocols::two_party_rsa::hmrt::HmrtParams;
+use crate::utlities::SMALL_PRIMES;
+use crate::TwoPartyRSAError;
+use curv::BigInt;
+use rand::prelude::*;
+use std::cmp;
+
+pub mod party_one;
+pub mod party_two;
+
+/// Identifies one run of the key generation, chosen by the parties before they start
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionId(pub [u8; 32]);
+
+impl SessionId {
+    pub fn random() -> Self {
+        let mut id = [0u8; 32];
+        thread_rng().fill_bytes(&mut id);
+        SessionId(id)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HmrtParty {
+    PartyOne,
+    PartyTwo,
+}
+
+/// Envelope for key generation messages on any transport. Round r is the r-th message sent
+/// by its sender, starting from 0. Rounds 0 and 1 are key setup: party one proposes the
+/// ElGamal group in round 0 and party two answers with its keys in that group in round 1.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum HmrtMessage {
+    PartyOne {
+        session_id: SessionId,
+        round: usize,
+        msg: PartyOneKeyGenMsg,
+    },
+    PartyTwo {
+        session_id: SessionId,
+        round: usize,
+        msg: PartyTwoKeyGenMsg,
+    },
+}
+
+impl HmrtMessage {
+    pub fn session_id(&self) -> &SessionId {
+        match self {
+            HmrtMessage::PartyOne { session_id, .. } => session_id,
+            HmrtMessage::PartyTwo { session_id, .. } => session_id,
+        }
+    }
+
+    pub fn round(&self) -> usize {
+        match self {
+            HmrtMessage::PartyOne { round, .. } => *round,
+            HmrtMessage::PartyTwo { round, .. } => *round,
+        }
+    }
+
+    pub fn sender(&self) -> HmrtParty {
+        match self {
+            HmrtMessage::PartyOne { .. } => HmrtParty::PartyOne,
+            HmrtMessage::PartyTwo { .. } => HmrtParty::PartyTwo,
+        }
+    }
+}
+
+// checks the envelope of a message addressed to a party expecting the given round
+fn check_envelope(
+    message: &HmrtMessage,
+    session_id: &SessionId,
+    expected_sender: HmrtParty,
+    expected_round: usize,
+) -> Result<(), TwoPartyRSAError> {
+    if message.session_id() != session_id {
+        return Err(TwoPartyRSAError::WrongSession);
+    }
+    if message.sender() != expected_sender {
+        return Err(TwoPartyRSAError::UnexpectedSender);
+    }
+    if message.round() < expected_round {
+        return Err(TwoPartyRSAError::ReplayedMessage);
+    }
+    if message.round() > expected_round {
+        return Err(TwoPartyRSAError::OutOfOrderMessage);
+    }
+    Ok(())
+}
+
+/// Counters collected while running the key generation
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct HmrtKeyGenStats {
+    /// shares of prime candidates generated, for both p and q
+    pub candidates_tried: usize,
+    /// trial divisions of a candidate by a small prime
+    pub primes_sieved: usize,
+    /// joint moduli N computed and sent to the biprimality test
+    pub moduli_tested: usize,
+}
+
+// candidates are 4(p_0 + p_1) + 3, hence odd: the sweep starts at 3
+const FIRST_SIEVING_PRIME_INDEX: usize = 1;
+
+// index in SMALL_PRIMES where trial division of a new candidate starts: sieved candidates are
+// coprime to the primes dividing M by construction
+fn first_trial_division_index(params: &HmrtParams) -> usize {
+    if params.distributed_sieving {
+        sieving_modulus(params).1
+    } else {
+        FIRST_SIEVING_PRIME_INDEX
+    }
+}
+
+// number of small primes sieved together in one trial division round
+const TRIAL_DIVISION_BATCH_SIZE: usize = 32;
+
+// the batch of small primes starting at alpha_index
+fn sieving_batch(params: &HmrtParams, alpha_index: usize) -> Vec<BigInt> {
+    let end = cmp::min(
+        alpha_index + TRIAL_DIVISION_BATCH_SIZE,
+        params.trial_division_end(),
+    );
+    SMALL_PRIMES[alpha_index..end]
+        .iter()
+        .map(|alpha| BigInt::from(*alpha))
+        .collect()
+}
+
+fn is_last_sieving_batch(params: &HmrtParams, alpha_index: usize) -> bool {
+    alpha_index + TRIAL_DIVISION_BATCH_SIZE >= params.trial_division_end()
+}
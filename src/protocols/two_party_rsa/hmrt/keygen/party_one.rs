@@ -0,0 +1,823 @@
+use crate::protocols::two_party_rsa::hmrt::keygen::check_envelope;
+use crate::protocols::two_party_rsa::hmrt::keygen::first_trial_division_index;
+use crate::protocols::two_party_rsa::hmrt::keygen::is_last_sieving_batch;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_two::PartyTwoKeyGenMsg;
+use crate::protocols::two_party_rsa::hmrt::keygen::sieving_batch;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtKeyGenStats;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtMessage;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtParty;
+use crate::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use crate::protocols::two_party_rsa::hmrt::keygen::TRIAL_DIVISION_BATCH_SIZE;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTest;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTestFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGeneration;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationBatchSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationBatchThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateSievingFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateSievingSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateWitness;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulus;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusWitness;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetup;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetupFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivate;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShare;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareWitness;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneRSAKeyShare;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::CiphertextPair;
+use crate::protocols::two_party_rsa::hmrt::HmrtParams;
+use crate::protocols::two_party_rsa::hmrt::JointModulus;
+use crate::protocols::two_party_rsa::DEFAULT_PUBLIC_EXPONENT;
+use crate::TwoPartyRSAError;
+use curv::BigInt;
+use elgamal::ElGamalCiphertext;
+use std::mem;
+
+/// Messages sent by party one during key generation, one variant per round. Key setup takes
+/// two rounds, KeySetup and Wait: party two generates its keys in the ElGamal group proposed
+/// by party one and answers in round 1. With distributed sieving a candidate takes three
+/// rounds, CandidateSieving, Wait and SievedCandidate. Wait fills the rounds in which party one
+/// needs party two's message first.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PartyOneKeyGenMsg {
+    KeySetup(PartyOneKeySetupFirstMsg),
+    CandidateGeneration(PartyOneCandidateGenerationFirstMsg),
+    CandidateSieving(PartyOneCandidateSievingFirstMsg),
+    SievedCandidate(
+        PartyOneCandidateGenerationFirstMsg,
+        PartyOneCandidateSievingSecondMsg,
+    ),
+    Wait,
+    TrialDivision(PartyOneCandidateGenerationBatchSecondMsg),
+    TrialDivisionDecrypt(PartyOneCandidateGenerationBatchThirdMsg),
+    JointModulusFirst(PartyOneJointModulusFirstMsg),
+    JointModulusSecond(PartyOneJointModulusSecondMsg),
+    JointModulusThird(PartyOneJointModulusThirdMsg),
+    BiprimalityTest(PartyOneBiprimalityTestFirstMsg),
+    PrivateExponentShareFirst(PartyOnePrivateExponentShareFirstMsg),
+    PrivateExponentShareSecond(PartyOnePrivateExponentShareSecondMsg),
+}
+
+/// Result of handling a message: the next message to send or the key share once done
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum PartyOneKeyGenStep {
+    Message(PartyOneKeyGenMsg),
+    Done(PartyOneRSAKeyShare),
+}
+
+/// Runs the full HMRT key generation for party one: key setup, candidate generation (by
+/// distributed sieving if params.distributed_sieving is set) with trial division over
+/// SMALL_PRIMES, joint modulus, biprimality test and private exponent shares. Candidates are
+/// resampled whenever a test fails.
+pub struct PartyOneHmrtKeyGen {
+    params: HmrtParams,
+    e: BigInt,
+    keys: Option<PartyOneKeySetup>,
+    p_candidate: Option<PartyOneCandidate>,
+    stage: PartyOneKeyGenStage,
+    stats: HmrtKeyGenStats,
+}
+
+// a candidate share together with everything exchanged about it in candidate generation
+struct PartyOneCandidate {
+    witness: PartyOneCandidateWitness,
+    first_message: PartyOneCandidateGenerationFirstMsg,
+    party_two_first_message: PartyTwoCandidateGenerationFirstMsg,
+    ciphertexts: CiphertextPair,
+}
+
+#[allow(clippy::large_enum_variant)]
+enum PartyOneKeyGenStage {
+    KeySetup {
+        first_message: PartyOneKeySetupFirstMsg,
+        private: PartyOnePrivate,
+    },
+    KeySetupReply {
+        first_message: PartyOneKeySetupFirstMsg,
+        private: PartyOnePrivate,
+    },
+    CandidateGeneration {
+        witness: PartyOneCandidateWitness,
+        first_message: PartyOneCandidateGenerationFirstMsg,
+    },
+    CandidateSieving {
+        sieving_message: PartyOneCandidateSievingFirstMsg,
+    },
+    SievingConversion {
+        sieving_message: PartyOneCandidateSievingFirstMsg,
+    },
+    SievedCandidate {
+        witness: PartyOneCandidateWitness,
+        first_message: PartyOneCandidateGenerationFirstMsg,
+        party_two_first_message: PartyTwoCandidateGenerationFirstMsg,
+    },
+    TrialDivision {
+        candidate: PartyOneCandidate,
+        alpha_index: usize,
+        second_message: PartyOneCandidateGenerationBatchSecondMsg,
+    },
+    TrialDivisionDecrypt {
+        candidate: PartyOneCandidate,
+        alpha_index: usize,
+        c_alpha_vec: Vec<ElGamalCiphertext>,
+        c_alpha_tilde_vec: Vec<ElGamalCiphertext>,
+    },
+    JointModulusFirst {
+        q_candidate: PartyOneCandidate,
+        witness: PartyOneJointModulusWitness,
+        first_message: PartyOneJointModulusFirstMsg,
+    },
+    JointModulusSecond {
+        q_candidate: PartyOneCandidate,
+        first_message: PartyOneJointModulusFirstMsg,
+        party_two_first_message: PartyTwoJointModulusFirstMsg,
+        second_message: PartyOneJointModulusSecondMsg,
+    },
+    JointModulusThird {
+        q_candidate: PartyOneCandidate,
+        party_two_first_message: PartyTwoJointModulusFirstMsg,
+        second_message: PartyOneJointModulusSecondMsg,
+        third_message: PartyOneJointModulusThirdMsg,
+    },
+    BiprimalityTest {
+        q_candidate: PartyOneCandidate,
+        joint_modulus: JointModulus,
+        first_message: PartyOneBiprimalityTestFirstMsg,
+    },
+    PrivateExponentShareFirst {
+        q_candidate: PartyOneCandidate,
+        joint_modulus: JointModulus,
+        first_message: PartyOnePrivateExponentShareFirstMsg,
+    },
+    PrivateExponentShareSecond {
+        joint_modulus: JointModulus,
+        witness: PartyOnePrivateExponentShareWitness,
+        second_message: PartyOnePrivateExponentShareSecondMsg,
+    },
+    Done,
+    Aborted,
+}
+
+impl PartyOneHmrtKeyGen {
+    /// Starts key generation for the default public exponent, returns the first message.
+    /// Party two must use the same session id.
+    pub fn new(session_id: SessionId) -> (Self, PartyOneKeyGenMsg) {
+        Self::with_public_exponent(session_id, &BigInt::from(DEFAULT_PUBLIC_EXPONENT))
+    }
+
+    pub fn with_public_exponent(session_id: SessionId, e: &BigInt) -> (Self, PartyOneKeyGenMsg) {
+        Self::with_params(session_id, &HmrtParams::default(), e)
+            .expect("default parameters are valid")
+    }
+
+    /// Starts key generation with the given parameters, party two must use the same ones.
+    /// params.elgamal_group is the group proposed to party two, which only uses its own as the
+    /// smallest group it accepts.
+    pub fn with_params(
+        session_id: SessionId,
+        params: &HmrtParams,
+        e: &BigInt,
+    ) -> Result<(Self, PartyOneKeyGenMsg), TwoPartyRSAError> {
+        let (first_message, private) =
+            PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(params, &session_id)?;
+        let keygen = PartyOneHmrtKeyGen {
+            params: *params,
+            e: e.clone(),
+            keys: None,
+            p_candidate: None,
+            stage: PartyOneKeyGenStage::KeySetup {
+                first_message: first_message.clone(),
+                private,
+            },
+            stats: HmrtKeyGenStats::default(),
+        };
+        Ok((keygen, PartyOneKeyGenMsg::KeySetup(first_message)))
+    }
+
+    pub fn stats(&self) -> &HmrtKeyGenStats {
+        &self.stats
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.stage, PartyOneKeyGenStage::Done)
+    }
+
+    /// Handles party two's message for the current round. After an error the key generation
+    /// is aborted and every further message is rejected.
+    pub fn handle_party_two_message(
+        &mut self,
+        party_two_message: &PartyTwoKeyGenMsg,
+    ) -> Result<PartyOneKeyGenStep, TwoPartyRSAError> {
+        let stage = mem::replace(&mut self.stage, PartyOneKeyGenStage::Aborted);
+        let (next_stage, step) = self.advance(stage, party_two_message)?;
+        self.stage = next_stage;
+        Ok(step)
+    }
+
+    fn advance(
+        &mut self,
+        stage: PartyOneKeyGenStage,
+        party_two_message: &PartyTwoKeyGenMsg,
+    ) -> Result<(PartyOneKeyGenStage, PartyOneKeyGenStep), TwoPartyRSAError> {
+        match (stage, party_two_message) {
+            (
+                PartyOneKeyGenStage::KeySetup {
+                    first_message,
+                    private,
+                },
+                PartyTwoKeyGenMsg::Wait,
+            ) => Ok((
+                PartyOneKeyGenStage::KeySetupReply {
+                    first_message,
+                    private,
+                },
+                PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::Wait),
+            )),
+
+            (
+                PartyOneKeyGenStage::KeySetupReply {
+                    first_message,
+                    private,
+                },
+                PartyTwoKeyGenMsg::KeySetup(party_two_first_message),
+            ) => {
+                let keys =
+                    PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+                        &self.params,
+                        &first_message,
+                        party_two_first_message,
+                        private,
+                    )?;
+                self.keys = Some(keys);
+                self.new_candidate()
+            }
+
+            (
+                PartyOneKeyGenStage::CandidateGeneration {
+                    witness,
+                    first_message,
+                },
+                PartyTwoKeyGenMsg::CandidateGeneration(party_two_first_message),
+            ) => {
+                let ciphertexts =
+                    PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                        self.keys(),
+                        &first_message,
+                        party_two_first_message,
+                    )?;
+                let candidate = PartyOneCandidate {
+                    witness,
+                    first_message,
+                    party_two_first_message: party_two_first_message.clone(),
+                    ciphertexts,
+                };
+                self.start_trial_division(candidate)
+            }
+
+            (
+                PartyOneKeyGenStage::CandidateSieving { sieving_message },
+                PartyTwoKeyGenMsg::Wait,
+            ) => Ok((
+                PartyOneKeyGenStage::SievingConversion { sieving_message },
+                PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::Wait),
+            )),
+
+            (
+                PartyOneKeyGenStage::SievingConversion { sieving_message },
+                PartyTwoKeyGenMsg::SievedCandidate(
+                    party_two_sieving_message,
+                    party_two_first_message,
+                ),
+            ) => {
+                let (witness, first_message, second_message) =
+                    PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+                        self.keys(),
+                        &sieving_message,
+                        party_two_sieving_message,
+                        party_two_first_message,
+                    )?;
+                Ok((
+                    PartyOneKeyGenStage::SievedCandidate {
+                        witness,
+                        first_message: first_message.clone(),
+                        party_two_first_message: party_two_first_message.clone(),
+                    },
+                    PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::SievedCandidate(
+                        first_message,
+                        second_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyOneKeyGenStage::SievedCandidate {
+                    witness,
+                    first_message,
+                    party_two_first_message,
+                },
+                PartyTwoKeyGenMsg::Wait,
+            ) => {
+                let ciphertexts =
+                    PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                        self.keys(),
+                        &first_message,
+                        &party_two_first_message,
+                    )?;
+                let candidate = PartyOneCandidate {
+                    witness,
+                    first_message,
+                    party_two_first_message,
+                    ciphertexts,
+                };
+                self.start_trial_division(candidate)
+            }
+
+            (
+                PartyOneKeyGenStage::TrialDivision {
+                    candidate,
+                    alpha_index,
+                    second_message,
+                },
+                PartyTwoKeyGenMsg::TrialDivision(party_two_second_message),
+            ) => {
+                let alpha_vec = sieving_batch(&self.params, alpha_index);
+                let (third_message, c_alpha_vec, c_alpha_tilde_vec) =
+                    PartyOneCandidateGeneration::verify_party_two_batch_second_message_and_partial_decrypt(
+                        &second_message,
+                        party_two_second_message,
+                        &alpha_vec,
+                        self.keys(),
+                        &candidate.ciphertexts,
+                    )?;
+                Ok((
+                    PartyOneKeyGenStage::TrialDivisionDecrypt {
+                        candidate,
+                        alpha_index,
+                        c_alpha_vec,
+                        c_alpha_tilde_vec,
+                    },
+                    PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::TrialDivisionDecrypt(
+                        third_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyOneKeyGenStage::TrialDivisionDecrypt {
+                    candidate,
+                    alpha_index,
+                    c_alpha_vec,
+                    c_alpha_tilde_vec,
+                },
+                PartyTwoKeyGenMsg::TrialDivisionDecrypt(party_two_third_message),
+            ) => {
+                let coprime_vec = PartyOneCandidateGeneration::verify_party_two_batch_third_message_full_decrypt_and_conclude_division(
+                    &c_alpha_vec,
+                    &c_alpha_tilde_vec,
+                    party_two_third_message,
+                    &sieving_batch(&self.params, alpha_index),
+                    self.keys(),
+                )?;
+                self.stats.primes_sieved += coprime_vec.len();
+                if coprime_vec.iter().any(|coprime| !coprime) {
+                    self.new_candidate()
+                } else if !is_last_sieving_batch(&self.params, alpha_index) {
+                    self.trial_division(candidate, alpha_index + TRIAL_DIVISION_BATCH_SIZE)
+                } else {
+                    self.accept_candidate(candidate)
+                }
+            }
+
+            (
+                PartyOneKeyGenStage::JointModulusFirst {
+                    q_candidate,
+                    witness,
+                    first_message,
+                },
+                PartyTwoKeyGenMsg::JointModulusFirst(party_two_first_message),
+            ) => {
+                let p_candidate = self.p_candidate();
+                let second_message =
+                    PartyOneJointModulus::verify_party_two_first_message_and_compute_cross_terms(
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &witness,
+                        &p_candidate.ciphertexts,
+                        &q_candidate.ciphertexts,
+                        &first_message,
+                        party_two_first_message,
+                    )?;
+                Ok((
+                    PartyOneKeyGenStage::JointModulusSecond {
+                        q_candidate,
+                        first_message,
+                        party_two_first_message: party_two_first_message.clone(),
+                        second_message: second_message.clone(),
+                    },
+                    PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::JointModulusSecond(
+                        second_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyOneKeyGenStage::JointModulusSecond {
+                    q_candidate,
+                    first_message,
+                    party_two_first_message,
+                    second_message,
+                },
+                PartyTwoKeyGenMsg::JointModulusSecond(party_two_second_message),
+            ) => {
+                let third_message =
+                    PartyOneJointModulus::verify_party_two_second_message_and_decrypt_modulus(
+                        self.keys(),
+                        &self.p_candidate().ciphertexts,
+                        &q_candidate.ciphertexts,
+                        &first_message,
+                        &party_two_first_message,
+                        party_two_second_message,
+                    )?;
+                Ok((
+                    PartyOneKeyGenStage::JointModulusThird {
+                        q_candidate,
+                        party_two_first_message,
+                        second_message,
+                        third_message: third_message.clone(),
+                    },
+                    PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::JointModulusThird(
+                        third_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyOneKeyGenStage::JointModulusThird {
+                    q_candidate,
+                    party_two_first_message,
+                    second_message,
+                    third_message,
+                },
+                PartyTwoKeyGenMsg::JointModulusThird(party_two_third_message),
+            ) => {
+                let joint_modulus =
+                    PartyOneJointModulus::verify_party_two_third_message_and_output_joint_modulus(
+                        self.keys(),
+                        &second_message,
+                        &third_message,
+                        &party_two_first_message,
+                        party_two_third_message,
+                    )?;
+                self.stats.moduli_tested += 1;
+                let p_candidate = self.p_candidate();
+                let first_message =
+                    PartyOneBiprimalityTest::compute_exponentiations_and_first_message(
+                        &joint_modulus.n,
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &p_candidate.first_message,
+                        &q_candidate.first_message,
+                    )?;
+                Ok((
+                    PartyOneKeyGenStage::BiprimalityTest {
+                        q_candidate,
+                        joint_modulus,
+                        first_message: first_message.clone(),
+                    },
+                    PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::BiprimalityTest(first_message)),
+                ))
+            }
+
+            (
+                PartyOneKeyGenStage::BiprimalityTest {
+                    q_candidate,
+                    joint_modulus,
+                    first_message,
+                },
+                PartyTwoKeyGenMsg::BiprimalityTest(party_two_first_message),
+            ) => {
+                let p_candidate = self.p_candidate();
+                let biprime =
+                    PartyOneBiprimalityTest::verify_party_two_first_message_and_conclude_biprimality(
+                        &joint_modulus.n,
+                        self.keys(),
+                        &p_candidate.party_two_first_message,
+                        &q_candidate.party_two_first_message,
+                        &first_message,
+                        party_two_first_message,
+                    )?;
+                if !biprime {
+                    self.p_candidate = None;
+                    return self.new_candidate();
+                }
+
+                let first_message =
+                    PartyOnePrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+                        &self.e,
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &p_candidate.ciphertexts,
+                        &q_candidate.ciphertexts,
+                    )?;
+                Ok((
+                    PartyOneKeyGenStage::PrivateExponentShareFirst {
+                        q_candidate,
+                        joint_modulus,
+                        first_message: first_message.clone(),
+                    },
+                    PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::PrivateExponentShareFirst(
+                        first_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyOneKeyGenStage::PrivateExponentShareFirst {
+                    q_candidate,
+                    joint_modulus,
+                    first_message,
+                },
+                PartyTwoKeyGenMsg::PrivateExponentShareFirst(party_two_first_message),
+            ) => {
+                let p_candidate = self.p_candidate();
+                let result =
+                    PartyOnePrivateExponentShare::verify_party_two_first_message_and_compute_exponent_share(
+                        &self.e,
+                        &joint_modulus,
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &p_candidate.ciphertexts,
+                        &q_candidate.ciphertexts,
+                        &first_message,
+                        party_two_first_message,
+                    );
+                match result {
+                    Ok((witness, second_message)) => Ok((
+                        PartyOneKeyGenStage::PrivateExponentShareSecond {
+                            joint_modulus,
+                            witness,
+                            second_message: second_message.clone(),
+                        },
+                        PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::PrivateExponentShareSecond(
+                            second_message,
+                        )),
+                    )),
+                    // e | phi(N): N cannot be used with this public exponent
+                    Err(TwoPartyRSAError::InvalidPublicExponent) => {
+                        self.p_candidate = None;
+                        self.new_candidate()
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+
+            (
+                PartyOneKeyGenStage::PrivateExponentShareSecond {
+                    joint_modulus,
+                    witness,
+                    second_message,
+                },
+                PartyTwoKeyGenMsg::PrivateExponentShareSecond(party_two_second_message),
+            ) => {
+                let key_share =
+                    PartyOnePrivateExponentShare::verify_party_two_second_message_and_output_key_share(
+                        &self.e,
+                        &joint_modulus,
+                        witness,
+                        &second_message,
+                        party_two_second_message,
+                    )?;
+                self.p_candidate = None;
+                Ok((
+                    PartyOneKeyGenStage::Done,
+                    PartyOneKeyGenStep::Done(key_share),
+                ))
+            }
+
+            _ => Err(TwoPartyRSAError::UnexpectedMessage),
+        }
+    }
+
+    fn keys(&self) -> &PartyOneKeySetup {
+        self.keys
+            .as_ref()
+            .expect("key setup is done before any other round")
+    }
+
+    fn p_candidate(&self) -> &PartyOneCandidate {
+        self.p_candidate
+            .as_ref()
+            .expect("p is fixed before the joint modulus rounds")
+    }
+
+    // generates shares of a new candidate for p, or for q once p passed trial division
+    fn new_candidate(
+        &mut self,
+    ) -> Result<(PartyOneKeyGenStage, PartyOneKeyGenStep), TwoPartyRSAError> {
+        self.stats.candidates_tried += 1;
+        if self.params.distributed_sieving {
+            let sieving_message =
+                PartyOneCandidateGeneration::sample_multiplicative_share_and_sieving_message(
+                    self.keys(),
+                )?;
+            return Ok((
+                PartyOneKeyGenStage::CandidateSieving {
+                    sieving_message: sieving_message.clone(),
+                },
+                PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::CandidateSieving(sieving_message)),
+            ));
+        }
+        let (witness, first_message) =
+            PartyOneCandidateGeneration::generate_shares_of_candidate(self.keys())?;
+        Ok((
+            PartyOneKeyGenStage::CandidateGeneration {
+                witness,
+                first_message: first_message.clone(),
+            },
+            PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::CandidateGeneration(first_message)),
+        ))
+    }
+
+    // a candidate that passed trial division becomes p, or gives N together with p
+    fn accept_candidate(
+        &mut self,
+        candidate: PartyOneCandidate,
+    ) -> Result<(PartyOneKeyGenStage, PartyOneKeyGenStep), TwoPartyRSAError> {
+        if self.p_candidate.is_none() {
+            self.p_candidate = Some(candidate);
+            self.new_candidate()
+        } else {
+            self.joint_modulus(candidate)
+        }
+    }
+
+    // sieved candidates may have no small prime left to divide by
+    fn start_trial_division(
+        &mut self,
+        candidate: PartyOneCandidate,
+    ) -> Result<(PartyOneKeyGenStage, PartyOneKeyGenStep), TwoPartyRSAError> {
+        let alpha_index = first_trial_division_index(&self.params);
+        if alpha_index < self.params.trial_division_end() {
+            self.trial_division(candidate, alpha_index)
+        } else {
+            self.accept_candidate(candidate)
+        }
+    }
+
+    fn trial_division(
+        &mut self,
+        candidate: PartyOneCandidate,
+        alpha_index: usize,
+    ) -> Result<(PartyOneKeyGenStage, PartyOneKeyGenStep), TwoPartyRSAError> {
+        let alpha_vec = sieving_batch(&self.params, alpha_index);
+        let second_message = PartyOneCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            self.keys(),
+            &candidate.ciphertexts,
+            &candidate.witness,
+        )?;
+        Ok((
+            PartyOneKeyGenStage::TrialDivision {
+                candidate,
+                alpha_index,
+                second_message: second_message.clone(),
+            },
+            PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::TrialDivision(second_message)),
+        ))
+    }
+
+    fn joint_modulus(
+        &mut self,
+        q_candidate: PartyOneCandidate,
+    ) -> Result<(PartyOneKeyGenStage, PartyOneKeyGenStep), TwoPartyRSAError> {
+        let p_candidate = self.p_candidate();
+        let (witness, first_message) = PartyOneJointModulus::encrypt_shares_and_first_message(
+            self.keys(),
+            &p_candidate.witness,
+            &q_candidate.witness,
+            &p_candidate.ciphertexts,
+            &q_candidate.ciphertexts,
+        )?;
+        Ok((
+            PartyOneKeyGenStage::JointModulusFirst {
+                q_candidate,
+                witness,
+                first_message: first_message.clone(),
+            },
+            PartyOneKeyGenStep::Message(PartyOneKeyGenMsg::JointModulusFirst(first_message)),
+        ))
+    }
+}
+
+/// State machine for party one over HmrtMessage envelopes. Messages from another session, from
+/// the wrong sender, replayed or ahead of the current round are rejected before they reach the
+/// key generation, which keeps all intermediate values internally.
+pub struct PartyOneState {
+    session_id: SessionId,
+    round: usize,
+    keygen: PartyOneHmrtKeyGen,
+    key_share: Option<PartyOneRSAKeyShare>,
+    aborted: bool,
+}
+
+impl PartyOneState {
+    /// Starts key generation for the default public exponent, returns the message of round 0
+    pub fn new(session_id: SessionId) -> (Self, HmrtMessage) {
+        Self::with_public_exponent(session_id, &BigInt::from(DEFAULT_PUBLIC_EXPONENT))
+    }
+
+    pub fn with_public_exponent(session_id: SessionId, e: &BigInt) -> (Self, HmrtMessage) {
+        Self::with_params(session_id, &HmrtParams::default(), e)
+            .expect("default parameters are valid")
+    }
+
+    pub fn with_params(
+        session_id: SessionId,
+        params: &HmrtParams,
+        e: &BigInt,
+    ) -> Result<(Self, HmrtMessage), TwoPartyRSAError> {
+        let (keygen, msg) = PartyOneHmrtKeyGen::with_params(session_id, params, e)?;
+        let state = PartyOneState {
+            session_id,
+            round: 0,
+            keygen,
+            key_share: None,
+            aborted: false,
+        };
+        let message = HmrtMessage::PartyOne {
+            session_id,
+            round: 0,
+            msg,
+        };
+        Ok((state, message))
+    }
+
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn stats(&self) -> &HmrtKeyGenStats {
+        self.keygen.stats()
+    }
+
+    pub fn key_share(&self) -> Option<&PartyOneRSAKeyShare> {
+        self.key_share.as_ref()
+    }
+
+    /// Returns the message for the next round, or None once the key share is ready.
+    /// A message that fails verification aborts the key generation.
+    pub fn handle_message(
+        &mut self,
+        message: &HmrtMessage,
+    ) -> Result<Option<HmrtMessage>, TwoPartyRSAError> {
+        if self.aborted {
+            return Err(TwoPartyRSAError::KeyGenAborted);
+        }
+        check_envelope(message, &self.session_id, HmrtParty::PartyTwo, self.round)?;
+        if self.key_share.is_some() {
+            return Err(TwoPartyRSAError::UnexpectedMessage);
+        }
+        let msg = match message {
+            HmrtMessage::PartyTwo { msg, .. } => msg,
+            _ => return Err(TwoPartyRSAError::UnexpectedSender),
+        };
+
+        match self.keygen.handle_party_two_message(msg) {
+            Ok(PartyOneKeyGenStep::Message(msg)) => {
+                self.round += 1;
+                Ok(Some(HmrtMessage::PartyOne {
+                    session_id: self.session_id,
+                    round: self.round,
+                    msg,
+                }))
+            }
+            Ok(PartyOneKeyGenStep::Done(key_share)) => {
+                self.round += 1;
+                self.key_share = Some(key_share);
+                Ok(None)
+            }
+            Err(err) => {
+                self.aborted = true;
+                Err(err)
+            }
+        }
+    }
+}
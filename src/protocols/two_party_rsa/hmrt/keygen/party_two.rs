@@ -0,0 +1,813 @@
+use crate::protocols::two_party_rsa::hmrt::keygen::check_envelope;
+use crate::protocols::two_party_rsa::hmrt::keygen::first_trial_division_index;
+use crate::protocols::two_party_rsa::hmrt::keygen::is_last_sieving_batch;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_one::PartyOneKeyGenMsg;
+use crate::protocols::two_party_rsa::hmrt::keygen::sieving_batch;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtKeyGenStats;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtMessage;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtParty;
+use crate::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use crate::protocols::two_party_rsa::hmrt::keygen::TRIAL_DIVISION_BATCH_SIZE;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::KeySetupFirstMsg as KeySetupFirstMsgPartyTwo;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoBiprimalityTest;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoBiprimalityTestFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGeneration;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationBatchSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationBatchThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateSievingFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateWitness;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulus;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusWitness;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoKeySetup;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShare;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareWitness;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoRSAKeyShare;
+use crate::protocols::two_party_rsa::hmrt::CiphertextPair;
+use crate::protocols::two_party_rsa::hmrt::HmrtParams;
+use crate::protocols::two_party_rsa::hmrt::JointModulus;
+use crate::protocols::two_party_rsa::DEFAULT_PUBLIC_EXPONENT;
+use crate::TwoPartyRSAError;
+use curv::BigInt;
+use elgamal::ElGamalCiphertext;
+use std::mem;
+
+/// Messages sent by party two during key generation, one variant per round. Key setup takes
+/// two rounds, Wait and KeySetup: party two generates its keys only once it knows the ElGamal
+/// group proposed by party one. With distributed sieving a candidate takes three rounds, Wait,
+/// SievedCandidate and Wait. Wait fills the rounds in which party two needs party one's
+/// message first.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PartyTwoKeyGenMsg {
+    KeySetup(KeySetupFirstMsgPartyTwo),
+    CandidateGeneration(PartyTwoCandidateGenerationFirstMsg),
+    SievedCandidate(
+        PartyTwoCandidateSievingFirstMsg,
+        PartyTwoCandidateGenerationFirstMsg,
+    ),
+    Wait,
+    TrialDivision(PartyTwoCandidateGenerationBatchSecondMsg),
+    TrialDivisionDecrypt(PartyTwoCandidateGenerationBatchThirdMsg),
+    JointModulusFirst(PartyTwoJointModulusFirstMsg),
+    JointModulusSecond(PartyTwoJointModulusSecondMsg),
+    JointModulusThird(PartyTwoJointModulusThirdMsg),
+    BiprimalityTest(PartyTwoBiprimalityTestFirstMsg),
+    PrivateExponentShareFirst(PartyTwoPrivateExponentShareFirstMsg),
+    PrivateExponentShareSecond(PartyTwoPrivateExponentShareSecondMsg),
+}
+
+/// Result of handling a message: the next message to send or the key share once done
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum PartyTwoKeyGenStep {
+    Message(PartyTwoKeyGenMsg),
+    Done(PartyTwoRSAKeyShare),
+}
+
+/// Runs the full HMRT key generation for party two: key setup, candidate generation (by
+/// distributed sieving if params.distributed_sieving is set) with trial division over
+/// SMALL_PRIMES, joint modulus, biprimality test and private exponent shares. Candidates are
+/// resampled whenever a test fails.
+pub struct PartyTwoHmrtKeyGen {
+    session_id: SessionId,
+    params: HmrtParams,
+    e: BigInt,
+    keys: Option<PartyTwoKeySetup>,
+    p_candidate: Option<PartyTwoCandidate>,
+    stage: PartyTwoKeyGenStage,
+    stats: HmrtKeyGenStats,
+}
+
+// a candidate share together with everything exchanged about it in candidate generation
+struct PartyTwoCandidate {
+    witness: PartyTwoCandidateWitness,
+    first_message: PartyTwoCandidateGenerationFirstMsg,
+    party_one_first_message: PartyOneCandidateGenerationFirstMsg,
+    ciphertexts: CiphertextPair,
+}
+
+#[allow(clippy::large_enum_variant)]
+enum PartyTwoKeyGenStage {
+    KeySetup,
+    KeySetupReply,
+    CandidateGeneration {
+        witness: PartyTwoCandidateWitness,
+        first_message: PartyTwoCandidateGenerationFirstMsg,
+    },
+    CandidateSieving,
+    SievingConversion {
+        witness: PartyTwoCandidateWitness,
+        first_message: PartyTwoCandidateGenerationFirstMsg,
+        sieving_message: PartyTwoCandidateSievingFirstMsg,
+    },
+    SievedCandidate {
+        witness: PartyTwoCandidateWitness,
+        first_message: PartyTwoCandidateGenerationFirstMsg,
+        sieving_message: PartyTwoCandidateSievingFirstMsg,
+    },
+    TrialDivision {
+        candidate: PartyTwoCandidate,
+        alpha_index: usize,
+        second_message: PartyTwoCandidateGenerationBatchSecondMsg,
+    },
+    TrialDivisionDecrypt {
+        candidate: PartyTwoCandidate,
+        alpha_index: usize,
+        c_alpha_vec: Vec<ElGamalCiphertext>,
+        c_alpha_tilde_vec: Vec<ElGamalCiphertext>,
+    },
+    JointModulusFirst {
+        q_candidate: PartyTwoCandidate,
+        witness: PartyTwoJointModulusWitness,
+        first_message: PartyTwoJointModulusFirstMsg,
+    },
+    JointModulusSecond {
+        q_candidate: PartyTwoCandidate,
+        first_message: PartyTwoJointModulusFirstMsg,
+        party_one_first_message: PartyOneJointModulusFirstMsg,
+        second_message: PartyTwoJointModulusSecondMsg,
+    },
+    JointModulusThird {
+        q_candidate: PartyTwoCandidate,
+        party_one_first_message: PartyOneJointModulusFirstMsg,
+        second_message: PartyTwoJointModulusSecondMsg,
+        third_message: PartyTwoJointModulusThirdMsg,
+    },
+    BiprimalityTest {
+        q_candidate: PartyTwoCandidate,
+        joint_modulus: JointModulus,
+        first_message: PartyTwoBiprimalityTestFirstMsg,
+    },
+    PrivateExponentShareFirst {
+        q_candidate: PartyTwoCandidate,
+        joint_modulus: JointModulus,
+        first_message: PartyTwoPrivateExponentShareFirstMsg,
+    },
+    PrivateExponentShareSecond {
+        joint_modulus: JointModulus,
+        witness: PartyTwoPrivateExponentShareWitness,
+        second_message: PartyTwoPrivateExponentShareSecondMsg,
+    },
+    Done,
+    Aborted,
+}
+
+impl PartyTwoHmrtKeyGen {
+    /// Starts key generation for the default public exponent, returns the first message.
+    /// Party one must use the same session id.
+    pub fn new(session_id: SessionId) -> (Self, PartyTwoKeyGenMsg) {
+        Self::with_public_exponent(session_id, &BigInt::from(DEFAULT_PUBLIC_EXPONENT))
+    }
+
+    pub fn with_public_exponent(session_id: SessionId, e: &BigInt) -> (Self, PartyTwoKeyGenMsg) {
+        Self::with_params(session_id, &HmrtParams::default(), e)
+            .expect("default parameters are valid")
+    }
+
+    /// Starts key generation with the given parameters, party one must use the same ones.
+    /// params.elgamal_group is the smallest group party two accepts: its keys are generated in
+    /// the group proposed by party one, once party one's first message arrives.
+    pub fn with_params(
+        session_id: SessionId,
+        params: &HmrtParams,
+        e: &BigInt,
+    ) -> Result<(Self, PartyTwoKeyGenMsg), TwoPartyRSAError> {
+        params.validate()?;
+        let keygen = PartyTwoHmrtKeyGen {
+            session_id,
+            params: *params,
+            e: e.clone(),
+            keys: None,
+            p_candidate: None,
+            stage: PartyTwoKeyGenStage::KeySetup,
+            stats: HmrtKeyGenStats::default(),
+        };
+        Ok((keygen, PartyTwoKeyGenMsg::Wait))
+    }
+
+    pub fn stats(&self) -> &HmrtKeyGenStats {
+        &self.stats
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self.stage, PartyTwoKeyGenStage::Done)
+    }
+
+    /// Handles party one's message for the current round. After an error the key generation
+    /// is aborted and every further message is rejected.
+    pub fn handle_party_one_message(
+        &mut self,
+        party_one_message: &PartyOneKeyGenMsg,
+    ) -> Result<PartyTwoKeyGenStep, TwoPartyRSAError> {
+        let stage = mem::replace(&mut self.stage, PartyTwoKeyGenStage::Aborted);
+        let (next_stage, step) = self.advance(stage, party_one_message)?;
+        self.stage = next_stage;
+        Ok(step)
+    }
+
+    fn advance(
+        &mut self,
+        stage: PartyTwoKeyGenStage,
+        party_one_message: &PartyOneKeyGenMsg,
+    ) -> Result<(PartyTwoKeyGenStage, PartyTwoKeyGenStep), TwoPartyRSAError> {
+        match (stage, party_one_message) {
+            (
+                PartyTwoKeyGenStage::KeySetup,
+                PartyOneKeyGenMsg::KeySetup(party_one_first_message),
+            ) => {
+                let (first_message, private) =
+                    PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+                        &self.params,
+                        party_one_first_message.group,
+                        &self.session_id,
+                    )?;
+                let keys =
+                    PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+                        &self.params,
+                        party_one_first_message,
+                        &first_message,
+                        private,
+                    )?;
+                self.keys = Some(keys);
+                Ok((
+                    PartyTwoKeyGenStage::KeySetupReply,
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::KeySetup(first_message)),
+                ))
+            }
+
+            (PartyTwoKeyGenStage::KeySetupReply, PartyOneKeyGenMsg::Wait) => self.new_candidate(),
+
+            (
+                PartyTwoKeyGenStage::CandidateGeneration {
+                    witness,
+                    first_message,
+                },
+                PartyOneKeyGenMsg::CandidateGeneration(party_one_first_message),
+            ) => {
+                let ciphertexts =
+                    PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+                        self.keys(),
+                        party_one_first_message,
+                        &first_message,
+                    )?;
+                let candidate = PartyTwoCandidate {
+                    witness,
+                    first_message,
+                    party_one_first_message: party_one_first_message.clone(),
+                    ciphertexts,
+                };
+                self.start_trial_division(candidate)
+            }
+
+            (
+                PartyTwoKeyGenStage::CandidateSieving,
+                PartyOneKeyGenMsg::CandidateSieving(party_one_sieving_message),
+            ) => {
+                let (sieving_message, witness, first_message) =
+                    PartyTwoCandidateGeneration::verify_party_one_sieving_message_and_generate_sieved_candidate(
+                        self.keys(),
+                        party_one_sieving_message,
+                    )?;
+                Ok((
+                    PartyTwoKeyGenStage::SievingConversion {
+                        witness,
+                        first_message: first_message.clone(),
+                        sieving_message: sieving_message.clone(),
+                    },
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::SievedCandidate(
+                        sieving_message,
+                        first_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyTwoKeyGenStage::SievingConversion {
+                    witness,
+                    first_message,
+                    sieving_message,
+                },
+                PartyOneKeyGenMsg::Wait,
+            ) => Ok((
+                PartyTwoKeyGenStage::SievedCandidate {
+                    witness,
+                    first_message,
+                    sieving_message,
+                },
+                PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::Wait),
+            )),
+
+            (
+                PartyTwoKeyGenStage::SievedCandidate {
+                    witness,
+                    first_message,
+                    sieving_message,
+                },
+                PartyOneKeyGenMsg::SievedCandidate(
+                    party_one_first_message,
+                    party_one_sieving_second_message,
+                ),
+            ) => {
+                PartyTwoCandidateGeneration::verify_party_one_sieved_candidate(
+                    self.keys(),
+                    &sieving_message,
+                    party_one_first_message,
+                    party_one_sieving_second_message,
+                )?;
+                let ciphertexts =
+                    PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+                        self.keys(),
+                        party_one_first_message,
+                        &first_message,
+                    )?;
+                let candidate = PartyTwoCandidate {
+                    witness,
+                    first_message,
+                    party_one_first_message: party_one_first_message.clone(),
+                    ciphertexts,
+                };
+                self.start_trial_division(candidate)
+            }
+
+            (
+                PartyTwoKeyGenStage::TrialDivision {
+                    candidate,
+                    alpha_index,
+                    second_message,
+                },
+                PartyOneKeyGenMsg::TrialDivision(party_one_second_message),
+            ) => {
+                let alpha_vec = sieving_batch(&self.params, alpha_index);
+                let (third_message, c_alpha_vec, c_alpha_tilde_vec) =
+                    PartyTwoCandidateGeneration::verify_party_one_batch_second_message_and_partial_decrypt(
+                        party_one_second_message,
+                        &second_message,
+                        &alpha_vec,
+                        self.keys(),
+                        &candidate.ciphertexts,
+                    )?;
+                Ok((
+                    PartyTwoKeyGenStage::TrialDivisionDecrypt {
+                        candidate,
+                        alpha_index,
+                        c_alpha_vec,
+                        c_alpha_tilde_vec,
+                    },
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::TrialDivisionDecrypt(
+                        third_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyTwoKeyGenStage::TrialDivisionDecrypt {
+                    candidate,
+                    alpha_index,
+                    c_alpha_vec,
+                    c_alpha_tilde_vec,
+                },
+                PartyOneKeyGenMsg::TrialDivisionDecrypt(party_one_third_message),
+            ) => {
+                let coprime_vec = PartyTwoCandidateGeneration::verify_party_one_batch_third_message_full_decrypt_and_conclude_division(
+                    &c_alpha_vec,
+                    &c_alpha_tilde_vec,
+                    party_one_third_message,
+                    &sieving_batch(&self.params, alpha_index),
+                    self.keys(),
+                )?;
+                self.stats.primes_sieved += coprime_vec.len();
+                if coprime_vec.iter().any(|coprime| !coprime) {
+                    self.new_candidate()
+                } else if !is_last_sieving_batch(&self.params, alpha_index) {
+                    self.trial_division(candidate, alpha_index + TRIAL_DIVISION_BATCH_SIZE)
+                } else {
+                    self.accept_candidate(candidate)
+                }
+            }
+
+            (
+                PartyTwoKeyGenStage::JointModulusFirst {
+                    q_candidate,
+                    witness,
+                    first_message,
+                },
+                PartyOneKeyGenMsg::JointModulusFirst(party_one_first_message),
+            ) => {
+                let p_candidate = self.p_candidate();
+                let second_message =
+                    PartyTwoJointModulus::verify_party_one_first_message_and_compute_cross_terms(
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &witness,
+                        &p_candidate.ciphertexts,
+                        &q_candidate.ciphertexts,
+                        &first_message,
+                        party_one_first_message,
+                    )?;
+                Ok((
+                    PartyTwoKeyGenStage::JointModulusSecond {
+                        q_candidate,
+                        first_message,
+                        party_one_first_message: party_one_first_message.clone(),
+                        second_message: second_message.clone(),
+                    },
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::JointModulusSecond(
+                        second_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyTwoKeyGenStage::JointModulusSecond {
+                    q_candidate,
+                    first_message,
+                    party_one_first_message,
+                    second_message,
+                },
+                PartyOneKeyGenMsg::JointModulusSecond(party_one_second_message),
+            ) => {
+                let third_message =
+                    PartyTwoJointModulus::verify_party_one_second_message_and_decrypt_modulus(
+                        self.keys(),
+                        &self.p_candidate().ciphertexts,
+                        &q_candidate.ciphertexts,
+                        &first_message,
+                        &party_one_first_message,
+                        party_one_second_message,
+                    )?;
+                Ok((
+                    PartyTwoKeyGenStage::JointModulusThird {
+                        q_candidate,
+                        party_one_first_message,
+                        second_message,
+                        third_message: third_message.clone(),
+                    },
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::JointModulusThird(
+                        third_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyTwoKeyGenStage::JointModulusThird {
+                    q_candidate,
+                    party_one_first_message,
+                    second_message,
+                    third_message,
+                },
+                PartyOneKeyGenMsg::JointModulusThird(party_one_third_message),
+            ) => {
+                let joint_modulus =
+                    PartyTwoJointModulus::verify_party_one_third_message_and_output_joint_modulus(
+                        self.keys(),
+                        &second_message,
+                        &third_message,
+                        &party_one_first_message,
+                        party_one_third_message,
+                    )?;
+                self.stats.moduli_tested += 1;
+                let p_candidate = self.p_candidate();
+                let first_message =
+                    PartyTwoBiprimalityTest::compute_exponentiations_and_first_message(
+                        &joint_modulus.n,
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &p_candidate.first_message,
+                        &q_candidate.first_message,
+                    )?;
+                Ok((
+                    PartyTwoKeyGenStage::BiprimalityTest {
+                        q_candidate,
+                        joint_modulus,
+                        first_message: first_message.clone(),
+                    },
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::BiprimalityTest(first_message)),
+                ))
+            }
+
+            (
+                PartyTwoKeyGenStage::BiprimalityTest {
+                    q_candidate,
+                    joint_modulus,
+                    first_message,
+                },
+                PartyOneKeyGenMsg::BiprimalityTest(party_one_first_message),
+            ) => {
+                let p_candidate = self.p_candidate();
+                let biprime =
+                    PartyTwoBiprimalityTest::verify_party_one_first_message_and_conclude_biprimality(
+                        &joint_modulus.n,
+                        self.keys(),
+                        &p_candidate.party_one_first_message,
+                        &q_candidate.party_one_first_message,
+                        party_one_first_message,
+                        &first_message,
+                    )?;
+                if !biprime {
+                    self.p_candidate = None;
+                    return self.new_candidate();
+                }
+
+                let first_message =
+                    PartyTwoPrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+                        &self.e,
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &p_candidate.ciphertexts,
+                        &q_candidate.ciphertexts,
+                    )?;
+                Ok((
+                    PartyTwoKeyGenStage::PrivateExponentShareFirst {
+                        q_candidate,
+                        joint_modulus,
+                        first_message: first_message.clone(),
+                    },
+                    PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::PrivateExponentShareFirst(
+                        first_message,
+                    )),
+                ))
+            }
+
+            (
+                PartyTwoKeyGenStage::PrivateExponentShareFirst {
+                    q_candidate,
+                    joint_modulus,
+                    first_message,
+                },
+                PartyOneKeyGenMsg::PrivateExponentShareFirst(party_one_first_message),
+            ) => {
+                let p_candidate = self.p_candidate();
+                let result =
+                    PartyTwoPrivateExponentShare::verify_party_one_first_message_and_compute_exponent_share(
+                        &self.e,
+                        &joint_modulus,
+                        self.keys(),
+                        &p_candidate.witness,
+                        &q_candidate.witness,
+                        &p_candidate.ciphertexts,
+                        &q_candidate.ciphertexts,
+                        &first_message,
+                        party_one_first_message,
+                    );
+                match result {
+                    Ok((witness, second_message)) => Ok((
+                        PartyTwoKeyGenStage::PrivateExponentShareSecond {
+                            joint_modulus,
+                            witness,
+                            second_message: second_message.clone(),
+                        },
+                        PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::PrivateExponentShareSecond(
+                            second_message,
+                        )),
+                    )),
+                    // e | phi(N): N cannot be used with this public exponent
+                    Err(TwoPartyRSAError::InvalidPublicExponent) => {
+                        self.p_candidate = None;
+                        self.new_candidate()
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+
+            (
+                PartyTwoKeyGenStage::PrivateExponentShareSecond {
+                    joint_modulus,
+                    witness,
+                    second_message,
+                },
+                PartyOneKeyGenMsg::PrivateExponentShareSecond(party_one_second_message),
+            ) => {
+                let key_share =
+                    PartyTwoPrivateExponentShare::verify_party_one_second_message_and_output_key_share(
+                        &self.e,
+                        &joint_modulus,
+                        witness,
+                        &second_message,
+                        party_one_second_message,
+                    )?;
+                self.p_candidate = None;
+                Ok((
+                    PartyTwoKeyGenStage::Done,
+                    PartyTwoKeyGenStep::Done(key_share),
+                ))
+            }
+
+            _ => Err(TwoPartyRSAError::UnexpectedMessage),
+        }
+    }
+
+    fn keys(&self) -> &PartyTwoKeySetup {
+        self.keys
+            .as_ref()
+            .expect("key setup is done before any other round")
+    }
+
+    fn p_candidate(&self) -> &PartyTwoCandidate {
+        self.p_candidate
+            .as_ref()
+            .expect("p is fixed before the joint modulus rounds")
+    }
+
+    // generates shares of a new candidate for p, or for q once p passed trial division. With
+    // distributed sieving party two waits for party one's multiplicative share instead.
+    fn new_candidate(
+        &mut self,
+    ) -> Result<(PartyTwoKeyGenStage, PartyTwoKeyGenStep), TwoPartyRSAError> {
+        self.stats.candidates_tried += 1;
+        if self.params.distributed_sieving {
+            return Ok((
+                PartyTwoKeyGenStage::CandidateSieving,
+                PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::Wait),
+            ));
+        }
+        let (witness, first_message) =
+            PartyTwoCandidateGeneration::generate_shares_of_candidate(self.keys())?;
+        Ok((
+            PartyTwoKeyGenStage::CandidateGeneration {
+                witness,
+                first_message: first_message.clone(),
+            },
+            PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::CandidateGeneration(first_message)),
+        ))
+    }
+
+    // a candidate that passed trial division becomes p, or gives N together with p
+    fn accept_candidate(
+        &mut self,
+        candidate: PartyTwoCandidate,
+    ) -> Result<(PartyTwoKeyGenStage, PartyTwoKeyGenStep), TwoPartyRSAError> {
+        if self.p_candidate.is_none() {
+            self.p_candidate = Some(candidate);
+            self.new_candidate()
+        } else {
+            self.joint_modulus(candidate)
+        }
+    }
+
+    // sieved candidates may have no small prime left to divide by
+    fn start_trial_division(
+        &mut self,
+        candidate: PartyTwoCandidate,
+    ) -> Result<(PartyTwoKeyGenStage, PartyTwoKeyGenStep), TwoPartyRSAError> {
+        let alpha_index = first_trial_division_index(&self.params);
+        if alpha_index < self.params.trial_division_end() {
+            self.trial_division(candidate, alpha_index)
+        } else {
+            self.accept_candidate(candidate)
+        }
+    }
+
+    fn trial_division(
+        &mut self,
+        candidate: PartyTwoCandidate,
+        alpha_index: usize,
+    ) -> Result<(PartyTwoKeyGenStage, PartyTwoKeyGenStep), TwoPartyRSAError> {
+        let alpha_vec = sieving_batch(&self.params, alpha_index);
+        let second_message = PartyTwoCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            self.keys(),
+            &candidate.ciphertexts,
+            &candidate.witness,
+        )?;
+        Ok((
+            PartyTwoKeyGenStage::TrialDivision {
+                candidate,
+                alpha_index,
+                second_message: second_message.clone(),
+            },
+            PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::TrialDivision(second_message)),
+        ))
+    }
+
+    fn joint_modulus(
+        &mut self,
+        q_candidate: PartyTwoCandidate,
+    ) -> Result<(PartyTwoKeyGenStage, PartyTwoKeyGenStep), TwoPartyRSAError> {
+        let p_candidate = self.p_candidate();
+        let (witness, first_message) = PartyTwoJointModulus::encrypt_shares_and_first_message(
+            self.keys(),
+            &p_candidate.witness,
+            &q_candidate.witness,
+            &p_candidate.ciphertexts,
+            &q_candidate.ciphertexts,
+        )?;
+        Ok((
+            PartyTwoKeyGenStage::JointModulusFirst {
+                q_candidate,
+                witness,
+                first_message: first_message.clone(),
+            },
+            PartyTwoKeyGenStep::Message(PartyTwoKeyGenMsg::JointModulusFirst(first_message)),
+        ))
+    }
+}
+
+/// State machine for party two over HmrtMessage envelopes. Messages from another session, from
+/// the wrong sender, replayed or ahead of the current round are rejected before they reach the
+/// key generation, which keeps all intermediate values internally.
+pub struct PartyTwoState {
+    session_id: SessionId,
+    round: usize,
+    keygen: PartyTwoHmrtKeyGen,
+    key_share: Option<PartyTwoRSAKeyShare>,
+    aborted: bool,
+}
+
+impl PartyTwoState {
+    /// Starts key generation for the default public exponent, returns the message of round 0
+    pub fn new(session_id: SessionId) -> (Self, HmrtMessage) {
+        Self::with_public_exponent(session_id, &BigInt::from(DEFAULT_PUBLIC_EXPONENT))
+    }
+
+    pub fn with_public_exponent(session_id: SessionId, e: &BigInt) -> (Self, HmrtMessage) {
+        Self::with_params(session_id, &HmrtParams::default(), e)
+            .expect("default parameters are valid")
+    }
+
+    pub fn with_params(
+        session_id: SessionId,
+        params: &HmrtParams,
+        e: &BigInt,
+    ) -> Result<(Self, HmrtMessage), TwoPartyRSAError> {
+        let (keygen, msg) = PartyTwoHmrtKeyGen::with_params(session_id, params, e)?;
+        let state = PartyTwoState {
+            session_id,
+            round: 0,
+            keygen,
+            key_share: None,
+            aborted: false,
+        };
+        let message = HmrtMessage::PartyTwo {
+            session_id,
+            round: 0,
+            msg,
+        };
+        Ok((state, message))
+    }
+
+    pub fn session_id(&self) -> &SessionId {
+        &self.session_id
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn stats(&self) -> &HmrtKeyGenStats {
+        self.keygen.stats()
+    }
+
+    pub fn key_share(&self) -> Option<&PartyTwoRSAKeyShare> {
+        self.key_share.as_ref()
+    }
+
+    /// Returns the message for the next round, or None once the key share is ready.
+    /// A message that fails verification aborts the key generation.
+    pub fn handle_message(
+        &mut self,
+        message: &HmrtMessage,
+    ) -> Result<Option<HmrtMessage>, TwoPartyRSAError> {
+        if self.aborted {
+            return Err(TwoPartyRSAError::KeyGenAborted);
+        }
+        check_envelope(message, &self.session_id, HmrtParty::PartyOne, self.round)?;
+        if self.key_share.is_some() {
+            return Err(TwoPartyRSAError::UnexpectedMessage);
+        }
+        let msg = match message {
+            HmrtMessage::PartyOne { msg, .. } => msg,
+            _ => return Err(TwoPartyRSAError::UnexpectedSender),
+        };
+
+        match self.keygen.handle_party_one_message(msg) {
+            Ok(PartyTwoKeyGenStep::Message(msg)) => {
+                self.round += 1;
+                Ok(Some(HmrtMessage::PartyTwo {
+                    session_id: self.session_id,
+                    round: self.round,
+                    msg,
+                }))
+            }
+            Ok(PartyTwoKeyGenStep::Done(key_share)) => {
+                self.round += 1;
+                self.key_share = Some(key_share);
+                Ok(None)
+            }
+            Err(err) => {
+                self.aborted = true;
+                Err(err)
+            }
+        }
+    }
+}
@@ -1,11 +1,52 @@
-use crate::utlities::ddh_proof::DDHProof;
-use crate::utlities::ddh_proof::DDHStatement;
-use crate::utlities::ddh_proof::DDHWitness;
-use crate::utlities::ddh_proof::NISigmaProof;
+use crate::protocols::bulletproofs::bulletproof::BulletproofRangeProof;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtParty;
+use crate::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use crate::protocols::two_party_rsa::padding::i2osp;
+use crate::protocols::two_party_rsa::padding::rsaes_oaep_decode;
+use crate::protocols::two_party_rsa::padding::rsaes_oaep_encode;
+use crate::protocols::two_party_rsa::padding::SignatureEncoding;
+use crate::utlities::affine_proof::AffineProof;
+use crate::utlities::affine_proof::AffineStatement;
+use crate::utlities::affine_proof::AffineWitness;
+use crate::utlities::affine_proof::ExponentCommitment;
+use crate::utlities::exponent_elgamal::ExponentElGamalScheme;
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::hash_to_zn;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::homomorphic_scheme::PaillierScheme;
+use crate::utlities::jacobi;
+use crate::utlities::mod_proof::ModProof;
+use crate::utlities::mod_proof::ModRangeProof;
+use crate::utlities::mod_proof::ModStatement;
+use crate::utlities::mod_proof::ModWitness;
+use crate::utlities::rsa_ddh_proof::RSADDHStatement;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::SMALL_PRIMES;
+use crate::HomomorphicSchemeError;
+use crate::ProofError;
+use crate::ProtocolAbort;
+use crate::ProtocolRound;
+use crate::TwoPartyRSAError;
+use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::Samplable;
 use curv::BigInt;
+use elgamal::rfc7919_groups::SupportedGroups;
 use elgamal::ElGamalCiphertext;
 use elgamal::ElGamalPP;
+use elgamal::ElGamalPublicKey;
+use elgamal::ExponentElGamal;
+use paillier::traits::Decrypt;
+use paillier::traits::EncryptWithChosenRandomness;
+use paillier::DecryptionKey;
+use paillier::EncryptionKey;
+use paillier::Paillier;
+use paillier::Randomness;
+use paillier::RawCiphertext;
+use paillier::RawPlaintext;
+use serde::Serialize;
+use std::cmp;
 
+pub mod keygen;
 pub mod party_one;
 pub mod party_two;
 
@@ -15,25 +56,1113 @@ pub struct CiphertextPair {
     pub c1: ElGamalCiphertext,
 }
 
-fn gen_ddh_containers(
-    x: BigInt,
-    g1: &BigInt,
-    h1: &BigInt,
-    g2: &BigInt,
-    h2: &BigInt,
-    pp: &ElGamalPP,
-) -> (DDHWitness, DDHStatement, DDHProof) {
-    let witness = DDHWitness { x };
-    let statement = DDHStatement {
-        pp: pp.clone(),
-        g1: g1.clone(),
-        h1: h1.clone(),
-        g2: g2.clone(),
-        h2: h2.clone(),
+/// Normalized candidate shares of the Paillier variant of candidate generation: c0 encrypts
+/// 4 * p_0 + 3 under party one's Paillier key and c1 encrypts 4 * p_1 under party two's key.
+/// Divisibility by alpha is then decided by the zero tests of PaillierZeroTest.
+/// Each share is under the key of its owner rather than the remote party's: unlike the joint
+/// ElGamal key, a Paillier key is not shared, so the remote party could decrypt its share. Under
+/// the owner's key the share stays hidden and the remote party can still compute on it.
+/// The variant only exists to benchmark trial division against the ElGamal one and stops there:
+/// joint modulus, biprimality and the key generation orchestrators take ElGamal encrypted
+/// shares, so a candidate that passes it cannot be carried further.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaillierCiphertextPair {
+    pub c0: BigInt,
+    pub c1: BigInt,
+}
+
+/// Trial division of the Paillier variant. The randomizing party holds Enc_D(a_D), the residue of
+/// the decrypting party under its key, and its own residue a_P. It sends
+/// c_masked = Enc_D(r * (a_D + a_P + k) + Q * t) for a public offset k, r in [1, Q) and a mask t,
+/// with r, t and s = r * (a_P + k) + Q * t committed under its own key. The decrypting party
+/// learns whether a_D + a_P + k = 0 mod Q and nothing else: r hides the residue mod Q and t the
+/// rest. pi_s proves s against the committed residue of the randomizing party, pi_masked proves
+/// c_masked = Enc_D(a_D)^r * Enc_D(s).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaillierZeroTest {
+    pub c_r: BigInt,
+    pub c_t: BigInt,
+    pub c_s: BigInt,
+    pub c_masked: BigInt,
+    pub pi_s: AffineProof,
+    pub pi_masked: AffineProof,
+}
+
+/// Zero test of trial division under the joint exponent ElGamal key. A party raises the shared
+/// ciphertext c to a secret r in [1, q) and partially decrypts c_random = c^r: c_random encrypts
+/// zero iff c does, and any other plaintext becomes uniform in Z_q. pi_random proves the
+/// randomization and pi_dec the decryption share, the other party completes the decryption.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(bound = "")]
+pub struct ElGamalZeroTest<S: ExponentElGamalScheme = FFDHEExponentElGamal> {
+    pub c_random: S::Ciphertext,
+    pub pi_random: S::EqualityProof,
+    pub partial_dec: S::DecryptionShare,
+    pub pi_dec: S::EqualityProof,
+}
+
+/// The RSA modulus N = pq, jointly computed from the additive shares of p and q
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct JointModulus {
+    pub n: BigInt,
+}
+
+/// RSA signature s = EM^d mod N, where EM is the encoded message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RSASignature {
+    pub s: BigInt,
+}
+
+impl RSASignature {
+    // standard RSA verification: s^e mod N must be a valid encoding of the message
+    pub fn verify(
+        &self,
+        n: &BigInt,
+        e: &BigInt,
+        message: &[u8],
+        encoding: &SignatureEncoding,
+    ) -> Result<(), TwoPartyRSAError> {
+        if self.s <= BigInt::zero() || &self.s >= n {
+            return Err(TwoPartyRSAError::InvalidSignature);
+        }
+        let em = BigInt::mod_pow(&self.s, e, n);
+        match encoding.verify(message, &em, n) {
+            true => Ok(()),
+            false => Err(TwoPartyRSAError::InvalidSignature),
+        }
+    }
+}
+
+/// RFC 7919 finite field groups for the joint ElGamal key
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ElGamalGroup {
+    FFDHE2048,
+    FFDHE3072,
+    FFDHE4096,
+    FFDHE6144,
+    FFDHE8192,
+}
+
+impl ElGamalGroup {
+    pub fn bit_length(&self) -> usize {
+        match self {
+            ElGamalGroup::FFDHE2048 => 2048,
+            ElGamalGroup::FFDHE3072 => 3072,
+            ElGamalGroup::FFDHE4096 => 4096,
+            ElGamalGroup::FFDHE6144 => 6144,
+            ElGamalGroup::FFDHE8192 => 8192,
+        }
+    }
+
+    pub fn public_parameters(&self) -> ElGamalPP {
+        let group = match self {
+            ElGamalGroup::FFDHE2048 => SupportedGroups::FFDHE2048,
+            ElGamalGroup::FFDHE3072 => SupportedGroups::FFDHE3072,
+            ElGamalGroup::FFDHE4096 => SupportedGroups::FFDHE4096,
+            ElGamalGroup::FFDHE6144 => SupportedGroups::FFDHE6144,
+            ElGamalGroup::FFDHE8192 => SupportedGroups::FFDHE8192,
+        };
+        ElGamalPP::generate_from_rfc7919(group)
+    }
+}
+
+/// Range proofs inside the ModProofs of trial division
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ModRangeProofBackend {
+    /// `TightRangeProof`: one OR-proof per bit of the bound
+    TightRangeProof,
+    /// `BulletproofRangeProof`: logarithmic in the bound, and sound because neither party knows
+    /// the discrete log of the joint ElGamal key
+    Bulletproof,
+}
+
+/// ModProof of the residue of a candidate share modulo a small prime, with the range proofs of
+/// `HmrtParams::mod_range_proof`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TrialDivisionModProof {
+    TightRangeProof(ModProof),
+    Bulletproof(ModProof<FFDHEExponentElGamal, BulletproofRangeProof>),
+}
+
+impl TrialDivisionModProof {
+    // Residue of the normalized share a = Dec(c) and its proof, with r_a the randomness of c
+    fn prove(
+        params: &HmrtParams,
+        pk: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+        a: &BigInt,
+        r_a: &BigInt,
+        alpha: &BigInt,
+        context: &ProofContext,
+    ) -> Result<(ElGamalCiphertext, Self), TwoPartyRSAError> {
+        match params.mod_range_proof {
+            ModRangeProofBackend::TightRangeProof => {
+                let (c_alpha, pi_mod) = trial_division_residue::<FFDHEExponentElGamal, _>(
+                    params, pk, c, a, r_a, alpha, context,
+                )?;
+                Ok((c_alpha, TrialDivisionModProof::TightRangeProof(pi_mod)))
+            }
+            ModRangeProofBackend::Bulletproof => {
+                let (c_alpha, pi_mod) = trial_division_residue::<FFDHEExponentElGamal, _>(
+                    params, pk, c, a, r_a, alpha, context,
+                )?;
+                Ok((c_alpha, TrialDivisionModProof::Bulletproof(pi_mod)))
+            }
+        }
+    }
+
+    /// Fails if the proof uses other range proofs than the ones of params
+    pub fn verify(
+        &self,
+        params: &HmrtParams,
+        statement: &ModStatement,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        match (self, params.mod_range_proof) {
+            (
+                TrialDivisionModProof::TightRangeProof(pi_mod),
+                ModRangeProofBackend::TightRangeProof,
+            ) => pi_mod.verify(statement, context),
+            (TrialDivisionModProof::Bulletproof(pi_mod), ModRangeProofBackend::Bulletproof) => {
+                pi_mod.verify(statement, context)
+            }
+            _ => Err(ProofError::ModProofError),
+        }
+    }
+}
+
+/// Parameters of the key generation, both parties must use the same ones. The default
+/// generates a 2048 bits modulus.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct HmrtParams {
+    /// bit length of the RSA modulus N, each prime is half of it
+    pub candidate_bit_length: usize,
+    /// bit length of the Paillier moduli used to compute N
+    pub paillier_modulus: usize,
+    /// group of the joint ElGamal key encrypting the shares of the candidates. Party one
+    /// proposes it, party two accepts it as the smallest group
+    pub elgamal_group: ElGamalGroup,
+    /// repetitions of the cut-and-choose equal secret proofs of the biprimality test
+    pub sec_param: usize,
+    /// statistical hiding of the cut-and-choose equal secret proofs, in bits
+    pub kapa: usize,
+    /// candidates are trial divided by every odd prime of SMALL_PRIMES up to this bound
+    pub trial_division_bound: u32,
+    /// sample the candidates of the key generation by distributed sieving: they are coprime to
+    /// the primes of M (see sieving_modulus) and trial division starts after them
+    pub distributed_sieving: bool,
+    /// range proofs of the ModProofs of trial division
+    pub mod_range_proof: ModRangeProofBackend,
+}
+
+// an AffineProof bounds an exponent of b bits only to b + |challenge| + 80 + 1 bits
+const AFFINE_PROOF_SLACK: usize = 256 + 80 + 1;
+
+impl Default for HmrtParams {
+    fn default() -> Self {
+        HmrtParams {
+            candidate_bit_length: 2048,
+            paillier_modulus: 3072,
+            elgamal_group: ElGamalGroup::FFDHE2048,
+            sec_param: 120,
+            kapa: 100,
+            trial_division_bound: SMALL_PRIMES[SMALL_PRIMES.len() - 1],
+            distributed_sieving: false,
+            mod_range_proof: ModRangeProofBackend::TightRangeProof,
+        }
+    }
+}
+
+impl HmrtParams {
+    /// Checks that the parameters fit together: the ElGamal group must hold a share of a
+    /// candidate and the Paillier plaintext space must hold N, as well as the quotients of the
+    /// sieved shares, in all cases with the slack of the proofs bounding them.
+    pub fn validate(&self) -> Result<(), TwoPartyRSAError> {
+        let share_bit_length = self.candidate_bit_length / 2;
+        if self.candidate_bit_length < 1024
+            || self.candidate_bit_length % 2 != 0
+            || self.elgamal_group.bit_length() <= share_bit_length + AFFINE_PROOF_SLACK
+            || self.paillier_modulus < self.candidate_bit_length + 2 * AFFINE_PROOF_SLACK + 80
+            || self.sec_param < 40
+            || self.kapa < 100
+            || self.trial_division_bound < SMALL_PRIMES[1]
+            || self.trial_division_bound > SMALL_PRIMES[SMALL_PRIMES.len() - 1]
+        {
+            return Err(TwoPartyRSAError::InvalidHmrtParams);
+        }
+        // the relations proven for sieved shares must hold over the integers
+        let (m, _) = sieving_modulus(self);
+        if m.bit_length() + sieved_share_quotient_bit_length(self, &m) + AFFINE_PROOF_SLACK + 2
+            >= self.paillier_modulus - 1
+        {
+            return Err(TwoPartyRSAError::InvalidHmrtParams);
+        }
+        Ok(())
+    }
+
+    /// Index in SMALL_PRIMES after the last prime used for trial division
+    pub fn trial_division_end(&self) -> usize {
+        SMALL_PRIMES
+            .iter()
+            .position(|alpha| *alpha > self.trial_division_bound)
+            .unwrap_or(SMALL_PRIMES.len())
+    }
+
+    // exact range of the shares p_i of a candidate, their range proofs have no slack
+    fn share_range(&self) -> BigInt {
+        BigInt::from(2).pow((self.candidate_bit_length / 2 - 2) as u32)
+    }
+
+    // bound on the shares of a candidate once scaled, 4 * p_i + 3 < 4 * share_range
+    fn share_bound(&self) -> BigInt {
+        BigInt::from(2).pow((self.candidate_bit_length / 2) as u32)
+    }
+}
+
+// blames the counterparty for a proof in its message that failed to verify
+fn protocol_abort<M: Serialize>(
+    round: ProtocolRound,
+    proof: &str,
+    alpha: Option<&BigInt>,
+    message: &M,
+    proof_error: Option<ProofError>,
+) -> TwoPartyRSAError {
+    TwoPartyRSAError::ProtocolAbort(ProtocolAbort {
+        round,
+        proof: proof.to_string(),
+        alpha: alpha.cloned(),
+        message: serde_json::to_string(message).unwrap_or_default(),
+        proof_error,
+    })
+}
+
+// Context of the proofs of the prover in a session: a proof does not verify in another session,
+// nor as a proof of the other party, so it cannot be replayed or reflected back to its prover.
+fn proof_context(session_id: &SessionId, prover: HmrtParty) -> ProofContext {
+    let party_id = match prover {
+        HmrtParty::PartyOne => 1,
+        HmrtParty::PartyTwo => 2,
+    };
+    ProofContext::new(&session_id.0, party_id)
+}
+
+// Statement for c_alpha = Enc(a mod alpha), where c = Enc(a) is a normalized candidate share
+fn trial_division_residue_statement<S: ExponentElGamalScheme>(
+    params: &HmrtParams,
+    pk: &S::PublicKey,
+    c: &S::Ciphertext,
+    c_alpha: &S::Ciphertext,
+    alpha: &BigInt,
+) -> ModStatement<S> {
+    ModStatement {
+        c: c.clone(),
+        c_prime: c_alpha.clone(),
+        modulus_p: alpha.clone(),
+        upper_bound_m: params.share_bound(), // n/2 instead of n/2-2 as is written in the paper : we suspect paper has a typo and do not consider the fact that ciphertexts and plaintext are scaled by mul4
+        pk: pk.clone(),
+    }
+}
+
+// Residue of the normalized share a = Dec(c), with r_a the randomness of c
+fn trial_division_residue<S: ExponentElGamalScheme, R: ModRangeProof<S>>(
+    params: &HmrtParams,
+    pk: &S::PublicKey,
+    c: &S::Ciphertext,
+    a: &BigInt,
+    r_a: &BigInt,
+    alpha: &BigInt,
+    context: &ProofContext,
+) -> Result<(S::Ciphertext, ModProof<S, R>), TwoPartyRSAError> {
+    let a_mod_alpha = a.mod_floor(alpha);
+    let r_alpha = S::sample_randomness(pk);
+    let c_alpha = match S::encrypt_with_randomness(pk, &a_mod_alpha, &r_alpha) {
+        Ok(c_alpha) => c_alpha,
+        Err(_) => return Err(TwoPartyRSAError::CandidateGenerationEncError),
+    };
+    let statement = trial_division_residue_statement::<S>(params, pk, c, &c_alpha, alpha);
+    let witness = ModWitness {
+        r_a: r_a.clone(),
+        a: a.clone(),
+        r_b: r_alpha,
+        b: a_mod_alpha,
+    };
+    match ModProof::prove(&witness, &statement, context) {
+        Ok(pi_mod) => Ok((c_alpha, pi_mod)),
+        Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+    }
+}
+
+// c_alpha = Enc(a_0 + a_1) and c_alpha_tilde = Enc(a_0 + a_1 - alpha) for the residues a_i of the
+// shares: alpha divides the candidate iff one of them encrypts zero
+fn trial_division_zero_test_ciphertexts<S: ExponentElGamalScheme>(
+    pk: &S::PublicKey,
+    c_0_alpha: &S::Ciphertext,
+    c_1_alpha: &S::Ciphertext,
+    alpha: &BigInt,
+) -> Result<(S::Ciphertext, S::Ciphertext), HomomorphicSchemeError> {
+    let c_alpha = S::add(pk, c_0_alpha, c_1_alpha)?;
+    // Enc(-alpha) is known to both parties therefore we use a predefined randomness known to both (r = 2)
+    let enc_alpha = S::encrypt_with_randomness(pk, alpha, &BigInt::from(2))?;
+    let enc_minus_alpha = S::mul(pk, &enc_alpha, &-BigInt::one())?;
+    let c_alpha_tilde = S::add(pk, &c_alpha, &enc_minus_alpha)?;
+    Ok((c_alpha, c_alpha_tilde))
+}
+
+// Local half of the zero test of c, local_pk = g^x_i is the public key of the share sk = x_i of
+// the joint private key
+fn elgamal_zero_test<S: ExponentElGamalScheme>(
+    joint_pk: &S::PublicKey,
+    local_pk: &S::PublicKey,
+    sk: &S::PrivateKey,
+    c: &S::Ciphertext,
+    context: &ProofContext,
+) -> Result<ElGamalZeroTest<S>, HomomorphicSchemeError> {
+    let r = BigInt::sample_range(&BigInt::one(), &S::plaintext_modulus(joint_pk));
+    let c_random = S::mul(joint_pk, c, &r)?;
+    let pi_random = S::prove_randomization(joint_pk, c, &c_random, &r, context);
+    let partial_dec = S::partial_decrypt(&c_random, sk);
+    let pi_dec = S::prove_partial_decryption(local_pk, &c_random, &partial_dec, sk, context);
+    Ok(ElGamalZeroTest {
+        c_random,
+        pi_random,
+        partial_dec,
+        pi_dec,
+    })
+}
+
+// Verifies the zero test of the remote party on c and completes the decryption with the local
+// share. An error names the field of the zero test that failed to verify.
+fn conclude_elgamal_zero_test<S: ExponentElGamalScheme>(
+    zero_test: &ElGamalZeroTest<S>,
+    c: &S::Ciphertext,
+    joint_pk: &S::PublicKey,
+    remote_pk: &S::PublicKey,
+    sk: &S::PrivateKey,
+    context: &ProofContext,
+) -> Result<bool, (&'static str, Option<ProofError>)> {
+    // the decryption below works in the group carried by the ciphertext
+    if S::validate_ciphertext(joint_pk, &zero_test.c_random).is_err() {
+        return Err(("c_random", None));
+    }
+    S::verify_randomization(
+        joint_pk,
+        c,
+        &zero_test.c_random,
+        &zero_test.pi_random,
+        context,
+    )
+    .map_err(|err| ("pi_random", Some(err)))?;
+    S::verify_partial_decryption(
+        remote_pk,
+        &zero_test.c_random,
+        &zero_test.partial_dec,
+        &zero_test.pi_dec,
+        context,
+    )
+    .map_err(|err| ("pi_dec", Some(err)))?;
+    let shares = [
+        S::partial_decrypt(&zero_test.c_random, sk),
+        zero_test.partial_dec.clone(),
+    ];
+    S::is_zero(&zero_test.c_random, &shares).map_err(|_| ("partial_dec", None))
+}
+
+// Statement for c = Enc_n(x) where x is the plaintext of the (normalized) ElGamal ciphertext c_x
+fn share_encryption_statement(
+    params: &HmrtParams,
+    n: &BigInt,
+    pk: &ElGamalPublicKey,
+    c_x: &ElGamalCiphertext,
+    c: &BigInt,
+) -> AffineStatement {
+    AffineStatement {
+        n: n.clone(),
+        bases: vec![n + BigInt::one()],
+        commitments: vec![ExponentCommitment::ElGamal {
+            pk: pk.clone(),
+            c: c_x.clone(),
+        }],
+        bit_bounds: vec![params.candidate_bit_length / 2],
+        ciphertext: c.clone(),
+    }
+}
+
+// Statement for c_pq = c_q^p * rho^n where p is the plaintext of the ElGamal ciphertext c_p
+fn share_product_statement(
+    params: &HmrtParams,
+    n: &BigInt,
+    pk: &ElGamalPublicKey,
+    c_p: &ElGamalCiphertext,
+    c_q: &BigInt,
+    c_pq: &BigInt,
+) -> AffineStatement {
+    AffineStatement {
+        n: n.clone(),
+        bases: vec![c_q.clone()],
+        commitments: vec![ExponentCommitment::ElGamal {
+            pk: pk.clone(),
+            c: c_p.clone(),
+        }],
+        bit_bounds: vec![params.candidate_bit_length / 2],
+        ciphertext: c_pq.clone(),
+    }
+}
+
+// Statement for the cross terms computed under the counter party key:
+// c_n = c_p_remote^q * c_q_remote^p * (1 + n_remote)^pq * rho^n_remote, where p, q are committed
+// in the local ElGamal ciphertexts and pq in the local Paillier ciphertext c_pq.
+#[allow(clippy::too_many_arguments)]
+fn cross_product_statement(
+    params: &HmrtParams,
+    n_remote: &BigInt,
+    c_p_remote: &BigInt,
+    c_q_remote: &BigInt,
+    pk: &ElGamalPublicKey,
+    c_p_local: &ElGamalCiphertext,
+    c_q_local: &ElGamalCiphertext,
+    n_local: &BigInt,
+    c_pq_local: &BigInt,
+    c_n: &BigInt,
+) -> AffineStatement {
+    AffineStatement {
+        n: n_remote.clone(),
+        bases: vec![
+            c_p_remote.clone(),
+            c_q_remote.clone(),
+            n_remote + BigInt::one(),
+        ],
+        commitments: vec![
+            ExponentCommitment::ElGamal {
+                pk: pk.clone(),
+                c: c_q_local.clone(),
+            },
+            ExponentCommitment::ElGamal {
+                pk: pk.clone(),
+                c: c_p_local.clone(),
+            },
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_pq_local.clone(),
+            },
+        ],
+        bit_bounds: vec![
+            params.candidate_bit_length / 2,
+            params.candidate_bit_length / 2,
+            params.candidate_bit_length,
+        ],
+        ciphertext: c_n.clone(),
+    }
+}
+
+/// Modulus M of distributed sieving: the product of the odd primes of SMALL_PRIMES, as many as
+/// fit in candidate_bit_length / 4 bits. Also returns the index in SMALL_PRIMES of the first
+/// prime that does not divide M, trial division of sieved candidates can start there.
+pub fn sieving_modulus(params: &HmrtParams) -> (BigInt, usize) {
+    let max_bit_length = params.candidate_bit_length / 4;
+    let mut m = BigInt::one();
+    let mut index = 1;
+    while index < SMALL_PRIMES.len() {
+        let next = &m * BigInt::from(SMALL_PRIMES[index]);
+        if next.bit_length() > max_bit_length {
+            break;
+        }
+        m = next;
+        index += 1;
+    }
+    (m, index)
+}
+
+// the mask of the sieving conversion must hide a * b for shares as large as an AffineProof allows
+fn sieving_mask_bit_length(m: &BigInt) -> usize {
+    2 * (m.bit_length() + AFFINE_PROOF_SLACK) + 80
+}
+
+fn sample_unit_mod(m: &BigInt) -> BigInt {
+    loop {
+        let a = BigInt::sample_below(m);
+        if a.gcd(m) == BigInt::one() {
+            return a;
+        }
+    }
+}
+
+// Statement for c_a = (1 + n)^a * rho^n with 0 <= a < M, c_a serves as its own commitment
+fn sieving_share_statement(n: &BigInt, c_a: &BigInt, m: &BigInt) -> AffineStatement {
+    AffineStatement {
+        n: n.clone(),
+        bases: vec![n + BigInt::one()],
+        commitments: vec![ExponentCommitment::Paillier {
+            n: n.clone(),
+            c: c_a.clone(),
+        }],
+        bit_bounds: vec![m.bit_length()],
+        ciphertext: c_a.clone(),
+    }
+}
+
+// Statement for the conversion of the multiplicative shares a, b into additive shares:
+// c_ab = c_a^b * (1 + n_remote)^s * rho^n_remote, where b and the mask s are committed in the
+// local Paillier ciphertexts c_b and c_s.
+fn sieving_conversion_statement(
+    n_remote: &BigInt,
+    c_a: &BigInt,
+    n_local: &BigInt,
+    c_b: &BigInt,
+    c_s: &BigInt,
+    m: &BigInt,
+    c_ab: &BigInt,
+) -> AffineStatement {
+    AffineStatement {
+        n: n_remote.clone(),
+        bases: vec![c_a.clone(), n_remote + BigInt::one()],
+        commitments: vec![
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_b.clone(),
+            },
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_s.clone(),
+            },
+        ],
+        bit_bounds: vec![m.bit_length(), sieving_mask_bit_length(m)],
+        ciphertext: c_ab.clone(),
+    }
+}
+
+// Share of a sieved candidate: (residue * 4^-1 mod M) + M * u. With residue_0 = x_0 - 3 and
+// residue_1 = x_1 the candidate is 4(p_0 + p_1) + 3 = x_0 + x_1 = a * b mod M.
+fn sieved_candidate_share(params: &HmrtParams, residue: &BigInt, m: &BigInt) -> BigInt {
+    let four_inv = BigInt::mod_inv(&BigInt::from(4), m);
+    let x = BigInt::mod_mul(&residue.mod_floor(m), &four_inv, m);
+    let u = BigInt::sample(params.candidate_bit_length / 2 - 3 - m.bit_length());
+    x + m * u
+}
+
+// bound on the quotient k of a sieved share statement, for shares as large as the sieving
+// conversion proof allows
+fn sieved_share_quotient_bit_length(params: &HmrtParams, m: &BigInt) -> usize {
+    cmp::max(
+        sieving_mask_bit_length(m) + AFFINE_PROOF_SLACK + 2 - m.bit_length(),
+        params.candidate_bit_length / 2,
+    ) + 1
+}
+
+// Statement for a sieved share p, committed in the ElGamal ciphertext c_p, and the quotient k,
+// committed in the Paillier ciphertext c_k: c_x * (1 + n)^x_offset = (1 + n)^(p_factor * p) *
+// (1 + n)^(M * k) * rho^n. Both sides are far below n, so the relation holds over the integers
+// and modulo M.
+#[allow(clippy::too_many_arguments)]
+fn sieved_share_statement(
+    params: &HmrtParams,
+    n: &BigInt,
+    c_x: &BigInt,
+    x_offset: &BigInt,
+    p_factor: &BigInt,
+    pk: &ElGamalPublicKey,
+    c_p: &ElGamalCiphertext,
+    c_k: &BigInt,
+    m: &BigInt,
+) -> AffineStatement {
+    let nn = n * n;
+    let one_plus_n = n + BigInt::one();
+    AffineStatement {
+        n: n.clone(),
+        bases: vec![
+            BigInt::mod_pow(&one_plus_n, p_factor, &nn),
+            BigInt::mod_pow(&one_plus_n, m, &nn),
+        ],
+        commitments: vec![
+            ExponentCommitment::ElGamal {
+                pk: pk.clone(),
+                c: c_p.clone(),
+            },
+            ExponentCommitment::Paillier {
+                n: n.clone(),
+                c: c_k.clone(),
+            },
+        ],
+        bit_bounds: vec![
+            params.candidate_bit_length / 2,
+            sieved_share_quotient_bit_length(params, m),
+        ],
+        ciphertext: BigInt::mod_mul(c_x, &BigInt::mod_pow(&one_plus_n, x_offset, &nn), &nn),
+    }
+}
+
+// Party one's sieved share p_0 against its decrypted share x_0 of c_ab (under its own key n):
+// x_0 + M * share_bound - 3 = 4 * p_0 + M * k. The offset keeps k positive, 4 * p_0 + 3 is
+// below share_bound.
+fn party_one_sieved_share_statement(
+    params: &HmrtParams,
+    n: &BigInt,
+    c_ab: &BigInt,
+    pk: &ElGamalPublicKey,
+    c_p: &ElGamalCiphertext,
+    c_k: &BigInt,
+    m: &BigInt,
+) -> AffineStatement {
+    let x_offset = m * params.share_bound() - BigInt::from(3);
+    sieved_share_statement(
+        params,
+        n,
+        c_ab,
+        &x_offset,
+        &BigInt::from(4),
+        pk,
+        c_p,
+        c_k,
+        m,
+    )
+}
+
+// Party two's sieved share p_1 against its mask s, committed in c_s (under its own key n):
+// s = M * k - 4 * p_1, with -4 taken mod n. The conversion proof bounds s far below n, so the
+// right hand side cannot wrap around.
+fn party_two_sieved_share_statement(
+    params: &HmrtParams,
+    n: &BigInt,
+    c_s: &BigInt,
+    pk: &ElGamalPublicKey,
+    c_p: &ElGamalCiphertext,
+    c_k: &BigInt,
+    m: &BigInt,
+) -> AffineStatement {
+    sieved_share_statement(
+        params,
+        n,
+        c_s,
+        &BigInt::zero(),
+        &(n - BigInt::from(4)),
+        pk,
+        c_p,
+        c_k,
+        m,
+    )
+}
+
+// Given a Paillier ciphertext c of a known plaintext m, recovers rho such that
+// c = (1 + n)^m * rho^n mod n^2. Revealing rho proves that c decrypts to m.
+fn paillier_extract_randomness(c: &BigInt, m: &BigInt, dk: &DecryptionKey) -> BigInt {
+    let n = &dk.p * &dk.q;
+    let nn = &n * &n;
+    let one_plus_n_minus_m = BigInt::mod_sub(&BigInt::one(), &BigInt::mod_mul(m, &n, &nn), &nn);
+    let rho_n = BigInt::mod_mul(c, &one_plus_n_minus_m, &nn).modulus(&n);
+    let phi = (&dk.p - BigInt::one()) * (&dk.q - BigInt::one());
+    let n_inv = BigInt::mod_inv(&n, &phi);
+    BigInt::mod_pow(&rho_n, &n_inv, &n)
+}
+
+// checks that rho opens c to m under modulus n
+fn paillier_verify_randomness(c: &BigInt, m: &BigInt, rho: &BigInt, n: &BigInt) -> bool {
+    let nn = n * n;
+    let one_plus_n_m = BigInt::mod_add(&BigInt::one(), &BigInt::mod_mul(m, n, &nn), &nn);
+    BigInt::mod_mul(&one_plus_n_m, &BigInt::mod_pow(rho, n, &nn), &nn) == c.modulus(&nn)
+}
+
+// Q of the Paillier zero test, the Mersenne prime 2^127 - 1. Residues of trial division are far
+// below it, so a sum of residues is zero mod Q iff it is zero.
+fn zero_test_modulus() -> BigInt {
+    BigInt::from(2).pow(ZERO_TEST_MODULUS_BIT_LENGTH as u32) - BigInt::one()
+}
+
+const ZERO_TEST_MODULUS_BIT_LENGTH: usize = 127;
+// t hides floor(r * (a_D + a_P + k) / Q) < 2^(|Q| + 1)
+const ZERO_TEST_MASK_BIT_LENGTH: usize = 2 * ZERO_TEST_MODULUS_BIT_LENGTH + 80;
+
+fn zero_test_exponent_bit_length() -> usize {
+    ZERO_TEST_MODULUS_BIT_LENGTH + ZERO_TEST_MASK_BIT_LENGTH + 1
+}
+
+// offset of the second zero test: a_D + a_P + Q - alpha = 0 mod Q iff a_D + a_P = alpha
+fn zero_test_tilde_offset(alpha: &BigInt) -> BigInt {
+    zero_test_modulus() - alpha
+}
+
+// c0 = Enc_0(p_0)^4 * Enc_0(3; 1) and c1 = Enc_1(p_1)^4, encrypting the shares of 4(p_0 + p_1) + 3
+fn normalize_paillier_ciphertexts(
+    party_one_ek: &EncryptionKey,
+    c_0: &BigInt,
+    party_two_ek: &EncryptionKey,
+    c_1: &BigInt,
+) -> Result<PaillierCiphertextPair, TwoPartyRSAError> {
+    let c_0_mul_4 = PaillierScheme::mul(party_one_ek, c_0, &BigInt::from(4));
+    let enc_3 =
+        PaillierScheme::encrypt_with_randomness(party_one_ek, &BigInt::from(3), &BigInt::one());
+    let c_1_mul_4 = PaillierScheme::mul(party_two_ek, c_1, &BigInt::from(4));
+    match (c_0_mul_4, enc_3, c_1_mul_4) {
+        (Ok(c_0_mul_4), Ok(enc_3), Ok(c_1_mul_4)) => {
+            match PaillierScheme::add(party_one_ek, &c_0_mul_4, &enc_3) {
+                Ok(c0) => Ok(PaillierCiphertextPair { c0, c1: c_1_mul_4 }),
+                Err(_) => Err(TwoPartyRSAError::CandidateGenerationEncError),
+            }
+        }
+        _ => Err(TwoPartyRSAError::CandidateGenerationEncError),
+    }
+}
+
+// Statement for c_s = (Enc_P(a_P) * (1 + n_P)^k)^r * ((1 + n_P)^Q)^t * rho^n_P, with r and t
+// committed in c_r and c_t
+fn zero_test_commitment_statement(
+    n_local: &BigInt,
+    c_local_alpha: &BigInt,
+    offset: &BigInt,
+    c_r: &BigInt,
+    c_t: &BigInt,
+    c_s: &BigInt,
+) -> AffineStatement {
+    let nn = n_local * n_local;
+    let one_plus_n = n_local + BigInt::one();
+    AffineStatement {
+        n: n_local.clone(),
+        bases: vec![
+            BigInt::mod_mul(
+                c_local_alpha,
+                &BigInt::mod_pow(&one_plus_n, offset, &nn),
+                &nn,
+            ),
+            BigInt::mod_pow(&one_plus_n, &zero_test_modulus(), &nn),
+        ],
+        commitments: vec![
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_r.clone(),
+            },
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_t.clone(),
+            },
+        ],
+        bit_bounds: vec![ZERO_TEST_MODULUS_BIT_LENGTH, ZERO_TEST_MASK_BIT_LENGTH],
+        ciphertext: c_s.clone(),
+    }
+}
+
+// Statement for c_masked = Enc_D(a_D)^r * (1 + n_D)^s * rho^n_D, with r and s committed in c_r
+// and c_s under the key of the randomizing party
+fn zero_test_masking_statement(
+    n_remote: &BigInt,
+    c_remote_alpha: &BigInt,
+    n_local: &BigInt,
+    c_r: &BigInt,
+    c_s: &BigInt,
+    c_masked: &BigInt,
+) -> AffineStatement {
+    AffineStatement {
+        n: n_remote.clone(),
+        bases: vec![c_remote_alpha.clone(), n_remote + BigInt::one()],
+        commitments: vec![
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_r.clone(),
+            },
+            ExponentCommitment::Paillier {
+                n: n_local.clone(),
+                c: c_s.clone(),
+            },
+        ],
+        bit_bounds: vec![
+            ZERO_TEST_MODULUS_BIT_LENGTH,
+            zero_test_exponent_bit_length(),
+        ],
+        ciphertext: c_masked.clone(),
+    }
+}
+
+fn paillier_encrypt(ek: &EncryptionKey, m: &BigInt, rho: &BigInt) -> BigInt {
+    Paillier::encrypt_with_chosen_randomness(
+        ek,
+        RawPlaintext::from(m.clone()),
+        &Randomness(rho.clone()),
+    )
+    .0
+    .into_owned()
+}
+
+// Randomizing party of the Paillier zero test: masks a_D + a_P + offset under the remote key,
+// where c_remote_alpha = Enc_D(a_D) and c_local_alpha = Enc_P(a_P).
+#[allow(clippy::too_many_arguments)]
+fn paillier_zero_test(
+    local_ek: &EncryptionKey,
+    local_dk: &DecryptionKey,
+    c_local_alpha: &BigInt,
+    a_local: &BigInt,
+    remote_ek: &EncryptionKey,
+    c_remote_alpha: &BigInt,
+    offset: &BigInt,
+    context: &ProofContext,
+) -> Result<PaillierZeroTest, TwoPartyRSAError> {
+    let q = zero_test_modulus();
+    let r = BigInt::sample_range(&BigInt::one(), &q);
+    let t = BigInt::sample(ZERO_TEST_MASK_BIT_LENGTH);
+    let s = &r * (a_local + offset) + &q * &t;
+
+    let rho_r = BigInt::sample_below(&local_ek.n);
+    let rho_t = BigInt::sample_below(&local_ek.n);
+    let rho_s = BigInt::sample_below(&local_ek.n);
+    let sigma = BigInt::sample_below(&remote_ek.n);
+    let c_r = paillier_encrypt(local_ek, &r, &rho_r);
+    let c_t = paillier_encrypt(local_ek, &t, &rho_t);
+
+    // c_s = Enc_P(a_P)^r * Enc_P(r * k + Q * t), its randomness as a commitment to s is recovered
+    // with the local decryption key
+    let c_s = BigInt::mod_mul(
+        &BigInt::mod_pow(c_local_alpha, &r, &local_ek.nn),
+        &paillier_encrypt(local_ek, &(&r * offset + &q * &t), &rho_s),
+        &local_ek.nn,
+    );
+    let rho_s_commitment = paillier_extract_randomness(&c_s, &s, local_dk);
+    let c_masked = BigInt::mod_mul(
+        &BigInt::mod_pow(c_remote_alpha, &r, &remote_ek.nn),
+        &paillier_encrypt(remote_ek, &s, &sigma),
+        &remote_ek.nn,
+    );
+
+    let commitment_statement =
+        zero_test_commitment_statement(&local_ek.n, c_local_alpha, offset, &c_r, &c_t, &c_s);
+    let commitment_witness = AffineWitness {
+        a_vec: vec![r.clone(), t],
+        r_vec: vec![rho_r.clone(), rho_t],
+        rho: rho_s,
     };
+    let masking_statement = zero_test_masking_statement(
+        &remote_ek.n,
+        c_remote_alpha,
+        &local_ek.n,
+        &c_r,
+        &c_s,
+        &c_masked,
+    );
+    let masking_witness = AffineWitness {
+        a_vec: vec![r, s],
+        r_vec: vec![rho_r, rho_s_commitment],
+        rho: sigma,
+    };
+    match (
+        AffineProof::prove(&commitment_witness, &commitment_statement, context),
+        AffineProof::prove(&masking_witness, &masking_statement, context),
+    ) {
+        (Ok(pi_s), Ok(pi_masked)) => Ok(PaillierZeroTest {
+            c_r,
+            c_t,
+            c_s,
+            c_masked,
+            pi_s,
+            pi_masked,
+        }),
+        (Err(err), _) | (_, Err(err)) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+    }
+}
+
+// Decrypting party of the Paillier zero test: verifies the proofs of the randomizing party and
+// tests whether the masked value is zero mod Q. c_local_alpha = Enc_D(a_D) is the residue of the
+// decrypting party and c_remote_alpha = Enc_P(a_P) the one of the randomizing party.
+#[allow(clippy::too_many_arguments)]
+fn conclude_paillier_zero_test(
+    zero_test: &PaillierZeroTest,
+    local_ek: &EncryptionKey,
+    local_dk: &DecryptionKey,
+    c_local_alpha: &BigInt,
+    remote_ek: &EncryptionKey,
+    c_remote_alpha: &BigInt,
+    offset: &BigInt,
+    context: &ProofContext,
+) -> Result<bool, ProofError> {
+    let commitment_statement = zero_test_commitment_statement(
+        &remote_ek.n,
+        c_remote_alpha,
+        offset,
+        &zero_test.c_r,
+        &zero_test.c_t,
+        &zero_test.c_s,
+    );
+    let masking_statement = zero_test_masking_statement(
+        &local_ek.n,
+        c_local_alpha,
+        &remote_ek.n,
+        &zero_test.c_r,
+        &zero_test.c_s,
+        &zero_test.c_masked,
+    );
+    zero_test.pi_s.verify(&commitment_statement, context)?;
+    zero_test.pi_masked.verify(&masking_statement, context)?;
+    let y = Paillier::decrypt(local_dk, RawCiphertext::from(zero_test.c_masked.clone()))
+        .0
+        .into_owned();
+    Ok(y.mod_floor(&zero_test_modulus()) == BigInt::zero())
+}
+
+// e must be odd and at least 3 for an inverse modulo phi(N) to exist
+fn is_valid_public_exponent(e: &BigInt) -> bool {
+    e >= &BigInt::from(3) && e.mod_floor(&BigInt::from(2)) == BigInt::one()
+}
+
+// Statement for the residue (p_i + q_i) mod e, where c_p and c_q are the normalized candidate
+// ciphertexts of the proving party and c_prime encrypts the residue. p_i + q_i < 2^(|p| + 1).
+fn phi_share_mod_statement(
+    params: &HmrtParams,
+    pk: &ElGamalPublicKey,
+    c_p: &ElGamalCiphertext,
+    c_q: &ElGamalCiphertext,
+    c_prime: &ElGamalCiphertext,
+    e: &BigInt,
+) -> Result<ModStatement, TwoPartyRSAError> {
+    match ExponentElGamal::add(c_p, c_q) {
+        Ok(c) => Ok(ModStatement {
+            c,
+            c_prime: c_prime.clone(),
+            modulus_p: e.clone(),
+            upper_bound_m: BigInt::from(2) * params.share_bound(),
+            pk: pk.clone(),
+        }),
+        Err(_) => Err(TwoPartyRSAError::PrivateExponentShareError),
+    }
+}
+
+// Base for the verification keys vk_i = g^d_i mod N: a random square in Z_N^* derived from N
+fn verification_key_base(n: &BigInt) -> BigInt {
+    let g = hash_to_zn(&[n, &BigInt::from(2)], n);
+    BigInt::mod_mul(&g, &g, n)
+}
+
+// Boneh-Franklin inversion for a small public e: given phi(N) mod e, the parties use
+// zeta = -phi(N)^-1 mod e, so that d = (1 + zeta * phi(N)) / e. With phi(N) = phi_0 - phi_1 for
+// phi_0 = N + 1 - p_0 - q_0 and phi_1 = p_1 + q_1 the shares are d_0 = floor((1 + zeta * phi_0) / e)
+// and d_1 = floor(zeta * phi_1 / e), and d = d_0 - d_1 exactly.
+fn exponent_inversion_zeta(
+    n: &BigInt,
+    e: &BigInt,
+    party_one_residue: &BigInt,
+    party_two_residue: &BigInt,
+) -> Option<BigInt> {
+    let phi_mod_e = (n + BigInt::one() - party_one_residue - party_two_residue).mod_floor(e);
+    phi_mod_e
+        .invert(e)
+        .map(|phi_inv| BigInt::mod_sub(&BigInt::zero(), &phi_inv, e))
+}
+
+// (vk_0 / vk_1)^e = g mod N iff e * (d_0 - d_1) = 1 in the group generated by g
+fn verify_verification_keys(
+    n: &BigInt,
+    e: &BigInt,
+    vk_base: &BigInt,
+    party_one_vk: &BigInt,
+    party_two_vk: &BigInt,
+) -> bool {
+    match party_two_vk.invert(n) {
+        Some(party_two_vk_inv) => {
+            let vk = BigInt::mod_mul(party_one_vk, &party_two_vk_inv, n);
+            BigInt::mod_pow(&vk, e, n) == vk_base.modulus(n)
+        }
+        None => false,
+    }
+}
+
+/// RSA-OAEP encryption under the jointly generated public key (N, e)
+pub fn rsa_oaep_encrypt(
+    n: &BigInt,
+    e: &BigInt,
+    message: &[u8],
+    label: &[u8],
+) -> Result<BigInt, TwoPartyRSAError> {
+    let em = rsaes_oaep_encode(message, label, (n.bit_length() + 7) / 8)?;
+    Ok(BigInt::mod_pow(&BigInt::from(&em[..]), e, n))
+}
+
+// c must be an element of Z_N^*
+fn is_valid_rsa_ciphertext(n: &BigInt, c: &BigInt) -> bool {
+    c > &BigInt::zero() && c < n && c.gcd(n) == BigInt::one()
+}
+
+// removes the OAEP padding from the combined decryption m = c^d mod N
+fn rsa_oaep_decode(n: &BigInt, m: &BigInt, label: &[u8]) -> Result<Vec<u8>, TwoPartyRSAError> {
+    let k = (n.bit_length() + 7) / 8;
+    match i2osp(m, k) {
+        Some(em) => rsaes_oaep_decode(&em, label, k),
+        None => Err(TwoPartyRSAError::MessageEncodingError),
+    }
+}
+
+// Statement for a partial exponentiation y_i = x^d_i mod N, tied to the key share through
+// the verification key vk_i = vk_base^d_i mod N
+fn partial_exponentiation_statement(
+    n: &BigInt,
+    vk_base: &BigInt,
+    vk: &BigInt,
+    x: &BigInt,
+    y: &BigInt,
+) -> RSADDHStatement {
+    RSADDHStatement {
+        n: n.clone(),
+        g1: vk_base.clone(),
+        h1: vk.clone(),
+        g2: x.clone(),
+        h2: y.clone(),
+    }
+}
+
+// d = d_0 - d_1, hence x^d = x^d_0 * (x^d_1)^-1 mod N
+fn combine_partial_exponentiations(
+    n: &BigInt,
+    party_one_partial: &BigInt,
+    party_two_partial: &BigInt,
+) -> Option<BigInt> {
+    party_two_partial
+        .invert(n)
+        .map(|party_two_partial_inv| BigInt::mod_mul(party_one_partial, &party_two_partial_inv, n))
+}
+
+/// Returns the first alpha of a trial division batch that divides the candidate, given the
+/// coprimality results in the same order as alpha_vec.
+pub fn first_dividing_prime(alpha_vec: &[BigInt], coprime_vec: &[bool]) -> Option<BigInt> {
+    alpha_vec
+        .iter()
+        .zip(coprime_vec)
+        .find(|(_, coprime)| !**coprime)
+        .map(|(alpha, _)| alpha.clone())
+}
+
+// Public base for the j-th round of the biprimality test: both parties derive the same g with
+// Jacobi symbol (g/N) = 1 by hashing (N, j, counter) until a suitable value is found.
+fn biprimality_test_base(n: &BigInt, round: usize) -> BigInt {
+    let round = BigInt::from(round as u64);
+    let mut counter = BigInt::zero();
+    loop {
+        let g = hash_to_zn(&[n, &round, &counter], n);
+        if jacobi(&g, n) == 1 {
+            return g;
+        }
+        counter = counter + BigInt::one();
+    }
+}
 
-    let proof = DDHProof::prove(&witness, &statement);
-    (witness, statement, proof)
+// Boneh-Franklin test: for N = pq, with p = 4(p_0 + p_1) + 3 and q = 4(q_0 + q_1) + 3,
+// phi(N)/4 = (N - 5)/4 - (p_0 + q_0) - (p_1 + q_1). Party one sends g^(p_0 + q_0), party two
+// sends g^(p_1 + q_1), and N passes a round if g^((N - 5)/4) = +-g^(p_0 + q_0) * g^(p_1 + q_1).
+//
+// Only the first step of Boneh-Franklin runs here. A round catches any N that is not of the
+// form r^a * s^b for primes r, s with probability at least 1/2. Their second step (the test in
+// Z_N[x]/(x^2 + 1), or gcd(N, p + q - 1) = 1) rules out the remaining N = r^a * s^b with a > 1,
+// which is needed when N can be adversarial. Here it cannot be: N = pq for a composite candidate
+// is of that form only if p or q is a proper prime power, or if q is a product of powers of the
+// at most two primes dividing p (p = q included). Each candidate is 4(p_0 + p_1) + 3 where the
+// share of an honest party is uniform over at least 2^(|p|/2 - 3) values, sieved or not, and
+// independent of the other share, which is fixed by its range proven ciphertext. Proper prime
+// powers have density about 2^(-|p|/2) among |p| bits integers, and given p there are at most
+// |p|^2 such products, so both events have negligible probability and the second step would not
+// change the outcome.
+fn conclude_biprimality(n: &BigInt, v_party_one: &[BigInt], v_party_two: &[BigInt]) -> bool {
+    if n.mod_floor(&BigInt::from(4)) != BigInt::one() {
+        return false;
+    }
+    let exp = (n - BigInt::from(5)).div_floor(&BigInt::from(4));
+    let n_minus_one = n - BigInt::one();
+    (0..v_party_one.len()).all(|j| {
+        let g = biprimality_test_base(n, j);
+        let lhs = BigInt::mod_pow(&g, &exp, n);
+        let rhs = BigInt::mod_mul(&v_party_one[j], &v_party_two[j], n);
+        lhs == rhs || lhs == BigInt::mod_mul(&rhs, &n_minus_one, n)
+    })
 }
 
 #[cfg(test)]
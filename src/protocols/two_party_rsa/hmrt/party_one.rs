@@ -1,14 +1,70 @@
-use crate::protocols::two_party_rsa::hmrt::gen_ddh_containers;
+use crate::protocols::two_party_rsa::hmrt::biprimality_test_base;
+use crate::protocols::two_party_rsa::hmrt::combine_partial_exponentiations;
+use crate::protocols::two_party_rsa::hmrt::conclude_biprimality;
+use crate::protocols::two_party_rsa::hmrt::conclude_elgamal_zero_test;
+use crate::protocols::two_party_rsa::hmrt::conclude_paillier_zero_test;
+use crate::protocols::two_party_rsa::hmrt::cross_product_statement;
+use crate::protocols::two_party_rsa::hmrt::elgamal_zero_test;
+use crate::protocols::two_party_rsa::hmrt::exponent_inversion_zeta;
+use crate::protocols::two_party_rsa::hmrt::is_valid_public_exponent;
+use crate::protocols::two_party_rsa::hmrt::is_valid_rsa_ciphertext;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtParty;
+use crate::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use crate::protocols::two_party_rsa::hmrt::normalize_paillier_ciphertexts;
+use crate::protocols::two_party_rsa::hmrt::paillier_encrypt;
+use crate::protocols::two_party_rsa::hmrt::paillier_extract_randomness;
+use crate::protocols::two_party_rsa::hmrt::paillier_verify_randomness;
+use crate::protocols::two_party_rsa::hmrt::paillier_zero_test;
+use crate::protocols::two_party_rsa::hmrt::partial_exponentiation_statement;
+use crate::protocols::two_party_rsa::hmrt::party_one_sieved_share_statement;
 use crate::protocols::two_party_rsa::hmrt::party_two::KeySetupFirstMsg as KeySetupFirstMsgPartyTwo;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoBiprimalityTestFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationBatchSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationBatchThirdMsg;
 use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationFirstMsg;
 use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationSecondMsg;
 use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateSievingFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoDecryptFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulusThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGenerationSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGenerationThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShareSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoSignFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two_sieved_share_statement;
+use crate::protocols::two_party_rsa::hmrt::phi_share_mod_statement;
+use crate::protocols::two_party_rsa::hmrt::proof_context;
+use crate::protocols::two_party_rsa::hmrt::protocol_abort;
+use crate::protocols::two_party_rsa::hmrt::rsa_oaep_decode;
+use crate::protocols::two_party_rsa::hmrt::sample_unit_mod;
+use crate::protocols::two_party_rsa::hmrt::share_encryption_statement;
+use crate::protocols::two_party_rsa::hmrt::share_product_statement;
+use crate::protocols::two_party_rsa::hmrt::sieved_candidate_share;
+use crate::protocols::two_party_rsa::hmrt::sieving_conversion_statement;
+use crate::protocols::two_party_rsa::hmrt::sieving_modulus;
+use crate::protocols::two_party_rsa::hmrt::sieving_share_statement;
+use crate::protocols::two_party_rsa::hmrt::trial_division_residue_statement;
+use crate::protocols::two_party_rsa::hmrt::trial_division_zero_test_ciphertexts;
+use crate::protocols::two_party_rsa::hmrt::verification_key_base;
+use crate::protocols::two_party_rsa::hmrt::verify_verification_keys;
+use crate::protocols::two_party_rsa::hmrt::zero_test_tilde_offset;
 use crate::protocols::two_party_rsa::hmrt::CiphertextPair;
-use crate::protocols::two_party_rsa::CANDIDATE_BIT_LENGTH;
-use crate::protocols::two_party_rsa::PAILLIER_MODULUS;
-use crate::utlities::ddh_proof::DDHProof;
-use crate::utlities::ddh_proof::DDHStatement;
-use crate::utlities::ddh_proof::DDHWitness;
+use crate::protocols::two_party_rsa::hmrt::ElGamalGroup;
+use crate::protocols::two_party_rsa::hmrt::ElGamalZeroTest;
+use crate::protocols::two_party_rsa::hmrt::HmrtParams;
+use crate::protocols::two_party_rsa::hmrt::JointModulus;
+use crate::protocols::two_party_rsa::hmrt::PaillierCiphertextPair;
+use crate::protocols::two_party_rsa::hmrt::PaillierZeroTest;
+use crate::protocols::two_party_rsa::hmrt::RSASignature;
+use crate::protocols::two_party_rsa::hmrt::TrialDivisionModProof;
+use crate::protocols::two_party_rsa::padding::SignatureEncoding;
+use crate::protocols::two_party_rsa::BIPRIMALITY_TEST_ROUNDS;
+use crate::utlities::affine_proof::AffineProof;
+use crate::utlities::affine_proof::AffineWitness;
 use crate::utlities::ddh_proof::NISigmaProof;
 use crate::utlities::dlog_proof::DLogProof;
 use crate::utlities::dlog_proof::ProveDLog;
@@ -17,30 +73,53 @@ use crate::utlities::dlog_proof::Witness as DLogWitness;
 use crate::utlities::elgamal_enc_proof::HomoELGamalProof;
 use crate::utlities::elgamal_enc_proof::HomoElGamalStatement;
 use crate::utlities::elgamal_enc_proof::HomoElGamalWitness;
+use crate::utlities::equal_secret_proof::EqProof;
+use crate::utlities::equal_secret_proof::Statement as EqStatement;
+use crate::utlities::equal_secret_proof::Witness as EqWitness;
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::group_element::GroupElement;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::homomorphic_scheme::PaillierScheme;
 use crate::utlities::mod_proof::ModProof;
 use crate::utlities::mod_proof::ModStatement;
 use crate::utlities::mod_proof::ModWitness;
-use crate::utlities::range_proof::RangeProof;
-use crate::utlities::range_proof::Statement as BoundStatement;
-use crate::utlities::range_proof::Witness as BoundWitness;
+use crate::utlities::rsa_ddh_proof::RSADDHProof;
+use crate::utlities::rsa_ddh_proof::RSADDHWitness;
+use crate::utlities::sealed_share;
+use crate::utlities::sealed_share::SealedShare;
+use crate::utlities::sealed_share::StorageKey;
+use crate::utlities::tight_range_proof::Statement as BoundStatement;
+use crate::utlities::tight_range_proof::TightRangeProof;
+use crate::utlities::tight_range_proof::Witness as BoundWitness;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::Redacted;
+use crate::ProtocolRound;
+use crate::SealedShareError;
 use crate::TwoPartyRSAError;
 use curv::arithmetic::traits::Modulo;
 use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::ZeroizeBN;
 use curv::BigInt;
-use elgamal::rfc7919_groups::SupportedGroups;
 use elgamal::ElGamalCiphertext;
 use elgamal::ElGamalKeyPair;
 use elgamal::ElGamalPP;
 use elgamal::ElGamalPrivateKey;
 use elgamal::ElGamalPublicKey;
 use elgamal::ExponentElGamal;
+use paillier::traits::Decrypt;
+use paillier::traits::EncryptWithChosenRandomness;
 use paillier::traits::KeyGeneration;
 use paillier::DecryptionKey;
 use paillier::EncryptionKey;
 use paillier::Paillier;
+use paillier::Randomness;
+use paillier::RawCiphertext;
+use paillier::RawPlaintext;
+use rayon::prelude::*;
+use std::fmt;
+use zeroize::Zeroize;
 use zk_paillier::zkproofs::NICorrectKeyProof;
 
-// TODO: add zeroize if needed
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartyOneKeySetup {
     pub local_paillier_pubkey: EncryptionKey,
@@ -48,6 +127,7 @@ pub struct PartyOneKeySetup {
     pub remote_paillier_pubkey: EncryptionKey,
     pub remote_elgamal_pubkey: ElGamalPublicKey,
     pub joint_elgamal_pubkey: ElGamalPublicKey,
+    pub params: HmrtParams,
     private: PartyOnePrivate,
 }
 
@@ -55,14 +135,40 @@ pub struct PartyOneKeySetup {
 pub struct PartyOneKeySetupFirstMsg {
     pub ek: EncryptionKey,
     pub pk: ElGamalPublicKey,
+    pub group: ElGamalGroup,
     pub correct_key_proof: NICorrectKeyProof,
     pub dlog_proof: DLogProof,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyOnePrivate {
     dk: DecryptionKey,
     sk: ElGamalPrivateKey,
+    session_id: SessionId,
+}
+
+impl Zeroize for PartyOnePrivate {
+    fn zeroize(&mut self) {
+        self.dk.p.zeroize_bn();
+        self.dk.q.zeroize_bn();
+        self.sk.x.zeroize_bn();
+    }
+}
+
+impl Drop for PartyOnePrivate {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyOnePrivate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyOnePrivate")
+            .field("dk", &Redacted)
+            .field("sk", &Redacted)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,75 +178,378 @@ pub struct PartyOneCandidateGeneration {}
 pub struct PartyOneCandidateGenerationFirstMsg {
     pub c_i: ElGamalCiphertext,
     pub pi_enc: HomoELGamalProof,
-    pub pi_bound: RangeProof,
+    pub pi_bound: TightRangeProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneCandidateSievingFirstMsg {
+    pub c_a: BigInt,
+    pub pi_range: AffineProof,
+}
+
+/// Links party one's sieved share p_0, encrypted in its candidate generation message, to its
+/// decrypted share x_0 of c_ab: c_k encrypts k under party one's Paillier key, with
+/// x_0 + M * share_bound - 3 = 4 * p_0 + M * k, hence 4 * p_0 + 3 = x_0 mod M.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneCandidateSievingSecondMsg {
+    pub c_k: BigInt,
+    pub pi_sieved: AffineProof,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartyOneCandidateGenerationSecondMsg {
-    pub pi_mod: ModProof,
+    pub pi_mod: TrialDivisionModProof,
     pub c_0_alpha: ElGamalCiphertext,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartyOneCandidateGenerationThirdMsg {
-    pub proof_alpha: DDHProof,
-    pub proof_alpha_tilde: DDHProof,
-    pub c_alpha_random: ElGamalCiphertext,
-    pub c_alpha_tilde_random: ElGamalCiphertext,
-    pub partial_dec_c_alpha: BigInt,
-    pub partial_dec_c_alpha_tilde: BigInt,
-    pub ddh_proof_alpha: DDHProof,
-    pub ddh_proof_alpha_tilde: DDHProof,
+    pub zero_test_alpha: ElGamalZeroTest,
+    pub zero_test_alpha_tilde: ElGamalZeroTest,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneCandidateGenerationBatchSecondMsg {
+    pub second_msg_vec: Vec<PartyOneCandidateGenerationSecondMsg>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneCandidateGenerationBatchThirdMsg {
+    pub third_msg_vec: Vec<PartyOneCandidateGenerationThirdMsg>,
+}
+
+/// Party one's side of the Paillier trial division benchmark, see PaillierCiphertextPair
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePaillierCandidateGeneration {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePaillierCandidateGenerationFirstMsg {
+    pub c_i: BigInt,
+    pub pi_enc: HomoELGamalProof<PaillierScheme>,
+    pub pi_bound: TightRangeProof<PaillierScheme>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePaillierCandidateGenerationSecondMsg {
+    pub pi_mod: ModProof<PaillierScheme>,
+    pub c_0_alpha: BigInt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePaillierCandidateGenerationThirdMsg {
+    pub zero_test_alpha: PaillierZeroTest,
+    pub zero_test_alpha_tilde: PaillierZeroTest,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyOnePaillierCandidateWitness {
+    pub p_0: BigInt,
+    pub r_0: BigInt,
+}
+
+impl Zeroize for PartyOnePaillierCandidateWitness {
+    fn zeroize(&mut self) {
+        self.p_0.zeroize_bn();
+        self.r_0.zeroize_bn();
+    }
+}
+
+impl Drop for PartyOnePaillierCandidateWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyOnePaillierCandidateWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyOnePaillierCandidateWitness")
+            .field("p_0", &Redacted)
+            .field("r_0", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneBiprimalityTest {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneBiprimalityTestFirstMsg {
+    pub v_vec: Vec<BigInt>,
+    pub pi_eq_vec: Vec<EqProof>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneJointModulus {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneJointModulusFirstMsg {
+    pub c_p: BigInt,
+    pub c_q: BigInt,
+    pub c_pq: BigInt,
+    pub pi_p: AffineProof,
+    pub pi_q: AffineProof,
+    pub pi_pq: AffineProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneJointModulusSecondMsg {
+    pub c_n: BigInt,
+    pub pi_n: AffineProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneJointModulusThirdMsg {
+    pub n: BigInt,
+    pub rho_n: BigInt,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyOneJointModulusWitness {
+    pub rho_pq: BigInt,
+}
+
+impl Zeroize for PartyOneJointModulusWitness {
+    fn zeroize(&mut self) {
+        self.rho_pq.zeroize_bn();
+    }
+}
+
+impl Drop for PartyOneJointModulusWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyOneJointModulusWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyOneJointModulusWitness")
+            .field("rho_pq", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePrivateExponentShare {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePrivateExponentShareFirstMsg {
+    pub c_phi_mod_e: ElGamalCiphertext,
+    pub pi_mod: ModProof,
+    pub phi_mod_e: BigInt,
+    pub r_phi_mod_e: BigInt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOnePrivateExponentShareSecondMsg {
+    pub vk: BigInt,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyOnePrivateExponentShareWitness {
+    d_0: BigInt,
+    session_id: SessionId,
+}
+
+impl Zeroize for PartyOnePrivateExponentShareWitness {
+    fn zeroize(&mut self) {
+        self.d_0.zeroize_bn();
+    }
+}
+
+impl Drop for PartyOnePrivateExponentShareWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyOnePrivateExponentShareWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyOnePrivateExponentShareWitness")
+            .field("d_0", &Redacted)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
+/// Party one's share of the RSA private key: d = d_0 - d_1 where d_1 is held by party two.
+/// vk_base^d_i are public verification keys for the shares.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyOneRSAKeyShare {
+    pub n: BigInt,
+    pub e: BigInt,
+    pub vk_base: BigInt,
+    pub local_vk: BigInt,
+    pub remote_vk: BigInt,
+    /// session of the key generation, binds the proofs of signing and decryption to the key
+    pub session_id: SessionId,
+    d_0: BigInt,
+}
+
+impl Zeroize for PartyOneRSAKeyShare {
+    fn zeroize(&mut self) {
+        self.d_0.zeroize_bn();
+    }
+}
+
+impl Drop for PartyOneRSAKeyShare {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyOneRSAKeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyOneRSAKeyShare")
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("vk_base", &self.vk_base)
+            .field("local_vk", &self.local_vk)
+            .field("remote_vk", &self.remote_vk)
+            .field("session_id", &self.session_id)
+            .field("d_0", &Redacted)
+            .finish()
+    }
+}
+
+impl PartyOneRSAKeyShare {
+    /// Encrypts the key share for storage, see `SealedShare`
+    pub fn export_encrypted(&self, key: &StorageKey) -> Result<SealedShare, SealedShareError> {
+        sealed_share::seal(self, "PartyOneRSAKeyShare", key)
+    }
+
+    pub fn import_encrypted(
+        sealed: &SealedShare,
+        key: &StorageKey,
+    ) -> Result<Self, SealedShareError> {
+        sealed_share::open(sealed, "PartyOneRSAKeyShare", key)
+    }
+
+    fn proof_context(&self, prover: HmrtParty) -> ProofContext {
+        proof_context(&self.session_id, prover)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneSign {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneSignFirstMsg {
+    pub em: BigInt,
+    pub sigma: BigInt,
+    pub pi_sigma: RSADDHProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneDecrypt {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyOneDecryptFirstMsg {
+    pub partial_dec_c: BigInt,
+    pub pi_partial_dec: RSADDHProof,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyOneCandidateWitness {
     pub p_0: BigInt,
     pub r_0: BigInt,
 }
 
+impl Zeroize for PartyOneCandidateWitness {
+    fn zeroize(&mut self) {
+        self.p_0.zeroize_bn();
+        self.r_0.zeroize_bn();
+    }
+}
+
+impl Drop for PartyOneCandidateWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyOneCandidateWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyOneCandidateWitness")
+            .field("p_0", &Redacted)
+            .field("r_0", &Redacted)
+            .finish()
+    }
+}
+
 impl PartyOneKeySetup {
+    /// Both parties must use the same session id, all proofs of the session are bound to it
     pub fn gen_local_keys_and_first_message_to_party_two(
-    ) -> (PartyOneKeySetupFirstMsg, PartyOnePrivate) {
-        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        params: &HmrtParams,
+        session_id: &SessionId,
+    ) -> Result<(PartyOneKeySetupFirstMsg, PartyOnePrivate), TwoPartyRSAError> {
+        params.validate()?;
+        let pp = params.elgamal_group.public_parameters();
         let keypair = ElGamalKeyPair::generate(&pp);
         let witness = DLogWitness {
-            //TODO: zeroize
             x: keypair.sk.x.clone(),
         };
-        let dlog_proof = DLogProof::prove(&witness, &pp);
+        let dlog_proof = DLogProof::prove(
+            &witness,
+            &pp,
+            &proof_context(session_id, HmrtParty::PartyOne),
+        );
 
-        let (ek_new, dk_new) = Paillier::keypair_with_modulus_size(PAILLIER_MODULUS).keys();
+        let (ek_new, dk_new) = Paillier::keypair_with_modulus_size(params.paillier_modulus).keys();
         let correct_key_proof = NICorrectKeyProof::proof(&dk_new);
 
         let party_one_private = PartyOnePrivate {
             dk: dk_new,
             sk: keypair.sk,
+            session_id: *session_id,
         };
-        (
+        Ok((
             PartyOneKeySetupFirstMsg {
                 ek: ek_new,
                 pk: keypair.pk,
+                group: params.elgamal_group,
                 correct_key_proof,
                 dlog_proof,
             },
             party_one_private,
-        )
+        ))
     }
 
     pub fn verify_party_two_first_message_and_output_party_one_keys(
+        params: &HmrtParams,
         party_one_first_message: &PartyOneKeySetupFirstMsg,
         party_two_first_message: &KeySetupFirstMsgPartyTwo,
         party_one_private: PartyOnePrivate,
     ) -> Result<Self, TwoPartyRSAError> {
+        params.validate()?;
+        if party_two_first_message.pk.pp != party_one_first_message.pk.pp {
+            return Err(TwoPartyRSAError::InvalidElGamalKey);
+        }
+        let remote_ek = &party_two_first_message.ek;
+        if remote_ek.n.bit_length() < params.paillier_modulus - 1
+            || remote_ek.nn != &remote_ek.n * &remote_ek.n
+        {
+            return Err(TwoPartyRSAError::InvalidPaillierKey);
+        }
+        // an element of small order in the remote key would carry over to the joint key
+        let remote_h = GroupElement::from_bigint(
+            &party_two_first_message.pk.h,
+            &party_one_first_message.pk.pp,
+        )
+        .map_err(|_| TwoPartyRSAError::InvalidElGamalKey)?;
+        let joint_elgamal_pubkey = match party_one_first_message.pk.add(&party_two_first_message.pk)
+        {
+            Ok(joint_elgamal_pubkey) => joint_elgamal_pubkey,
+            Err(_) => return Err(TwoPartyRSAError::InvalidElGamalKey),
+        };
         let dlog_statement = DLogStatement {
-            h: party_two_first_message.pk.h.clone(),
+            h: remote_h.into_bigint(),
         };
 
-        match party_two_first_message
-            .dlog_proof
-            .verify(&dlog_statement, &party_one_first_message.pk.pp)
-        {
+        match party_two_first_message.dlog_proof.verify(
+            &dlog_statement,
+            &party_one_first_message.pk.pp,
+            &proof_context(&party_one_private.session_id, HmrtParty::PartyTwo),
+        ) {
             Ok(()) => {
                 match party_two_first_message
                     .correct_key_proof
@@ -151,29 +560,77 @@ impl PartyOneKeySetup {
                         local_elgamal_puubkey: party_one_first_message.pk.clone(),
                         remote_paillier_pubkey: party_two_first_message.ek.clone(),
                         remote_elgamal_pubkey: party_two_first_message.pk.clone(),
-                        joint_elgamal_pubkey: party_one_first_message
-                            .pk
-                            .add(&party_two_first_message.pk)
-                            .unwrap(),
+                        joint_elgamal_pubkey,
+                        params: *params,
                         private: party_one_private,
                     }),
-                    Err(_) => Err(TwoPartyRSAError::InvalidPaillierKey),
+                    Err(_) => Err(protocol_abort(
+                        ProtocolRound::KeySetup,
+                        "correct_key_proof",
+                        None,
+                        party_two_first_message,
+                        None,
+                    )),
                 }
             }
-            Err(_) => Err(TwoPartyRSAError::InvalidElGamalKey),
+            Err(err) => Err(protocol_abort(
+                ProtocolRound::KeySetup,
+                "dlog_proof",
+                None,
+                party_two_first_message,
+                Some(err),
+            )),
         }
     }
+
+    /// Encrypts the key setup, including the Paillier and ElGamal private keys, for storage
+    /// between sessions. Serializing `PartyOneKeySetup` directly writes them in the clear.
+    pub fn export_encrypted(&self, key: &StorageKey) -> Result<SealedShare, SealedShareError> {
+        sealed_share::seal(self, "PartyOneKeySetup", key)
+    }
+
+    pub fn import_encrypted(
+        sealed: &SealedShare,
+        key: &StorageKey,
+    ) -> Result<Self, SealedShareError> {
+        sealed_share::open(sealed, "PartyOneKeySetup", key)
+    }
+
+    pub fn session_id(&self) -> &SessionId {
+        &self.private.session_id
+    }
+
+    fn proof_context(&self, prover: HmrtParty) -> ProofContext {
+        proof_context(&self.private.session_id, prover)
+    }
 }
 
 impl PartyOneCandidateGeneration {
     pub fn generate_shares_of_candidate(
         keys: &PartyOneKeySetup,
-    ) -> (
-        PartyOneCandidateWitness,
-        PartyOneCandidateGenerationFirstMsg,
-    ) {
-        let share_bit_size: usize = CANDIDATE_BIT_LENGTH / 2 - 2;
+    ) -> Result<
+        (
+            PartyOneCandidateWitness,
+            PartyOneCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let share_bit_size: usize = keys.params.candidate_bit_length / 2 - 2;
         let p_i = BigInt::sample(share_bit_size);
+        Self::encrypt_candidate_share(keys, p_i)
+    }
+
+    // encrypts a share of the candidate under the joint ElGamal key and proves it is in range
+    fn encrypt_candidate_share(
+        keys: &PartyOneKeySetup,
+        p_i: BigInt,
+    ) -> Result<
+        (
+            PartyOneCandidateWitness,
+            PartyOneCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
         let r_i = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
 
         let c_i = ExponentElGamal::encrypt_from_predefined_randomness(
@@ -181,7 +638,7 @@ impl PartyOneCandidateGeneration {
             &keys.joint_elgamal_pubkey,
             &r_i,
         )
-        .unwrap();
+        .map_err(|_| TwoPartyRSAError::CandidateGenerationEncError)?;
 
         let enc_witness = HomoElGamalWitness {
             r: r_i.clone(),
@@ -197,24 +654,177 @@ impl PartyOneCandidateGeneration {
         };
         let bound_statement = BoundStatement {
             pk: keys.joint_elgamal_pubkey.clone(),
-            // TODO: in current range proof this will give some slack such that it is possible that a prover chose 2^(N/2-2)<x< 1/3 * 2^(N/2).
-            range: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32),
+            range: keys.params.share_range(),
             ciphertext: c_i.clone(),
-            sec_param: 120, //TODO : parameterize
-            kapa: 100,      //TODO : parameterize
         };
 
-        let enc_proof = HomoELGamalProof::prove(&enc_witness, &enc_statement);
-        let bound_proof = RangeProof::prove(&bound_witness, &bound_statement).unwrap(); // TODO: handle error properly
+        let enc_proof = HomoELGamalProof::prove(
+            &enc_witness,
+            &enc_statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        )
+        .map_err(TwoPartyRSAError::ProofGenerationError)?;
+        let bound_proof = TightRangeProof::prove(
+            &bound_witness,
+            &bound_statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        )
+        .map_err(TwoPartyRSAError::ProofGenerationError)?;
 
-        (
+        Ok((
             PartyOneCandidateWitness { p_0: p_i, r_0: r_i },
             PartyOneCandidateGenerationFirstMsg {
                 c_i,
                 pi_enc: enc_proof,
                 pi_bound: bound_proof,
             },
+        ))
+    }
+
+    /// Distributed sieving, an alternative to generate_shares_of_candidate: the parties hold
+    /// multiplicative shares a, b of a unit of Z_M (see sieving_modulus) and convert them to
+    /// additive shares, so that the candidate 4(p_0 + p_1) + 3 = a * b mod M is coprime to every
+    /// prime dividing M by construction. Party one sends a encrypted under its own Paillier key.
+    /// A party that picks a share which is not a unit only makes the candidate divisible by a
+    /// prime of M: trial division from the first prime of SMALL_PRIMES still catches it, and
+    /// when it starts after the primes of M the biprimality test rejects the modulus.
+    pub fn sample_multiplicative_share_and_sieving_message(
+        keys: &PartyOneKeySetup,
+    ) -> Result<PartyOneCandidateSievingFirstMsg, TwoPartyRSAError> {
+        let (m, _) = sieving_modulus(&keys.params);
+        let ek = &keys.local_paillier_pubkey;
+        let a = sample_unit_mod(&m);
+        let rho_a = BigInt::sample_below(&ek.n);
+        let c_a = Paillier::encrypt_with_chosen_randomness(
+            ek,
+            RawPlaintext::from(a.clone()),
+            &Randomness(rho_a.clone()),
+        )
+        .0
+        .into_owned();
+
+        let statement = sieving_share_statement(&ek.n, &c_a, &m);
+        let witness = AffineWitness {
+            a_vec: vec![a],
+            r_vec: vec![rho_a.clone()],
+            rho: rho_a,
+        };
+        match AffineProof::prove(
+            &witness,
+            &statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        ) {
+            Ok(pi_range) => Ok(PartyOneCandidateSievingFirstMsg { c_a, pi_range }),
+            Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+        }
+    }
+
+    /// Party one decrypts its additive share x_0 = a * b + s and derives its share of the
+    /// sieved candidate. The witness and first message replace the ones of
+    /// generate_shares_of_candidate, the second message proves that the share matches x_0.
+    /// Party two's sieved share is checked against its mask s in the same way.
+    pub fn verify_party_two_sieving_message_and_generate_sieved_candidate(
+        keys: &PartyOneKeySetup,
+        party_one_sieving_message: &PartyOneCandidateSievingFirstMsg,
+        party_two_sieving_message: &PartyTwoCandidateSievingFirstMsg,
+        party_two_first_message: &PartyTwoCandidateGenerationFirstMsg,
+    ) -> Result<
+        (
+            PartyOneCandidateWitness,
+            PartyOneCandidateGenerationFirstMsg,
+            PartyOneCandidateSievingSecondMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let (m, _) = sieving_modulus(&keys.params);
+        let ek = &keys.local_paillier_pubkey;
+        let statement = sieving_conversion_statement(
+            &ek.n,
+            &party_one_sieving_message.c_a,
+            &keys.remote_paillier_pubkey.n,
+            &party_two_sieving_message.c_b,
+            &party_two_sieving_message.c_s,
+            &m,
+            &party_two_sieving_message.c_ab,
+        );
+        if let Err(err) = party_two_sieving_message
+            .pi_affine
+            .verify(&statement, &keys.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_affine",
+                None,
+                party_two_sieving_message,
+                Some(err),
+            ));
+        }
+        let sieved_statement = party_two_sieved_share_statement(
+            &keys.params,
+            &keys.remote_paillier_pubkey.n,
+            &party_two_sieving_message.c_s,
+            &keys.joint_elgamal_pubkey,
+            &party_two_first_message.c_i,
+            &party_two_sieving_message.c_k,
+            &m,
+        );
+        if let Err(err) = party_two_sieving_message
+            .pi_sieved
+            .verify(&sieved_statement, &keys.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_sieved",
+                None,
+                party_two_sieving_message,
+                Some(err),
+            ));
+        }
+
+        let x_0 = Paillier::decrypt(
+            &keys.private.dk,
+            RawCiphertext::from(party_two_sieving_message.c_ab.clone()),
         )
+        .0
+        .into_owned();
+        let p_0 = sieved_candidate_share(&keys.params, &(&x_0 - BigInt::from(3)), &m);
+        let (witness, first_message) = Self::encrypt_candidate_share(keys, p_0)?;
+
+        // x_0 + M * share_bound - 3 - 4 * p_0 is a positive multiple of M
+        let k = (&x_0 + &m * keys.params.share_bound()
+            - BigInt::from(3)
+            - BigInt::from(4) * &witness.p_0)
+            .div_floor(&m);
+        let rho_k = BigInt::sample_below(&ek.n);
+        let c_k = paillier_encrypt(ek, &k, &rho_k);
+        let rho =
+            paillier_extract_randomness(&party_two_sieving_message.c_ab, &x_0, &keys.private.dk);
+        let sieved_statement = party_one_sieved_share_statement(
+            &keys.params,
+            &ek.n,
+            &party_two_sieving_message.c_ab,
+            &keys.joint_elgamal_pubkey,
+            &first_message.c_i,
+            &c_k,
+            &m,
+        );
+        let sieved_witness = AffineWitness {
+            a_vec: vec![witness.p_0.clone(), k],
+            r_vec: vec![witness.r_0.clone(), rho_k],
+            rho,
+        };
+        match AffineProof::prove(
+            &sieved_witness,
+            &sieved_statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        ) {
+            Ok(pi_sieved) => Ok((
+                witness,
+                first_message,
+                PartyOneCandidateSievingSecondMsg { c_k, pi_sieved },
+            )),
+            Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+        }
     }
 
     pub fn verify_party_two_first_message_and_normalize_ciphertexts(
@@ -229,41 +839,51 @@ impl PartyOneCandidateGeneration {
 
         let bound_statement = BoundStatement {
             pk: keys.joint_elgamal_pubkey.clone(),
-            range: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32),
+            range: keys.params.share_range(),
             ciphertext: party_two_first_message.c_i.clone(),
-            sec_param: 120,
-            kapa: 100,
         };
 
-        match party_two_first_message
+        if let Err(err) = party_two_first_message
             .pi_enc
-            .verify(&enc_statement)
-            .is_ok()
-            && party_two_first_message
-                .pi_bound
-                .verify(&bound_statement)
-                .is_ok()
+            .verify(&enc_statement, &keys.proof_context(HmrtParty::PartyTwo))
         {
-            true => {
-                let c_party_one_mul_4 =
-                    ExponentElGamal::mul(&party_one_first_message.c_i, &BigInt::from(4));
-                let c_party_two_mul_4 =
-                    ExponentElGamal::mul(&party_two_first_message.c_i, &BigInt::from(4));
-                Ok(CiphertextPair {
-                    c0: ExponentElGamal::add(
-                        &c_party_one_mul_4,
-                        &ExponentElGamal::encrypt_from_predefined_randomness(
-                            &BigInt::from(3),
-                            &keys.joint_elgamal_pubkey,
-                            &BigInt::zero(),
-                        )
-                        .unwrap(),
-                    )
-                    .unwrap(),
-                    c1: c_party_two_mul_4,
-                })
-            }
-            false => Err(TwoPartyRSAError::CandidateGenerationEncError),
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_enc",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
+        }
+        if let Err(err) = party_two_first_message
+            .pi_bound
+            .verify(&bound_statement, &keys.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_bound",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
+        }
+
+        let c_party_one_mul_4 =
+            ExponentElGamal::mul(&party_one_first_message.c_i, &BigInt::from(4));
+        let c_party_two_mul_4 =
+            ExponentElGamal::mul(&party_two_first_message.c_i, &BigInt::from(4));
+        let c0 = ExponentElGamal::encrypt_from_predefined_randomness(
+            &BigInt::from(3),
+            &keys.joint_elgamal_pubkey,
+            &BigInt::zero(),
+        )
+        .and_then(|enc_3| ExponentElGamal::add(&c_party_one_mul_4, &enc_3));
+        match c0 {
+            Ok(c0) => Ok(CiphertextPair {
+                c0,
+                c1: c_party_two_mul_4,
+            }),
+            Err(_) => Err(TwoPartyRSAError::CandidateGenerationEncError),
         }
     }
 
@@ -281,23 +901,361 @@ impl PartyOneCandidateGeneration {
         );
         let r_0 = BigInt::mod_mul(&w.r_0, &BigInt::from(4), &keys.joint_elgamal_pubkey.pp.q);
 
-        let p_0_mod_alpha = p_0.mod_floor(alpha);
-        let r_0_alpha = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
-        let c_0_alpha = ExponentElGamal::encrypt_from_predefined_randomness(
-            &p_0_mod_alpha,
+        let (c_0_alpha, pi_mod) = TrialDivisionModProof::prove(
+            &keys.params,
+            &keys.joint_elgamal_pubkey,
+            &c.c0,
+            &p_0,
+            &r_0,
+            alpha,
+            &keys.proof_context(HmrtParty::PartyOne),
+        )?;
+        Ok(PartyOneCandidateGenerationSecondMsg { pi_mod, c_0_alpha })
+    }
+
+    pub fn verify_party_two_second_message_and_partial_decrypt(
+        party_one_second_message: &PartyOneCandidateGenerationSecondMsg,
+        party_two_second_message: &PartyTwoCandidateGenerationSecondMsg,
+        alpha: &BigInt,
+        keys: &PartyOneKeySetup,
+        c: &CiphertextPair,
+    ) -> Result<
+        (
+            PartyOneCandidateGenerationThirdMsg,
+            ElGamalCiphertext,
+            ElGamalCiphertext,
+        ),
+        TwoPartyRSAError,
+    > {
+        let mod_statement = trial_division_residue_statement::<FFDHEExponentElGamal>(
+            &keys.params,
             &keys.joint_elgamal_pubkey,
-            &r_0_alpha,
+            &c.c1,
+            &party_two_second_message.c_1_alpha,
+            alpha,
+        );
+        let verify = party_two_second_message.pi_mod.verify(
+            &keys.params,
+            &mod_statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        );
+        if let Err(err) = verify {
+            return Err(protocol_abort(
+                ProtocolRound::TrialDivision,
+                "pi_mod",
+                Some(alpha),
+                party_two_second_message,
+                Some(err),
+            ));
+        };
+        let (c_alpha, c_alpha_tilde) =
+            match trial_division_zero_test_ciphertexts::<FFDHEExponentElGamal>(
+                &keys.joint_elgamal_pubkey,
+                &party_one_second_message.c_0_alpha,
+                &party_two_second_message.c_1_alpha,
+                alpha,
+            ) {
+                Ok(c_alphas) => c_alphas,
+                Err(_) => return Err(TwoPartyRSAError::CandidateGenerationEncError),
+            };
+
+        let zero_test = |c: &ElGamalCiphertext| {
+            elgamal_zero_test::<FFDHEExponentElGamal>(
+                &keys.joint_elgamal_pubkey,
+                &keys.local_elgamal_puubkey,
+                &keys.private.sk,
+                c,
+                &keys.proof_context(HmrtParty::PartyOne),
+            )
+        };
+        match (zero_test(&c_alpha), zero_test(&c_alpha_tilde)) {
+            (Ok(zero_test_alpha), Ok(zero_test_alpha_tilde)) => Ok((
+                PartyOneCandidateGenerationThirdMsg {
+                    zero_test_alpha,
+                    zero_test_alpha_tilde,
+                },
+                c_alpha,
+                c_alpha_tilde,
+            )),
+            _ => Err(TwoPartyRSAError::CandidateGenerationEncError),
+        }
+    }
+
+    pub fn verify_party_two_third_message_full_decrypt_and_conclude_division(
+        c_alpha: &ElGamalCiphertext,
+        c_alpha_tilde: &ElGamalCiphertext,
+        party_two_third_message: &PartyTwoCandidateGenerationThirdMsg,
+        alpha: &BigInt,
+        keys: &PartyOneKeySetup,
+    ) -> Result<bool, TwoPartyRSAError> {
+        // the plaintext of c_alpha or c_alpha_tilde is zero iff alpha divides the candidate
+        let conclude = |zero_test: &ElGamalZeroTest, c: &ElGamalCiphertext, name: &str| {
+            conclude_elgamal_zero_test::<FFDHEExponentElGamal>(
+                zero_test,
+                c,
+                &keys.joint_elgamal_pubkey,
+                &keys.remote_elgamal_pubkey,
+                &keys.private.sk,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            )
+            .map_err(|(field, proof_error)| {
+                protocol_abort(
+                    ProtocolRound::TrialDivision,
+                    &format!("{}.{}", name, field),
+                    Some(alpha),
+                    party_two_third_message,
+                    proof_error,
+                )
+            })
+        };
+        let test1 = conclude(
+            &party_two_third_message.zero_test_alpha,
+            c_alpha,
+            "zero_test_alpha",
+        )?;
+        let test2 = conclude(
+            &party_two_third_message.zero_test_alpha_tilde,
+            c_alpha_tilde,
+            "zero_test_alpha_tilde",
+        )?;
+        Ok(!test1 && !test2)
+    }
+
+    /// Batched trial division: a single message carries c_alpha and the mod proof for every
+    /// alpha in alpha_vec. The work on each alpha is done in parallel.
+    pub fn trial_division_prepare_c_alpha_batch(
+        alpha_vec: &[BigInt],
+        keys: &PartyOneKeySetup,
+        c: &CiphertextPair,
+        w: &PartyOneCandidateWitness,
+    ) -> Result<PartyOneCandidateGenerationBatchSecondMsg, TwoPartyRSAError> {
+        let second_msg_vec = alpha_vec
+            .par_iter()
+            .map(|alpha| Self::trial_division_prepare_c_alpha(alpha, keys, c, w))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PartyOneCandidateGenerationBatchSecondMsg { second_msg_vec })
+    }
+
+    pub fn verify_party_two_batch_second_message_and_partial_decrypt(
+        party_one_batch_second_message: &PartyOneCandidateGenerationBatchSecondMsg,
+        party_two_batch_second_message: &PartyTwoCandidateGenerationBatchSecondMsg,
+        alpha_vec: &[BigInt],
+        keys: &PartyOneKeySetup,
+        c: &CiphertextPair,
+    ) -> Result<
+        (
+            PartyOneCandidateGenerationBatchThirdMsg,
+            Vec<ElGamalCiphertext>,
+            Vec<ElGamalCiphertext>,
+        ),
+        TwoPartyRSAError,
+    > {
+        if party_one_batch_second_message.second_msg_vec.len() != alpha_vec.len()
+            || party_two_batch_second_message.second_msg_vec.len() != alpha_vec.len()
+        {
+            return Err(TwoPartyRSAError::InvalidModProof);
+        }
+        let results = alpha_vec
+            .par_iter()
+            .zip(party_one_batch_second_message.second_msg_vec.par_iter())
+            .zip(party_two_batch_second_message.second_msg_vec.par_iter())
+            .map(
+                |((alpha, party_one_second_message), party_two_second_message)| {
+                    Self::verify_party_two_second_message_and_partial_decrypt(
+                        party_one_second_message,
+                        party_two_second_message,
+                        alpha,
+                        keys,
+                        c,
+                    )
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut third_msg_vec = Vec::with_capacity(results.len());
+        let mut c_alpha_vec = Vec::with_capacity(results.len());
+        let mut c_alpha_tilde_vec = Vec::with_capacity(results.len());
+        for (third_msg, c_alpha, c_alpha_tilde) in results {
+            third_msg_vec.push(third_msg);
+            c_alpha_vec.push(c_alpha);
+            c_alpha_tilde_vec.push(c_alpha_tilde);
+        }
+        Ok((
+            PartyOneCandidateGenerationBatchThirdMsg { third_msg_vec },
+            c_alpha_vec,
+            c_alpha_tilde_vec,
+        ))
+    }
+
+    /// Returns for every alpha in the batch whether it is coprime to the candidate,
+    /// use first_dividing_prime to find the first alpha that divides it.
+    pub fn verify_party_two_batch_third_message_full_decrypt_and_conclude_division(
+        c_alpha_vec: &[ElGamalCiphertext],
+        c_alpha_tilde_vec: &[ElGamalCiphertext],
+        party_two_batch_third_message: &PartyTwoCandidateGenerationBatchThirdMsg,
+        alpha_vec: &[BigInt],
+        keys: &PartyOneKeySetup,
+    ) -> Result<Vec<bool>, TwoPartyRSAError> {
+        if c_alpha_vec.len() != c_alpha_tilde_vec.len()
+            || party_two_batch_third_message.third_msg_vec.len() != c_alpha_vec.len()
+            || alpha_vec.len() != c_alpha_vec.len()
+        {
+            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+        }
+        c_alpha_vec
+            .par_iter()
+            .zip(c_alpha_tilde_vec.par_iter())
+            .zip(party_two_batch_third_message.third_msg_vec.par_iter())
+            .zip(alpha_vec.par_iter())
+            .map(
+                |(((c_alpha, c_alpha_tilde), party_two_third_message), alpha)| {
+                    Self::verify_party_two_third_message_full_decrypt_and_conclude_division(
+                        c_alpha,
+                        c_alpha_tilde,
+                        party_two_third_message,
+                        alpha,
+                        keys,
+                    )
+                },
+            )
+            .collect()
+    }
+}
+
+impl PartyOnePaillierCandidateGeneration {
+    pub fn generate_shares_of_candidate(
+        keys: &PartyOneKeySetup,
+    ) -> Result<
+        (
+            PartyOnePaillierCandidateWitness,
+            PartyOnePaillierCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let share_bit_size: usize = keys.params.candidate_bit_length / 2 - 2;
+        let ek = &keys.local_paillier_pubkey;
+        let p_i = BigInt::sample(share_bit_size);
+        let r_i = PaillierScheme::sample_randomness(ek);
+        let c_i = match PaillierScheme::encrypt_with_randomness(ek, &p_i, &r_i) {
+            Ok(c_i) => c_i,
+            Err(_) => return Err(TwoPartyRSAError::CandidateGenerationEncError),
+        };
+
+        let enc_witness = HomoElGamalWitness {
+            r: r_i.clone(),
+            m: p_i.clone(),
+        };
+        let enc_statement = HomoElGamalStatement::<PaillierScheme> {
+            pk: ek.clone(),
+            ciphertext: c_i.clone(),
+        };
+        let bound_witness = BoundWitness {
+            x: p_i.clone(),
+            r: r_i.clone(),
+        };
+        let bound_statement = BoundStatement::<PaillierScheme> {
+            pk: ek.clone(),
+            range: keys.params.share_range(),
+            ciphertext: c_i.clone(),
+        };
+
+        match (
+            HomoELGamalProof::prove(
+                &enc_witness,
+                &enc_statement,
+                &keys.proof_context(HmrtParty::PartyOne),
+            ),
+            TightRangeProof::prove(
+                &bound_witness,
+                &bound_statement,
+                &keys.proof_context(HmrtParty::PartyOne),
+            ),
+        ) {
+            (Ok(pi_enc), Ok(pi_bound)) => Ok((
+                PartyOnePaillierCandidateWitness { p_0: p_i, r_0: r_i },
+                PartyOnePaillierCandidateGenerationFirstMsg {
+                    c_i,
+                    pi_enc,
+                    pi_bound,
+                },
+            )),
+            (Err(err), _) | (_, Err(err)) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+        }
+    }
+
+    pub fn verify_party_two_first_message_and_normalize_ciphertexts(
+        keys: &PartyOneKeySetup,
+        party_one_first_message: &PartyOnePaillierCandidateGenerationFirstMsg,
+        party_two_first_message: &PartyTwoPaillierCandidateGenerationFirstMsg,
+    ) -> Result<PaillierCiphertextPair, TwoPartyRSAError> {
+        let enc_statement = HomoElGamalStatement::<PaillierScheme> {
+            pk: keys.remote_paillier_pubkey.clone(),
+            ciphertext: party_two_first_message.c_i.clone(),
+        };
+        let bound_statement = BoundStatement::<PaillierScheme> {
+            pk: keys.remote_paillier_pubkey.clone(),
+            range: keys.params.share_range(),
+            ciphertext: party_two_first_message.c_i.clone(),
+        };
+
+        if let Err(err) = party_two_first_message
+            .pi_enc
+            .verify(&enc_statement, &keys.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_enc",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
+        }
+        if let Err(err) = party_two_first_message
+            .pi_bound
+            .verify(&bound_statement, &keys.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_bound",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
+        }
+        normalize_paillier_ciphertexts(
+            &keys.local_paillier_pubkey,
+            &party_one_first_message.c_i,
+            &keys.remote_paillier_pubkey,
+            &party_two_first_message.c_i,
         )
-        .unwrap();
+    }
+
+    pub fn trial_division_prepare_c_alpha(
+        alpha: &BigInt,
+        keys: &PartyOneKeySetup,
+        c: &PaillierCiphertextPair,
+        w: &PartyOnePaillierCandidateWitness,
+    ) -> Result<PartyOnePaillierCandidateGenerationSecondMsg, TwoPartyRSAError> {
+        let ek = &keys.local_paillier_pubkey;
+        // witness of c0 = Enc(p_0)^4 * Enc(3; 1)
+        let p_0 = &w.p_0 * BigInt::from(4) + BigInt::from(3);
+        let r_0 = PaillierScheme::mul_randomness(ek, &w.r_0, &BigInt::from(4));
+
+        let p_0_mod_alpha = p_0.mod_floor(alpha);
+        let r_0_alpha = PaillierScheme::sample_randomness(ek);
+        let c_0_alpha =
+            match PaillierScheme::encrypt_with_randomness(ek, &p_0_mod_alpha, &r_0_alpha) {
+                Ok(c_0_alpha) => c_0_alpha,
+                Err(_) => return Err(TwoPartyRSAError::InvalidModProof),
+            };
 
-        let mod_statement = ModStatement {
+        let mod_statement = ModStatement::<PaillierScheme> {
             c: c.c0.clone(),
             c_prime: c_0_alpha.clone(),
             modulus_p: alpha.clone(),
-            upper_bound_m: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32), // n/2 instead of n/2-2 as is written in the paper : we suspect paper has a typo and do not consider the fact that ciphertexts and plaintext are scaled by mul4
-            pk: keys.joint_elgamal_pubkey.clone(),
+            upper_bound_m: keys.params.share_bound(),
+            pk: ek.clone(),
         };
-
         let mod_witness = ModWitness {
             r_a: r_0,
             a: p_0,
@@ -305,245 +1263,842 @@ impl PartyOneCandidateGeneration {
             b: p_0_mod_alpha,
         };
 
-        let proof = ModProof::prove(&mod_witness, &mod_statement);
-
-        match proof {
-            Ok(_) => Ok(PartyOneCandidateGenerationSecondMsg {
-                pi_mod: proof.unwrap(),
-                c_0_alpha,
-            }),
-            Err(_) => Err(TwoPartyRSAError::InvalidModProof),
+        match ModProof::prove(
+            &mod_witness,
+            &mod_statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        ) {
+            Ok(pi_mod) => Ok(PartyOnePaillierCandidateGenerationSecondMsg { pi_mod, c_0_alpha }),
+            Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
         }
     }
 
-    pub fn verify_party_two_second_message_and_partial_decrypt(
-        party_one_second_message: &PartyOneCandidateGenerationSecondMsg,
-        party_two_second_message: &PartyTwoCandidateGenerationSecondMsg,
+    /// Verifies the residue of party two and masks the sum of residues under its key, once to
+    /// test it against zero and once against alpha
+    pub fn verify_party_two_second_message_and_mask_residues(
+        party_one_second_message: &PartyOnePaillierCandidateGenerationSecondMsg,
+        party_two_second_message: &PartyTwoPaillierCandidateGenerationSecondMsg,
         alpha: &BigInt,
         keys: &PartyOneKeySetup,
-        c: &CiphertextPair,
-    ) -> Result<
-        (
-            PartyOneCandidateGenerationThirdMsg,
-            ElGamalCiphertext,
-            ElGamalCiphertext,
-        ),
-        TwoPartyRSAError,
-    > {
-        let mod_statement = ModStatement {
+        c: &PaillierCiphertextPair,
+        w: &PartyOnePaillierCandidateWitness,
+    ) -> Result<PartyOnePaillierCandidateGenerationThirdMsg, TwoPartyRSAError> {
+        let mod_statement = ModStatement::<PaillierScheme> {
             c: c.c1.clone(),
             c_prime: party_two_second_message.c_1_alpha.clone(),
             modulus_p: alpha.clone(),
-            upper_bound_m: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32),
-            pk: keys.joint_elgamal_pubkey.clone(),
+            upper_bound_m: keys.params.share_bound(),
+            pk: keys.remote_paillier_pubkey.clone(),
         };
-        let verify = party_two_second_message.pi_mod.verify(&mod_statement);
-        if verify.is_err() {
-            return Err(TwoPartyRSAError::InvalidModProof);
+        if let Err(err) = party_two_second_message
+            .pi_mod
+            .verify(&mod_statement, &keys.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::TrialDivision,
+                "pi_mod",
+                Some(alpha),
+                party_two_second_message,
+                Some(err),
+            ));
+        }
+
+        let p_0_mod_alpha = (&w.p_0 * BigInt::from(4) + BigInt::from(3)).mod_floor(alpha);
+        let zero_test = |offset: &BigInt| {
+            paillier_zero_test(
+                &keys.local_paillier_pubkey,
+                &keys.private.dk,
+                &party_one_second_message.c_0_alpha,
+                &p_0_mod_alpha,
+                &keys.remote_paillier_pubkey,
+                &party_two_second_message.c_1_alpha,
+                offset,
+                &keys.proof_context(HmrtParty::PartyOne),
+            )
         };
-        let c_alpha = ExponentElGamal::add(
-            &party_one_second_message.c_0_alpha,
-            &party_two_second_message.c_1_alpha,
-        )
-        .unwrap();
-        // Enc(-alpha) is known to both parties therefore we use a predefined randomness known to both (r = 2)
-        let enc_alpha = ExponentElGamal::encrypt_from_predefined_randomness(
-            alpha,
-            &keys.joint_elgamal_pubkey,
-            &BigInt::from(2),
+        Ok(PartyOnePaillierCandidateGenerationThirdMsg {
+            zero_test_alpha: zero_test(&BigInt::zero())?,
+            zero_test_alpha_tilde: zero_test(&zero_test_tilde_offset(alpha))?,
+        })
+    }
+
+    /// Decrypts the zero tests of party two, true if alpha does not divide the candidate
+    pub fn verify_party_two_third_message_and_conclude_division(
+        party_one_second_message: &PartyOnePaillierCandidateGenerationSecondMsg,
+        party_two_second_message: &PartyTwoPaillierCandidateGenerationSecondMsg,
+        party_two_third_message: &PartyTwoPaillierCandidateGenerationThirdMsg,
+        alpha: &BigInt,
+        keys: &PartyOneKeySetup,
+    ) -> Result<bool, TwoPartyRSAError> {
+        let conclude = |zero_test: &PaillierZeroTest, offset: &BigInt| {
+            conclude_paillier_zero_test(
+                zero_test,
+                &keys.local_paillier_pubkey,
+                &keys.private.dk,
+                &party_one_second_message.c_0_alpha,
+                &keys.remote_paillier_pubkey,
+                &party_two_second_message.c_1_alpha,
+                offset,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            )
+        };
+        let test1 =
+            conclude(&party_two_third_message.zero_test_alpha, &BigInt::zero()).map_err(|err| {
+                protocol_abort(
+                    ProtocolRound::TrialDivision,
+                    "zero_test_alpha",
+                    Some(alpha),
+                    party_two_third_message,
+                    Some(err),
+                )
+            })?;
+        let test2 = conclude(
+            &party_two_third_message.zero_test_alpha_tilde,
+            &zero_test_tilde_offset(alpha),
         )
-        .unwrap();
-        let enc_minus_alpha = ExponentElGamal::mul(&enc_alpha, &(-BigInt::one()));
-        let c_alpha_tilde = ExponentElGamal::add(&c_alpha, &enc_minus_alpha).unwrap();
-
-        // we raise each ciphertext with a secret random number
-        let r_alpha = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
-        let r_alpha_tilde = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
-
-        let c_alpha_random = ExponentElGamal::mul(&c_alpha, &r_alpha);
-        let c_alpha_tilde_random = ExponentElGamal::mul(&c_alpha_tilde, &r_alpha_tilde);
-
-        // we use proof of DDH to prove to counter party that c_alpha_random = c_alpha ^r and same for c_alpha_tilde
-        let (_, _, ddh_proof_alpha) = gen_ddh_containers(
-            r_alpha,
-            &c_alpha.c1,
-            &c_alpha_random.c1,
-            &c_alpha.c2,
-            &c_alpha_random.c2,
-            &keys.joint_elgamal_pubkey.pp,
+        .map_err(|err| {
+            protocol_abort(
+                ProtocolRound::TrialDivision,
+                "zero_test_alpha_tilde",
+                Some(alpha),
+                party_two_third_message,
+                Some(err),
+            )
+        })?;
+        Ok(!test1 && !test2)
+    }
+}
+
+impl PartyOneBiprimalityTest {
+    // for every round j we send v_j = g_j^(p_0 + q_0) mod N together with a proof that the
+    // exponent is the plaintext of c_p * c_q, the ciphertexts sent in candidate generation.
+    pub fn compute_exponentiations_and_first_message(
+        n: &BigInt,
+        keys: &PartyOneKeySetup,
+        p_witness: &PartyOneCandidateWitness,
+        q_witness: &PartyOneCandidateWitness,
+        p_first_message: &PartyOneCandidateGenerationFirstMsg,
+        q_first_message: &PartyOneCandidateGenerationFirstMsg,
+    ) -> Result<PartyOneBiprimalityTestFirstMsg, TwoPartyRSAError> {
+        let x = &p_witness.p_0 + &q_witness.p_0;
+        let r = BigInt::mod_add(
+            &p_witness.r_0,
+            &q_witness.r_0,
+            &keys.joint_elgamal_pubkey.pp.q,
         );
+        let c_x = ExponentElGamal::add(&p_first_message.c_i, &q_first_message.c_i).unwrap();
+
+        let mut v_vec = Vec::with_capacity(BIPRIMALITY_TEST_ROUNDS);
+        let mut pi_eq_vec = Vec::with_capacity(BIPRIMALITY_TEST_ROUNDS);
+        for j in 0..BIPRIMALITY_TEST_ROUNDS {
+            let g = biprimality_test_base(n, j);
+            let v = BigInt::mod_pow(&g, &x, n);
+            let eq_statement = EqStatement {
+                pk: keys.joint_elgamal_pubkey.clone(),
+                h: g,
+                h_prime: v.clone(),
+                n: n.clone(),
+                ciphertext: c_x.clone(),
+                sec_param: keys.params.sec_param,
+                kapa: keys.params.kapa,
+            };
+            let eq_witness = EqWitness {
+                x: x.clone(),
+                r: r.clone(),
+            };
+            match EqProof::prove(
+                &eq_statement,
+                &eq_witness,
+                &keys.proof_context(HmrtParty::PartyOne),
+            ) {
+                Ok(proof) => pi_eq_vec.push(proof),
+                Err(err) => return Err(TwoPartyRSAError::ProofGenerationError(err)),
+            }
+            v_vec.push(v);
+        }
+
+        Ok(PartyOneBiprimalityTestFirstMsg { v_vec, pi_eq_vec })
+    }
+
+    pub fn verify_party_two_first_message_and_conclude_biprimality(
+        n: &BigInt,
+        keys: &PartyOneKeySetup,
+        party_two_p_first_message: &PartyTwoCandidateGenerationFirstMsg,
+        party_two_q_first_message: &PartyTwoCandidateGenerationFirstMsg,
+        party_one_biprimality_message: &PartyOneBiprimalityTestFirstMsg,
+        party_two_biprimality_message: &PartyTwoBiprimalityTestFirstMsg,
+    ) -> Result<bool, TwoPartyRSAError> {
+        if party_two_biprimality_message.v_vec.len() != BIPRIMALITY_TEST_ROUNDS
+            || party_two_biprimality_message.pi_eq_vec.len() != BIPRIMALITY_TEST_ROUNDS
+        {
+            return Err(TwoPartyRSAError::InvalidBiprimalityProof);
+        }
+        let c_x = match ExponentElGamal::add(
+            &party_two_p_first_message.c_i,
+            &party_two_q_first_message.c_i,
+        ) {
+            Ok(c_x) => c_x,
+            Err(_) => return Err(TwoPartyRSAError::InvalidBiprimalityProof),
+        };
 
-        let (_, _, ddh_proof_alpha_tilde) = gen_ddh_containers(
-            r_alpha_tilde,
-            &c_alpha_tilde.c1,
-            &c_alpha_tilde_random.c1,
-            &c_alpha_tilde.c2,
-            &c_alpha_tilde_random.c2,
-            &keys.joint_elgamal_pubkey.pp,
+        for j in 0..BIPRIMALITY_TEST_ROUNDS {
+            let eq_statement = EqStatement {
+                pk: keys.joint_elgamal_pubkey.clone(),
+                h: biprimality_test_base(n, j),
+                h_prime: party_two_biprimality_message.v_vec[j].clone(),
+                n: n.clone(),
+                ciphertext: c_x.clone(),
+                sec_param: keys.params.sec_param,
+                kapa: keys.params.kapa,
+            };
+            if let Err(err) = party_two_biprimality_message.pi_eq_vec[j]
+                .verify(&eq_statement, &keys.proof_context(HmrtParty::PartyTwo))
+            {
+                return Err(protocol_abort(
+                    ProtocolRound::BiprimalityTest,
+                    &format!("pi_eq_vec[{}]", j),
+                    None,
+                    party_two_biprimality_message,
+                    Some(err),
+                ));
+            }
+        }
+
+        Ok(conclude_biprimality(
+            n,
+            &party_one_biprimality_message.v_vec,
+            &party_two_biprimality_message.v_vec,
+        ))
+    }
+}
+
+impl PartyOneJointModulus {
+    // p = 4p_0 + 3 and q are encrypted under our own Paillier key, together with
+    // Enc(p*q), and linked to the normalized ElGamal ciphertexts of the candidates.
+    pub fn encrypt_shares_and_first_message(
+        keys: &PartyOneKeySetup,
+        p_witness: &PartyOneCandidateWitness,
+        q_witness: &PartyOneCandidateWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+    ) -> Result<(PartyOneJointModulusWitness, PartyOneJointModulusFirstMsg), TwoPartyRSAError> {
+        let ek = &keys.local_paillier_pubkey;
+        let q_elgamal = &keys.joint_elgamal_pubkey.pp.q;
+        let p = BigInt::from(4) * &p_witness.p_0 + BigInt::from(3);
+        let q = BigInt::from(4) * &q_witness.p_0 + BigInt::from(3);
+        let r_p = BigInt::mod_mul(&p_witness.r_0, &BigInt::from(4), q_elgamal);
+        let r_q = BigInt::mod_mul(&q_witness.r_0, &BigInt::from(4), q_elgamal);
+
+        let rho_p = BigInt::sample_below(&ek.n);
+        let rho_q = BigInt::sample_below(&ek.n);
+        let c_p = Paillier::encrypt_with_chosen_randomness(
+            ek,
+            RawPlaintext::from(p.clone()),
+            &Randomness(rho_p.clone()),
+        )
+        .0
+        .into_owned();
+        let c_q = Paillier::encrypt_with_chosen_randomness(
+            ek,
+            RawPlaintext::from(q.clone()),
+            &Randomness(rho_q.clone()),
+        )
+        .0
+        .into_owned();
+        let sigma = BigInt::sample_below(&ek.n);
+        let c_pq = BigInt::mod_mul(
+            &BigInt::mod_pow(&c_q, &p, &ek.nn),
+            &BigInt::mod_pow(&sigma, &ek.n, &ek.nn),
+            &ek.nn,
         );
+        let rho_pq = BigInt::mod_mul(&BigInt::mod_pow(&rho_q, &p, &ek.n), &sigma, &ek.n);
 
-        let dec_key_alpha = BigInt::mod_pow(
-            &c_alpha_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.p,
+        let statement_p = share_encryption_statement(
+            &keys.params,
+            &ek.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &c_p,
         );
-        let dec_key_alpha_tilde = BigInt::mod_pow(
-            &c_alpha_tilde_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.p,
+        let statement_q = share_encryption_statement(
+            &keys.params,
+            &ek.n,
+            &keys.joint_elgamal_pubkey,
+            &q_ciphertexts.c0,
+            &c_q,
         );
-
-        let statement_alpha = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.local_elgamal_puubkey.h.clone(),
-            g2: c_alpha_random.c1.clone(),
-            h2: dec_key_alpha.clone(),
-        };
-        let witness_alpha = DDHWitness {
-            x: keys.private.sk.x.clone(),
+        let statement_pq = share_product_statement(
+            &keys.params,
+            &ek.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &c_q,
+            &c_pq,
+        );
+        let witness_p = AffineWitness {
+            a_vec: vec![p.clone()],
+            r_vec: vec![r_p.clone()],
+            rho: rho_p,
         };
-        let statement_alpha_tilde = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.local_elgamal_puubkey.h.clone(),
-            g2: c_alpha_tilde_random.c1.clone(),
-            h2: dec_key_alpha_tilde.clone(),
+        let witness_q = AffineWitness {
+            a_vec: vec![q],
+            r_vec: vec![r_q],
+            rho: rho_q,
         };
-        let witness_alpha_tilde = DDHWitness {
-            x: keys.private.sk.x.clone(),
+        let witness_pq = AffineWitness {
+            a_vec: vec![p],
+            r_vec: vec![r_p],
+            rho: sigma,
         };
 
-        let proof_alpha = DDHProof::prove(&witness_alpha, &statement_alpha);
-        let proof_alpha_tilde = DDHProof::prove(&witness_alpha_tilde, &statement_alpha_tilde);
-
-        Ok((
-            PartyOneCandidateGenerationThirdMsg {
-                proof_alpha,
-                proof_alpha_tilde,
-                c_alpha_random,
-                c_alpha_tilde_random,
-                partial_dec_c_alpha: dec_key_alpha,
-                partial_dec_c_alpha_tilde: dec_key_alpha_tilde,
-                ddh_proof_alpha,
-                ddh_proof_alpha_tilde,
-            },
-            c_alpha,
-            c_alpha_tilde,
-        ))
+        let pi_p = AffineProof::prove(
+            &witness_p,
+            &statement_p,
+            &keys.proof_context(HmrtParty::PartyOne),
+        );
+        let pi_q = AffineProof::prove(
+            &witness_q,
+            &statement_q,
+            &keys.proof_context(HmrtParty::PartyOne),
+        );
+        let pi_pq = AffineProof::prove(
+            &witness_pq,
+            &statement_pq,
+            &keys.proof_context(HmrtParty::PartyOne),
+        );
+        match (pi_p, pi_q, pi_pq) {
+            (Ok(pi_p), Ok(pi_q), Ok(pi_pq)) => Ok((
+                PartyOneJointModulusWitness { rho_pq },
+                PartyOneJointModulusFirstMsg {
+                    c_p,
+                    c_q,
+                    c_pq,
+                    pi_p,
+                    pi_q,
+                    pi_pq,
+                },
+            )),
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                Err(TwoPartyRSAError::ProofGenerationError(err))
+            }
+        }
     }
 
-    pub fn verify_party_two_third_message_full_decrypt_and_conclude_division(
-        c_alpha: &ElGamalCiphertext,
-        c_alpha_tilde: &ElGamalCiphertext,
-        party_two_third_message: &PartyTwoCandidateGenerationThirdMsg,
+    // We compute under the counter party key: Enc(p_remote * q + q_remote * p + p * q).
+    // The counter party adds p_remote * q_remote after decryption to get N.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_party_two_first_message_and_compute_cross_terms(
         keys: &PartyOneKeySetup,
-    ) -> Result<bool, TwoPartyRSAError> {
-        // check that the randomization of the ciphertexts was done properly:
-        let statement_alpha_ddh = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: c_alpha.c1.clone(),
-            h1: party_two_third_message.c_alpha_random.c1.clone(),
-            g2: c_alpha.c2.clone(),
-            h2: party_two_third_message.c_alpha_random.c2.clone(),
-        };
-        let statement_alpha_tilde_ddh = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: c_alpha_tilde.c1.clone(),
-            h1: party_two_third_message.c_alpha_tilde_random.c1.clone(),
-            g2: c_alpha_tilde.c2.clone(),
-            h2: party_two_third_message.c_alpha_tilde_random.c2.clone(),
-        };
-        if party_two_third_message
-            .ddh_proof_alpha
-            .verify(&statement_alpha_ddh)
-            .is_err()
+        p_witness: &PartyOneCandidateWitness,
+        q_witness: &PartyOneCandidateWitness,
+        joint_modulus_witness: &PartyOneJointModulusWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+        party_one_first_message: &PartyOneJointModulusFirstMsg,
+        party_two_first_message: &PartyTwoJointModulusFirstMsg,
+    ) -> Result<PartyOneJointModulusSecondMsg, TwoPartyRSAError> {
+        let ek_remote = &keys.remote_paillier_pubkey;
+        let statement_p = share_encryption_statement(
+            &keys.params,
+            &ek_remote.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &party_two_first_message.c_p,
+        );
+        let statement_q = share_encryption_statement(
+            &keys.params,
+            &ek_remote.n,
+            &keys.joint_elgamal_pubkey,
+            &q_ciphertexts.c1,
+            &party_two_first_message.c_q,
+        );
+        let statement_pq = share_product_statement(
+            &keys.params,
+            &ek_remote.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &party_two_first_message.c_q,
+            &party_two_first_message.c_pq,
+        );
+        for (proof, statement, name) in [
+            (&party_two_first_message.pi_p, &statement_p, "pi_p"),
+            (&party_two_first_message.pi_q, &statement_q, "pi_q"),
+            (&party_two_first_message.pi_pq, &statement_pq, "pi_pq"),
+        ]
+        .iter()
         {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+            if let Err(err) = proof.verify(statement, &keys.proof_context(HmrtParty::PartyTwo)) {
+                return Err(protocol_abort(
+                    ProtocolRound::JointModulus,
+                    name,
+                    None,
+                    party_two_first_message,
+                    Some(err),
+                ));
+            }
         }
 
-        if party_two_third_message
-            .ddh_proof_alpha_tilde
-            .verify(&statement_alpha_tilde_ddh)
-            .is_err()
+        let q_elgamal = &keys.joint_elgamal_pubkey.pp.q;
+        let p = BigInt::from(4) * &p_witness.p_0 + BigInt::from(3);
+        let q = BigInt::from(4) * &q_witness.p_0 + BigInt::from(3);
+        let r_p = BigInt::mod_mul(&p_witness.r_0, &BigInt::from(4), q_elgamal);
+        let r_q = BigInt::mod_mul(&q_witness.r_0, &BigInt::from(4), q_elgamal);
+        let pq = &p * &q;
+
+        let rho = BigInt::sample_below(&ek_remote.n);
+        let c_n = [
+            BigInt::mod_pow(&party_two_first_message.c_p, &q, &ek_remote.nn),
+            BigInt::mod_pow(&party_two_first_message.c_q, &p, &ek_remote.nn),
+            BigInt::mod_pow(&(&ek_remote.n + BigInt::one()), &pq, &ek_remote.nn),
+            BigInt::mod_pow(&rho, &ek_remote.n, &ek_remote.nn),
+        ]
+        .iter()
+        .fold(BigInt::one(), |acc, x| {
+            BigInt::mod_mul(&acc, x, &ek_remote.nn)
+        });
+
+        let statement_n = cross_product_statement(
+            &keys.params,
+            &ek_remote.n,
+            &party_two_first_message.c_p,
+            &party_two_first_message.c_q,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &q_ciphertexts.c0,
+            &keys.local_paillier_pubkey.n,
+            &party_one_first_message.c_pq,
+            &c_n,
+        );
+        let witness_n = AffineWitness {
+            a_vec: vec![q, p, pq],
+            r_vec: vec![r_q, r_p, joint_modulus_witness.rho_pq.clone()],
+            rho,
+        };
+        match AffineProof::prove(
+            &witness_n,
+            &statement_n,
+            &keys.proof_context(HmrtParty::PartyOne),
+        ) {
+            Ok(pi_n) => Ok(PartyOneJointModulusSecondMsg { c_n, pi_n }),
+            Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+        }
+    }
+
+    // Enc(cross terms) * Enc(p * q) encrypts N under our key: we decrypt it and reveal the
+    // randomness of the product ciphertext as a proof of correct decryption.
+    pub fn verify_party_two_second_message_and_decrypt_modulus(
+        keys: &PartyOneKeySetup,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+        party_one_first_message: &PartyOneJointModulusFirstMsg,
+        party_two_first_message: &PartyTwoJointModulusFirstMsg,
+        party_two_second_message: &PartyTwoJointModulusSecondMsg,
+    ) -> Result<PartyOneJointModulusThirdMsg, TwoPartyRSAError> {
+        let ek = &keys.local_paillier_pubkey;
+        let statement_n = cross_product_statement(
+            &keys.params,
+            &ek.n,
+            &party_one_first_message.c_p,
+            &party_one_first_message.c_q,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &q_ciphertexts.c1,
+            &keys.remote_paillier_pubkey.n,
+            &party_two_first_message.c_pq,
+            &party_two_second_message.c_n,
+        );
+        if let Err(err) = party_two_second_message
+            .pi_n
+            .verify(&statement_n, &keys.proof_context(HmrtParty::PartyTwo))
         {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+            return Err(protocol_abort(
+                ProtocolRound::JointModulus,
+                "pi_n",
+                None,
+                party_two_second_message,
+                Some(err),
+            ));
+        }
+
+        let c_n_full = BigInt::mod_mul(
+            &party_two_second_message.c_n,
+            &party_one_first_message.c_pq,
+            &ek.nn,
+        );
+        let n = Paillier::decrypt(&keys.private.dk, RawCiphertext::from(c_n_full.clone()))
+            .0
+            .into_owned();
+        let rho_n = paillier_extract_randomness(&c_n_full, &n, &keys.private.dk);
+
+        Ok(PartyOneJointModulusThirdMsg { n, rho_n })
+    }
+
+    pub fn verify_party_two_third_message_and_output_joint_modulus(
+        keys: &PartyOneKeySetup,
+        party_one_second_message: &PartyOneJointModulusSecondMsg,
+        party_one_third_message: &PartyOneJointModulusThirdMsg,
+        party_two_first_message: &PartyTwoJointModulusFirstMsg,
+        party_two_third_message: &PartyTwoJointModulusThirdMsg,
+    ) -> Result<JointModulus, TwoPartyRSAError> {
+        let ek_remote = &keys.remote_paillier_pubkey;
+        let c_n_full = BigInt::mod_mul(
+            &party_one_second_message.c_n,
+            &party_two_first_message.c_pq,
+            &ek_remote.nn,
+        );
+        if !paillier_verify_randomness(
+            &c_n_full,
+            &party_two_third_message.n,
+            &party_two_third_message.rho_n,
+            &ek_remote.n,
+        ) {
+            return Err(protocol_abort(
+                ProtocolRound::JointModulus,
+                "rho_n",
+                None,
+                party_two_third_message,
+                None,
+            ));
+        }
+        if party_two_third_message.n != party_one_third_message.n {
+            return Err(TwoPartyRSAError::JointModulusError);
         }
+        Ok(JointModulus {
+            n: party_one_third_message.n.clone(),
+        })
+    }
+}
+
+impl PartyOnePrivateExponentShare {
+    // phi(N) = phi_0 - phi_1 with phi_0 = N + 1 - p - q for our shares p = 4p_0 + 3, q = 4q_0 + 3.
+    // We reveal (p + q) mod e, proven to be the residue of the plaintext of c_p * c_q. As in
+    // Boneh-Franklin this leaks log(e) bits of our shares.
+    pub fn reveal_phi_share_mod_e_and_first_message(
+        e: &BigInt,
+        keys: &PartyOneKeySetup,
+        p_witness: &PartyOneCandidateWitness,
+        q_witness: &PartyOneCandidateWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+    ) -> Result<PartyOnePrivateExponentShareFirstMsg, TwoPartyRSAError> {
+        if !is_valid_public_exponent(e) {
+            return Err(TwoPartyRSAError::InvalidPublicExponent);
+        }
+        let q_elgamal = &keys.joint_elgamal_pubkey.pp.q;
+        let x = BigInt::from(4) * (&p_witness.p_0 + &q_witness.p_0) + BigInt::from(6);
+        let r_x = BigInt::mod_mul(
+            &BigInt::mod_add(&p_witness.r_0, &q_witness.r_0, q_elgamal),
+            &BigInt::from(4),
+            q_elgamal,
+        );
+
+        let phi_mod_e = x.mod_floor(e);
+        let r_phi_mod_e = BigInt::sample_below(q_elgamal);
+        let c_phi_mod_e = ExponentElGamal::encrypt_from_predefined_randomness(
+            &phi_mod_e,
+            &keys.joint_elgamal_pubkey,
+            &r_phi_mod_e,
+        )
+        .unwrap();
 
-        // verify proofs of decryption:
-        let statement_alpha = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.remote_elgamal_pubkey.h.clone(),
-            g2: party_two_third_message.c_alpha_random.c1.clone(),
-            h2: party_two_third_message.partial_dec_c_alpha.clone(),
+        let mod_statement = phi_share_mod_statement(
+            &keys.params,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &q_ciphertexts.c0,
+            &c_phi_mod_e,
+            e,
+        )?;
+        let mod_witness = ModWitness {
+            r_a: r_x,
+            a: x,
+            r_b: r_phi_mod_e.clone(),
+            b: phi_mod_e.clone(),
         };
-        let statement_alpha_tilde = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.remote_elgamal_pubkey.h.clone(),
-            g2: party_two_third_message.c_alpha_tilde_random.c1.clone(),
-            h2: party_two_third_message.partial_dec_c_alpha_tilde.clone(),
+        let pi_mod = match ModProof::prove(
+            &mod_witness,
+            &mod_statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        ) {
+            Ok(proof) => proof,
+            Err(err) => return Err(TwoPartyRSAError::ProofGenerationError(err)),
         };
 
-        if party_two_third_message
-            .proof_alpha
-            .verify(&statement_alpha)
-            .is_err()
+        Ok(PartyOnePrivateExponentShareFirstMsg {
+            c_phi_mod_e,
+            pi_mod,
+            phi_mod_e,
+            r_phi_mod_e,
+        })
+    }
+
+    // given phi(N) mod e both parties compute zeta = -phi(N)^-1 mod e locally. Our share is
+    // d_0 = floor((1 + zeta * phi_0) / e) and vk_0 = vk_base^d_0 mod N.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_party_two_first_message_and_compute_exponent_share(
+        e: &BigInt,
+        joint_modulus: &JointModulus,
+        keys: &PartyOneKeySetup,
+        p_witness: &PartyOneCandidateWitness,
+        q_witness: &PartyOneCandidateWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+        party_one_first_message: &PartyOnePrivateExponentShareFirstMsg,
+        party_two_first_message: &PartyTwoPrivateExponentShareFirstMsg,
+    ) -> Result<
+        (
+            PartyOnePrivateExponentShareWitness,
+            PartyOnePrivateExponentShareSecondMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let opening_ok = ExponentElGamal::encrypt_from_predefined_randomness(
+            &party_two_first_message.phi_mod_e,
+            &keys.joint_elgamal_pubkey,
+            &party_two_first_message.r_phi_mod_e,
+        )
+        .map(|expected_c_phi_mod_e| expected_c_phi_mod_e == party_two_first_message.c_phi_mod_e)
+        .unwrap_or(false);
+        if !opening_ok || &party_two_first_message.phi_mod_e >= e {
+            return Err(protocol_abort(
+                ProtocolRound::PrivateExponentShare,
+                "r_phi_mod_e",
+                None,
+                party_two_first_message,
+                None,
+            ));
+        }
+        let mod_statement = phi_share_mod_statement(
+            &keys.params,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &q_ciphertexts.c1,
+            &party_two_first_message.c_phi_mod_e,
+            e,
+        )?;
+        if let Err(err) = party_two_first_message
+            .pi_mod
+            .verify(&mod_statement, &keys.proof_context(HmrtParty::PartyTwo))
         {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+            return Err(protocol_abort(
+                ProtocolRound::PrivateExponentShare,
+                "pi_mod",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
         }
 
-        if party_two_third_message
-            .proof_alpha_tilde
-            .verify(&statement_alpha_tilde)
-            .is_err()
-        {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+        let n = &joint_modulus.n;
+        let zeta = match exponent_inversion_zeta(
+            n,
+            e,
+            &party_one_first_message.phi_mod_e,
+            &party_two_first_message.phi_mod_e,
+        ) {
+            Some(zeta) => zeta,
+            None => return Err(TwoPartyRSAError::InvalidPublicExponent),
+        };
+
+        let x = BigInt::from(4) * (&p_witness.p_0 + &q_witness.p_0) + BigInt::from(6);
+        let phi_0 = n + BigInt::one() - x;
+        let d_0 = (BigInt::one() + zeta * phi_0).div_floor(e);
+        let vk = BigInt::mod_pow(&verification_key_base(n), &d_0, n);
+
+        Ok((
+            PartyOnePrivateExponentShareWitness {
+                d_0,
+                session_id: *keys.session_id(),
+            },
+            PartyOnePrivateExponentShareSecondMsg { vk },
+        ))
+    }
+
+    // (vk_0 / vk_1)^e = vk_base mod N confirms that both shares were computed correctly
+    pub fn verify_party_two_second_message_and_output_key_share(
+        e: &BigInt,
+        joint_modulus: &JointModulus,
+        witness: PartyOnePrivateExponentShareWitness,
+        party_one_second_message: &PartyOnePrivateExponentShareSecondMsg,
+        party_two_second_message: &PartyTwoPrivateExponentShareSecondMsg,
+    ) -> Result<PartyOneRSAKeyShare, TwoPartyRSAError> {
+        let n = &joint_modulus.n;
+        let vk_base = verification_key_base(n);
+        if !verify_verification_keys(
+            n,
+            e,
+            &vk_base,
+            &party_one_second_message.vk,
+            &party_two_second_message.vk,
+        ) {
+            return Err(protocol_abort(
+                ProtocolRound::PrivateExponentShare,
+                "vk",
+                None,
+                party_two_second_message,
+                None,
+            ));
         }
 
-        // full decryption
-        let dec_key_alpha = BigInt::mod_pow(
-            &party_two_third_message.c_alpha_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.q,
-        );
-        let dec_key_alpha_tilde = BigInt::mod_pow(
-            &party_two_third_message.c_alpha_tilde_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.q,
+        Ok(PartyOneRSAKeyShare {
+            n: n.clone(),
+            e: e.clone(),
+            vk_base,
+            local_vk: party_one_second_message.vk.clone(),
+            remote_vk: party_two_second_message.vk.clone(),
+            session_id: witness.session_id,
+            d_0: witness.d_0.clone(),
+        })
+    }
+}
+
+impl PartyOneSign {
+    // we encode the message (for PSS this fixes the salt) and send EM with the partial signature
+    // sigma_0 = EM^d_0 mod N and a proof that it is consistent with vk_0
+    pub fn encode_message_and_partial_sign(
+        key_share: &PartyOneRSAKeyShare,
+        message: &[u8],
+        encoding: &SignatureEncoding,
+    ) -> Result<PartyOneSignFirstMsg, TwoPartyRSAError> {
+        let n = &key_share.n;
+        let em = encoding.encode(message, n)?;
+        let sigma = BigInt::mod_pow(&em, &key_share.d_0, n);
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.local_vk,
+            &em,
+            &sigma,
         );
-        let dec_key_alpha_full = BigInt::mod_mul(
-            &dec_key_alpha,
-            &party_two_third_message.partial_dec_c_alpha,
-            &keys.joint_elgamal_pubkey.pp.q,
+        let witness = RSADDHWitness {
+            x: key_share.d_0.clone(),
+        };
+        let pi_sigma = RSADDHProof::prove(
+            &witness,
+            &statement,
+            &key_share.proof_context(HmrtParty::PartyOne),
         );
-        let dec_key_alpha_tilde_full = BigInt::mod_mul(
-            &dec_key_alpha_tilde,
-            &party_two_third_message.partial_dec_c_alpha_tilde,
-            &keys.joint_elgamal_pubkey.pp.q,
+
+        Ok(PartyOneSignFirstMsg {
+            em,
+            sigma,
+            pi_sigma,
+        })
+    }
+
+    // sigma = sigma_0 * sigma_1^-1 mod N is checked with the standard RSA verification
+    pub fn verify_party_two_first_message_and_output_signature(
+        key_share: &PartyOneRSAKeyShare,
+        message: &[u8],
+        encoding: &SignatureEncoding,
+        party_one_first_message: &PartyOneSignFirstMsg,
+        party_two_first_message: &PartyTwoSignFirstMsg,
+    ) -> Result<RSASignature, TwoPartyRSAError> {
+        let n = &key_share.n;
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.remote_vk,
+            &party_one_first_message.em,
+            &party_two_first_message.sigma,
         );
+        if let Err(err) = party_two_first_message
+            .pi_sigma
+            .verify(&statement, &key_share.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::Sign,
+                "pi_sigma",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
+        }
 
-        let dec_key_alpha_full_inv =
-            BigInt::mod_inv(&dec_key_alpha_full, &keys.joint_elgamal_pubkey.pp.q);
-        let dec_key_alpha_tilde_full_inv =
-            BigInt::mod_inv(&dec_key_alpha_tilde_full, &keys.joint_elgamal_pubkey.pp.q);
+        let s = match combine_partial_exponentiations(
+            n,
+            &party_one_first_message.sigma,
+            &party_two_first_message.sigma,
+        ) {
+            Some(s) => s,
+            None => return Err(TwoPartyRSAError::InvalidPartialSignature),
+        };
+        let signature = RSASignature { s };
+        signature.verify(n, &key_share.e, message, encoding)?;
+        Ok(signature)
+    }
+}
 
-        let test1 = BigInt::mod_mul(
-            &party_two_third_message.c_alpha_random.c2,
-            &dec_key_alpha_full_inv,
-            &keys.joint_elgamal_pubkey.pp.q,
+impl PartyOneDecrypt {
+    // partial decryption c^d_0 mod N with a proof that it is consistent with our verification key
+    pub fn partial_decrypt_and_first_message(
+        key_share: &PartyOneRSAKeyShare,
+        c: &BigInt,
+    ) -> Result<PartyOneDecryptFirstMsg, TwoPartyRSAError> {
+        let n = &key_share.n;
+        if !is_valid_rsa_ciphertext(n, c) {
+            return Err(TwoPartyRSAError::InvalidCiphertext);
+        }
+        let partial_dec_c = BigInt::mod_pow(c, &key_share.d_0, n);
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.local_vk,
+            c,
+            &partial_dec_c,
         );
-        let test2 = BigInt::mod_mul(
-            &party_two_third_message.c_alpha_tilde_random.c2,
-            &dec_key_alpha_tilde_full_inv,
-            &keys.joint_elgamal_pubkey.pp.q,
+        let witness = RSADDHWitness {
+            x: key_share.d_0.clone(),
+        };
+        let pi_partial_dec = RSADDHProof::prove(
+            &witness,
+            &statement,
+            &key_share.proof_context(HmrtParty::PartyOne),
         );
 
-        if test1 == BigInt::one() || test2 == BigInt::one() {
-            Ok(false)
-        } else {
-            Ok(true)
+        Ok(PartyOneDecryptFirstMsg {
+            partial_dec_c,
+            pi_partial_dec,
+        })
+    }
+
+    // c^d = c^d_0 * (c^d_1)^-1 mod N, the OAEP padding is removed from the result
+    pub fn verify_party_two_first_message_and_decrypt(
+        key_share: &PartyOneRSAKeyShare,
+        c: &BigInt,
+        label: &[u8],
+        party_one_first_message: &PartyOneDecryptFirstMsg,
+        party_two_first_message: &PartyTwoDecryptFirstMsg,
+    ) -> Result<Vec<u8>, TwoPartyRSAError> {
+        let n = &key_share.n;
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.remote_vk,
+            c,
+            &party_two_first_message.partial_dec_c,
+        );
+        if let Err(err) = party_two_first_message
+            .pi_partial_dec
+            .verify(&statement, &key_share.proof_context(HmrtParty::PartyTwo))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::Decrypt,
+                "pi_partial_dec",
+                None,
+                party_two_first_message,
+                Some(err),
+            ));
         }
+
+        let m = match combine_partial_exponentiations(
+            n,
+            &party_one_first_message.partial_dec_c,
+            &party_two_first_message.partial_dec_c,
+        ) {
+            Some(m) => m,
+            None => return Err(TwoPartyRSAError::InvalidPartialDecryption),
+        };
+        rsa_oaep_decode(n, &m, label)
     }
 }
@@ -1,14 +1,72 @@
-use crate::protocols::two_party_rsa::hmrt::gen_ddh_containers;
+use crate::protocols::two_party_rsa::hmrt::biprimality_test_base;
+use crate::protocols::two_party_rsa::hmrt::combine_partial_exponentiations;
+use crate::protocols::two_party_rsa::hmrt::conclude_biprimality;
+use crate::protocols::two_party_rsa::hmrt::conclude_elgamal_zero_test;
+use crate::protocols::two_party_rsa::hmrt::conclude_paillier_zero_test;
+use crate::protocols::two_party_rsa::hmrt::cross_product_statement;
+use crate::protocols::two_party_rsa::hmrt::elgamal_zero_test;
+use crate::protocols::two_party_rsa::hmrt::exponent_inversion_zeta;
+use crate::protocols::two_party_rsa::hmrt::is_valid_public_exponent;
+use crate::protocols::two_party_rsa::hmrt::is_valid_rsa_ciphertext;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtParty;
+use crate::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use crate::protocols::two_party_rsa::hmrt::normalize_paillier_ciphertexts;
+use crate::protocols::two_party_rsa::hmrt::paillier_encrypt;
+use crate::protocols::two_party_rsa::hmrt::paillier_extract_randomness;
+use crate::protocols::two_party_rsa::hmrt::paillier_verify_randomness;
+use crate::protocols::two_party_rsa::hmrt::paillier_zero_test;
+use crate::protocols::two_party_rsa::hmrt::partial_exponentiation_statement;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTestFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationBatchSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationBatchThirdMsg;
 use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationFirstMsg;
 use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationSecondMsg;
 use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateSievingFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateSievingSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneDecryptFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulusThirdMsg;
 use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetupFirstMsg as KeySetupFirstMsgPartyOne;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGenerationSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGenerationThirdMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShareSecondMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneSignFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one_sieved_share_statement;
+use crate::protocols::two_party_rsa::hmrt::party_two_sieved_share_statement;
+use crate::protocols::two_party_rsa::hmrt::phi_share_mod_statement;
+use crate::protocols::two_party_rsa::hmrt::proof_context;
+use crate::protocols::two_party_rsa::hmrt::protocol_abort;
+use crate::protocols::two_party_rsa::hmrt::rsa_oaep_decode;
+use crate::protocols::two_party_rsa::hmrt::sample_unit_mod;
+use crate::protocols::two_party_rsa::hmrt::share_encryption_statement;
+use crate::protocols::two_party_rsa::hmrt::share_product_statement;
+use crate::protocols::two_party_rsa::hmrt::sieved_candidate_share;
+use crate::protocols::two_party_rsa::hmrt::sieving_conversion_statement;
+use crate::protocols::two_party_rsa::hmrt::sieving_mask_bit_length;
+use crate::protocols::two_party_rsa::hmrt::sieving_modulus;
+use crate::protocols::two_party_rsa::hmrt::sieving_share_statement;
+use crate::protocols::two_party_rsa::hmrt::trial_division_residue_statement;
+use crate::protocols::two_party_rsa::hmrt::trial_division_zero_test_ciphertexts;
+use crate::protocols::two_party_rsa::hmrt::verification_key_base;
+use crate::protocols::two_party_rsa::hmrt::verify_verification_keys;
+use crate::protocols::two_party_rsa::hmrt::zero_test_tilde_offset;
 use crate::protocols::two_party_rsa::hmrt::CiphertextPair;
-use crate::protocols::two_party_rsa::CANDIDATE_BIT_LENGTH;
-use crate::protocols::two_party_rsa::PAILLIER_MODULUS;
-use crate::utlities::ddh_proof::DDHProof;
-use crate::utlities::ddh_proof::DDHStatement;
-use crate::utlities::ddh_proof::DDHWitness;
+use crate::protocols::two_party_rsa::hmrt::ElGamalGroup;
+use crate::protocols::two_party_rsa::hmrt::ElGamalZeroTest;
+use crate::protocols::two_party_rsa::hmrt::HmrtParams;
+use crate::protocols::two_party_rsa::hmrt::JointModulus;
+use crate::protocols::two_party_rsa::hmrt::PaillierCiphertextPair;
+use crate::protocols::two_party_rsa::hmrt::PaillierZeroTest;
+use crate::protocols::two_party_rsa::hmrt::RSASignature;
+use crate::protocols::two_party_rsa::hmrt::TrialDivisionModProof;
+use crate::protocols::two_party_rsa::padding::SignatureEncoding;
+use crate::protocols::two_party_rsa::BIPRIMALITY_TEST_ROUNDS;
+use crate::utlities::affine_proof::AffineProof;
+use crate::utlities::affine_proof::AffineWitness;
 use crate::utlities::ddh_proof::NISigmaProof;
 use crate::utlities::dlog_proof::DLogProof;
 use crate::utlities::dlog_proof::ProveDLog;
@@ -17,30 +75,53 @@ use crate::utlities::dlog_proof::Witness as DLogWitness;
 use crate::utlities::elgamal_enc_proof::HomoELGamalProof;
 use crate::utlities::elgamal_enc_proof::HomoElGamalStatement;
 use crate::utlities::elgamal_enc_proof::HomoElGamalWitness;
+use crate::utlities::equal_secret_proof::EqProof;
+use crate::utlities::equal_secret_proof::Statement as EqStatement;
+use crate::utlities::equal_secret_proof::Witness as EqWitness;
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::group_element::GroupElement;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::homomorphic_scheme::PaillierScheme;
 use crate::utlities::mod_proof::ModProof;
 use crate::utlities::mod_proof::ModStatement;
 use crate::utlities::mod_proof::ModWitness;
-use crate::utlities::range_proof::RangeProof;
-use crate::utlities::range_proof::Statement as BoundStatement;
-use crate::utlities::range_proof::Witness as BoundWitness;
+use crate::utlities::rsa_ddh_proof::RSADDHProof;
+use crate::utlities::rsa_ddh_proof::RSADDHWitness;
+use crate::utlities::sealed_share;
+use crate::utlities::sealed_share::SealedShare;
+use crate::utlities::sealed_share::StorageKey;
+use crate::utlities::tight_range_proof::Statement as BoundStatement;
+use crate::utlities::tight_range_proof::TightRangeProof;
+use crate::utlities::tight_range_proof::Witness as BoundWitness;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::Redacted;
+use crate::ProtocolRound;
+use crate::SealedShareError;
 use crate::TwoPartyRSAError;
 use curv::arithmetic::traits::Modulo;
 use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::ZeroizeBN;
 use curv::BigInt;
-use elgamal::rfc7919_groups::SupportedGroups;
 use elgamal::ElGamalCiphertext;
 use elgamal::ElGamalKeyPair;
 use elgamal::ElGamalPP;
 use elgamal::ElGamalPrivateKey;
 use elgamal::ElGamalPublicKey;
 use elgamal::ExponentElGamal;
+use paillier::traits::Decrypt;
+use paillier::traits::EncryptWithChosenRandomness;
 use paillier::traits::KeyGeneration;
 use paillier::DecryptionKey;
 use paillier::EncryptionKey;
 use paillier::Paillier;
+use paillier::Randomness;
+use paillier::RawCiphertext;
+use paillier::RawPlaintext;
+use rayon::prelude::*;
+use std::fmt;
+use zeroize::Zeroize;
 use zk_paillier::zkproofs::NICorrectKeyProof;
 
-//TODO: add zeroize if needed
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartyTwoKeySetup {
     pub local_paillier_pubkey: EncryptionKey,
@@ -48,6 +129,7 @@ pub struct PartyTwoKeySetup {
     pub remote_paillier_pubkey: EncryptionKey,
     pub remote_elgamal_pubkey: ElGamalPublicKey,
     pub joint_elgamal_pubkey: ElGamalPublicKey,
+    pub params: HmrtParams,
     private: PartyTwoPrivate,
 }
 
@@ -59,10 +141,35 @@ pub struct KeySetupFirstMsg {
     pub dlog_proof: DLogProof,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyTwoPrivate {
     dk: DecryptionKey,
     sk: ElGamalPrivateKey,
+    session_id: SessionId,
+}
+
+impl Zeroize for PartyTwoPrivate {
+    fn zeroize(&mut self) {
+        self.dk.p.zeroize_bn();
+        self.dk.q.zeroize_bn();
+        self.sk.x.zeroize_bn();
+    }
+}
+
+impl Drop for PartyTwoPrivate {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyTwoPrivate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyTwoPrivate")
+            .field("dk", &Redacted)
+            .field("sk", &Redacted)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,50 +179,334 @@ pub struct PartyTwoCandidateGeneration {}
 pub struct PartyTwoCandidateGenerationFirstMsg {
     pub c_i: ElGamalCiphertext,
     pub pi_enc: HomoELGamalProof,
-    pub pi_bound: RangeProof,
+    pub pi_bound: TightRangeProof,
+}
+
+/// c_ab = Enc(a * b + s) under party one's Paillier key, proven by pi_affine against b and s
+/// committed in c_b and c_s under party two's key. pi_sieved links party two's sieved share p_1
+/// to s: c_k encrypts k under party two's key, with s + 4 * p_1 = M * k.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoCandidateSievingFirstMsg {
+    pub c_b: BigInt,
+    pub c_s: BigInt,
+    pub c_ab: BigInt,
+    pub pi_affine: AffineProof,
+    pub c_k: BigInt,
+    pub pi_sieved: AffineProof,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartyTwoCandidateGenerationSecondMsg {
-    pub pi_mod: ModProof,
+    pub pi_mod: TrialDivisionModProof,
     pub c_1_alpha: ElGamalCiphertext,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PartyTwoCandidateGenerationThirdMsg {
-    pub proof_alpha: DDHProof,
-    pub proof_alpha_tilde: DDHProof,
-    pub c_alpha_random: ElGamalCiphertext,
-    pub c_alpha_tilde_random: ElGamalCiphertext,
-    pub partial_dec_c_alpha: BigInt,
-    pub partial_dec_c_alpha_tilde: BigInt,
-    pub ddh_proof_alpha: DDHProof,
-    pub ddh_proof_alpha_tilde: DDHProof,
+    pub zero_test_alpha: ElGamalZeroTest,
+    pub zero_test_alpha_tilde: ElGamalZeroTest,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoCandidateGenerationBatchSecondMsg {
+    pub second_msg_vec: Vec<PartyTwoCandidateGenerationSecondMsg>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoCandidateGenerationBatchThirdMsg {
+    pub third_msg_vec: Vec<PartyTwoCandidateGenerationThirdMsg>,
+}
+
+/// Party two's side of the Paillier trial division benchmark, see PaillierCiphertextPair
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPaillierCandidateGeneration {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPaillierCandidateGenerationFirstMsg {
+    pub c_i: BigInt,
+    pub pi_enc: HomoELGamalProof<PaillierScheme>,
+    pub pi_bound: TightRangeProof<PaillierScheme>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPaillierCandidateGenerationSecondMsg {
+    pub pi_mod: ModProof<PaillierScheme>,
+    pub c_1_alpha: BigInt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPaillierCandidateGenerationThirdMsg {
+    pub zero_test_alpha: PaillierZeroTest,
+    pub zero_test_alpha_tilde: PaillierZeroTest,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyTwoPaillierCandidateWitness {
+    pub p_1: BigInt,
+    pub r_1: BigInt,
+}
+
+impl Zeroize for PartyTwoPaillierCandidateWitness {
+    fn zeroize(&mut self) {
+        self.p_1.zeroize_bn();
+        self.r_1.zeroize_bn();
+    }
+}
+
+impl Drop for PartyTwoPaillierCandidateWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyTwoPaillierCandidateWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyTwoPaillierCandidateWitness")
+            .field("p_1", &Redacted)
+            .field("r_1", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoBiprimalityTest {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoBiprimalityTestFirstMsg {
+    pub v_vec: Vec<BigInt>,
+    pub pi_eq_vec: Vec<EqProof>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoJointModulus {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoJointModulusFirstMsg {
+    pub c_p: BigInt,
+    pub c_q: BigInt,
+    pub c_pq: BigInt,
+    pub pi_p: AffineProof,
+    pub pi_q: AffineProof,
+    pub pi_pq: AffineProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoJointModulusSecondMsg {
+    pub c_n: BigInt,
+    pub pi_n: AffineProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoJointModulusThirdMsg {
+    pub n: BigInt,
+    pub rho_n: BigInt,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyTwoJointModulusWitness {
+    pub rho_pq: BigInt,
+}
+
+impl Zeroize for PartyTwoJointModulusWitness {
+    fn zeroize(&mut self) {
+        self.rho_pq.zeroize_bn();
+    }
+}
+
+impl Drop for PartyTwoJointModulusWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyTwoJointModulusWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyTwoJointModulusWitness")
+            .field("rho_pq", &Redacted)
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPrivateExponentShare {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPrivateExponentShareFirstMsg {
+    pub c_phi_mod_e: ElGamalCiphertext,
+    pub pi_mod: ModProof,
+    pub phi_mod_e: BigInt,
+    pub r_phi_mod_e: BigInt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoPrivateExponentShareSecondMsg {
+    pub vk: BigInt,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyTwoPrivateExponentShareWitness {
+    d_1: BigInt,
+    session_id: SessionId,
+}
+
+impl Zeroize for PartyTwoPrivateExponentShareWitness {
+    fn zeroize(&mut self) {
+        self.d_1.zeroize_bn();
+    }
+}
+
+impl Drop for PartyTwoPrivateExponentShareWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyTwoPrivateExponentShareWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyTwoPrivateExponentShareWitness")
+            .field("d_1", &Redacted)
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
+/// Party two's share of the RSA private key: d = d_0 - d_1 where d_0 is held by party one.
+/// vk_base^d_i are public verification keys for the shares.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartyTwoRSAKeyShare {
+    pub n: BigInt,
+    pub e: BigInt,
+    pub vk_base: BigInt,
+    pub local_vk: BigInt,
+    pub remote_vk: BigInt,
+    /// session of the key generation, binds the proofs of signing and decryption to the key
+    pub session_id: SessionId,
+    d_1: BigInt,
+}
+
+impl Zeroize for PartyTwoRSAKeyShare {
+    fn zeroize(&mut self) {
+        self.d_1.zeroize_bn();
+    }
+}
+
+impl Drop for PartyTwoRSAKeyShare {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyTwoRSAKeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyTwoRSAKeyShare")
+            .field("n", &self.n)
+            .field("e", &self.e)
+            .field("vk_base", &self.vk_base)
+            .field("local_vk", &self.local_vk)
+            .field("remote_vk", &self.remote_vk)
+            .field("session_id", &self.session_id)
+            .field("d_1", &Redacted)
+            .finish()
+    }
+}
+
+impl PartyTwoRSAKeyShare {
+    /// Encrypts the key share for storage, see `SealedShare`
+    pub fn export_encrypted(&self, key: &StorageKey) -> Result<SealedShare, SealedShareError> {
+        sealed_share::seal(self, "PartyTwoRSAKeyShare", key)
+    }
+
+    pub fn import_encrypted(
+        sealed: &SealedShare,
+        key: &StorageKey,
+    ) -> Result<Self, SealedShareError> {
+        sealed_share::open(sealed, "PartyTwoRSAKeyShare", key)
+    }
+
+    fn proof_context(&self, prover: HmrtParty) -> ProofContext {
+        proof_context(&self.session_id, prover)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoSign {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoSignFirstMsg {
+    pub sigma: BigInt,
+    pub pi_sigma: RSADDHProof,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoDecrypt {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartyTwoDecryptFirstMsg {
+    pub partial_dec_c: BigInt,
+    pub pi_partial_dec: RSADDHProof,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyTwoCandidateWitness {
     pub p_1: BigInt,
     pub r_1: BigInt,
 }
 
+impl Zeroize for PartyTwoCandidateWitness {
+    fn zeroize(&mut self) {
+        self.p_1.zeroize_bn();
+        self.r_1.zeroize_bn();
+    }
+}
+
+impl Drop for PartyTwoCandidateWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for PartyTwoCandidateWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PartyTwoCandidateWitness")
+            .field("p_1", &Redacted)
+            .field("r_1", &Redacted)
+            .finish()
+    }
+}
+
 impl PartyTwoKeySetup {
-    pub fn gen_local_keys_and_first_message_to_party_one() -> (KeySetupFirstMsg, PartyTwoPrivate) {
-        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+    /// Generates the keys of party two, with the ElGamal key in the group proposed by party one.
+    /// The group must not be smaller than the one in params.
+    /// Both parties must use the same session id, all proofs of the session are bound to it
+    pub fn gen_local_keys_and_first_message_to_party_one(
+        params: &HmrtParams,
+        group: ElGamalGroup,
+        session_id: &SessionId,
+    ) -> Result<(KeySetupFirstMsg, PartyTwoPrivate), TwoPartyRSAError> {
+        params.validate()?;
+        if group < params.elgamal_group {
+            return Err(TwoPartyRSAError::ElGamalGroupTooSmall);
+        }
+        let pp = group.public_parameters();
         let keypair = ElGamalKeyPair::generate(&pp);
         let witness = DLogWitness {
             x: keypair.sk.x.clone(),
         };
-        let dlog_proof = DLogProof::prove(&witness, &pp);
+        let dlog_proof = DLogProof::prove(
+            &witness,
+            &pp,
+            &proof_context(session_id, HmrtParty::PartyTwo),
+        );
 
-        let (ek_new, dk_new) = Paillier::keypair_with_modulus_size(PAILLIER_MODULUS).keys();
+        let (ek_new, dk_new) = Paillier::keypair_with_modulus_size(params.paillier_modulus).keys();
         let correct_key_proof = NICorrectKeyProof::proof(&dk_new);
 
         let party_two_private = PartyTwoPrivate {
             dk: dk_new,
             sk: keypair.sk,
+            session_id: *session_id,
         };
-        (
+        Ok((
             KeySetupFirstMsg {
                 ek: ek_new,
                 pk: keypair.pk.clone(),
@@ -123,22 +514,50 @@ impl PartyTwoKeySetup {
                 dlog_proof,
             },
             party_two_private,
-        )
+        ))
     }
 
     pub fn verify_party_one_first_message_and_output_party_two_keys(
+        params: &HmrtParams,
         party_one_first_message: &KeySetupFirstMsgPartyOne,
         party_two_first_message: &KeySetupFirstMsg,
         party_two_private: PartyTwoPrivate,
     ) -> Result<Self, TwoPartyRSAError> {
+        params.validate()?;
+        if party_one_first_message.group < params.elgamal_group {
+            return Err(TwoPartyRSAError::ElGamalGroupTooSmall);
+        }
+        if party_one_first_message.pk.pp != party_two_first_message.pk.pp
+            || party_one_first_message.pk.pp != party_one_first_message.group.public_parameters()
+        {
+            return Err(TwoPartyRSAError::InvalidElGamalKey);
+        }
+        let remote_ek = &party_one_first_message.ek;
+        if remote_ek.n.bit_length() < params.paillier_modulus - 1
+            || remote_ek.nn != &remote_ek.n * &remote_ek.n
+        {
+            return Err(TwoPartyRSAError::InvalidPaillierKey);
+        }
+        // an element of small order in the remote key would carry over to the joint key
+        let remote_h = GroupElement::from_bigint(
+            &party_one_first_message.pk.h,
+            &party_two_first_message.pk.pp,
+        )
+        .map_err(|_| TwoPartyRSAError::InvalidElGamalKey)?;
+        let joint_elgamal_pubkey = match party_two_first_message.pk.add(&party_one_first_message.pk)
+        {
+            Ok(joint_elgamal_pubkey) => joint_elgamal_pubkey,
+            Err(_) => return Err(TwoPartyRSAError::InvalidElGamalKey),
+        };
         let dlog_statement = DLogStatement {
-            h: party_one_first_message.pk.h.clone(),
+            h: remote_h.into_bigint(),
         };
 
-        match party_one_first_message
-            .dlog_proof
-            .verify(&dlog_statement, &party_two_first_message.pk.pp)
-        {
+        match party_one_first_message.dlog_proof.verify(
+            &dlog_statement,
+            &party_two_first_message.pk.pp,
+            &proof_context(&party_two_private.session_id, HmrtParty::PartyOne),
+        ) {
             Ok(()) => {
                 match party_one_first_message
                     .correct_key_proof
@@ -149,29 +568,80 @@ impl PartyTwoKeySetup {
                         local_elgamal_puubkey: party_two_first_message.pk.clone(),
                         remote_paillier_pubkey: party_one_first_message.ek.clone(),
                         remote_elgamal_pubkey: party_one_first_message.pk.clone(),
-                        joint_elgamal_pubkey: party_two_first_message
-                            .pk
-                            .add(&party_one_first_message.pk)
-                            .unwrap(),
+                        joint_elgamal_pubkey,
+                        params: HmrtParams {
+                            elgamal_group: party_one_first_message.group,
+                            ..*params
+                        },
                         private: party_two_private,
                     }),
-                    Err(_) => Err(TwoPartyRSAError::InvalidPaillierKey),
+                    Err(_) => Err(protocol_abort(
+                        ProtocolRound::KeySetup,
+                        "correct_key_proof",
+                        None,
+                        party_one_first_message,
+                        None,
+                    )),
                 }
             }
-            Err(_) => Err(TwoPartyRSAError::InvalidElGamalKey),
+            Err(err) => Err(protocol_abort(
+                ProtocolRound::KeySetup,
+                "dlog_proof",
+                None,
+                party_one_first_message,
+                Some(err),
+            )),
         }
     }
+
+    /// Encrypts the key setup, including the Paillier and ElGamal private keys, for storage
+    /// between sessions. Serializing `PartyTwoKeySetup` directly writes them in the clear.
+    pub fn export_encrypted(&self, key: &StorageKey) -> Result<SealedShare, SealedShareError> {
+        sealed_share::seal(self, "PartyTwoKeySetup", key)
+    }
+
+    pub fn import_encrypted(
+        sealed: &SealedShare,
+        key: &StorageKey,
+    ) -> Result<Self, SealedShareError> {
+        sealed_share::open(sealed, "PartyTwoKeySetup", key)
+    }
+
+    pub fn session_id(&self) -> &SessionId {
+        &self.private.session_id
+    }
+
+    fn proof_context(&self, prover: HmrtParty) -> ProofContext {
+        proof_context(&self.private.session_id, prover)
+    }
 }
 
 impl PartyTwoCandidateGeneration {
     pub fn generate_shares_of_candidate(
         keys: &PartyTwoKeySetup,
-    ) -> (
-        PartyTwoCandidateWitness,
-        PartyTwoCandidateGenerationFirstMsg,
-    ) {
-        let share_bit_size: usize = CANDIDATE_BIT_LENGTH / 2 - 2;
+    ) -> Result<
+        (
+            PartyTwoCandidateWitness,
+            PartyTwoCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let share_bit_size: usize = keys.params.candidate_bit_length / 2 - 2;
         let p_i = BigInt::sample(share_bit_size);
+        Self::encrypt_candidate_share(keys, p_i)
+    }
+
+    // encrypts a share of the candidate under the joint ElGamal key and proves it is in range
+    fn encrypt_candidate_share(
+        keys: &PartyTwoKeySetup,
+        p_i: BigInt,
+    ) -> Result<
+        (
+            PartyTwoCandidateWitness,
+            PartyTwoCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
         let r_i = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
 
         let c_i = ExponentElGamal::encrypt_from_predefined_randomness(
@@ -179,7 +649,7 @@ impl PartyTwoCandidateGeneration {
             &keys.joint_elgamal_pubkey,
             &r_i,
         )
-        .unwrap();
+        .map_err(|_| TwoPartyRSAError::CandidateGenerationEncError)?;
         let enc_witness = HomoElGamalWitness {
             r: r_i.clone(),
             m: p_i.clone(),
@@ -194,26 +664,200 @@ impl PartyTwoCandidateGeneration {
         };
         let bound_statement = BoundStatement {
             pk: keys.joint_elgamal_pubkey.clone(),
-            // TODO: in current range proof this will give some slack such that it is possible that
-            // a prover chose 2^(N/2-2)<x< 1/3 * 2^(N/2).
-            range: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32),
+            range: keys.params.share_range(),
             ciphertext: c_i.clone(),
-            sec_param: 120, //TODO : parameterize
-            kapa: 100,      //TODO : parameterize
         };
 
-        let enc_proof = HomoELGamalProof::prove(&enc_witness, &enc_statement);
-        let bound_proof = RangeProof::prove(&bound_witness, &bound_statement).unwrap(); // TODO: handle error properly
+        let enc_proof = HomoELGamalProof::prove(
+            &enc_witness,
+            &enc_statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        )
+        .map_err(TwoPartyRSAError::ProofGenerationError)?;
+        let bound_proof = TightRangeProof::prove(
+            &bound_witness,
+            &bound_statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        )
+        .map_err(TwoPartyRSAError::ProofGenerationError)?;
 
-        (
+        Ok((
             PartyTwoCandidateWitness { p_1: p_i, r_1: r_i },
             PartyTwoCandidateGenerationFirstMsg {
                 c_i,
                 pi_enc: enc_proof,
                 pi_bound: bound_proof,
             },
+        ))
+    }
+    /// Distributed sieving, party two's side (see
+    /// PartyOneCandidateGeneration::sample_multiplicative_share_and_sieving_message): party two
+    /// samples its multiplicative share b and a mask s, and computes Enc(a * b + s) under party
+    /// one's Paillier key, its additive share is x_1 = -s. Returns the sieving message together
+    /// with the output of generate_shares_of_candidate for the sieved candidate, party one
+    /// needs both to check the sieved share.
+    pub fn verify_party_one_sieving_message_and_generate_sieved_candidate(
+        keys: &PartyTwoKeySetup,
+        party_one_sieving_message: &PartyOneCandidateSievingFirstMsg,
+    ) -> Result<
+        (
+            PartyTwoCandidateSievingFirstMsg,
+            PartyTwoCandidateWitness,
+            PartyTwoCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let (m, _) = sieving_modulus(&keys.params);
+        let ek_local = &keys.local_paillier_pubkey;
+        let ek_remote = &keys.remote_paillier_pubkey;
+        let range_statement =
+            sieving_share_statement(&ek_remote.n, &party_one_sieving_message.c_a, &m);
+        if let Err(err) = party_one_sieving_message
+            .pi_range
+            .verify(&range_statement, &keys.proof_context(HmrtParty::PartyOne))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_range",
+                None,
+                party_one_sieving_message,
+                Some(err),
+            ));
+        }
+
+        let b = sample_unit_mod(&m);
+        let s = BigInt::sample(sieving_mask_bit_length(&m));
+        let rho_b = BigInt::sample_below(&ek_local.n);
+        let rho_s = BigInt::sample_below(&ek_local.n);
+        let rho = BigInt::sample_below(&ek_remote.n);
+        let c_b = Paillier::encrypt_with_chosen_randomness(
+            ek_local,
+            RawPlaintext::from(b.clone()),
+            &Randomness(rho_b.clone()),
+        )
+        .0
+        .into_owned();
+        let c_s = Paillier::encrypt_with_chosen_randomness(
+            ek_local,
+            RawPlaintext::from(s.clone()),
+            &Randomness(rho_s.clone()),
+        )
+        .0
+        .into_owned();
+
+        // c_ab = c_a^b * (1 + n)^s * rho^n, an encryption of a * b + s
+        let c_s_remote = Paillier::encrypt_with_chosen_randomness(
+            ek_remote,
+            RawPlaintext::from(s.clone()),
+            &Randomness(rho.clone()),
         )
+        .0
+        .into_owned();
+        let c_ab = BigInt::mod_mul(
+            &BigInt::mod_pow(&party_one_sieving_message.c_a, &b, &ek_remote.nn),
+            &c_s_remote,
+            &ek_remote.nn,
+        );
+
+        let statement = sieving_conversion_statement(
+            &ek_remote.n,
+            &party_one_sieving_message.c_a,
+            &ek_local.n,
+            &c_b,
+            &c_s,
+            &m,
+            &c_ab,
+        );
+        let witness = AffineWitness {
+            a_vec: vec![b, s.clone()],
+            r_vec: vec![rho_b, rho_s.clone()],
+            rho,
+        };
+        let pi_affine = match AffineProof::prove(
+            &witness,
+            &statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        ) {
+            Ok(pi_affine) => pi_affine,
+            Err(err) => return Err(TwoPartyRSAError::ProofGenerationError(err)),
+        };
+
+        let p_1 = sieved_candidate_share(&keys.params, &(BigInt::zero() - &s), &m);
+        let (witness, first_message) = Self::encrypt_candidate_share(keys, p_1)?;
+
+        // s + 4 * p_1 = 0 mod M
+        let k = (&s + BigInt::from(4) * &witness.p_1).div_floor(&m);
+        let rho_k = BigInt::sample_below(&ek_local.n);
+        let c_k = paillier_encrypt(ek_local, &k, &rho_k);
+        let sieved_statement = party_two_sieved_share_statement(
+            &keys.params,
+            &ek_local.n,
+            &c_s,
+            &keys.joint_elgamal_pubkey,
+            &first_message.c_i,
+            &c_k,
+            &m,
+        );
+        let sieved_witness = AffineWitness {
+            a_vec: vec![witness.p_1.clone(), k],
+            r_vec: vec![witness.r_1.clone(), rho_k],
+            rho: rho_s,
+        };
+        let pi_sieved = match AffineProof::prove(
+            &sieved_witness,
+            &sieved_statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        ) {
+            Ok(pi_sieved) => pi_sieved,
+            Err(err) => return Err(TwoPartyRSAError::ProofGenerationError(err)),
+        };
+        Ok((
+            PartyTwoCandidateSievingFirstMsg {
+                c_b,
+                c_s,
+                c_ab,
+                pi_affine,
+                c_k,
+                pi_sieved,
+            },
+            witness,
+            first_message,
+        ))
+    }
+
+    /// Checks that party one's sieved share, encrypted in its candidate generation message,
+    /// matches its share x_0 of c_ab modulo M.
+    pub fn verify_party_one_sieved_candidate(
+        keys: &PartyTwoKeySetup,
+        party_two_sieving_message: &PartyTwoCandidateSievingFirstMsg,
+        party_one_first_message: &PartyOneCandidateGenerationFirstMsg,
+        party_one_sieving_second_message: &PartyOneCandidateSievingSecondMsg,
+    ) -> Result<(), TwoPartyRSAError> {
+        let (m, _) = sieving_modulus(&keys.params);
+        let statement = party_one_sieved_share_statement(
+            &keys.params,
+            &keys.remote_paillier_pubkey.n,
+            &party_two_sieving_message.c_ab,
+            &keys.joint_elgamal_pubkey,
+            &party_one_first_message.c_i,
+            &party_one_sieving_second_message.c_k,
+            &m,
+        );
+        match party_one_sieving_second_message
+            .pi_sieved
+            .verify(&statement, &keys.proof_context(HmrtParty::PartyOne))
+        {
+            Ok(()) => Ok(()),
+            Err(err) => Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_sieved",
+                None,
+                party_one_sieving_second_message,
+                Some(err),
+            )),
+        }
     }
+
     pub fn verify_party_one_first_message_and_normalize_ciphertexts(
         keys: &PartyTwoKeySetup,
         party_one_first_message: &PartyOneCandidateGenerationFirstMsg,
@@ -226,41 +870,51 @@ impl PartyTwoCandidateGeneration {
 
         let bound_statement = BoundStatement {
             pk: keys.joint_elgamal_pubkey.clone(),
-            range: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32),
+            range: keys.params.share_range(),
             ciphertext: party_one_first_message.c_i.clone(),
-            sec_param: 120,
-            kapa: 100,
         };
 
-        match party_one_first_message
+        if let Err(err) = party_one_first_message
             .pi_enc
-            .verify(&enc_statement)
-            .is_ok()
-            && party_one_first_message
-                .pi_bound
-                .verify(&bound_statement)
-                .is_ok()
+            .verify(&enc_statement, &keys.proof_context(HmrtParty::PartyOne))
         {
-            true => {
-                let c_party_one_mul_4 =
-                    ExponentElGamal::mul(&party_one_first_message.c_i, &BigInt::from(4));
-                let c_party_two_mul_4 =
-                    ExponentElGamal::mul(&party_two_first_message.c_i, &BigInt::from(4));
-                Ok(CiphertextPair {
-                    c0: ExponentElGamal::add(
-                        &c_party_one_mul_4,
-                        &ExponentElGamal::encrypt_from_predefined_randomness(
-                            &BigInt::from(3),
-                            &keys.joint_elgamal_pubkey,
-                            &BigInt::zero(),
-                        )
-                        .unwrap(),
-                    )
-                    .unwrap(),
-                    c1: c_party_two_mul_4,
-                })
-            }
-            false => Err(TwoPartyRSAError::CandidateGenerationEncError),
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_enc",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
+        }
+        if let Err(err) = party_one_first_message
+            .pi_bound
+            .verify(&bound_statement, &keys.proof_context(HmrtParty::PartyOne))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_bound",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
+        }
+
+        let c_party_one_mul_4 =
+            ExponentElGamal::mul(&party_one_first_message.c_i, &BigInt::from(4));
+        let c_party_two_mul_4 =
+            ExponentElGamal::mul(&party_two_first_message.c_i, &BigInt::from(4));
+        let c0 = ExponentElGamal::encrypt_from_predefined_randomness(
+            &BigInt::from(3),
+            &keys.joint_elgamal_pubkey,
+            &BigInt::zero(),
+        )
+        .and_then(|enc_3| ExponentElGamal::add(&c_party_one_mul_4, &enc_3));
+        match c0 {
+            Ok(c0) => Ok(CiphertextPair {
+                c0,
+                c1: c_party_two_mul_4,
+            }),
+            Err(_) => Err(TwoPartyRSAError::CandidateGenerationEncError),
         }
     }
 
@@ -274,23 +928,361 @@ impl PartyTwoCandidateGeneration {
         let p_1 = BigInt::mod_mul(&w.p_1, &BigInt::from(4), &keys.joint_elgamal_pubkey.pp.q);
         let r_1 = BigInt::mod_mul(&w.r_1, &BigInt::from(4), &keys.joint_elgamal_pubkey.pp.q);
 
-        let p_1_mod_alpha = p_1.mod_floor(alpha);
-        let r_1_alpha = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
-        let c_1_alpha = ExponentElGamal::encrypt_from_predefined_randomness(
-            &p_1_mod_alpha,
+        let (c_1_alpha, pi_mod) = TrialDivisionModProof::prove(
+            &keys.params,
             &keys.joint_elgamal_pubkey,
-            &r_1_alpha,
+            &c.c1,
+            &p_1,
+            &r_1,
+            alpha,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        )?;
+        Ok(PartyTwoCandidateGenerationSecondMsg { pi_mod, c_1_alpha })
+    }
+
+    pub fn verify_party_one_second_message_and_partial_decrypt(
+        party_one_second_message: &PartyOneCandidateGenerationSecondMsg,
+        party_two_second_message: &PartyTwoCandidateGenerationSecondMsg,
+        alpha: &BigInt,
+        keys: &PartyTwoKeySetup,
+        c: &CiphertextPair,
+    ) -> Result<
+        (
+            PartyTwoCandidateGenerationThirdMsg,
+            ElGamalCiphertext,
+            ElGamalCiphertext,
+        ),
+        TwoPartyRSAError,
+    > {
+        let mod_statement = trial_division_residue_statement::<FFDHEExponentElGamal>(
+            &keys.params,
+            &keys.joint_elgamal_pubkey,
+            &c.c0,
+            &party_one_second_message.c_0_alpha,
+            alpha,
+        );
+        let verify = party_one_second_message.pi_mod.verify(
+            &keys.params,
+            &mod_statement,
+            &keys.proof_context(HmrtParty::PartyOne),
+        );
+        if let Err(err) = verify {
+            return Err(protocol_abort(
+                ProtocolRound::TrialDivision,
+                "pi_mod",
+                Some(alpha),
+                party_one_second_message,
+                Some(err),
+            ));
+        };
+        let (c_alpha, c_alpha_tilde) =
+            match trial_division_zero_test_ciphertexts::<FFDHEExponentElGamal>(
+                &keys.joint_elgamal_pubkey,
+                &party_one_second_message.c_0_alpha,
+                &party_two_second_message.c_1_alpha,
+                alpha,
+            ) {
+                Ok(c_alphas) => c_alphas,
+                Err(_) => return Err(TwoPartyRSAError::CandidateGenerationEncError),
+            };
+
+        let zero_test = |c: &ElGamalCiphertext| {
+            elgamal_zero_test::<FFDHEExponentElGamal>(
+                &keys.joint_elgamal_pubkey,
+                &keys.local_elgamal_puubkey,
+                &keys.private.sk,
+                c,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            )
+        };
+        match (zero_test(&c_alpha), zero_test(&c_alpha_tilde)) {
+            (Ok(zero_test_alpha), Ok(zero_test_alpha_tilde)) => Ok((
+                PartyTwoCandidateGenerationThirdMsg {
+                    zero_test_alpha,
+                    zero_test_alpha_tilde,
+                },
+                c_alpha,
+                c_alpha_tilde,
+            )),
+            _ => Err(TwoPartyRSAError::CandidateGenerationEncError),
+        }
+    }
+
+    pub fn verify_party_one_third_message_full_decrypt_and_conclude_division(
+        c_alpha: &ElGamalCiphertext,
+        c_alpha_tilde: &ElGamalCiphertext,
+        party_one_third_message: &PartyOneCandidateGenerationThirdMsg,
+        alpha: &BigInt,
+        keys: &PartyTwoKeySetup,
+    ) -> Result<bool, TwoPartyRSAError> {
+        // the plaintext of c_alpha or c_alpha_tilde is zero iff alpha divides the candidate
+        let conclude = |zero_test: &ElGamalZeroTest, c: &ElGamalCiphertext, name: &str| {
+            conclude_elgamal_zero_test::<FFDHEExponentElGamal>(
+                zero_test,
+                c,
+                &keys.joint_elgamal_pubkey,
+                &keys.remote_elgamal_pubkey,
+                &keys.private.sk,
+                &keys.proof_context(HmrtParty::PartyOne),
+            )
+            .map_err(|(field, proof_error)| {
+                protocol_abort(
+                    ProtocolRound::TrialDivision,
+                    &format!("{}.{}", name, field),
+                    Some(alpha),
+                    party_one_third_message,
+                    proof_error,
+                )
+            })
+        };
+        let test1 = conclude(
+            &party_one_third_message.zero_test_alpha,
+            c_alpha,
+            "zero_test_alpha",
+        )?;
+        let test2 = conclude(
+            &party_one_third_message.zero_test_alpha_tilde,
+            c_alpha_tilde,
+            "zero_test_alpha_tilde",
+        )?;
+        Ok(!test1 && !test2)
+    }
+
+    /// Batched trial division: a single message carries c_alpha and the mod proof for every
+    /// alpha in alpha_vec. The work on each alpha is done in parallel.
+    pub fn trial_division_prepare_c_alpha_batch(
+        alpha_vec: &[BigInt],
+        keys: &PartyTwoKeySetup,
+        c: &CiphertextPair,
+        w: &PartyTwoCandidateWitness,
+    ) -> Result<PartyTwoCandidateGenerationBatchSecondMsg, TwoPartyRSAError> {
+        let second_msg_vec = alpha_vec
+            .par_iter()
+            .map(|alpha| Self::trial_division_prepare_c_alpha(alpha, keys, c, w))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PartyTwoCandidateGenerationBatchSecondMsg { second_msg_vec })
+    }
+
+    pub fn verify_party_one_batch_second_message_and_partial_decrypt(
+        party_one_batch_second_message: &PartyOneCandidateGenerationBatchSecondMsg,
+        party_two_batch_second_message: &PartyTwoCandidateGenerationBatchSecondMsg,
+        alpha_vec: &[BigInt],
+        keys: &PartyTwoKeySetup,
+        c: &CiphertextPair,
+    ) -> Result<
+        (
+            PartyTwoCandidateGenerationBatchThirdMsg,
+            Vec<ElGamalCiphertext>,
+            Vec<ElGamalCiphertext>,
+        ),
+        TwoPartyRSAError,
+    > {
+        if party_one_batch_second_message.second_msg_vec.len() != alpha_vec.len()
+            || party_two_batch_second_message.second_msg_vec.len() != alpha_vec.len()
+        {
+            return Err(TwoPartyRSAError::InvalidModProof);
+        }
+        let results = alpha_vec
+            .par_iter()
+            .zip(party_one_batch_second_message.second_msg_vec.par_iter())
+            .zip(party_two_batch_second_message.second_msg_vec.par_iter())
+            .map(
+                |((alpha, party_one_second_message), party_two_second_message)| {
+                    Self::verify_party_one_second_message_and_partial_decrypt(
+                        party_one_second_message,
+                        party_two_second_message,
+                        alpha,
+                        keys,
+                        c,
+                    )
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut third_msg_vec = Vec::with_capacity(results.len());
+        let mut c_alpha_vec = Vec::with_capacity(results.len());
+        let mut c_alpha_tilde_vec = Vec::with_capacity(results.len());
+        for (third_msg, c_alpha, c_alpha_tilde) in results {
+            third_msg_vec.push(third_msg);
+            c_alpha_vec.push(c_alpha);
+            c_alpha_tilde_vec.push(c_alpha_tilde);
+        }
+        Ok((
+            PartyTwoCandidateGenerationBatchThirdMsg { third_msg_vec },
+            c_alpha_vec,
+            c_alpha_tilde_vec,
+        ))
+    }
+
+    /// Returns for every alpha in the batch whether it is coprime to the candidate,
+    /// use first_dividing_prime to find the first alpha that divides it.
+    pub fn verify_party_one_batch_third_message_full_decrypt_and_conclude_division(
+        c_alpha_vec: &[ElGamalCiphertext],
+        c_alpha_tilde_vec: &[ElGamalCiphertext],
+        party_one_batch_third_message: &PartyOneCandidateGenerationBatchThirdMsg,
+        alpha_vec: &[BigInt],
+        keys: &PartyTwoKeySetup,
+    ) -> Result<Vec<bool>, TwoPartyRSAError> {
+        if c_alpha_vec.len() != c_alpha_tilde_vec.len()
+            || party_one_batch_third_message.third_msg_vec.len() != c_alpha_vec.len()
+            || alpha_vec.len() != c_alpha_vec.len()
+        {
+            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+        }
+        c_alpha_vec
+            .par_iter()
+            .zip(c_alpha_tilde_vec.par_iter())
+            .zip(party_one_batch_third_message.third_msg_vec.par_iter())
+            .zip(alpha_vec.par_iter())
+            .map(
+                |(((c_alpha, c_alpha_tilde), party_one_third_message), alpha)| {
+                    Self::verify_party_one_third_message_full_decrypt_and_conclude_division(
+                        c_alpha,
+                        c_alpha_tilde,
+                        party_one_third_message,
+                        alpha,
+                        keys,
+                    )
+                },
+            )
+            .collect()
+    }
+}
+
+impl PartyTwoPaillierCandidateGeneration {
+    pub fn generate_shares_of_candidate(
+        keys: &PartyTwoKeySetup,
+    ) -> Result<
+        (
+            PartyTwoPaillierCandidateWitness,
+            PartyTwoPaillierCandidateGenerationFirstMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let share_bit_size: usize = keys.params.candidate_bit_length / 2 - 2;
+        let ek = &keys.local_paillier_pubkey;
+        let p_i = BigInt::sample(share_bit_size);
+        let r_i = PaillierScheme::sample_randomness(ek);
+        let c_i = match PaillierScheme::encrypt_with_randomness(ek, &p_i, &r_i) {
+            Ok(c_i) => c_i,
+            Err(_) => return Err(TwoPartyRSAError::CandidateGenerationEncError),
+        };
+
+        let enc_witness = HomoElGamalWitness {
+            r: r_i.clone(),
+            m: p_i.clone(),
+        };
+        let enc_statement = HomoElGamalStatement::<PaillierScheme> {
+            pk: ek.clone(),
+            ciphertext: c_i.clone(),
+        };
+        let bound_witness = BoundWitness {
+            x: p_i.clone(),
+            r: r_i.clone(),
+        };
+        let bound_statement = BoundStatement::<PaillierScheme> {
+            pk: ek.clone(),
+            range: keys.params.share_range(),
+            ciphertext: c_i.clone(),
+        };
+
+        match (
+            HomoELGamalProof::prove(
+                &enc_witness,
+                &enc_statement,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            ),
+            TightRangeProof::prove(
+                &bound_witness,
+                &bound_statement,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            ),
+        ) {
+            (Ok(pi_enc), Ok(pi_bound)) => Ok((
+                PartyTwoPaillierCandidateWitness { p_1: p_i, r_1: r_i },
+                PartyTwoPaillierCandidateGenerationFirstMsg {
+                    c_i,
+                    pi_enc,
+                    pi_bound,
+                },
+            )),
+            (Err(err), _) | (_, Err(err)) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+        }
+    }
+
+    pub fn verify_party_one_first_message_and_normalize_ciphertexts(
+        keys: &PartyTwoKeySetup,
+        party_one_first_message: &PartyOnePaillierCandidateGenerationFirstMsg,
+        party_two_first_message: &PartyTwoPaillierCandidateGenerationFirstMsg,
+    ) -> Result<PaillierCiphertextPair, TwoPartyRSAError> {
+        let enc_statement = HomoElGamalStatement::<PaillierScheme> {
+            pk: keys.remote_paillier_pubkey.clone(),
+            ciphertext: party_one_first_message.c_i.clone(),
+        };
+        let bound_statement = BoundStatement::<PaillierScheme> {
+            pk: keys.remote_paillier_pubkey.clone(),
+            range: keys.params.share_range(),
+            ciphertext: party_one_first_message.c_i.clone(),
+        };
+
+        if let Err(err) = party_one_first_message
+            .pi_enc
+            .verify(&enc_statement, &keys.proof_context(HmrtParty::PartyOne))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_enc",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
+        }
+        if let Err(err) = party_one_first_message
+            .pi_bound
+            .verify(&bound_statement, &keys.proof_context(HmrtParty::PartyOne))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::CandidateGeneration,
+                "pi_bound",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
+        }
+        normalize_paillier_ciphertexts(
+            &keys.remote_paillier_pubkey,
+            &party_one_first_message.c_i,
+            &keys.local_paillier_pubkey,
+            &party_two_first_message.c_i,
         )
-        .unwrap();
+    }
+
+    pub fn trial_division_prepare_c_alpha(
+        alpha: &BigInt,
+        keys: &PartyTwoKeySetup,
+        c: &PaillierCiphertextPair,
+        w: &PartyTwoPaillierCandidateWitness,
+    ) -> Result<PartyTwoPaillierCandidateGenerationSecondMsg, TwoPartyRSAError> {
+        let ek = &keys.local_paillier_pubkey;
+        // witness of c1 = Enc(p_1)^4
+        let p_1 = &w.p_1 * BigInt::from(4);
+        let r_1 = PaillierScheme::mul_randomness(ek, &w.r_1, &BigInt::from(4));
+
+        let p_1_mod_alpha = p_1.mod_floor(alpha);
+        let r_1_alpha = PaillierScheme::sample_randomness(ek);
+        let c_1_alpha =
+            match PaillierScheme::encrypt_with_randomness(ek, &p_1_mod_alpha, &r_1_alpha) {
+                Ok(c_1_alpha) => c_1_alpha,
+                Err(_) => return Err(TwoPartyRSAError::InvalidModProof),
+            };
 
-        let mod_statement = ModStatement {
+        let mod_statement = ModStatement::<PaillierScheme> {
             c: c.c1.clone(),
             c_prime: c_1_alpha.clone(),
             modulus_p: alpha.clone(),
-            upper_bound_m: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32), // n/2 instead of n/2-2 as is written in the paper : we suspect paper has a typo and do not consider the fact that ciphertexts and plaintext are scaled by mul4
-            pk: keys.joint_elgamal_pubkey.clone(),
+            upper_bound_m: keys.params.share_bound(),
+            pk: ek.clone(),
         };
-
         let mod_witness = ModWitness {
             r_a: r_1,
             a: p_1,
@@ -298,242 +1290,841 @@ impl PartyTwoCandidateGeneration {
             b: p_1_mod_alpha,
         };
 
-        let proof = ModProof::prove(&mod_witness, &mod_statement);
-
-        match proof {
-            Ok(_) => Ok(PartyTwoCandidateGenerationSecondMsg {
-                pi_mod: proof.unwrap(),
-                c_1_alpha,
-            }),
-            Err(_) => Err(TwoPartyRSAError::InvalidModProof),
+        match ModProof::prove(
+            &mod_witness,
+            &mod_statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        ) {
+            Ok(pi_mod) => Ok(PartyTwoPaillierCandidateGenerationSecondMsg { pi_mod, c_1_alpha }),
+            Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
         }
     }
 
-    pub fn verify_party_one_second_message_and_partial_decrypt(
-        party_one_second_message: &PartyOneCandidateGenerationSecondMsg,
-        party_two_second_message: &PartyTwoCandidateGenerationSecondMsg,
+    /// Verifies the residue of party one and masks the sum of residues under its key, once to
+    /// test it against zero and once against alpha
+    pub fn verify_party_one_second_message_and_mask_residues(
+        party_two_second_message: &PartyTwoPaillierCandidateGenerationSecondMsg,
+        party_one_second_message: &PartyOnePaillierCandidateGenerationSecondMsg,
         alpha: &BigInt,
         keys: &PartyTwoKeySetup,
-        c: &CiphertextPair,
-    ) -> Result<
-        (
-            PartyTwoCandidateGenerationThirdMsg,
-            ElGamalCiphertext,
-            ElGamalCiphertext,
-        ),
-        TwoPartyRSAError,
-    > {
-        let mod_statement = ModStatement {
+        c: &PaillierCiphertextPair,
+        w: &PartyTwoPaillierCandidateWitness,
+    ) -> Result<PartyTwoPaillierCandidateGenerationThirdMsg, TwoPartyRSAError> {
+        let mod_statement = ModStatement::<PaillierScheme> {
             c: c.c0.clone(),
             c_prime: party_one_second_message.c_0_alpha.clone(),
             modulus_p: alpha.clone(),
-            upper_bound_m: BigInt::from(2).pow((CANDIDATE_BIT_LENGTH / 2) as u32),
-            pk: keys.joint_elgamal_pubkey.clone(),
+            upper_bound_m: keys.params.share_bound(),
+            pk: keys.remote_paillier_pubkey.clone(),
         };
-        let verify = party_one_second_message.pi_mod.verify(&mod_statement);
-        if verify.is_err() {
-            return Err(TwoPartyRSAError::InvalidModProof);
+        if let Err(err) = party_one_second_message
+            .pi_mod
+            .verify(&mod_statement, &keys.proof_context(HmrtParty::PartyOne))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::TrialDivision,
+                "pi_mod",
+                Some(alpha),
+                party_one_second_message,
+                Some(err),
+            ));
+        }
+
+        let p_1_mod_alpha = (&w.p_1 * BigInt::from(4)).mod_floor(alpha);
+        let zero_test = |offset: &BigInt| {
+            paillier_zero_test(
+                &keys.local_paillier_pubkey,
+                &keys.private.dk,
+                &party_two_second_message.c_1_alpha,
+                &p_1_mod_alpha,
+                &keys.remote_paillier_pubkey,
+                &party_one_second_message.c_0_alpha,
+                offset,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            )
         };
-        let c_alpha = ExponentElGamal::add(
-            &party_one_second_message.c_0_alpha,
-            &party_two_second_message.c_1_alpha,
-        )
-        .unwrap();
-        // Enc(-alpha) is known to both parties therefore we use a predefined randomness known to both (r = 2)
-        let enc_alpha = ExponentElGamal::encrypt_from_predefined_randomness(
-            alpha,
-            &keys.joint_elgamal_pubkey,
-            &BigInt::from(2),
+        Ok(PartyTwoPaillierCandidateGenerationThirdMsg {
+            zero_test_alpha: zero_test(&BigInt::zero())?,
+            zero_test_alpha_tilde: zero_test(&zero_test_tilde_offset(alpha))?,
+        })
+    }
+
+    /// Decrypts the zero tests of party one, true if alpha does not divide the candidate
+    pub fn verify_party_one_third_message_and_conclude_division(
+        party_two_second_message: &PartyTwoPaillierCandidateGenerationSecondMsg,
+        party_one_second_message: &PartyOnePaillierCandidateGenerationSecondMsg,
+        party_one_third_message: &PartyOnePaillierCandidateGenerationThirdMsg,
+        alpha: &BigInt,
+        keys: &PartyTwoKeySetup,
+    ) -> Result<bool, TwoPartyRSAError> {
+        let conclude = |zero_test: &PaillierZeroTest, offset: &BigInt| {
+            conclude_paillier_zero_test(
+                zero_test,
+                &keys.local_paillier_pubkey,
+                &keys.private.dk,
+                &party_two_second_message.c_1_alpha,
+                &keys.remote_paillier_pubkey,
+                &party_one_second_message.c_0_alpha,
+                offset,
+                &keys.proof_context(HmrtParty::PartyOne),
+            )
+        };
+        let test1 =
+            conclude(&party_one_third_message.zero_test_alpha, &BigInt::zero()).map_err(|err| {
+                protocol_abort(
+                    ProtocolRound::TrialDivision,
+                    "zero_test_alpha",
+                    Some(alpha),
+                    party_one_third_message,
+                    Some(err),
+                )
+            })?;
+        let test2 = conclude(
+            &party_one_third_message.zero_test_alpha_tilde,
+            &zero_test_tilde_offset(alpha),
         )
-        .unwrap();
-        let enc_minus_alpha = ExponentElGamal::mul(&enc_alpha, &(-BigInt::one()));
-        let c_alpha_tilde = ExponentElGamal::add(&c_alpha, &enc_minus_alpha).unwrap();
-
-        // we raise each ciphertext with a secret random number
-        let r_alpha = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
-        let r_alpha_tilde = BigInt::sample_below(&keys.joint_elgamal_pubkey.pp.q);
-
-        let c_alpha_random = ExponentElGamal::mul(&c_alpha, &r_alpha);
-        let c_alpha_tilde_random = ExponentElGamal::mul(&c_alpha_tilde, &r_alpha_tilde);
-
-        // we use proof of DDH to prove to counter party that c_alpha_random = c_alpha ^r and same for c_alpha_tilde
-        let (_, _, ddh_proof_alpha) = gen_ddh_containers(
-            r_alpha,
-            &c_alpha.c1,
-            &c_alpha_random.c1,
-            &c_alpha.c2,
-            &c_alpha_random.c2,
-            &keys.joint_elgamal_pubkey.pp,
+        .map_err(|err| {
+            protocol_abort(
+                ProtocolRound::TrialDivision,
+                "zero_test_alpha_tilde",
+                Some(alpha),
+                party_one_third_message,
+                Some(err),
+            )
+        })?;
+        Ok(!test1 && !test2)
+    }
+}
+
+impl PartyTwoBiprimalityTest {
+    // for every round j we send v_j = g_j^(p_1 + q_1) mod N together with a proof that the
+    // exponent is the plaintext of c_p * c_q, the ciphertexts sent in candidate generation.
+    pub fn compute_exponentiations_and_first_message(
+        n: &BigInt,
+        keys: &PartyTwoKeySetup,
+        p_witness: &PartyTwoCandidateWitness,
+        q_witness: &PartyTwoCandidateWitness,
+        p_first_message: &PartyTwoCandidateGenerationFirstMsg,
+        q_first_message: &PartyTwoCandidateGenerationFirstMsg,
+    ) -> Result<PartyTwoBiprimalityTestFirstMsg, TwoPartyRSAError> {
+        let x = &p_witness.p_1 + &q_witness.p_1;
+        let r = BigInt::mod_add(
+            &p_witness.r_1,
+            &q_witness.r_1,
+            &keys.joint_elgamal_pubkey.pp.q,
         );
+        let c_x = ExponentElGamal::add(&p_first_message.c_i, &q_first_message.c_i).unwrap();
 
-        let (_, _, ddh_proof_alpha_tilde) = gen_ddh_containers(
-            r_alpha_tilde,
-            &c_alpha_tilde.c1,
-            &c_alpha_tilde_random.c1,
-            &c_alpha_tilde.c2,
-            &c_alpha_tilde_random.c2,
-            &keys.joint_elgamal_pubkey.pp,
+        let mut v_vec = Vec::with_capacity(BIPRIMALITY_TEST_ROUNDS);
+        let mut pi_eq_vec = Vec::with_capacity(BIPRIMALITY_TEST_ROUNDS);
+        for j in 0..BIPRIMALITY_TEST_ROUNDS {
+            let g = biprimality_test_base(n, j);
+            let v = BigInt::mod_pow(&g, &x, n);
+            let eq_statement = EqStatement {
+                pk: keys.joint_elgamal_pubkey.clone(),
+                h: g,
+                h_prime: v.clone(),
+                n: n.clone(),
+                ciphertext: c_x.clone(),
+                sec_param: keys.params.sec_param,
+                kapa: keys.params.kapa,
+            };
+            let eq_witness = EqWitness {
+                x: x.clone(),
+                r: r.clone(),
+            };
+            match EqProof::prove(
+                &eq_statement,
+                &eq_witness,
+                &keys.proof_context(HmrtParty::PartyTwo),
+            ) {
+                Ok(proof) => pi_eq_vec.push(proof),
+                Err(err) => return Err(TwoPartyRSAError::ProofGenerationError(err)),
+            }
+            v_vec.push(v);
+        }
+
+        Ok(PartyTwoBiprimalityTestFirstMsg { v_vec, pi_eq_vec })
+    }
+
+    pub fn verify_party_one_first_message_and_conclude_biprimality(
+        n: &BigInt,
+        keys: &PartyTwoKeySetup,
+        party_one_p_first_message: &PartyOneCandidateGenerationFirstMsg,
+        party_one_q_first_message: &PartyOneCandidateGenerationFirstMsg,
+        party_one_biprimality_message: &PartyOneBiprimalityTestFirstMsg,
+        party_two_biprimality_message: &PartyTwoBiprimalityTestFirstMsg,
+    ) -> Result<bool, TwoPartyRSAError> {
+        if party_one_biprimality_message.v_vec.len() != BIPRIMALITY_TEST_ROUNDS
+            || party_one_biprimality_message.pi_eq_vec.len() != BIPRIMALITY_TEST_ROUNDS
+        {
+            return Err(TwoPartyRSAError::InvalidBiprimalityProof);
+        }
+        let c_x = match ExponentElGamal::add(
+            &party_one_p_first_message.c_i,
+            &party_one_q_first_message.c_i,
+        ) {
+            Ok(c_x) => c_x,
+            Err(_) => return Err(TwoPartyRSAError::InvalidBiprimalityProof),
+        };
+
+        for j in 0..BIPRIMALITY_TEST_ROUNDS {
+            let eq_statement = EqStatement {
+                pk: keys.joint_elgamal_pubkey.clone(),
+                h: biprimality_test_base(n, j),
+                h_prime: party_one_biprimality_message.v_vec[j].clone(),
+                n: n.clone(),
+                ciphertext: c_x.clone(),
+                sec_param: keys.params.sec_param,
+                kapa: keys.params.kapa,
+            };
+            if let Err(err) = party_one_biprimality_message.pi_eq_vec[j]
+                .verify(&eq_statement, &keys.proof_context(HmrtParty::PartyOne))
+            {
+                return Err(protocol_abort(
+                    ProtocolRound::BiprimalityTest,
+                    &format!("pi_eq_vec[{}]", j),
+                    None,
+                    party_one_biprimality_message,
+                    Some(err),
+                ));
+            }
+        }
+
+        Ok(conclude_biprimality(
+            n,
+            &party_one_biprimality_message.v_vec,
+            &party_two_biprimality_message.v_vec,
+        ))
+    }
+}
+
+impl PartyTwoJointModulus {
+    // p = 4p_1 and q are encrypted under our own Paillier key, together with
+    // Enc(p*q), and linked to the normalized ElGamal ciphertexts of the candidates.
+    pub fn encrypt_shares_and_first_message(
+        keys: &PartyTwoKeySetup,
+        p_witness: &PartyTwoCandidateWitness,
+        q_witness: &PartyTwoCandidateWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+    ) -> Result<(PartyTwoJointModulusWitness, PartyTwoJointModulusFirstMsg), TwoPartyRSAError> {
+        let ek = &keys.local_paillier_pubkey;
+        let q_elgamal = &keys.joint_elgamal_pubkey.pp.q;
+        let p = BigInt::from(4) * &p_witness.p_1;
+        let q = BigInt::from(4) * &q_witness.p_1;
+        let r_p = BigInt::mod_mul(&p_witness.r_1, &BigInt::from(4), q_elgamal);
+        let r_q = BigInt::mod_mul(&q_witness.r_1, &BigInt::from(4), q_elgamal);
+
+        let rho_p = BigInt::sample_below(&ek.n);
+        let rho_q = BigInt::sample_below(&ek.n);
+        let c_p = Paillier::encrypt_with_chosen_randomness(
+            ek,
+            RawPlaintext::from(p.clone()),
+            &Randomness(rho_p.clone()),
+        )
+        .0
+        .into_owned();
+        let c_q = Paillier::encrypt_with_chosen_randomness(
+            ek,
+            RawPlaintext::from(q.clone()),
+            &Randomness(rho_q.clone()),
+        )
+        .0
+        .into_owned();
+        let sigma = BigInt::sample_below(&ek.n);
+        let c_pq = BigInt::mod_mul(
+            &BigInt::mod_pow(&c_q, &p, &ek.nn),
+            &BigInt::mod_pow(&sigma, &ek.n, &ek.nn),
+            &ek.nn,
         );
+        let rho_pq = BigInt::mod_mul(&BigInt::mod_pow(&rho_q, &p, &ek.n), &sigma, &ek.n);
 
-        let dec_key_alpha = BigInt::mod_pow(
-            &c_alpha_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.p,
+        let statement_p = share_encryption_statement(
+            &keys.params,
+            &ek.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &c_p,
         );
-        let dec_key_alpha_tilde = BigInt::mod_pow(
-            &c_alpha_tilde_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.p,
+        let statement_q = share_encryption_statement(
+            &keys.params,
+            &ek.n,
+            &keys.joint_elgamal_pubkey,
+            &q_ciphertexts.c1,
+            &c_q,
         );
-
-        let statement_alpha = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.local_elgamal_puubkey.h.clone(),
-            g2: c_alpha_random.c1.clone(),
-            h2: dec_key_alpha.clone(),
+        let statement_pq = share_product_statement(
+            &keys.params,
+            &ek.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &c_q,
+            &c_pq,
+        );
+        let witness_p = AffineWitness {
+            a_vec: vec![p.clone()],
+            r_vec: vec![r_p.clone()],
+            rho: rho_p,
         };
-        let witness_alpha = DDHWitness {
-            x: keys.private.sk.x.clone(),
+        let witness_q = AffineWitness {
+            a_vec: vec![q],
+            r_vec: vec![r_q],
+            rho: rho_q,
         };
-        let statement_alpha_tilde = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.local_elgamal_puubkey.h.clone(),
-            g2: c_alpha_tilde_random.c1.clone(),
-            h2: dec_key_alpha_tilde.clone(),
+        let witness_pq = AffineWitness {
+            a_vec: vec![p],
+            r_vec: vec![r_p],
+            rho: sigma,
         };
 
-        let proof_alpha = DDHProof::prove(&witness_alpha, &statement_alpha);
-        let proof_alpha_tilde = DDHProof::prove(&witness_alpha, &statement_alpha_tilde);
-
-        Ok((
-            PartyTwoCandidateGenerationThirdMsg {
-                proof_alpha,
-                proof_alpha_tilde,
-                c_alpha_random,
-                c_alpha_tilde_random,
-                partial_dec_c_alpha: dec_key_alpha,
-                partial_dec_c_alpha_tilde: dec_key_alpha_tilde,
-                ddh_proof_alpha,
-                ddh_proof_alpha_tilde,
-            },
-            c_alpha,
-            c_alpha_tilde,
-        ))
+        let pi_p = AffineProof::prove(
+            &witness_p,
+            &statement_p,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        );
+        let pi_q = AffineProof::prove(
+            &witness_q,
+            &statement_q,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        );
+        let pi_pq = AffineProof::prove(
+            &witness_pq,
+            &statement_pq,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        );
+        match (pi_p, pi_q, pi_pq) {
+            (Ok(pi_p), Ok(pi_q), Ok(pi_pq)) => Ok((
+                PartyTwoJointModulusWitness { rho_pq },
+                PartyTwoJointModulusFirstMsg {
+                    c_p,
+                    c_q,
+                    c_pq,
+                    pi_p,
+                    pi_q,
+                    pi_pq,
+                },
+            )),
+            (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+                Err(TwoPartyRSAError::ProofGenerationError(err))
+            }
+        }
     }
 
-    pub fn verify_party_one_third_message_full_decrypt_and_conclude_division(
-        c_alpha: &ElGamalCiphertext,
-        c_alpha_tilde: &ElGamalCiphertext,
-        party_one_third_message: &PartyOneCandidateGenerationThirdMsg,
+    // We compute under the counter party key: Enc(p_remote * q + q_remote * p + p * q).
+    // The counter party adds p_remote * q_remote after decryption to get N.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_party_one_first_message_and_compute_cross_terms(
         keys: &PartyTwoKeySetup,
-    ) -> Result<bool, TwoPartyRSAError> {
-        // check that the randomization of the ciphertexts was done properly:
-        let statement_alpha_ddh = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: c_alpha.c1.clone(),
-            h1: party_one_third_message.c_alpha_random.c1.clone(),
-            g2: c_alpha.c2.clone(),
-            h2: party_one_third_message.c_alpha_random.c2.clone(),
-        };
-        let statement_alpha_tilde_ddh = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: c_alpha_tilde.c1.clone(),
-            h1: party_one_third_message.c_alpha_tilde_random.c1.clone(),
-            g2: c_alpha_tilde.c2.clone(),
-            h2: party_one_third_message.c_alpha_tilde_random.c2.clone(),
-        };
-        if party_one_third_message
-            .ddh_proof_alpha
-            .verify(&statement_alpha_ddh)
-            .is_err()
+        p_witness: &PartyTwoCandidateWitness,
+        q_witness: &PartyTwoCandidateWitness,
+        joint_modulus_witness: &PartyTwoJointModulusWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+        party_two_first_message: &PartyTwoJointModulusFirstMsg,
+        party_one_first_message: &PartyOneJointModulusFirstMsg,
+    ) -> Result<PartyTwoJointModulusSecondMsg, TwoPartyRSAError> {
+        let ek_remote = &keys.remote_paillier_pubkey;
+        let statement_p = share_encryption_statement(
+            &keys.params,
+            &ek_remote.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &party_one_first_message.c_p,
+        );
+        let statement_q = share_encryption_statement(
+            &keys.params,
+            &ek_remote.n,
+            &keys.joint_elgamal_pubkey,
+            &q_ciphertexts.c0,
+            &party_one_first_message.c_q,
+        );
+        let statement_pq = share_product_statement(
+            &keys.params,
+            &ek_remote.n,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &party_one_first_message.c_q,
+            &party_one_first_message.c_pq,
+        );
+        for (proof, statement, name) in [
+            (&party_one_first_message.pi_p, &statement_p, "pi_p"),
+            (&party_one_first_message.pi_q, &statement_q, "pi_q"),
+            (&party_one_first_message.pi_pq, &statement_pq, "pi_pq"),
+        ]
+        .iter()
         {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+            if let Err(err) = proof.verify(statement, &keys.proof_context(HmrtParty::PartyOne)) {
+                return Err(protocol_abort(
+                    ProtocolRound::JointModulus,
+                    name,
+                    None,
+                    party_one_first_message,
+                    Some(err),
+                ));
+            }
         }
 
-        if party_one_third_message
-            .ddh_proof_alpha_tilde
-            .verify(&statement_alpha_tilde_ddh)
-            .is_err()
+        let q_elgamal = &keys.joint_elgamal_pubkey.pp.q;
+        let p = BigInt::from(4) * &p_witness.p_1;
+        let q = BigInt::from(4) * &q_witness.p_1;
+        let r_p = BigInt::mod_mul(&p_witness.r_1, &BigInt::from(4), q_elgamal);
+        let r_q = BigInt::mod_mul(&q_witness.r_1, &BigInt::from(4), q_elgamal);
+        let pq = &p * &q;
+
+        let rho = BigInt::sample_below(&ek_remote.n);
+        let c_n = [
+            BigInt::mod_pow(&party_one_first_message.c_p, &q, &ek_remote.nn),
+            BigInt::mod_pow(&party_one_first_message.c_q, &p, &ek_remote.nn),
+            BigInt::mod_pow(&(&ek_remote.n + BigInt::one()), &pq, &ek_remote.nn),
+            BigInt::mod_pow(&rho, &ek_remote.n, &ek_remote.nn),
+        ]
+        .iter()
+        .fold(BigInt::one(), |acc, x| {
+            BigInt::mod_mul(&acc, x, &ek_remote.nn)
+        });
+
+        let statement_n = cross_product_statement(
+            &keys.params,
+            &ek_remote.n,
+            &party_one_first_message.c_p,
+            &party_one_first_message.c_q,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &q_ciphertexts.c1,
+            &keys.local_paillier_pubkey.n,
+            &party_two_first_message.c_pq,
+            &c_n,
+        );
+        let witness_n = AffineWitness {
+            a_vec: vec![q, p, pq],
+            r_vec: vec![r_q, r_p, joint_modulus_witness.rho_pq.clone()],
+            rho,
+        };
+        match AffineProof::prove(
+            &witness_n,
+            &statement_n,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        ) {
+            Ok(pi_n) => Ok(PartyTwoJointModulusSecondMsg { c_n, pi_n }),
+            Err(err) => Err(TwoPartyRSAError::ProofGenerationError(err)),
+        }
+    }
+
+    // Enc(cross terms) * Enc(p * q) encrypts N under our key: we decrypt it and reveal the
+    // randomness of the product ciphertext as a proof of correct decryption.
+    pub fn verify_party_one_second_message_and_decrypt_modulus(
+        keys: &PartyTwoKeySetup,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+        party_two_first_message: &PartyTwoJointModulusFirstMsg,
+        party_one_first_message: &PartyOneJointModulusFirstMsg,
+        party_one_second_message: &PartyOneJointModulusSecondMsg,
+    ) -> Result<PartyTwoJointModulusThirdMsg, TwoPartyRSAError> {
+        let ek = &keys.local_paillier_pubkey;
+        let statement_n = cross_product_statement(
+            &keys.params,
+            &ek.n,
+            &party_two_first_message.c_p,
+            &party_two_first_message.c_q,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &q_ciphertexts.c0,
+            &keys.remote_paillier_pubkey.n,
+            &party_one_first_message.c_pq,
+            &party_one_second_message.c_n,
+        );
+        if let Err(err) = party_one_second_message
+            .pi_n
+            .verify(&statement_n, &keys.proof_context(HmrtParty::PartyOne))
         {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+            return Err(protocol_abort(
+                ProtocolRound::JointModulus,
+                "pi_n",
+                None,
+                party_one_second_message,
+                Some(err),
+            ));
         }
 
-        // verify proofs of decryption:
-        let statement_alpha = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.remote_elgamal_pubkey.h.clone(),
-            g2: party_one_third_message.c_alpha_random.c1.clone(),
-            h2: party_one_third_message.partial_dec_c_alpha.clone(),
+        let c_n_full = BigInt::mod_mul(
+            &party_one_second_message.c_n,
+            &party_two_first_message.c_pq,
+            &ek.nn,
+        );
+        let n = Paillier::decrypt(&keys.private.dk, RawCiphertext::from(c_n_full.clone()))
+            .0
+            .into_owned();
+        let rho_n = paillier_extract_randomness(&c_n_full, &n, &keys.private.dk);
+
+        Ok(PartyTwoJointModulusThirdMsg { n, rho_n })
+    }
+
+    pub fn verify_party_one_third_message_and_output_joint_modulus(
+        keys: &PartyTwoKeySetup,
+        party_two_second_message: &PartyTwoJointModulusSecondMsg,
+        party_two_third_message: &PartyTwoJointModulusThirdMsg,
+        party_one_first_message: &PartyOneJointModulusFirstMsg,
+        party_one_third_message: &PartyOneJointModulusThirdMsg,
+    ) -> Result<JointModulus, TwoPartyRSAError> {
+        let ek_remote = &keys.remote_paillier_pubkey;
+        let c_n_full = BigInt::mod_mul(
+            &party_two_second_message.c_n,
+            &party_one_first_message.c_pq,
+            &ek_remote.nn,
+        );
+        if !paillier_verify_randomness(
+            &c_n_full,
+            &party_one_third_message.n,
+            &party_one_third_message.rho_n,
+            &ek_remote.n,
+        ) {
+            return Err(protocol_abort(
+                ProtocolRound::JointModulus,
+                "rho_n",
+                None,
+                party_one_third_message,
+                None,
+            ));
+        }
+        if party_one_third_message.n != party_two_third_message.n {
+            return Err(TwoPartyRSAError::JointModulusError);
+        }
+        Ok(JointModulus {
+            n: party_two_third_message.n.clone(),
+        })
+    }
+}
+
+impl PartyTwoPrivateExponentShare {
+    // phi(N) = phi_0 - phi_1 with phi_1 = p + q for our shares p = 4p_1, q = 4q_1.
+    // We reveal (p + q) mod e, proven to be the residue of the plaintext of c_p * c_q. As in
+    // Boneh-Franklin this leaks log(e) bits of our shares.
+    pub fn reveal_phi_share_mod_e_and_first_message(
+        e: &BigInt,
+        keys: &PartyTwoKeySetup,
+        p_witness: &PartyTwoCandidateWitness,
+        q_witness: &PartyTwoCandidateWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+    ) -> Result<PartyTwoPrivateExponentShareFirstMsg, TwoPartyRSAError> {
+        if !is_valid_public_exponent(e) {
+            return Err(TwoPartyRSAError::InvalidPublicExponent);
+        }
+        let q_elgamal = &keys.joint_elgamal_pubkey.pp.q;
+        let x = BigInt::from(4) * (&p_witness.p_1 + &q_witness.p_1);
+        let r_x = BigInt::mod_mul(
+            &BigInt::mod_add(&p_witness.r_1, &q_witness.r_1, q_elgamal),
+            &BigInt::from(4),
+            q_elgamal,
+        );
+
+        let phi_mod_e = x.mod_floor(e);
+        let r_phi_mod_e = BigInt::sample_below(q_elgamal);
+        let c_phi_mod_e = ExponentElGamal::encrypt_from_predefined_randomness(
+            &phi_mod_e,
+            &keys.joint_elgamal_pubkey,
+            &r_phi_mod_e,
+        )
+        .unwrap();
+
+        let mod_statement = phi_share_mod_statement(
+            &keys.params,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c1,
+            &q_ciphertexts.c1,
+            &c_phi_mod_e,
+            e,
+        )?;
+        let mod_witness = ModWitness {
+            r_a: r_x,
+            a: x,
+            r_b: r_phi_mod_e.clone(),
+            b: phi_mod_e.clone(),
         };
-        let statement_alpha_tilde = DDHStatement {
-            pp: keys.joint_elgamal_pubkey.pp.clone(),
-            g1: keys.joint_elgamal_pubkey.pp.g.clone(),
-            h1: keys.remote_elgamal_pubkey.h.clone(),
-            g2: party_one_third_message.c_alpha_tilde_random.c1.clone(),
-            h2: party_one_third_message.partial_dec_c_alpha_tilde.clone(),
+        let pi_mod = match ModProof::prove(
+            &mod_witness,
+            &mod_statement,
+            &keys.proof_context(HmrtParty::PartyTwo),
+        ) {
+            Ok(proof) => proof,
+            Err(err) => return Err(TwoPartyRSAError::ProofGenerationError(err)),
         };
 
-        if party_one_third_message
-            .proof_alpha
-            .verify(&statement_alpha)
-            .is_err()
+        Ok(PartyTwoPrivateExponentShareFirstMsg {
+            c_phi_mod_e,
+            pi_mod,
+            phi_mod_e,
+            r_phi_mod_e,
+        })
+    }
+
+    // given phi(N) mod e both parties compute zeta = -phi(N)^-1 mod e locally. Our share is
+    // d_1 = floor(zeta * phi_1 / e) and vk_1 = vk_base^d_1 mod N.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_party_one_first_message_and_compute_exponent_share(
+        e: &BigInt,
+        joint_modulus: &JointModulus,
+        keys: &PartyTwoKeySetup,
+        p_witness: &PartyTwoCandidateWitness,
+        q_witness: &PartyTwoCandidateWitness,
+        p_ciphertexts: &CiphertextPair,
+        q_ciphertexts: &CiphertextPair,
+        party_two_first_message: &PartyTwoPrivateExponentShareFirstMsg,
+        party_one_first_message: &PartyOnePrivateExponentShareFirstMsg,
+    ) -> Result<
+        (
+            PartyTwoPrivateExponentShareWitness,
+            PartyTwoPrivateExponentShareSecondMsg,
+        ),
+        TwoPartyRSAError,
+    > {
+        let opening_ok = ExponentElGamal::encrypt_from_predefined_randomness(
+            &party_one_first_message.phi_mod_e,
+            &keys.joint_elgamal_pubkey,
+            &party_one_first_message.r_phi_mod_e,
+        )
+        .map(|expected_c_phi_mod_e| expected_c_phi_mod_e == party_one_first_message.c_phi_mod_e)
+        .unwrap_or(false);
+        if !opening_ok || &party_one_first_message.phi_mod_e >= e {
+            return Err(protocol_abort(
+                ProtocolRound::PrivateExponentShare,
+                "r_phi_mod_e",
+                None,
+                party_one_first_message,
+                None,
+            ));
+        }
+        let mod_statement = phi_share_mod_statement(
+            &keys.params,
+            &keys.joint_elgamal_pubkey,
+            &p_ciphertexts.c0,
+            &q_ciphertexts.c0,
+            &party_one_first_message.c_phi_mod_e,
+            e,
+        )?;
+        if let Err(err) = party_one_first_message
+            .pi_mod
+            .verify(&mod_statement, &keys.proof_context(HmrtParty::PartyOne))
         {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+            return Err(protocol_abort(
+                ProtocolRound::PrivateExponentShare,
+                "pi_mod",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
         }
 
-        if party_one_third_message
-            .proof_alpha_tilde
-            .verify(&statement_alpha_tilde)
-            .is_err()
-        {
-            return Err(TwoPartyRSAError::CandidateGenerationDecError);
+        let n = &joint_modulus.n;
+        let zeta = match exponent_inversion_zeta(
+            n,
+            e,
+            &party_one_first_message.phi_mod_e,
+            &party_two_first_message.phi_mod_e,
+        ) {
+            Some(zeta) => zeta,
+            None => return Err(TwoPartyRSAError::InvalidPublicExponent),
+        };
+
+        let phi_1 = BigInt::from(4) * (&p_witness.p_1 + &q_witness.p_1);
+        let d_1 = (zeta * phi_1).div_floor(e);
+        let vk = BigInt::mod_pow(&verification_key_base(n), &d_1, n);
+
+        Ok((
+            PartyTwoPrivateExponentShareWitness {
+                d_1,
+                session_id: *keys.session_id(),
+            },
+            PartyTwoPrivateExponentShareSecondMsg { vk },
+        ))
+    }
+
+    // (vk_0 / vk_1)^e = vk_base mod N confirms that both shares were computed correctly
+    pub fn verify_party_one_second_message_and_output_key_share(
+        e: &BigInt,
+        joint_modulus: &JointModulus,
+        witness: PartyTwoPrivateExponentShareWitness,
+        party_two_second_message: &PartyTwoPrivateExponentShareSecondMsg,
+        party_one_second_message: &PartyOnePrivateExponentShareSecondMsg,
+    ) -> Result<PartyTwoRSAKeyShare, TwoPartyRSAError> {
+        let n = &joint_modulus.n;
+        let vk_base = verification_key_base(n);
+        if !verify_verification_keys(
+            n,
+            e,
+            &vk_base,
+            &party_one_second_message.vk,
+            &party_two_second_message.vk,
+        ) {
+            return Err(protocol_abort(
+                ProtocolRound::PrivateExponentShare,
+                "vk",
+                None,
+                party_one_second_message,
+                None,
+            ));
         }
 
-        // full decryption
-        let dec_key_alpha = BigInt::mod_pow(
-            &party_one_third_message.c_alpha_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.q,
-        );
-        let dec_key_alpha_tilde = BigInt::mod_pow(
-            &party_one_third_message.c_alpha_tilde_random.c1,
-            &keys.private.sk.x,
-            &keys.joint_elgamal_pubkey.pp.q,
+        Ok(PartyTwoRSAKeyShare {
+            n: n.clone(),
+            e: e.clone(),
+            vk_base,
+            local_vk: party_two_second_message.vk.clone(),
+            remote_vk: party_one_second_message.vk.clone(),
+            session_id: witness.session_id,
+            d_1: witness.d_1.clone(),
+        })
+    }
+}
+
+impl PartyTwoSign {
+    // we check that EM chosen by party one encodes the message and that sigma_0 is consistent
+    // with vk_0, then send sigma_1 = EM^d_1 mod N and a proof that it is consistent with vk_1
+    pub fn verify_party_one_first_message_and_partial_sign(
+        key_share: &PartyTwoRSAKeyShare,
+        message: &[u8],
+        encoding: &SignatureEncoding,
+        party_one_first_message: &PartyOneSignFirstMsg,
+    ) -> Result<PartyTwoSignFirstMsg, TwoPartyRSAError> {
+        let n = &key_share.n;
+        let em = &party_one_first_message.em;
+        if !encoding.verify(message, em, n) {
+            return Err(TwoPartyRSAError::MessageEncodingError);
+        }
+        let statement_party_one = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.remote_vk,
+            em,
+            &party_one_first_message.sigma,
         );
-        let dec_key_alpha_full = BigInt::mod_mul(
-            &dec_key_alpha,
-            &party_one_third_message.partial_dec_c_alpha,
-            &keys.joint_elgamal_pubkey.pp.q,
+        if let Err(err) = party_one_first_message.pi_sigma.verify(
+            &statement_party_one,
+            &key_share.proof_context(HmrtParty::PartyOne),
+        ) {
+            return Err(protocol_abort(
+                ProtocolRound::Sign,
+                "pi_sigma",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
+        }
+
+        let sigma = BigInt::mod_pow(em, &key_share.d_1, n);
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.local_vk,
+            em,
+            &sigma,
         );
-        let dec_key_alpha_tilde_full = BigInt::mod_mul(
-            &dec_key_alpha_tilde,
-            &party_one_third_message.partial_dec_c_alpha_tilde,
-            &keys.joint_elgamal_pubkey.pp.q,
+        let witness = RSADDHWitness {
+            x: key_share.d_1.clone(),
+        };
+        let pi_sigma = RSADDHProof::prove(
+            &witness,
+            &statement,
+            &key_share.proof_context(HmrtParty::PartyTwo),
         );
 
-        let dec_key_alpha_full_inv =
-            BigInt::mod_inv(&dec_key_alpha_full, &keys.joint_elgamal_pubkey.pp.q);
-        let dec_key_alpha_tilde_full_inv =
-            BigInt::mod_inv(&dec_key_alpha_tilde_full, &keys.joint_elgamal_pubkey.pp.q);
+        Ok(PartyTwoSignFirstMsg { sigma, pi_sigma })
+    }
 
-        let test1 = BigInt::mod_mul(
-            &party_one_third_message.c_alpha_random.c2,
-            &dec_key_alpha_full_inv,
-            &keys.joint_elgamal_pubkey.pp.q,
+    // sigma = sigma_0 * sigma_1^-1 mod N is checked with the standard RSA verification
+    pub fn combine_partial_signatures_and_output_signature(
+        key_share: &PartyTwoRSAKeyShare,
+        message: &[u8],
+        encoding: &SignatureEncoding,
+        party_one_first_message: &PartyOneSignFirstMsg,
+        party_two_first_message: &PartyTwoSignFirstMsg,
+    ) -> Result<RSASignature, TwoPartyRSAError> {
+        let n = &key_share.n;
+        let s = match combine_partial_exponentiations(
+            n,
+            &party_one_first_message.sigma,
+            &party_two_first_message.sigma,
+        ) {
+            Some(s) => s,
+            None => return Err(TwoPartyRSAError::InvalidPartialSignature),
+        };
+        let signature = RSASignature { s };
+        signature.verify(n, &key_share.e, message, encoding)?;
+        Ok(signature)
+    }
+}
+
+impl PartyTwoDecrypt {
+    // partial decryption c^d_1 mod N with a proof that it is consistent with our verification key
+    pub fn partial_decrypt_and_first_message(
+        key_share: &PartyTwoRSAKeyShare,
+        c: &BigInt,
+    ) -> Result<PartyTwoDecryptFirstMsg, TwoPartyRSAError> {
+        let n = &key_share.n;
+        if !is_valid_rsa_ciphertext(n, c) {
+            return Err(TwoPartyRSAError::InvalidCiphertext);
+        }
+        let partial_dec_c = BigInt::mod_pow(c, &key_share.d_1, n);
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.local_vk,
+            c,
+            &partial_dec_c,
         );
-        let test2 = BigInt::mod_mul(
-            &party_one_third_message.c_alpha_tilde_random.c2,
-            &dec_key_alpha_tilde_full_inv,
-            &keys.joint_elgamal_pubkey.pp.q,
+        let witness = RSADDHWitness {
+            x: key_share.d_1.clone(),
+        };
+        let pi_partial_dec = RSADDHProof::prove(
+            &witness,
+            &statement,
+            &key_share.proof_context(HmrtParty::PartyTwo),
         );
 
-        if test1 == BigInt::one() || test2 == BigInt::one() {
-            Ok(false)
-        } else {
-            Ok(true)
+        Ok(PartyTwoDecryptFirstMsg {
+            partial_dec_c,
+            pi_partial_dec,
+        })
+    }
+
+    // c^d = c^d_0 * (c^d_1)^-1 mod N, the OAEP padding is removed from the result
+    pub fn verify_party_one_first_message_and_decrypt(
+        key_share: &PartyTwoRSAKeyShare,
+        c: &BigInt,
+        label: &[u8],
+        party_two_first_message: &PartyTwoDecryptFirstMsg,
+        party_one_first_message: &PartyOneDecryptFirstMsg,
+    ) -> Result<Vec<u8>, TwoPartyRSAError> {
+        let n = &key_share.n;
+        let statement = partial_exponentiation_statement(
+            n,
+            &key_share.vk_base,
+            &key_share.remote_vk,
+            c,
+            &party_one_first_message.partial_dec_c,
+        );
+        if let Err(err) = party_one_first_message
+            .pi_partial_dec
+            .verify(&statement, &key_share.proof_context(HmrtParty::PartyOne))
+        {
+            return Err(protocol_abort(
+                ProtocolRound::Decrypt,
+                "pi_partial_dec",
+                None,
+                party_one_first_message,
+                Some(err),
+            ));
         }
+
+        let m = match combine_partial_exponentiations(
+            n,
+            &party_one_first_message.partial_dec_c,
+            &party_two_first_message.partial_dec_c,
+        ) {
+            Some(m) => m,
+            None => return Err(TwoPartyRSAError::InvalidPartialDecryption),
+        };
+        rsa_oaep_decode(n, &m, label)
     }
 }
@@ -1,27 +1,87 @@
+use crate::protocols::two_party_rsa::hmrt::first_dividing_prime;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_one::PartyOneHmrtKeyGen;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_one::PartyOneKeyGenMsg;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_one::PartyOneKeyGenStep;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_one::PartyOneState;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_two::PartyTwoHmrtKeyGen;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_two::PartyTwoKeyGenMsg;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_two::PartyTwoKeyGenStep;
+use crate::protocols::two_party_rsa::hmrt::keygen::party_two::PartyTwoState;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtKeyGenStats;
+use crate::protocols::two_party_rsa::hmrt::keygen::HmrtParty;
+use crate::protocols::two_party_rsa::hmrt::keygen::SessionId;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneBiprimalityTest;
 use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGeneration;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneCandidateWitness;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneDecrypt;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneJointModulus;
 use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneKeySetup;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePaillierCandidateGeneration;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOnePrivateExponentShare;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneRSAKeyShare;
+use crate::protocols::two_party_rsa::hmrt::party_one::PartyOneSign;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoBiprimalityTest;
 use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGeneration;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateGenerationFirstMsg;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoCandidateWitness;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoDecrypt;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoJointModulus;
 use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoKeySetup;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPaillierCandidateGeneration;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoPrivateExponentShare;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoRSAKeyShare;
+use crate::protocols::two_party_rsa::hmrt::party_two::PartyTwoSign;
+use crate::protocols::two_party_rsa::hmrt::rsa_oaep_encrypt;
+use crate::protocols::two_party_rsa::hmrt::sieving_modulus;
+use crate::protocols::two_party_rsa::hmrt::ElGamalGroup;
+use crate::protocols::two_party_rsa::hmrt::HmrtParams;
+use crate::protocols::two_party_rsa::hmrt::JointModulus;
+use crate::protocols::two_party_rsa::hmrt::ModRangeProofBackend;
+use crate::protocols::two_party_rsa::hmrt::TrialDivisionModProof;
+use crate::protocols::two_party_rsa::padding::SignatureEncoding;
+use crate::protocols::two_party_rsa::DEFAULT_PUBLIC_EXPONENT;
+use crate::utlities::sealed_share::StorageKey;
 use crate::utlities::SMALL_PRIMES;
+use crate::HomomorphicSchemeError;
+use crate::ProofError;
+use crate::ProtocolRound;
+use crate::SealedShareError;
+use crate::TwoPartyRSAError;
+use curv::arithmetic::traits::Modulo;
 use curv::BigInt;
 use elgamal::prime::is_prime;
+use std::error::Error;
+use zeroize::Zeroize;
 
 #[test]
 fn test_simulate_key_setup() {
+    let session_id = SessionId::random();
     let (party_one_first_message, party_one_private) =
-        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two();
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
 
     let (party_two_first_message, party_two_private) =
-        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one();
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
 
     let party_one_key_setup_result =
         PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &HmrtParams::default(),
             &party_one_first_message,
             &party_two_first_message,
             party_one_private,
         );
     let party_two_key_setup_result =
         PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+            &HmrtParams::default(),
             &party_one_first_message,
             &party_two_first_message,
             party_two_private,
@@ -36,23 +96,234 @@ fn test_simulate_key_setup() {
     );
 }
 
+#[test]
+fn test_key_setup_in_another_session() {
+    let session_id = SessionId::random();
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
+
+    // party two proves knowledge of its ElGamal key for another session
+    let (party_two_first_message, _) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &SessionId::random(),
+        )
+        .expect("");
+
+    let result = PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+        &HmrtParams::default(),
+        &party_one_first_message,
+        &party_two_first_message,
+        party_one_private,
+    );
+    match result {
+        Err(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.proof, "dlog_proof");
+            assert_eq!(abort.proof_error, Some(ProofError::DlogProofError));
+        }
+        result => panic!("expected a protocol abort, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_hmrt_params_validation() {
+    assert!(HmrtParams::default().validate().is_ok());
+
+    let params_1024 = HmrtParams {
+        candidate_bit_length: 1024,
+        paillier_modulus: 2048,
+        ..HmrtParams::default()
+    };
+    assert!(params_1024.validate().is_ok());
+
+    let params_4096 = HmrtParams {
+        candidate_bit_length: 4096,
+        paillier_modulus: 6144,
+        elgamal_group: ElGamalGroup::FFDHE3072,
+        ..HmrtParams::default()
+    };
+    assert!(params_4096.validate().is_ok());
+
+    // the shares of a 4096 bits modulus do not fit in the 2048 bits group
+    let small_group = HmrtParams {
+        elgamal_group: ElGamalGroup::FFDHE2048,
+        ..params_4096
+    };
+    assert_eq!(
+        small_group.validate(),
+        Err(TwoPartyRSAError::InvalidHmrtParams)
+    );
+
+    // N does not fit in the Paillier plaintext space
+    let small_paillier = HmrtParams {
+        paillier_modulus: 2048,
+        ..HmrtParams::default()
+    };
+    assert_eq!(
+        small_paillier.validate(),
+        Err(TwoPartyRSAError::InvalidHmrtParams)
+    );
+    assert!(
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &small_paillier,
+            &SessionId::random()
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_key_setup_with_mismatched_params() {
+    let session_id = SessionId::random();
+    let params_1024 = HmrtParams {
+        candidate_bit_length: 1024,
+        paillier_modulus: 2048,
+        ..HmrtParams::default()
+    };
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(&params_1024, &session_id)
+            .expect("");
+    let (party_two_first_message, _) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
+
+    // party two's Paillier key is larger than required, its ElGamal key is in the same group
+    let party_one_key_setup_result =
+        PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &params_1024,
+            &party_one_first_message,
+            &party_two_first_message,
+            party_one_private,
+        );
+    assert!(party_one_key_setup_result.is_ok());
+
+    let params_3072_group = HmrtParams {
+        elgamal_group: ElGamalGroup::FFDHE3072,
+        ..HmrtParams::default()
+    };
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &params_3072_group,
+            &session_id,
+        )
+        .expect("");
+    let party_one_key_setup_result =
+        PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &params_3072_group,
+            &party_one_first_message,
+            &party_two_first_message,
+            party_one_private,
+        );
+    assert_eq!(
+        party_one_key_setup_result.err(),
+        Some(TwoPartyRSAError::InvalidElGamalKey)
+    );
+}
+
+#[test]
+fn test_key_setup_with_proposed_group() {
+    let session_id = SessionId::random();
+    let params_3072_group = HmrtParams {
+        elgamal_group: ElGamalGroup::FFDHE3072,
+        ..HmrtParams::default()
+    };
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &params_3072_group,
+            &session_id,
+        )
+        .expect("");
+    assert_eq!(party_one_first_message.group, ElGamalGroup::FFDHE3072);
+
+    // party two accepts any group from FFDHE2048 and follows party one's proposal
+    let (party_two_first_message, party_two_private) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
+
+    let party_one_keys =
+        PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &params_3072_group,
+            &party_one_first_message,
+            &party_two_first_message,
+            party_one_private,
+        )
+        .expect("");
+    let party_two_keys =
+        PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+            &HmrtParams::default(),
+            &party_one_first_message,
+            &party_two_first_message,
+            party_two_private.clone(),
+        )
+        .expect("");
+    assert_eq!(
+        party_one_keys.joint_elgamal_pubkey,
+        party_two_keys.joint_elgamal_pubkey
+    );
+    assert_eq!(party_two_keys.params.elgamal_group, ElGamalGroup::FFDHE3072);
+
+    // party two requiring at least FFDHE4096 rejects the proposal
+    let params_4096_group = HmrtParams {
+        elgamal_group: ElGamalGroup::FFDHE4096,
+        ..HmrtParams::default()
+    };
+    let result = PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+        &params_4096_group,
+        party_one_first_message.group,
+        &session_id,
+    );
+    assert_eq!(result.err(), Some(TwoPartyRSAError::ElGamalGroupTooSmall));
+    let result = PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+        &params_4096_group,
+        &party_one_first_message,
+        &party_two_first_message,
+        party_two_private,
+    );
+    assert_eq!(result.err(), Some(TwoPartyRSAError::ElGamalGroupTooSmall));
+}
+
 #[test]
 fn test_trial_division() {
+    let session_id = SessionId::random();
     // key setup first
     let (party_one_first_message, party_one_private) =
-        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two();
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
 
     let (party_two_first_message, party_two_private) =
-        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one();
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
 
     let party_one_key_setup_result =
         PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &HmrtParams::default(),
             &party_one_first_message,
             &party_two_first_message,
             party_one_private,
         );
     let party_two_key_setup_result =
         PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+            &HmrtParams::default(),
             &party_one_first_message,
             &party_two_first_message,
             party_two_private,
@@ -62,10 +333,10 @@ fn test_trial_division() {
     let party_two_keys = party_two_key_setup_result.unwrap();
 
     let (party_one_candidate_witness, party_one_td_first_message) =
-        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys);
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
 
     let (party_two_candidate_witness, party_two_td_first_message) =
-        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys);
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
 
     let party_one_first_message_res =
         PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
@@ -144,6 +415,7 @@ fn test_trial_division() {
             &party_one_c_alpha,
             &party_one_c_alpha_tilde,
             &party_two_td_third_message,
+            &alpha,
             &party_one_keys,
         );
 
@@ -151,25 +423,26 @@ fn test_trial_division() {
             &party_two_c_alpha,
             &party_two_c_alpha_tilde,
             &party_one_td_third_message,
+            &alpha,
             &party_two_keys,
         );
 
         assert!(party_one_td_result.is_ok());
         assert!(party_two_td_result.is_ok());
 
-        assert_eq!(party_one_td_result.unwrap(), party_two_td_result.unwrap());
+        let party_one_td_result = party_one_td_result.unwrap();
+        assert_eq!(party_one_td_result, party_two_td_result.unwrap());
         let prime = &party_one_candidate_witness.p_0 + &party_two_candidate_witness.p_1;
         let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
         println!(
             "is_prime(p'): {:?},result: {:?}, gcd(alpha,p'): {:?}",
             is_prime(&prime_scaled),
-            party_one_td_result.clone().unwrap(),
+            party_one_td_result,
             BigInt::gcd(&alpha, &prime_scaled)
         );
         assert!(
-            (party_one_td_result.unwrap() == true
-                && BigInt::gcd(&alpha, &prime_scaled) == BigInt::one())
-                || (party_one_td_result.unwrap() == false
+            (party_one_td_result == true && BigInt::gcd(&alpha, &prime_scaled) == BigInt::one())
+                || (party_one_td_result == false
                     && BigInt::gcd(&alpha, &prime_scaled) > BigInt::one())
         );
     }
@@ -178,21 +451,33 @@ fn test_trial_division() {
 
 #[test]
 fn test_trial_division_for_prime() {
+    let session_id = SessionId::random();
     // key setup first
     let (party_one_first_message, party_one_private) =
-        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two();
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
 
     let (party_two_first_message, party_two_private) =
-        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one();
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
 
     let party_one_key_setup_result =
         PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &HmrtParams::default(),
             &party_one_first_message,
             &party_two_first_message,
             party_one_private,
         );
     let party_two_key_setup_result =
         PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+            &HmrtParams::default(),
             &party_one_first_message,
             &party_two_first_message,
             party_two_private,
@@ -206,10 +491,12 @@ fn test_trial_division_for_prime() {
     let mut party_two_candidate_witness;
     let mut party_two_td_first_message;
     loop {
-        let res1 = PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys);
+        let res1 =
+            PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
         party_one_candidate_witness = res1.0;
         party_one_td_first_message = res1.1;
-        let res2 = PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys);
+        let res2 =
+            PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
         party_two_candidate_witness = res2.0;
         party_two_td_first_message = res2.1;
 
@@ -298,6 +585,7 @@ fn test_trial_division_for_prime() {
             &party_one_c_alpha,
             &party_one_c_alpha_tilde,
             &party_two_td_third_message,
+            &alpha,
             &party_one_keys,
         );
 
@@ -305,27 +593,1893 @@ fn test_trial_division_for_prime() {
             &party_two_c_alpha,
             &party_two_c_alpha_tilde,
             &party_one_td_third_message,
+            &alpha,
             &party_two_keys,
         );
 
         assert!(party_one_td_result.is_ok());
         assert!(party_two_td_result.is_ok());
 
-        assert_eq!(party_one_td_result.unwrap(), party_two_td_result.unwrap());
+        let party_one_td_result = party_one_td_result.unwrap();
+        assert_eq!(party_one_td_result, party_two_td_result.unwrap());
         let prime = &party_one_candidate_witness.p_0 + &party_two_candidate_witness.p_1;
         let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
         println!(
             "is_prime(p'): {:?},result: {:?}, gcd(alpha,p'): {:?}",
             is_prime(&prime_scaled),
-            party_one_td_result.clone().unwrap(),
+            party_one_td_result,
             BigInt::gcd(&alpha, &prime_scaled)
         );
         assert!(
-            (party_one_td_result.unwrap() == true
-                && BigInt::gcd(&alpha, &prime_scaled) == BigInt::one())
-                || (party_one_td_result.unwrap() == false
+            (party_one_td_result == true && BigInt::gcd(&alpha, &prime_scaled) == BigInt::one())
+                || (party_one_td_result == false
                     && BigInt::gcd(&alpha, &prime_scaled) > BigInt::one())
         );
     }
     assert!(false);
 }
+
+// Trial division with the bulletproofs range proofs inside the ModProofs
+#[test]
+fn test_trial_division_with_bulletproofs() {
+    let session_id = SessionId::random();
+    let params = HmrtParams {
+        mod_range_proof: ModRangeProofBackend::Bulletproof,
+        ..HmrtParams::default()
+    };
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(&params, &session_id)
+            .expect("");
+    let (party_two_first_message, party_two_private) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &params,
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
+    let party_one_keys =
+        PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &params,
+            &party_one_first_message,
+            &party_two_first_message,
+            party_one_private,
+        )
+        .expect("");
+    let party_two_keys =
+        PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+            &params,
+            &party_one_first_message,
+            &party_two_first_message,
+            party_two_private,
+        )
+        .expect("");
+
+    let (party_one_candidate_witness, party_one_td_first_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (party_two_candidate_witness, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+    let party_one_ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let prime = &party_one_candidate_witness.p_0 + &party_two_candidate_witness.p_1;
+    let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
+
+    for alpha in SMALL_PRIMES[1..6].iter().map(|alpha| BigInt::from(*alpha)) {
+        let party_one_td_second_message =
+            PartyOneCandidateGeneration::trial_division_prepare_c_alpha(
+                &alpha,
+                &party_one_keys,
+                &party_one_ciphertext_pair,
+                &party_one_candidate_witness,
+            )
+            .expect("");
+        let party_two_td_second_message =
+            PartyTwoCandidateGeneration::trial_division_prepare_c_alpha(
+                &alpha,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+                &party_two_candidate_witness,
+            )
+            .expect("");
+        match party_two_td_second_message.pi_mod {
+            TrialDivisionModProof::Bulletproof(_) => {}
+            _ => panic!("expected the bulletproofs backend"),
+        }
+
+        // a party expecting the tight range proofs rejects the message
+        let mut tight_keys = party_one_keys.clone();
+        tight_keys.params.mod_range_proof = ModRangeProofBackend::TightRangeProof;
+        let result =
+            PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+                &party_one_td_second_message,
+                &party_two_td_second_message,
+                &alpha,
+                &tight_keys,
+                &party_one_ciphertext_pair,
+            );
+        assert!(result.is_err());
+
+        let (party_one_td_third_message, party_one_c_alpha, party_one_c_alpha_tilde) =
+            PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+                &party_one_td_second_message,
+                &party_two_td_second_message,
+                &alpha,
+                &party_one_keys,
+                &party_one_ciphertext_pair,
+            )
+            .expect("");
+        let (party_two_td_third_message, party_two_c_alpha, party_two_c_alpha_tilde) =
+            PartyTwoCandidateGeneration::verify_party_one_second_message_and_partial_decrypt(
+                &party_one_td_second_message,
+                &party_two_td_second_message,
+                &alpha,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+            )
+            .expect("");
+
+        let party_one_td_result = PartyOneCandidateGeneration::verify_party_two_third_message_full_decrypt_and_conclude_division(
+            &party_one_c_alpha,
+            &party_one_c_alpha_tilde,
+            &party_two_td_third_message,
+            &alpha,
+            &party_one_keys,
+        )
+        .expect("");
+        let party_two_td_result = PartyTwoCandidateGeneration::verify_party_one_third_message_full_decrypt_and_conclude_division(
+            &party_two_c_alpha,
+            &party_two_c_alpha_tilde,
+            &party_one_td_third_message,
+            &alpha,
+            &party_two_keys,
+        )
+        .expect("");
+        assert_eq!(party_one_td_result, party_two_td_result);
+        assert_eq!(
+            party_one_td_result,
+            BigInt::gcd(&alpha, &prime_scaled) == BigInt::one()
+        );
+    }
+}
+
+#[test]
+fn test_batch_trial_division() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (party_one_candidate_witness, party_one_td_first_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (party_two_candidate_witness, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+
+    let party_one_ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+
+    let alpha_vec: Vec<BigInt> = SMALL_PRIMES[1..17]
+        .iter()
+        .map(|alpha| BigInt::from(*alpha))
+        .collect();
+
+    let party_one_td_second_message =
+        PartyOneCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+            &party_one_candidate_witness,
+        )
+        .expect("");
+    let party_two_td_second_message =
+        PartyTwoCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+            &party_two_candidate_witness,
+        )
+        .expect("");
+
+    let (party_one_td_third_message, party_one_c_alpha_vec, party_one_c_alpha_tilde_vec) =
+        PartyOneCandidateGeneration::verify_party_two_batch_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha_vec,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+        )
+        .expect("");
+    let (party_two_td_third_message, party_two_c_alpha_vec, party_two_c_alpha_tilde_vec) =
+        PartyTwoCandidateGeneration::verify_party_one_batch_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha_vec,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+        )
+        .expect("");
+
+    let party_one_td_result = PartyOneCandidateGeneration::verify_party_two_batch_third_message_full_decrypt_and_conclude_division(
+        &party_one_c_alpha_vec,
+        &party_one_c_alpha_tilde_vec,
+        &party_two_td_third_message,
+        &alpha_vec,
+        &party_one_keys,
+    )
+    .expect("");
+    let party_two_td_result = PartyTwoCandidateGeneration::verify_party_one_batch_third_message_full_decrypt_and_conclude_division(
+        &party_two_c_alpha_vec,
+        &party_two_c_alpha_tilde_vec,
+        &party_one_td_third_message,
+        &alpha_vec,
+        &party_two_keys,
+    )
+    .expect("");
+    assert_eq!(party_one_td_result, party_two_td_result);
+
+    // TEST ONLY: the candidate is computed in the clear
+    let prime = &party_one_candidate_witness.p_0 + &party_two_candidate_witness.p_1;
+    let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
+    for (alpha, coprime) in alpha_vec.iter().zip(&party_one_td_result) {
+        assert_eq!(*coprime, BigInt::gcd(alpha, &prime_scaled) == BigInt::one());
+    }
+    let first_divisor = alpha_vec
+        .iter()
+        .find(|alpha| BigInt::gcd(alpha, &prime_scaled) > BigInt::one())
+        .cloned();
+    assert_eq!(
+        first_dividing_prime(&alpha_vec, &party_one_td_result),
+        first_divisor
+    );
+}
+
+#[test]
+fn test_batch_trial_division_wrong_length() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let (
+        party_one_candidate_witness,
+        party_one_td_first_message,
+        party_two_candidate_witness,
+        party_two_td_first_message,
+    ) = simulate_prime_candidate(&party_one_keys, &party_two_keys);
+    let ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+
+    let alpha_vec: Vec<BigInt> = SMALL_PRIMES[1..5]
+        .iter()
+        .map(|alpha| BigInt::from(*alpha))
+        .collect();
+    let party_one_td_second_message =
+        PartyOneCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            &party_one_keys,
+            &ciphertext_pair,
+            &party_one_candidate_witness,
+        )
+        .expect("");
+    // party two drops the last alpha of the batch
+    let party_two_td_second_message =
+        PartyTwoCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec[..3],
+            &party_two_keys,
+            &ciphertext_pair,
+            &party_two_candidate_witness,
+        )
+        .expect("");
+
+    let result =
+        PartyOneCandidateGeneration::verify_party_two_batch_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha_vec,
+            &party_one_keys,
+            &ciphertext_pair,
+        );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_trial_division_blame() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (party_one_candidate_witness, party_one_td_first_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (party_two_candidate_witness, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+    let party_one_ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+
+    let alpha_vec: Vec<BigInt> = SMALL_PRIMES[1..3]
+        .iter()
+        .map(|alpha| BigInt::from(*alpha))
+        .collect();
+    let party_one_td_second_message =
+        PartyOneCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+            &party_one_candidate_witness,
+        )
+        .expect("");
+    let party_two_td_second_message =
+        PartyTwoCandidateGeneration::trial_division_prepare_c_alpha_batch(
+            &alpha_vec,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+            &party_two_candidate_witness,
+        )
+        .expect("");
+    let (_, party_one_c_alpha_vec, party_one_c_alpha_tilde_vec) =
+        PartyOneCandidateGeneration::verify_party_two_batch_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha_vec,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+        )
+        .expect("");
+    let (mut party_two_td_third_message, _, _) =
+        PartyTwoCandidateGeneration::verify_party_one_batch_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha_vec,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+        )
+        .expect("");
+    // party two randomizes c_alpha_tilde of the second alpha with an unproven exponent
+    let bad_third_message = &mut party_two_td_third_message.third_msg_vec[1];
+    bad_third_message.zero_test_alpha_tilde.pi_random =
+        bad_third_message.zero_test_alpha.pi_random.clone();
+    let bad_third_message = bad_third_message.clone();
+
+    let result = PartyOneCandidateGeneration::verify_party_two_batch_third_message_full_decrypt_and_conclude_division(
+        &party_one_c_alpha_vec,
+        &party_one_c_alpha_tilde_vec,
+        &party_two_td_third_message,
+        &alpha_vec,
+        &party_one_keys,
+    );
+    let err = result.err().expect("");
+    // the DDH proof error is kept as the source of the abort
+    assert_eq!(
+        err.source().map(|source| source.to_string()),
+        Some(ProofError::DHProofError.to_string())
+    );
+    match Some(err) {
+        Some(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.round, ProtocolRound::TrialDivision);
+            assert_eq!(abort.proof, "zero_test_alpha_tilde.pi_random");
+            assert_eq!(abort.alpha, Some(alpha_vec[1].clone()));
+            assert_eq!(abort.proof_error, Some(ProofError::DHProofError));
+            assert_eq!(
+                abort.source().map(|source| source.to_string()),
+                Some(ProofError::DHProofError.to_string())
+            );
+            assert_eq!(
+                abort.message,
+                serde_json::to_string(&bad_third_message).unwrap()
+            );
+        }
+        err => panic!("expected a protocol abort, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_malformed_messages_are_rejected() {
+    let session_id = SessionId::random();
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
+    let (mut party_two_first_message, _) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
+    // n^2 is sent along with n, a wrong value would be used as a modulus
+    party_two_first_message.ek.nn = BigInt::zero();
+    let result = PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+        &HmrtParams::default(),
+        &party_one_first_message,
+        &party_two_first_message,
+        party_one_private,
+    );
+    assert_eq!(result.err(), Some(TwoPartyRSAError::InvalidPaillierKey));
+
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let (party_one_candidate_witness, party_one_td_first_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (party_two_candidate_witness, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+    let party_one_ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+
+    let alpha = BigInt::from(SMALL_PRIMES[1]);
+    let party_one_td_second_message = PartyOneCandidateGeneration::trial_division_prepare_c_alpha(
+        &alpha,
+        &party_one_keys,
+        &party_one_ciphertext_pair,
+        &party_one_candidate_witness,
+    )
+    .expect("");
+    let party_two_td_second_message = PartyTwoCandidateGeneration::trial_division_prepare_c_alpha(
+        &alpha,
+        &party_two_keys,
+        &party_two_ciphertext_pair,
+        &party_two_candidate_witness,
+    )
+    .expect("");
+
+    // the ciphertext carries its own group, here one with a zero modulus
+    let mut bad_second_message = party_two_td_second_message.clone();
+    bad_second_message.c_1_alpha.pp.p = BigInt::zero();
+    let result = PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+        &party_one_td_second_message,
+        &bad_second_message,
+        &alpha,
+        &party_one_keys,
+        &party_one_ciphertext_pair,
+    );
+    assert!(result.is_err());
+
+    // a range proof for the quotient with fewer bits than its bound has
+    let mut bad_second_message = party_two_td_second_message.clone();
+    if let TrialDivisionModProof::TightRangeProof(pi_mod) = &mut bad_second_message.pi_mod {
+        pi_mod.range_proof2.bit_proofs.pop();
+    }
+    let result = PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+        &party_one_td_second_message,
+        &bad_second_message,
+        &alpha,
+        &party_one_keys,
+        &party_one_ciphertext_pair,
+    );
+    assert!(result.is_err());
+
+    // a range proof for the residue with fewer bits than alpha has
+    let mut bad_second_message = party_two_td_second_message.clone();
+    if let TrialDivisionModProof::TightRangeProof(pi_mod) = &mut bad_second_message.pi_mod {
+        pi_mod.range_proof1.bit_proofs.pop();
+    }
+    let result = PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+        &party_one_td_second_message,
+        &bad_second_message,
+        &alpha,
+        &party_one_keys,
+        &party_one_ciphertext_pair,
+    );
+    assert!(result.is_err());
+
+    let (_, party_one_c_alpha, party_one_c_alpha_tilde) =
+        PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+        )
+        .expect("");
+    let (mut party_two_td_third_message, _, _) =
+        PartyTwoCandidateGeneration::verify_party_one_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+        )
+        .expect("");
+    party_two_td_third_message.zero_test_alpha.c_random.pp.p = BigInt::zero();
+    let result =
+        PartyOneCandidateGeneration::verify_party_two_third_message_full_decrypt_and_conclude_division(
+            &party_one_c_alpha,
+            &party_one_c_alpha_tilde,
+            &party_two_td_third_message,
+            &alpha,
+            &party_one_keys,
+        );
+    match result {
+        Err(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.round, ProtocolRound::TrialDivision);
+            assert_eq!(abort.proof, "zero_test_alpha.c_random");
+        }
+        result => panic!("expected a protocol abort, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_small_subgroup_elements_are_rejected() {
+    let session_id = SessionId::random();
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
+    let (mut party_two_first_message, party_two_private) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
+    let pp = party_one_first_message.pk.pp.clone();
+    let p_minus_one = &pp.p - BigInt::one();
+
+    // h * (p - 1) has a component of order 2
+    let mut bad_party_one_first_message = party_one_first_message.clone();
+    bad_party_one_first_message.pk.h =
+        BigInt::mod_mul(&party_one_first_message.pk.h, &p_minus_one, &pp.p);
+    let result = PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+        &HmrtParams::default(),
+        &bad_party_one_first_message,
+        &party_two_first_message,
+        party_two_private,
+    );
+    assert_eq!(result.err(), Some(TwoPartyRSAError::InvalidElGamalKey));
+
+    party_two_first_message.pk.h = p_minus_one.clone();
+    let result = PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+        &HmrtParams::default(),
+        &party_one_first_message,
+        &party_two_first_message,
+        party_one_private,
+    );
+    assert_eq!(result.err(), Some(TwoPartyRSAError::InvalidElGamalKey));
+
+    // a partial decryption outside of the subgroup fails the proof of decryption
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let (party_one_candidate_witness, party_one_td_first_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (party_two_candidate_witness, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+    let party_one_ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let alpha = BigInt::from(SMALL_PRIMES[1]);
+    let party_one_td_second_message = PartyOneCandidateGeneration::trial_division_prepare_c_alpha(
+        &alpha,
+        &party_one_keys,
+        &party_one_ciphertext_pair,
+        &party_one_candidate_witness,
+    )
+    .expect("");
+    let party_two_td_second_message = PartyTwoCandidateGeneration::trial_division_prepare_c_alpha(
+        &alpha,
+        &party_two_keys,
+        &party_two_ciphertext_pair,
+        &party_two_candidate_witness,
+    )
+    .expect("");
+    let (_, party_one_c_alpha, party_one_c_alpha_tilde) =
+        PartyOneCandidateGeneration::verify_party_two_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+        )
+        .expect("");
+    let (mut party_two_td_third_message, _, _) =
+        PartyTwoCandidateGeneration::verify_party_one_second_message_and_partial_decrypt(
+            &party_one_td_second_message,
+            &party_two_td_second_message,
+            &alpha,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+        )
+        .expect("");
+    party_two_td_third_message.zero_test_alpha.partial_dec = BigInt::mod_mul(
+        &party_two_td_third_message.zero_test_alpha.partial_dec,
+        &p_minus_one,
+        &pp.p,
+    );
+    let result =
+        PartyOneCandidateGeneration::verify_party_two_third_message_full_decrypt_and_conclude_division(
+            &party_one_c_alpha,
+            &party_one_c_alpha_tilde,
+            &party_two_td_third_message,
+            &alpha,
+            &party_one_keys,
+        );
+    match result {
+        Err(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.proof, "zero_test_alpha.pi_dec");
+            // the decryption share is outside of the subgroup, the reason is kept
+            assert_eq!(
+                abort.proof_error,
+                Some(ProofError::SchemeError(
+                    HomomorphicSchemeError::NotInSubgroup
+                ))
+            );
+        }
+        result => panic!("expected a protocol abort, got {:?}", result),
+    }
+}
+
+#[test]
+fn test_secrets_are_redacted_and_zeroized() {
+    let (party_one_keys, _) = simulate_key_setup();
+    assert!(format!("{:?}", party_one_keys).contains("private: PartyOnePrivate { dk: [redacted]"));
+
+    let (mut witness, _) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let debug_output = format!("{:?}", witness);
+    assert_eq!(
+        debug_output,
+        "PartyOneCandidateWitness { p_0: [redacted], r_0: [redacted] }"
+    );
+
+    witness.zeroize();
+    assert_eq!(witness.p_0, BigInt::zero());
+    assert_eq!(witness.r_0, BigInt::zero());
+}
+
+#[test]
+fn test_distributed_sieving() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let party_one_sieving_message =
+        PartyOneCandidateGeneration::sample_multiplicative_share_and_sieving_message(
+            &party_one_keys,
+        )
+        .expect("");
+    let (party_two_sieving_message, party_two_candidate_witness, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::verify_party_one_sieving_message_and_generate_sieved_candidate(
+            &party_two_keys,
+            &party_one_sieving_message,
+        )
+        .expect("");
+    let (party_one_candidate_witness, party_one_td_first_message, party_one_sieving_second_message) =
+        PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+            &party_one_keys,
+            &party_one_sieving_message,
+            &party_two_sieving_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    PartyTwoCandidateGeneration::verify_party_one_sieved_candidate(
+        &party_two_keys,
+        &party_two_sieving_message,
+        &party_one_td_first_message,
+        &party_one_sieving_second_message,
+    )
+    .expect("");
+
+    // the sieved candidate goes through the rest of candidate generation unchanged
+    let party_one_ciphertext_pair =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_td_first_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    assert_eq!(party_one_ciphertext_pair, party_two_ciphertext_pair);
+
+    // TEST ONLY: the candidate is computed in the clear
+    let (m, first_unsieved_index) = sieving_modulus(&HmrtParams::default());
+    assert!(first_unsieved_index > 1);
+    assert!(m.bit_length() <= HmrtParams::default().candidate_bit_length / 4);
+    let prime = &party_one_candidate_witness.p_0 + &party_two_candidate_witness.p_1;
+    let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
+    assert_eq!(BigInt::gcd(&m, &prime_scaled), BigInt::one());
+}
+
+#[test]
+fn test_distributed_sieving_bad_conversion() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let party_one_sieving_message =
+        PartyOneCandidateGeneration::sample_multiplicative_share_and_sieving_message(
+            &party_one_keys,
+        )
+        .expect("");
+    let (mut party_two_sieving_message, _, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::verify_party_one_sieving_message_and_generate_sieved_candidate(
+            &party_two_keys,
+            &party_one_sieving_message,
+        )
+        .expect("");
+    // party two replaces Enc(a * b + s) with a ciphertext of a value it knows
+    party_two_sieving_message.c_ab = party_two_sieving_message.c_s.clone();
+
+    let result =
+        PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+            &party_one_keys,
+            &party_one_sieving_message,
+            &party_two_sieving_message,
+            &party_two_td_first_message,
+        );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_distributed_sieving_bad_sieved_share() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let party_one_sieving_message =
+        PartyOneCandidateGeneration::sample_multiplicative_share_and_sieving_message(
+            &party_one_keys,
+        )
+        .expect("");
+    let (party_two_sieving_message, _, party_two_td_first_message) =
+        PartyTwoCandidateGeneration::verify_party_one_sieving_message_and_generate_sieved_candidate(
+            &party_two_keys,
+            &party_one_sieving_message,
+        )
+        .expect("");
+
+    // party two sends the share of an unsieved candidate instead of its sieved share
+    let (_, party_two_unsieved_first_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+    let result =
+        PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+            &party_one_keys,
+            &party_one_sieving_message,
+            &party_two_sieving_message,
+            &party_two_unsieved_first_message,
+        );
+    match result.err() {
+        Some(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.round, ProtocolRound::CandidateGeneration);
+            assert_eq!(abort.proof, "pi_sieved");
+        }
+        err => panic!("expected a protocol abort, got {:?}", err),
+    }
+
+    // and so does party one
+    let (_, _, party_one_sieving_second_message) =
+        PartyOneCandidateGeneration::verify_party_two_sieving_message_and_generate_sieved_candidate(
+            &party_one_keys,
+            &party_one_sieving_message,
+            &party_two_sieving_message,
+            &party_two_td_first_message,
+        )
+        .expect("");
+    let (_, party_one_unsieved_first_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let result = PartyTwoCandidateGeneration::verify_party_one_sieved_candidate(
+        &party_two_keys,
+        &party_two_sieving_message,
+        &party_one_unsieved_first_message,
+        &party_one_sieving_second_message,
+    );
+    match result.err() {
+        Some(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.round, ProtocolRound::CandidateGeneration);
+            assert_eq!(abort.proof, "pi_sieved");
+        }
+        err => panic!("expected a protocol abort, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_paillier_trial_division() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (party_one_witness, party_one_first_message) =
+        PartyOnePaillierCandidateGeneration::generate_shares_of_candidate(&party_one_keys)
+            .expect("");
+    let (party_two_witness, party_two_first_message) =
+        PartyTwoPaillierCandidateGeneration::generate_shares_of_candidate(&party_two_keys)
+            .expect("");
+    let party_one_ciphertext_pair =
+        PartyOnePaillierCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_first_message,
+            &party_two_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoPaillierCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_first_message,
+            &party_two_first_message,
+        )
+        .expect("");
+    assert_eq!(party_one_ciphertext_pair, party_two_ciphertext_pair);
+
+    // TEST ONLY: the candidate is computed in the clear
+    let prime = &party_one_witness.p_0 + &party_two_witness.p_1;
+    let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
+
+    for i in 1..10 {
+        let alpha = BigInt::from(SMALL_PRIMES[i]);
+        let party_one_second_message =
+            PartyOnePaillierCandidateGeneration::trial_division_prepare_c_alpha(
+                &alpha,
+                &party_one_keys,
+                &party_one_ciphertext_pair,
+                &party_one_witness,
+            )
+            .expect("");
+        let party_two_second_message =
+            PartyTwoPaillierCandidateGeneration::trial_division_prepare_c_alpha(
+                &alpha,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+                &party_two_witness,
+            )
+            .expect("");
+        let party_one_third_message =
+            PartyOnePaillierCandidateGeneration::verify_party_two_second_message_and_mask_residues(
+                &party_one_second_message,
+                &party_two_second_message,
+                &alpha,
+                &party_one_keys,
+                &party_one_ciphertext_pair,
+                &party_one_witness,
+            )
+            .expect("");
+        let party_two_third_message =
+            PartyTwoPaillierCandidateGeneration::verify_party_one_second_message_and_mask_residues(
+                &party_two_second_message,
+                &party_one_second_message,
+                &alpha,
+                &party_two_keys,
+                &party_two_ciphertext_pair,
+                &party_two_witness,
+            )
+            .expect("");
+        let party_one_result =
+            PartyOnePaillierCandidateGeneration::verify_party_two_third_message_and_conclude_division(
+                &party_one_second_message,
+                &party_two_second_message,
+                &party_two_third_message,
+                &alpha,
+                &party_one_keys,
+            )
+            .expect("");
+        let party_two_result =
+            PartyTwoPaillierCandidateGeneration::verify_party_one_third_message_and_conclude_division(
+                &party_two_second_message,
+                &party_one_second_message,
+                &party_one_third_message,
+                &alpha,
+                &party_two_keys,
+            )
+            .expect("");
+        assert_eq!(party_one_result, party_two_result);
+        assert_eq!(
+            party_one_result,
+            prime_scaled.mod_floor(&alpha) != BigInt::zero()
+        );
+    }
+}
+
+#[test]
+fn test_paillier_trial_division_bad_zero_test() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (party_one_witness, party_one_first_message) =
+        PartyOnePaillierCandidateGeneration::generate_shares_of_candidate(&party_one_keys)
+            .expect("");
+    let (party_two_witness, party_two_first_message) =
+        PartyTwoPaillierCandidateGeneration::generate_shares_of_candidate(&party_two_keys)
+            .expect("");
+    let party_one_ciphertext_pair =
+        PartyOnePaillierCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &party_one_first_message,
+            &party_two_first_message,
+        )
+        .expect("");
+    let party_two_ciphertext_pair =
+        PartyTwoPaillierCandidateGeneration::verify_party_one_first_message_and_normalize_ciphertexts(
+            &party_two_keys,
+            &party_one_first_message,
+            &party_two_first_message,
+        )
+        .expect("");
+
+    let alpha = BigInt::from(SMALL_PRIMES[1]);
+    let party_one_second_message =
+        PartyOnePaillierCandidateGeneration::trial_division_prepare_c_alpha(
+            &alpha,
+            &party_one_keys,
+            &party_one_ciphertext_pair,
+            &party_one_witness,
+        )
+        .expect("");
+    let party_two_second_message =
+        PartyTwoPaillierCandidateGeneration::trial_division_prepare_c_alpha(
+            &alpha,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+            &party_two_witness,
+        )
+        .expect("");
+    let mut party_two_third_message =
+        PartyTwoPaillierCandidateGeneration::verify_party_one_second_message_and_mask_residues(
+            &party_two_second_message,
+            &party_one_second_message,
+            &alpha,
+            &party_two_keys,
+            &party_two_ciphertext_pair,
+            &party_two_witness,
+        )
+        .expect("");
+    // party two replaces the masked sum with an encryption of zero
+    party_two_third_message.zero_test_alpha.c_masked = BigInt::one();
+
+    let result =
+        PartyOnePaillierCandidateGeneration::verify_party_two_third_message_and_conclude_division(
+            &party_one_second_message,
+            &party_two_second_message,
+            &party_two_third_message,
+            &alpha,
+            &party_one_keys,
+        );
+    assert!(result.is_err());
+}
+
+fn simulate_key_setup() -> (PartyOneKeySetup, PartyTwoKeySetup) {
+    let session_id = SessionId::random();
+    let (party_one_first_message, party_one_private) =
+        PartyOneKeySetup::gen_local_keys_and_first_message_to_party_two(
+            &HmrtParams::default(),
+            &session_id,
+        )
+        .expect("");
+    let (party_two_first_message, party_two_private) =
+        PartyTwoKeySetup::gen_local_keys_and_first_message_to_party_one(
+            &HmrtParams::default(),
+            party_one_first_message.group,
+            &session_id,
+        )
+        .expect("");
+
+    let party_one_keys =
+        PartyOneKeySetup::verify_party_two_first_message_and_output_party_one_keys(
+            &HmrtParams::default(),
+            &party_one_first_message,
+            &party_two_first_message,
+            party_one_private,
+        )
+        .expect("");
+    let party_two_keys =
+        PartyTwoKeySetup::verify_party_one_first_message_and_output_party_two_keys(
+            &HmrtParams::default(),
+            &party_one_first_message,
+            &party_two_first_message,
+            party_two_private,
+        )
+        .expect("");
+    (party_one_keys, party_two_keys)
+}
+
+// TEST ONLY: samples shares until the candidate 4(p_0 + p_1) + 3 is prime, skipping trial division
+fn simulate_prime_candidate(
+    party_one_keys: &PartyOneKeySetup,
+    party_two_keys: &PartyTwoKeySetup,
+) -> (
+    PartyOneCandidateWitness,
+    PartyOneCandidateGenerationFirstMsg,
+    PartyTwoCandidateWitness,
+    PartyTwoCandidateGenerationFirstMsg,
+) {
+    loop {
+        let (party_one_witness, party_one_first_message) =
+            PartyOneCandidateGeneration::generate_shares_of_candidate(party_one_keys).expect("");
+        let (party_two_witness, party_two_first_message) =
+            PartyTwoCandidateGeneration::generate_shares_of_candidate(party_two_keys).expect("");
+        let prime = &party_one_witness.p_0 + &party_two_witness.p_1;
+        let prime_scaled = prime * BigInt::from(4) + BigInt::from(3);
+        if is_prime(&prime_scaled) {
+            return (
+                party_one_witness,
+                party_one_first_message,
+                party_two_witness,
+                party_two_first_message,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_biprimality() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (p_party_one_witness, p_party_one_message, p_party_two_witness, p_party_two_message) =
+        simulate_prime_candidate(&party_one_keys, &party_two_keys);
+    let (q_party_one_witness, q_party_one_message, q_party_two_witness, q_party_two_message) =
+        simulate_prime_candidate(&party_one_keys, &party_two_keys);
+
+    // TEST ONLY: N is computed in the clear
+    let p =
+        (&p_party_one_witness.p_0 + &p_party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+    let q =
+        (&q_party_one_witness.p_0 + &q_party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+    let n = p * q;
+
+    let party_one_biprimality_message =
+        PartyOneBiprimalityTest::compute_exponentiations_and_first_message(
+            &n,
+            &party_one_keys,
+            &p_party_one_witness,
+            &q_party_one_witness,
+            &p_party_one_message,
+            &q_party_one_message,
+        )
+        .expect("");
+    let party_two_biprimality_message =
+        PartyTwoBiprimalityTest::compute_exponentiations_and_first_message(
+            &n,
+            &party_two_keys,
+            &p_party_two_witness,
+            &q_party_two_witness,
+            &p_party_two_message,
+            &q_party_two_message,
+        )
+        .expect("");
+
+    let party_one_result =
+        PartyOneBiprimalityTest::verify_party_two_first_message_and_conclude_biprimality(
+            &n,
+            &party_one_keys,
+            &p_party_two_message,
+            &q_party_two_message,
+            &party_one_biprimality_message,
+            &party_two_biprimality_message,
+        );
+    let party_two_result =
+        PartyTwoBiprimalityTest::verify_party_one_first_message_and_conclude_biprimality(
+            &n,
+            &party_two_keys,
+            &p_party_one_message,
+            &q_party_one_message,
+            &party_one_biprimality_message,
+            &party_two_biprimality_message,
+        );
+
+    assert_eq!(party_one_result, Ok(true));
+    assert_eq!(party_two_result, Ok(true));
+}
+
+#[test]
+fn test_joint_modulus() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (p_party_one_witness, p_party_one_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (p_party_two_witness, p_party_two_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+    let (q_party_one_witness, q_party_one_message) =
+        PartyOneCandidateGeneration::generate_shares_of_candidate(&party_one_keys).expect("");
+    let (q_party_two_witness, q_party_two_message) =
+        PartyTwoCandidateGeneration::generate_shares_of_candidate(&party_two_keys).expect("");
+
+    let p_ciphertexts =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &p_party_one_message,
+            &p_party_two_message,
+        )
+        .expect("");
+    let q_ciphertexts =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &q_party_one_message,
+            &q_party_two_message,
+        )
+        .expect("");
+
+    let (party_one_jm_witness, party_one_jm_first_message) =
+        PartyOneJointModulus::encrypt_shares_and_first_message(
+            &party_one_keys,
+            &p_party_one_witness,
+            &q_party_one_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+        )
+        .expect("");
+    let (party_two_jm_witness, party_two_jm_first_message) =
+        PartyTwoJointModulus::encrypt_shares_and_first_message(
+            &party_two_keys,
+            &p_party_two_witness,
+            &q_party_two_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+        )
+        .expect("");
+
+    let party_one_jm_second_message =
+        PartyOneJointModulus::verify_party_two_first_message_and_compute_cross_terms(
+            &party_one_keys,
+            &p_party_one_witness,
+            &q_party_one_witness,
+            &party_one_jm_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+            &party_one_jm_first_message,
+            &party_two_jm_first_message,
+        )
+        .expect("");
+    let party_two_jm_second_message =
+        PartyTwoJointModulus::verify_party_one_first_message_and_compute_cross_terms(
+            &party_two_keys,
+            &p_party_two_witness,
+            &q_party_two_witness,
+            &party_two_jm_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+            &party_two_jm_first_message,
+            &party_one_jm_first_message,
+        )
+        .expect("");
+
+    let party_one_jm_third_message =
+        PartyOneJointModulus::verify_party_two_second_message_and_decrypt_modulus(
+            &party_one_keys,
+            &p_ciphertexts,
+            &q_ciphertexts,
+            &party_one_jm_first_message,
+            &party_two_jm_first_message,
+            &party_two_jm_second_message,
+        )
+        .expect("");
+    let party_two_jm_third_message =
+        PartyTwoJointModulus::verify_party_one_second_message_and_decrypt_modulus(
+            &party_two_keys,
+            &p_ciphertexts,
+            &q_ciphertexts,
+            &party_two_jm_first_message,
+            &party_one_jm_first_message,
+            &party_one_jm_second_message,
+        )
+        .expect("");
+
+    let party_one_modulus =
+        PartyOneJointModulus::verify_party_two_third_message_and_output_joint_modulus(
+            &party_one_keys,
+            &party_one_jm_second_message,
+            &party_one_jm_third_message,
+            &party_two_jm_first_message,
+            &party_two_jm_third_message,
+        )
+        .expect("");
+    let party_two_modulus =
+        PartyTwoJointModulus::verify_party_one_third_message_and_output_joint_modulus(
+            &party_two_keys,
+            &party_two_jm_second_message,
+            &party_two_jm_third_message,
+            &party_one_jm_first_message,
+            &party_one_jm_third_message,
+        )
+        .expect("");
+
+    let p =
+        (&p_party_one_witness.p_0 + &p_party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+    let q =
+        (&q_party_one_witness.p_0 + &q_party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+    assert_eq!(party_one_modulus, party_two_modulus);
+    assert_eq!(party_one_modulus.n, p * q);
+}
+
+#[test]
+fn test_private_exponent_share() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+
+    let (p_party_one_witness, p_party_one_message, p_party_two_witness, p_party_two_message) =
+        simulate_prime_candidate(&party_one_keys, &party_two_keys);
+    let (q_party_one_witness, q_party_one_message, q_party_two_witness, q_party_two_message) =
+        simulate_prime_candidate(&party_one_keys, &party_two_keys);
+
+    let p_ciphertexts =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &p_party_one_message,
+            &p_party_two_message,
+        )
+        .expect("");
+    let q_ciphertexts =
+        PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+            &party_one_keys,
+            &q_party_one_message,
+            &q_party_two_message,
+        )
+        .expect("");
+
+    // TEST ONLY: N is computed in the clear
+    let p =
+        (&p_party_one_witness.p_0 + &p_party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+    let q =
+        (&q_party_one_witness.p_0 + &q_party_two_witness.p_1) * BigInt::from(4) + BigInt::from(3);
+    let joint_modulus = JointModulus { n: &p * &q };
+    let phi = (&p - BigInt::one()) * (&q - BigInt::one());
+
+    let e = BigInt::from(DEFAULT_PUBLIC_EXPONENT);
+    let party_one_first_message =
+        PartyOnePrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+            &e,
+            &party_one_keys,
+            &p_party_one_witness,
+            &q_party_one_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+        )
+        .expect("");
+    let party_two_first_message =
+        PartyTwoPrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+            &e,
+            &party_two_keys,
+            &p_party_two_witness,
+            &q_party_two_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+        )
+        .expect("");
+
+    let party_one_result =
+        PartyOnePrivateExponentShare::verify_party_two_first_message_and_compute_exponent_share(
+            &e,
+            &joint_modulus,
+            &party_one_keys,
+            &p_party_one_witness,
+            &q_party_one_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+            &party_one_first_message,
+            &party_two_first_message,
+        );
+    let party_two_result =
+        PartyTwoPrivateExponentShare::verify_party_one_first_message_and_compute_exponent_share(
+            &e,
+            &joint_modulus,
+            &party_two_keys,
+            &p_party_two_witness,
+            &q_party_two_witness,
+            &p_ciphertexts,
+            &q_ciphertexts,
+            &party_two_first_message,
+            &party_one_first_message,
+        );
+
+    // e | phi(N) happens with probability 1/e: the candidates must be discarded
+    if phi.mod_floor(&e) == BigInt::zero() {
+        assert_eq!(
+            party_one_result.err(),
+            Some(TwoPartyRSAError::InvalidPublicExponent)
+        );
+        return;
+    }
+    let (party_one_witness, party_one_second_message) = party_one_result.expect("");
+    let (party_two_witness, party_two_second_message) = party_two_result.expect("");
+
+    let party_one_key_share =
+        PartyOnePrivateExponentShare::verify_party_two_second_message_and_output_key_share(
+            &e,
+            &joint_modulus,
+            party_one_witness,
+            &party_one_second_message,
+            &party_two_second_message,
+        )
+        .expect("");
+    let party_two_key_share =
+        PartyTwoPrivateExponentShare::verify_party_one_second_message_and_output_key_share(
+            &e,
+            &joint_modulus,
+            party_two_witness,
+            &party_two_second_message,
+            &party_one_second_message,
+        )
+        .expect("");
+
+    assert_eq!(party_one_key_share.n, joint_modulus.n);
+    assert_eq!(party_one_key_share.local_vk, party_two_key_share.remote_vk);
+    assert_eq!(party_two_key_share.local_vk, party_one_key_share.remote_vk);
+}
+
+// TEST ONLY: runs the private exponent stage on prime candidates with N computed in the clear,
+// resampling the candidates when e | phi(N)
+fn simulate_rsa_key_shares(
+    party_one_keys: &PartyOneKeySetup,
+    party_two_keys: &PartyTwoKeySetup,
+) -> (PartyOneRSAKeyShare, PartyTwoRSAKeyShare) {
+    let e = BigInt::from(DEFAULT_PUBLIC_EXPONENT);
+    loop {
+        let (p_party_one_witness, p_party_one_message, p_party_two_witness, p_party_two_message) =
+            simulate_prime_candidate(party_one_keys, party_two_keys);
+        let (q_party_one_witness, q_party_one_message, q_party_two_witness, q_party_two_message) =
+            simulate_prime_candidate(party_one_keys, party_two_keys);
+        let p_ciphertexts =
+            PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                party_one_keys,
+                &p_party_one_message,
+                &p_party_two_message,
+            )
+            .expect("");
+        let q_ciphertexts =
+            PartyOneCandidateGeneration::verify_party_two_first_message_and_normalize_ciphertexts(
+                party_one_keys,
+                &q_party_one_message,
+                &q_party_two_message,
+            )
+            .expect("");
+        let p = (&p_party_one_witness.p_0 + &p_party_two_witness.p_1) * BigInt::from(4)
+            + BigInt::from(3);
+        let q = (&q_party_one_witness.p_0 + &q_party_two_witness.p_1) * BigInt::from(4)
+            + BigInt::from(3);
+        let joint_modulus = JointModulus { n: p * q };
+
+        let party_one_first_message =
+            PartyOnePrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+                &e,
+                party_one_keys,
+                &p_party_one_witness,
+                &q_party_one_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+            )
+            .expect("");
+        let party_two_first_message =
+            PartyTwoPrivateExponentShare::reveal_phi_share_mod_e_and_first_message(
+                &e,
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+            )
+            .expect("");
+        let party_one_result =
+            PartyOnePrivateExponentShare::verify_party_two_first_message_and_compute_exponent_share(
+                &e,
+                &joint_modulus,
+                party_one_keys,
+                &p_party_one_witness,
+                &q_party_one_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_one_first_message,
+                &party_two_first_message,
+            );
+        let party_two_result =
+            PartyTwoPrivateExponentShare::verify_party_one_first_message_and_compute_exponent_share(
+                &e,
+                &joint_modulus,
+                party_two_keys,
+                &p_party_two_witness,
+                &q_party_two_witness,
+                &p_ciphertexts,
+                &q_ciphertexts,
+                &party_two_first_message,
+                &party_one_first_message,
+            );
+        if let (
+            Ok((party_one_witness, party_one_second_message)),
+            Ok((party_two_witness, party_two_second_message)),
+        ) = (party_one_result, party_two_result)
+        {
+            let party_one_key_share =
+                PartyOnePrivateExponentShare::verify_party_two_second_message_and_output_key_share(
+                    &e,
+                    &joint_modulus,
+                    party_one_witness,
+                    &party_one_second_message,
+                    &party_two_second_message,
+                )
+                .expect("");
+            let party_two_key_share =
+                PartyTwoPrivateExponentShare::verify_party_one_second_message_and_output_key_share(
+                    &e,
+                    &joint_modulus,
+                    party_two_witness,
+                    &party_two_second_message,
+                    &party_one_second_message,
+                )
+                .expect("");
+            return (party_one_key_share, party_two_key_share);
+        }
+    }
+}
+
+#[test]
+fn test_sign() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let (party_one_key_share, party_two_key_share) =
+        simulate_rsa_key_shares(&party_one_keys, &party_two_keys);
+    let message = b"vice city";
+
+    for encoding in &[SignatureEncoding::Pkcs1v15, SignatureEncoding::Pss] {
+        let party_one_first_message =
+            PartyOneSign::encode_message_and_partial_sign(&party_one_key_share, message, encoding)
+                .expect("");
+        let party_two_first_message =
+            PartyTwoSign::verify_party_one_first_message_and_partial_sign(
+                &party_two_key_share,
+                message,
+                encoding,
+                &party_one_first_message,
+            )
+            .expect("");
+
+        let party_one_signature =
+            PartyOneSign::verify_party_two_first_message_and_output_signature(
+                &party_one_key_share,
+                message,
+                encoding,
+                &party_one_first_message,
+                &party_two_first_message,
+            )
+            .expect("");
+        let party_two_signature = PartyTwoSign::combine_partial_signatures_and_output_signature(
+            &party_two_key_share,
+            message,
+            encoding,
+            &party_one_first_message,
+            &party_two_first_message,
+        )
+        .expect("");
+
+        assert_eq!(party_one_signature, party_two_signature);
+        assert!(party_one_signature
+            .verify(
+                &party_one_key_share.n,
+                &party_one_key_share.e,
+                message,
+                encoding
+            )
+            .is_ok());
+        assert!(party_one_signature
+            .verify(
+                &party_one_key_share.n,
+                &party_one_key_share.e,
+                b"other message",
+                encoding
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn test_encrypted_export_and_import() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let password = StorageKey::Password(b"correct horse battery staple");
+    let key = StorageKey::Key(&[42u8; 32]);
+
+    let sealed = party_one_keys.export_encrypted(&password).expect("");
+    let imported = PartyOneKeySetup::import_encrypted(&sealed, &password).expect("");
+    assert_eq!(
+        serde_json::to_string(&imported).unwrap(),
+        serde_json::to_string(&party_one_keys).unwrap()
+    );
+    // the envelope does not leak the private keys
+    let private = serde_json::to_value(&party_one_keys).unwrap()["private"].to_string();
+    assert!(!serde_json::to_string(&sealed).unwrap().contains(&private));
+    assert_eq!(
+        PartyOneKeySetup::import_encrypted(&sealed, &StorageKey::Password(b"wrong")).unwrap_err(),
+        SealedShareError::DecryptionFailed
+    );
+    assert_eq!(
+        PartyTwoKeySetup::import_encrypted(&sealed, &password).unwrap_err(),
+        SealedShareError::WrongContent
+    );
+
+    let sealed = party_two_keys.export_encrypted(&key).expect("");
+    let imported = PartyTwoKeySetup::import_encrypted(&sealed, &key).expect("");
+    assert_eq!(
+        serde_json::to_string(&imported).unwrap(),
+        serde_json::to_string(&party_two_keys).unwrap()
+    );
+
+    let (party_one_key_share, party_two_key_share) =
+        simulate_rsa_key_shares(&party_one_keys, &party_two_keys);
+    let sealed = party_one_key_share.export_encrypted(&password).expect("");
+    let imported = PartyOneRSAKeyShare::import_encrypted(&sealed, &password).expect("");
+    assert_eq!(
+        serde_json::to_string(&imported).unwrap(),
+        serde_json::to_string(&party_one_key_share).unwrap()
+    );
+    let sealed = party_two_key_share.export_encrypted(&key).expect("");
+    let imported = PartyTwoRSAKeyShare::import_encrypted(&sealed, &key).expect("");
+    assert_eq!(
+        serde_json::to_string(&imported).unwrap(),
+        serde_json::to_string(&party_two_key_share).unwrap()
+    );
+    assert_eq!(
+        PartyTwoRSAKeyShare::import_encrypted(&sealed, &StorageKey::Key(&[43u8; 32])).unwrap_err(),
+        SealedShareError::DecryptionFailed
+    );
+    assert_eq!(
+        PartyTwoRSAKeyShare::import_encrypted(&sealed, &password).unwrap_err(),
+        SealedShareError::KeyTypeMismatch
+    );
+}
+
+#[test]
+fn test_sign_bad_partial_signature() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let (party_one_key_share, party_two_key_share) =
+        simulate_rsa_key_shares(&party_one_keys, &party_two_keys);
+    let message = b"vice city";
+    let encoding = SignatureEncoding::Pkcs1v15;
+
+    let mut party_one_first_message =
+        PartyOneSign::encode_message_and_partial_sign(&party_one_key_share, message, &encoding)
+            .expect("");
+    party_one_first_message.sigma = BigInt::mod_mul(
+        &party_one_first_message.sigma,
+        &BigInt::from(2),
+        &party_one_key_share.n,
+    );
+    let result = PartyTwoSign::verify_party_one_first_message_and_partial_sign(
+        &party_two_key_share,
+        message,
+        &encoding,
+        &party_one_first_message,
+    );
+    match result.err() {
+        Some(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.round, ProtocolRound::Sign);
+            assert_eq!(abort.proof, "pi_sigma");
+            assert_eq!(abort.alpha, None);
+            assert_eq!(
+                abort.message,
+                serde_json::to_string(&party_one_first_message).unwrap()
+            );
+        }
+        err => panic!("expected a protocol abort, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_decrypt() {
+    let (party_one_keys, party_two_keys) = simulate_key_setup();
+    let (party_one_key_share, party_two_key_share) =
+        simulate_rsa_key_shares(&party_one_keys, &party_two_keys);
+    let message = b"vice city";
+    let label = b"label";
+    let c = rsa_oaep_encrypt(
+        &party_one_key_share.n,
+        &party_one_key_share.e,
+        message,
+        label,
+    )
+    .expect("");
+
+    let party_one_first_message =
+        PartyOneDecrypt::partial_decrypt_and_first_message(&party_one_key_share, &c).expect("");
+    let party_two_first_message =
+        PartyTwoDecrypt::partial_decrypt_and_first_message(&party_two_key_share, &c).expect("");
+
+    let party_one_plaintext = PartyOneDecrypt::verify_party_two_first_message_and_decrypt(
+        &party_one_key_share,
+        &c,
+        label,
+        &party_one_first_message,
+        &party_two_first_message,
+    )
+    .expect("");
+    let party_two_plaintext = PartyTwoDecrypt::verify_party_one_first_message_and_decrypt(
+        &party_two_key_share,
+        &c,
+        label,
+        &party_two_first_message,
+        &party_one_first_message,
+    )
+    .expect("");
+
+    assert_eq!(party_one_plaintext, message.to_vec());
+    assert_eq!(party_two_plaintext, message.to_vec());
+
+    // a partial decryption that does not match the verification key is rejected
+    let mut bad_party_two_first_message = party_two_first_message.clone();
+    bad_party_two_first_message.partial_dec_c = BigInt::mod_mul(
+        &party_two_first_message.partial_dec_c,
+        &BigInt::from(2),
+        &party_one_key_share.n,
+    );
+    let result = PartyOneDecrypt::verify_party_two_first_message_and_decrypt(
+        &party_one_key_share,
+        &c,
+        label,
+        &party_one_first_message,
+        &bad_party_two_first_message,
+    );
+    match result.err() {
+        Some(TwoPartyRSAError::ProtocolAbort(abort)) => {
+            assert_eq!(abort.round, ProtocolRound::Decrypt);
+            assert_eq!(abort.proof, "pi_partial_dec");
+        }
+        err => panic!("expected a protocol abort, got {:?}", err),
+    }
+}
+
+#[test]
+#[ignore] // runs a full key generation, including trial division of every candidate by SMALL_PRIMES
+fn test_hmrt_keygen() {
+    let session_id = SessionId::random();
+    let (party_one_keygen, party_one_message) = PartyOneHmrtKeyGen::new(session_id);
+    let (party_two_keygen, party_two_message) = PartyTwoHmrtKeyGen::new(session_id);
+    run_hmrt_keygen(
+        party_one_keygen,
+        party_one_message,
+        party_two_keygen,
+        party_two_message,
+        &HmrtParams::default(),
+    );
+}
+
+#[test]
+fn test_hmrt_keygen_with_reduced_params() {
+    let params = HmrtParams {
+        candidate_bit_length: 1024,
+        paillier_modulus: 2048,
+        sec_param: 40,
+        trial_division_bound: 1000,
+        ..HmrtParams::default()
+    };
+    let e = BigInt::from(DEFAULT_PUBLIC_EXPONENT);
+    let session_id = SessionId::random();
+    let (party_one_keygen, party_one_message) =
+        PartyOneHmrtKeyGen::with_params(session_id, &params, &e).expect("");
+    let (party_two_keygen, party_two_message) =
+        PartyTwoHmrtKeyGen::with_params(session_id, &params, &e).expect("");
+    run_hmrt_keygen(
+        party_one_keygen,
+        party_one_message,
+        party_two_keygen,
+        party_two_message,
+        &params,
+    );
+}
+
+#[test]
+fn test_hmrt_keygen_with_distributed_sieving() {
+    // trial division would stop at the last prime dividing M
+    let (_, first_unsieved_index) = sieving_modulus(&HmrtParams {
+        candidate_bit_length: 1024,
+        ..HmrtParams::default()
+    });
+    let params = HmrtParams {
+        candidate_bit_length: 1024,
+        paillier_modulus: 2048,
+        sec_param: 40,
+        trial_division_bound: SMALL_PRIMES[first_unsieved_index - 1],
+        ..HmrtParams::default()
+    };
+    let sieving_params = HmrtParams {
+        distributed_sieving: true,
+        ..params
+    };
+
+    // sieved candidates are coprime to all these primes: no trial division at all, and both
+    // candidates make it to the joint modulus
+    let sieving_stats = run_hmrt_keygen_until_first_modulus(&sieving_params, &sieving_params);
+    assert_eq!(sieving_stats.primes_sieved, 0);
+    assert_eq!(sieving_stats.candidates_tried, 2);
+
+    // without sieving both p and q went through every prime at least
+    let stats = run_hmrt_keygen_until_first_modulus(&params, &params);
+    assert!(stats.primes_sieved >= 2 * (params.trial_division_end() - 1));
+    assert!(stats.candidates_tried >= 2);
+}
+
+#[test]
+fn test_hmrt_keygen_with_proposed_group() {
+    let party_two_params = HmrtParams {
+        candidate_bit_length: 1024,
+        paillier_modulus: 2048,
+        sec_param: 40,
+        trial_division_bound: 100,
+        ..HmrtParams::default()
+    };
+    let party_one_params = HmrtParams {
+        elgamal_group: ElGamalGroup::FFDHE3072,
+        ..party_two_params
+    };
+
+    // party two accepts any group from FFDHE2048 and follows party one's proposal all the way
+    // to the joint modulus
+    let stats = run_hmrt_keygen_until_first_modulus(&party_one_params, &party_two_params);
+    assert_eq!(stats.moduli_tested, 1);
+
+    // party two requiring at least FFDHE4096 rejects the proposal before sending any key
+    let strict_party_two_params = HmrtParams {
+        elgamal_group: ElGamalGroup::FFDHE4096,
+        ..party_two_params
+    };
+    let e = BigInt::from(DEFAULT_PUBLIC_EXPONENT);
+    let session_id = SessionId::random();
+    let (_, party_one_message) =
+        PartyOneHmrtKeyGen::with_params(session_id, &party_one_params, &e).expect("");
+    let (mut party_two_keygen, _) =
+        PartyTwoHmrtKeyGen::with_params(session_id, &strict_party_two_params, &e).expect("");
+    assert_eq!(
+        party_two_keygen
+            .handle_party_one_message(&party_one_message)
+            .err(),
+        Some(TwoPartyRSAError::ElGamalGroupTooSmall)
+    );
+}
+
+// runs both parties in lockstep until the joint modulus of their first p and q is computed
+fn run_hmrt_keygen_until_first_modulus(
+    party_one_params: &HmrtParams,
+    party_two_params: &HmrtParams,
+) -> HmrtKeyGenStats {
+    let e = BigInt::from(DEFAULT_PUBLIC_EXPONENT);
+    let session_id = SessionId::random();
+    let (mut party_one_keygen, mut party_one_message) =
+        PartyOneHmrtKeyGen::with_params(session_id, party_one_params, &e).expect("");
+    let (mut party_two_keygen, mut party_two_message) =
+        PartyTwoHmrtKeyGen::with_params(session_id, party_two_params, &e).expect("");
+
+    while party_one_keygen.stats().moduli_tested == 0 {
+        let party_one_step = party_one_keygen
+            .handle_party_two_message(&party_two_message)
+            .expect("");
+        let party_two_step = party_two_keygen
+            .handle_party_one_message(&party_one_message)
+            .expect("");
+        match (party_one_step, party_two_step) {
+            (PartyOneKeyGenStep::Message(msg1), PartyTwoKeyGenStep::Message(msg2)) => {
+                party_one_message = msg1;
+                party_two_message = msg2;
+            }
+            _ => panic!("parties are out of sync"),
+        }
+    }
+    assert_eq!(party_one_keygen.stats(), party_two_keygen.stats());
+    *party_one_keygen.stats()
+}
+
+// runs both parties in lockstep until they output their key shares
+fn run_hmrt_keygen(
+    mut party_one_keygen: PartyOneHmrtKeyGen,
+    mut party_one_message: PartyOneKeyGenMsg,
+    mut party_two_keygen: PartyTwoHmrtKeyGen,
+    mut party_two_message: PartyTwoKeyGenMsg,
+    params: &HmrtParams,
+) {
+    let (party_one_key_share, party_two_key_share) = loop {
+        let party_one_step = party_one_keygen
+            .handle_party_two_message(&party_two_message)
+            .expect("");
+        let party_two_step = party_two_keygen
+            .handle_party_one_message(&party_one_message)
+            .expect("");
+        match (party_one_step, party_two_step) {
+            (PartyOneKeyGenStep::Message(msg1), PartyTwoKeyGenStep::Message(msg2)) => {
+                party_one_message = msg1;
+                party_two_message = msg2;
+            }
+            (PartyOneKeyGenStep::Done(share1), PartyTwoKeyGenStep::Done(share2)) => {
+                break (share1, share2)
+            }
+            _ => panic!("parties are out of sync"),
+        }
+    };
+
+    assert!(party_one_keygen.is_done() && party_two_keygen.is_done());
+    assert_eq!(party_one_keygen.stats(), party_two_keygen.stats());
+    assert!(party_one_keygen.stats().candidates_tried >= 2);
+    assert_eq!(party_one_key_share.n, party_two_key_share.n);
+    assert_eq!(party_one_key_share.local_vk, party_two_key_share.remote_vk);
+    assert_eq!(
+        party_one_key_share.n.bit_length(),
+        params.candidate_bit_length
+    );
+}
+
+#[test]
+fn test_hmrt_state_rejects_bad_envelopes() {
+    let session_id = SessionId::random();
+    let (mut party_one_state, party_one_message) = PartyOneState::new(session_id);
+    let (mut party_two_state, party_two_message) = PartyTwoState::new(session_id);
+
+    // a message from another session
+    let (_, other_session_message) = PartyTwoState::new(SessionId::random());
+    assert_eq!(
+        party_one_state.handle_message(&other_session_message).err(),
+        Some(TwoPartyRSAError::WrongSession)
+    );
+    // a party receiving its own message
+    assert_eq!(
+        party_one_state.handle_message(&party_one_message).err(),
+        Some(TwoPartyRSAError::UnexpectedSender)
+    );
+
+    let party_two_next_message = party_two_state
+        .handle_message(&party_one_message)
+        .expect("")
+        .expect("");
+    assert_eq!(party_two_next_message.round(), 1);
+    // round 1 of party two before round 0
+    assert_eq!(
+        party_one_state
+            .handle_message(&party_two_next_message)
+            .err(),
+        Some(TwoPartyRSAError::OutOfOrderMessage)
+    );
+
+    let party_one_next_message = party_one_state
+        .handle_message(&party_two_message)
+        .expect("")
+        .expect("");
+    assert_eq!(party_one_next_message.round(), 1);
+    assert_eq!(party_one_next_message.sender(), HmrtParty::PartyOne);
+    // round 0 again
+    assert_eq!(
+        party_one_state.handle_message(&party_two_message).err(),
+        Some(TwoPartyRSAError::ReplayedMessage)
+    );
+
+    // none of the rejected messages changed the state
+    assert_eq!(party_one_state.round(), 1);
+    assert!(party_two_state
+        .handle_message(&party_one_next_message)
+        .expect("")
+        .is_some());
+}
@@ -1,4 +1,7 @@
 pub mod hmrt;
+pub mod padding;
 
-const PAILLIER_MODULUS: usize = 3072;
-const CANDIDATE_BIT_LENGTH: usize = 2048;
+const BIPRIMALITY_TEST_ROUNDS: usize = 40;
+
+/// Public RSA exponent used unless the caller asks for another one
+pub const DEFAULT_PUBLIC_EXPONENT: u32 = 65537;
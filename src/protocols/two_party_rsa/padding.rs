@@ -0,0 +1,322 @@
+use crate::TwoPartyRSAError;
+use curv::arithmetic::traits::Converter;
+use curv::BigInt;
+use rand::prelude::*;
+use sha2::Digest;
+use sha2::Sha256;
+use subtle::Choice;
+use subtle::ConditionallySelectable;
+use subtle::ConstantTimeEq;
+
+const HASH_LEN: usize = 32;
+const SALT_LEN: usize = 32;
+
+// DER encoding of DigestInfo for SHA-256, RFC 8017 section 9.2 note 1
+const SHA256_DIGEST_INFO_PREFIX: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+
+/// Message encodings for RSA signatures, both with SHA-256, following RFC 8017:
+/// EMSA-PKCS1-v1_5 (section 9.2) and EMSA-PSS (section 9.1) with MGF1 and a 32 bytes salt.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum SignatureEncoding {
+    Pkcs1v15,
+    Pss,
+}
+
+impl SignatureEncoding {
+    /// Encodes the message to an integer smaller than n. PSS samples a fresh salt.
+    pub fn encode(&self, message: &[u8], n: &BigInt) -> Result<BigInt, TwoPartyRSAError> {
+        let mod_bits = n.bit_length();
+        let em = match self {
+            SignatureEncoding::Pkcs1v15 => emsa_pkcs1_v15_encode(message, (mod_bits + 7) / 8)?,
+            SignatureEncoding::Pss => {
+                let mut salt = [0u8; SALT_LEN];
+                thread_rng().fill_bytes(&mut salt);
+                emsa_pss_encode(message, mod_bits - 1, &salt)?
+            }
+        };
+        Ok(BigInt::from(&em[..]))
+    }
+
+    /// Checks that em is a valid encoding of the message
+    pub fn verify(&self, message: &[u8], em: &BigInt, n: &BigInt) -> bool {
+        let mod_bits = n.bit_length();
+        match self {
+            SignatureEncoding::Pkcs1v15 => {
+                match emsa_pkcs1_v15_encode(message, (mod_bits + 7) / 8) {
+                    Ok(expected_em) => &BigInt::from(&expected_em[..]) == em,
+                    Err(_) => false,
+                }
+            }
+            SignatureEncoding::Pss => {
+                let em_len = (mod_bits - 1 + 7) / 8;
+                match i2osp(em, em_len) {
+                    Some(em) => emsa_pss_verify(message, &em, mod_bits - 1),
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+// I2OSP from RFC 8017: big endian encoding of x padded to len bytes
+pub fn i2osp(x: &BigInt, len: usize) -> Option<Vec<u8>> {
+    if x < &BigInt::zero() {
+        return None;
+    }
+    let bytes = if x == &BigInt::zero() {
+        vec![]
+    } else {
+        BigInt::to_vec(x)
+    };
+    if bytes.len() > len {
+        return None;
+    }
+    let mut res = vec![0u8; len - bytes.len()];
+    res.extend_from_slice(&bytes);
+    Some(res)
+}
+
+pub fn mgf1_sha256(seed: &[u8], mask_len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(mask_len + HASH_LEN);
+    let mut counter: u32 = 0;
+    while mask.len() < mask_len {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&counter.to_be_bytes());
+        mask.extend_from_slice(&Sha256::digest(&input));
+        counter += 1;
+    }
+    mask.truncate(mask_len);
+    mask
+}
+
+pub fn emsa_pkcs1_v15_encode(message: &[u8], em_len: usize) -> Result<Vec<u8>, TwoPartyRSAError> {
+    let mut t = SHA256_DIGEST_INFO_PREFIX.to_vec();
+    t.extend_from_slice(&Sha256::digest(message));
+    if em_len < t.len() + 11 {
+        return Err(TwoPartyRSAError::MessageEncodingError);
+    }
+
+    // EM = 0x00 || 0x01 || PS || 0x00 || T
+    let mut em = vec![0x00, 0x01];
+    em.extend(vec![0xff; em_len - t.len() - 3]);
+    em.push(0x00);
+    em.extend_from_slice(&t);
+    Ok(em)
+}
+
+pub fn emsa_pss_encode(
+    message: &[u8],
+    em_bits: usize,
+    salt: &[u8],
+) -> Result<Vec<u8>, TwoPartyRSAError> {
+    let em_len = (em_bits + 7) / 8;
+    if em_len < HASH_LEN + salt.len() + 2 {
+        return Err(TwoPartyRSAError::MessageEncodingError);
+    }
+    let m_hash = Sha256::digest(message);
+
+    // M' = 0x00 * 8 || mHash || salt
+    let mut m_prime = vec![0u8; 8];
+    m_prime.extend_from_slice(&m_hash);
+    m_prime.extend_from_slice(salt);
+    let h = Sha256::digest(&m_prime);
+
+    // DB = PS || 0x01 || salt
+    let mut db = vec![0u8; em_len - salt.len() - HASH_LEN - 2];
+    db.push(0x01);
+    db.extend_from_slice(salt);
+    let db_mask = mgf1_sha256(&h, em_len - HASH_LEN - 1);
+    let mut masked_db: Vec<u8> = db.iter().zip(&db_mask).map(|(x, y)| x ^ y).collect();
+    masked_db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    // EM = maskedDB || H || 0xbc
+    let mut em = masked_db;
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    Ok(em)
+}
+
+pub fn emsa_pss_verify(message: &[u8], em: &[u8], em_bits: usize) -> bool {
+    let em_len = (em_bits + 7) / 8;
+    if em.len() != em_len || em_len < HASH_LEN + SALT_LEN + 2 || em[em_len - 1] != 0xbc {
+        return false;
+    }
+    let unused_bits = 8 * em_len - em_bits;
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - HASH_LEN - 1);
+    if masked_db[0] & !(0xff >> unused_bits) != 0 {
+        return false;
+    }
+
+    let db_mask = mgf1_sha256(h, em_len - HASH_LEN - 1);
+    let mut db: Vec<u8> = masked_db.iter().zip(&db_mask).map(|(x, y)| x ^ y).collect();
+    db[0] &= 0xff >> unused_bits;
+
+    let ps_len = em_len - HASH_LEN - SALT_LEN - 2;
+    if db[..ps_len].iter().any(|b| *b != 0x00) || db[ps_len] != 0x01 {
+        return false;
+    }
+    let salt = &db[ps_len + 1..];
+
+    let mut m_prime = vec![0u8; 8];
+    m_prime.extend_from_slice(&Sha256::digest(message));
+    m_prime.extend_from_slice(salt);
+    Sha256::digest(&m_prime).as_slice() == h
+}
+
+// RSAES-OAEP encoding from RFC 8017 section 7.1.1 with SHA-256 and MGF1, k is the byte length of N
+pub fn rsaes_oaep_encode(
+    message: &[u8],
+    label: &[u8],
+    k: usize,
+) -> Result<Vec<u8>, TwoPartyRSAError> {
+    if k < 2 * HASH_LEN + 2 || message.len() > k - 2 * HASH_LEN - 2 {
+        return Err(TwoPartyRSAError::MessageEncodingError);
+    }
+
+    // DB = lHash || PS || 0x01 || M
+    let mut db = Sha256::digest(label).to_vec();
+    db.extend(vec![0u8; k - message.len() - 2 * HASH_LEN - 2]);
+    db.push(0x01);
+    db.extend_from_slice(message);
+
+    let mut seed = [0u8; HASH_LEN];
+    thread_rng().fill_bytes(&mut seed);
+    let db_mask = mgf1_sha256(&seed, k - HASH_LEN - 1);
+    let masked_db: Vec<u8> = db.iter().zip(&db_mask).map(|(x, y)| x ^ y).collect();
+    let seed_mask = mgf1_sha256(&masked_db, HASH_LEN);
+    let masked_seed: Vec<u8> = seed.iter().zip(&seed_mask).map(|(x, y)| x ^ y).collect();
+
+    // EM = 0x00 || maskedSeed || maskedDB
+    let mut em = vec![0x00];
+    em.extend_from_slice(&masked_seed);
+    em.extend_from_slice(&masked_db);
+    Ok(em)
+}
+
+// RSAES-OAEP decoding from RFC 8017 section 7.1.2. All failures return the same error, and the
+// checks run in constant time: the leading byte, lHash and the whole of PS are examined before
+// the result is branched on, so the time taken does not tell which check failed (Manger's attack)
+pub fn rsaes_oaep_decode(em: &[u8], label: &[u8], k: usize) -> Result<Vec<u8>, TwoPartyRSAError> {
+    if k < 2 * HASH_LEN + 2 || em.len() != k {
+        return Err(TwoPartyRSAError::MessageEncodingError);
+    }
+    let (masked_seed, masked_db) = em[1..].split_at(HASH_LEN);
+    let seed_mask = mgf1_sha256(masked_db, HASH_LEN);
+    let seed: Vec<u8> = masked_seed
+        .iter()
+        .zip(&seed_mask)
+        .map(|(x, y)| x ^ y)
+        .collect();
+    let db_mask = mgf1_sha256(&seed, k - HASH_LEN - 1);
+    let db: Vec<u8> = masked_db.iter().zip(&db_mask).map(|(x, y)| x ^ y).collect();
+
+    let (l_hash, rest) = db.split_at(HASH_LEN);
+    let mut valid = em[0].ct_eq(&0x00) & Sha256::digest(label).as_slice().ct_eq(l_hash);
+    // rest = PS || 0x01 || M, the separator is the first nonzero byte and must be 0x01
+    let mut in_padding = Choice::from(1);
+    let mut found_separator = Choice::from(0);
+    let mut separator = 0u32;
+    for (i, b) in rest.iter().enumerate() {
+        let is_zero = b.ct_eq(&0x00);
+        let is_separator = in_padding & !is_zero;
+        separator.conditional_assign(&(i as u32), is_separator);
+        found_separator |= is_separator & b.ct_eq(&0x01);
+        in_padding &= is_zero;
+    }
+    valid &= found_separator;
+    if bool::from(valid) {
+        Ok(rest[separator as usize + 1..].to_vec())
+    } else {
+        Err(TwoPartyRSAError::MessageEncodingError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocols::two_party_rsa::padding::*;
+
+    #[test]
+    fn test_pkcs1_v15_encoding() {
+        let em = emsa_pkcs1_v15_encode(b"message", 256).unwrap();
+        assert_eq!(em.len(), 256);
+        assert_eq!(&em[..2], &[0x00, 0x01]);
+        assert_eq!(&em[256 - 51..256 - 32], &SHA256_DIGEST_INFO_PREFIX[..]);
+        assert!(emsa_pkcs1_v15_encode(b"message", 61).is_err());
+    }
+
+    #[test]
+    fn test_pss_encoding() {
+        let salt = [7u8; SALT_LEN];
+        let em = emsa_pss_encode(b"message", 2047, &salt).unwrap();
+        assert_eq!(em.len(), 256);
+        assert!(emsa_pss_verify(b"message", &em, 2047));
+        assert!(!emsa_pss_verify(b"other message", &em, 2047));
+    }
+
+    #[test]
+    fn test_oaep_encoding() {
+        let em = rsaes_oaep_encode(b"message", b"label", 256).unwrap();
+        assert_eq!(em.len(), 256);
+        assert_eq!(em[0], 0x00);
+        assert_eq!(
+            rsaes_oaep_decode(&em, b"label", 256).unwrap(),
+            b"message".to_vec()
+        );
+        assert!(rsaes_oaep_decode(&em, b"other label", 256).is_err());
+        assert!(rsaes_oaep_encode(&[0u8; 191], b"", 256).is_err());
+    }
+
+    // masks DB as rsaes_oaep_encode does, whatever its content
+    fn oaep_mask(db: &[u8], k: usize) -> Vec<u8> {
+        let mut seed = [0u8; HASH_LEN];
+        thread_rng().fill_bytes(&mut seed);
+        let db_mask = mgf1_sha256(&seed, k - HASH_LEN - 1);
+        let masked_db: Vec<u8> = db.iter().zip(&db_mask).map(|(x, y)| x ^ y).collect();
+        let seed_mask = mgf1_sha256(&masked_db, HASH_LEN);
+        let mut em = vec![0x00];
+        em.extend(seed.iter().zip(&seed_mask).map(|(x, y)| x ^ y));
+        em.extend_from_slice(&masked_db);
+        em
+    }
+
+    #[test]
+    fn test_oaep_decoding_failures() {
+        let k = 256;
+        let l_hash = Sha256::digest(b"label");
+        let db = |separator: u8| {
+            let mut db = l_hash.to_vec();
+            db.resize(k - HASH_LEN - 1 - 8, 0x00);
+            db.push(separator);
+            db.extend_from_slice(b"message");
+            db
+        };
+        assert_eq!(
+            rsaes_oaep_decode(&oaep_mask(&db(0x01), k), b"label", k).unwrap(),
+            b"message".to_vec()
+        );
+        // the first nonzero byte after lHash is not 0x01
+        assert!(rsaes_oaep_decode(&oaep_mask(&db(0x02), k), b"label", k).is_err());
+        // no separator at all
+        let mut no_separator = l_hash.to_vec();
+        no_separator.resize(k - HASH_LEN - 1, 0x00);
+        assert!(rsaes_oaep_decode(&oaep_mask(&no_separator, k), b"label", k).is_err());
+        // the leading byte is not zero
+        let mut em = oaep_mask(&db(0x01), k);
+        em[0] = 0x01;
+        assert!(rsaes_oaep_decode(&em, b"label", k).is_err());
+    }
+
+    #[test]
+    fn test_encoding_fits_modulus() {
+        let n = BigInt::from(2).pow(2047) + BigInt::one();
+        for encoding in &[SignatureEncoding::Pkcs1v15, SignatureEncoding::Pss] {
+            let em = encoding.encode(b"message", &n).unwrap();
+            assert!(em < n);
+            assert!(encoding.verify(b"message", &em, &n));
+            assert!(!encoding.verify(b"other message", &em, &n));
+        }
+    }
+}
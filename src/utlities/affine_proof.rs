@@ -0,0 +1,432 @@
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
+use crate::ProofError;
+use curv::arithmetic::traits::{Modulo, Samplable, ZeroizeBN};
+use curv::BigInt;
+use elgamal::ElGamalCiphertext;
+use elgamal::ElGamalPublicKey;
+use elgamal::ExponentElGamal;
+use std::fmt;
+use zeroize::Zeroize;
+
+const CHALLENGE_BIT_LENGTH: usize = 256;
+const STAT_SEC_PARAM: usize = 80;
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/affine-proof";
+
+/// Proof that a Paillier ciphertext C (modulus n) is the result of an affine operation on public
+/// Paillier ciphertexts B_1,...,B_k :  C = B_1^a_1 * ... * B_k^a_k * rho^n mod n^2,
+/// where every exponent a_i is the plaintext of a public commitment c_i: either an exponent
+/// ElGamal ciphertext or a Paillier ciphertext under some (possibly other) modulus.
+/// Taking B = 1 + n gives a proof that a Paillier ciphertext and an ElGamal ciphertext encrypt
+/// the same value. The exponents are integers and are bounded: 0 <= a_i < 2^bit_bounds[i].
+/// The protocol (Fiat-Shamir):
+/// 1. prover picks s_i of bit_bounds[i] + |e| + 80 bits, sigma in Z_n* and t_i, and sends
+///    A = prod B_i^s_i * sigma^n, D_i = Com(s_i; t_i)
+/// 2. e = H(n, B_i, c_i, bit_bounds, C, A, D_i)
+/// 3. prover sends z_i = s_i + e*a_i (over the integers), w = sigma * rho^e and
+///    u_i = t_i + e*r_i mod q (ElGamal) or u_i = t_i * r_i^e mod N (Paillier)
+/// 4. verifier checks prod B_i^z_i * w^n = A * C^e, Com(z_i; u_i) = D_i * c_i^e and that
+///    every z_i is within its bound.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AffineProof {
+    pub a: BigInt,
+    pub d_vec: Vec<MaskCommitment>,
+    pub z_vec: Vec<BigInt>,
+    pub u_vec: Vec<BigInt>,
+    pub w: BigInt,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ExponentCommitment {
+    ElGamal {
+        pk: ElGamalPublicKey,
+        c: ElGamalCiphertext,
+    },
+    Paillier {
+        n: BigInt,
+        c: BigInt,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum MaskCommitment {
+    ElGamal(ElGamalCiphertext),
+    Paillier(BigInt),
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct AffineWitness {
+    pub a_vec: Vec<BigInt>,
+    pub r_vec: Vec<BigInt>, // randomness of the commitments c_i
+    pub rho: BigInt,
+}
+
+impl Zeroize for AffineWitness {
+    fn zeroize(&mut self) {
+        self.a_vec.iter_mut().for_each(|x| x.zeroize_bn());
+        self.r_vec.iter_mut().for_each(|x| x.zeroize_bn());
+        self.rho.zeroize_bn();
+    }
+}
+
+impl Drop for AffineWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for AffineWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AffineWitness")
+            .field("a_vec", &Redacted)
+            .field("r_vec", &Redacted)
+            .field("rho", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AffineStatement {
+    pub n: BigInt,
+    pub bases: Vec<BigInt>,
+    pub commitments: Vec<ExponentCommitment>,
+    pub bit_bounds: Vec<usize>,
+    pub ciphertext: BigInt,
+}
+
+impl AffineProof {
+    pub fn prove(
+        witness: &AffineWitness,
+        statement: &AffineStatement,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        let k = statement.bases.len();
+        if statement.commitments.len() != k
+            || statement.bit_bounds.len() != k
+            || witness.a_vec.len() != k
+            || witness.r_vec.len() != k
+        {
+            return Err(ProofError::AffineProofError);
+        }
+        let nn = &statement.n * &statement.n;
+
+        let mut s_vec: Vec<BigInt> = statement
+            .bit_bounds
+            .iter()
+            .map(|bits| BigInt::sample(bits + CHALLENGE_BIT_LENGTH + STAT_SEC_PARAM))
+            .collect();
+        let mut t_vec: Vec<BigInt> = statement
+            .commitments
+            .iter()
+            .map(|commitment| match commitment {
+                ExponentCommitment::ElGamal { pk, .. } => BigInt::sample_below(&pk.pp.q),
+                ExponentCommitment::Paillier { n, .. } => BigInt::sample_below(n),
+            })
+            .collect();
+        let mut sigma = BigInt::sample_below(&statement.n);
+
+        let a = statement
+            .bases
+            .iter()
+            .zip(&s_vec)
+            .fold(BigInt::mod_pow(&sigma, &statement.n, &nn), |acc, (b, s)| {
+                BigInt::mod_mul(&acc, &BigInt::mod_pow(b, s, &nn), &nn)
+            });
+        let d_vec = (0..k)
+            .map(|i| commit(&statement.commitments[i], &s_vec[i], &t_vec[i]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let e = challenge(statement, &a, &d_vec, context);
+
+        let z_vec: Vec<BigInt> = (0..k).map(|i| &s_vec[i] + &e * &witness.a_vec[i]).collect();
+        let u_vec: Vec<BigInt> = (0..k)
+            .map(|i| match &statement.commitments[i] {
+                ExponentCommitment::ElGamal { pk, .. } => BigInt::mod_add(
+                    &t_vec[i],
+                    &BigInt::mod_mul(&e, &witness.r_vec[i], &pk.pp.q),
+                    &pk.pp.q,
+                ),
+                ExponentCommitment::Paillier { n, .. } => {
+                    BigInt::mod_mul(&t_vec[i], &BigInt::mod_pow(&witness.r_vec[i], &e, n), n)
+                }
+            })
+            .collect();
+        let w = BigInt::mod_mul(
+            &sigma,
+            &BigInt::mod_pow(&witness.rho, &e, &statement.n),
+            &statement.n,
+        );
+
+        sigma.zeroize_bn();
+        s_vec.iter_mut().for_each(|s| s.zeroize_bn());
+        t_vec.iter_mut().for_each(|t| t.zeroize_bn());
+        Ok(AffineProof {
+            a,
+            d_vec,
+            z_vec,
+            u_vec,
+            w,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        statement: &AffineStatement,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        let k = statement.bases.len();
+        if statement.commitments.len() != k
+            || statement.bit_bounds.len() != k
+            || self.d_vec.len() != k
+            || self.z_vec.len() != k
+            || self.u_vec.len() != k
+        {
+            return Err(ProofError::AffineProofError);
+        }
+        let bounds_ok = (0..k).all(|i| {
+            self.z_vec[i] >= BigInt::zero()
+                && self.z_vec[i].bit_length()
+                    <= statement.bit_bounds[i] + CHALLENGE_BIT_LENGTH + STAT_SEC_PARAM + 1
+        });
+        if !bounds_ok
+            || statement.n <= BigInt::one()
+            || !commitments_are_valid(&statement.commitments, &self.d_vec)
+        {
+            return Err(ProofError::AffineProofError);
+        }
+
+        let e = challenge(statement, &self.a, &self.d_vec, context);
+        let nn = &statement.n * &statement.n;
+
+        let lhs = statement.bases.iter().zip(&self.z_vec).fold(
+            BigInt::mod_pow(&self.w, &statement.n, &nn),
+            |acc, (b, z)| BigInt::mod_mul(&acc, &BigInt::mod_pow(b, z, &nn), &nn),
+        );
+        let rhs = BigInt::mod_mul(
+            &self.a,
+            &BigInt::mod_pow(&statement.ciphertext, &e, &nn),
+            &nn,
+        );
+        if lhs != rhs {
+            return Err(ProofError::AffineProofError);
+        }
+
+        let commitments_ok = (0..k).all(|i| {
+            let expected = match commit(&statement.commitments[i], &self.z_vec[i], &self.u_vec[i]) {
+                Ok(expected) => expected,
+                Err(_) => return false,
+            };
+            match (&statement.commitments[i], &self.d_vec[i]) {
+                (ExponentCommitment::ElGamal { c, .. }, MaskCommitment::ElGamal(d)) => {
+                    match ExponentElGamal::add(d, &ExponentElGamal::mul(c, &e)) {
+                        Ok(d_c_e) => expected == MaskCommitment::ElGamal(d_c_e),
+                        Err(_) => false,
+                    }
+                }
+                (ExponentCommitment::Paillier { n, c }, MaskCommitment::Paillier(d)) => {
+                    let nn_i = n * n;
+                    let d_c_e = BigInt::mod_mul(d, &BigInt::mod_pow(c, &e, &nn_i), &nn_i);
+                    expected == MaskCommitment::Paillier(d_c_e)
+                }
+                _ => false,
+            }
+        });
+
+        if commitments_ok {
+            Ok(())
+        } else {
+            Err(ProofError::AffineProofError)
+        }
+    }
+}
+
+// Com(m; r): exponent ElGamal encryption of m mod q, or Paillier encryption (1 + n)^m * r^n
+fn commit(
+    commitment: &ExponentCommitment,
+    m: &BigInt,
+    r: &BigInt,
+) -> Result<MaskCommitment, ProofError> {
+    match commitment {
+        ExponentCommitment::ElGamal { pk, .. } => {
+            ExponentElGamal::encrypt_from_predefined_randomness(&m.modulus(&pk.pp.q), pk, r)
+                .map(MaskCommitment::ElGamal)
+                .map_err(|_| ProofError::AffineProofError)
+        }
+        ExponentCommitment::Paillier { n, .. } => {
+            let nn = n * n;
+            let one_plus_n_m = BigInt::mod_add(&BigInt::one(), &BigInt::mod_mul(m, n, &nn), &nn);
+            Ok(MaskCommitment::Paillier(BigInt::mod_mul(
+                &one_plus_n_m,
+                &BigInt::mod_pow(r, n, &nn),
+                &nn,
+            )))
+        }
+    }
+}
+
+// the ElGamal commitments and masks must be ciphertexts of the group of their key and the
+// Paillier moduli must be usable as such
+fn commitments_are_valid(commitments: &[ExponentCommitment], d_vec: &[MaskCommitment]) -> bool {
+    commitments
+        .iter()
+        .zip(d_vec)
+        .all(|(commitment, d)| match (commitment, d) {
+            (ExponentCommitment::ElGamal { pk, c }, MaskCommitment::ElGamal(d)) => {
+                FFDHEExponentElGamal::validate_ciphertext(pk, c).is_ok()
+                    && FFDHEExponentElGamal::validate_ciphertext(pk, d).is_ok()
+            }
+            (ExponentCommitment::Paillier { n, .. }, MaskCommitment::Paillier(_)) => {
+                n > &BigInt::one()
+            }
+            _ => false,
+        })
+}
+
+fn challenge(
+    statement: &AffineStatement,
+    a: &BigInt,
+    d_vec: &[MaskCommitment],
+    context: &ProofContext,
+) -> BigInt {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigint(b"n", &statement.n);
+    transcript.append_bigint(b"C", &statement.ciphertext);
+    transcript.append_bigints(b"B", &statement.bases);
+    transcript.append_u64(b"k", statement.commitments.len() as u64);
+    for (commitment, bits) in statement.commitments.iter().zip(&statement.bit_bounds) {
+        match commitment {
+            ExponentCommitment::ElGamal { pk, c } => {
+                transcript.append_message(b"commitment", b"elgamal");
+                transcript.append_bigint(b"p", &pk.pp.p);
+                transcript.append_bigint(b"g", &pk.pp.g);
+                transcript.append_bigint(b"h", &pk.h);
+                transcript.append_bigint(b"c1", &c.c1);
+                transcript.append_bigint(b"c2", &c.c2);
+            }
+            ExponentCommitment::Paillier { n, c } => {
+                transcript.append_message(b"commitment", b"paillier");
+                transcript.append_bigint(b"n", n);
+                transcript.append_bigint(b"c", c);
+            }
+        }
+        transcript.append_u64(b"bit_bound", *bits as u64);
+    }
+    transcript.append_bigint(b"A", a);
+    for d in d_vec.iter() {
+        match d {
+            MaskCommitment::ElGamal(d) => {
+                transcript.append_bigint(b"D1", &d.c1);
+                transcript.append_bigint(b"D2", &d.c2);
+            }
+            MaskCommitment::Paillier(d) => transcript.append_bigint(b"D", d),
+        }
+    }
+    transcript.challenge_bits(b"e", CHALLENGE_BIT_LENGTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::affine_proof::*;
+    use crate::utlities::transcript::ProofContext;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalKeyPair;
+    use elgamal::ElGamalPP;
+    use elgamal::ExponentElGamal;
+    use paillier::traits::EncryptWithChosenRandomness;
+    use paillier::traits::KeyGeneration;
+    use paillier::Paillier;
+    use paillier::{Randomness, RawPlaintext};
+
+    #[test]
+    fn test_affine_proof() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let (ek, _) = Paillier::keypair_with_modulus_size(2048).keys();
+
+        // C = B^a * rho^n where a is committed in ElGamal
+        let a = BigInt::sample(1000);
+        let r_a = BigInt::sample_below(&pp.q);
+        let c_a =
+            ExponentElGamal::encrypt_from_predefined_randomness(&a, &keypair.pk, &r_a).unwrap();
+        let b = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(BigInt::sample(1000)),
+            &Randomness(BigInt::sample_below(&ek.n)),
+        )
+        .0
+        .into_owned();
+        let rho = BigInt::sample_below(&ek.n);
+        let ciphertext = BigInt::mod_mul(
+            &BigInt::mod_pow(&b, &a, &ek.nn),
+            &BigInt::mod_pow(&rho, &ek.n, &ek.nn),
+            &ek.nn,
+        );
+
+        let witness = AffineWitness {
+            a_vec: vec![a],
+            r_vec: vec![r_a],
+            rho,
+        };
+        let statement = AffineStatement {
+            n: ek.n.clone(),
+            bases: vec![b],
+            commitments: vec![ExponentCommitment::ElGamal {
+                pk: keypair.pk,
+                c: c_a,
+            }],
+            bit_bounds: vec![1000],
+            ciphertext,
+        };
+        let context = ProofContext::new(&[1u8; 32], 2);
+        let proof = AffineProof::prove(&witness, &statement, &context).unwrap();
+        assert!(proof.verify(&statement, &context).is_ok());
+        assert!(proof
+            .verify(&statement, &ProofContext::new(&[1u8; 32], 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_bad_affine_proof() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let (ek, _) = Paillier::keypair_with_modulus_size(2048).keys();
+
+        // Paillier ciphertext encrypts a + 1 while the ElGamal commitment is to a
+        let a = BigInt::sample(1000);
+        let r_a = BigInt::sample_below(&pp.q);
+        let c_a =
+            ExponentElGamal::encrypt_from_predefined_randomness(&a, &keypair.pk, &r_a).unwrap();
+        let rho = BigInt::sample_below(&ek.n);
+        let ciphertext = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(&a + BigInt::one()),
+            &Randomness(rho.clone()),
+        )
+        .0
+        .into_owned();
+
+        let witness = AffineWitness {
+            a_vec: vec![a],
+            r_vec: vec![r_a],
+            rho,
+        };
+        let statement = AffineStatement {
+            n: ek.n.clone(),
+            bases: vec![&ek.n + BigInt::one()],
+            commitments: vec![ExponentCommitment::ElGamal {
+                pk: keypair.pk,
+                c: c_a,
+            }],
+            bit_bounds: vec![1000],
+            ciphertext,
+        };
+        let context = ProofContext::default();
+        let proof = AffineProof::prove(&witness, &statement, &context).unwrap();
+        assert!(proof.verify(&statement, &context).is_err());
+    }
+}
@@ -1,11 +1,16 @@
-use crate::utlities::hash;
+use crate::utlities::group_element::validate_group_elements;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
 use crate::ProofError;
 use curv::arithmetic::traits::Modulo;
 use curv::arithmetic::traits::Samplable;
 use curv::arithmetic::traits::ZeroizeBN;
 use curv::BigInt;
 use elgamal::ElGamalPP;
-const HASH_OUTPUT_BIT_SIZE: usize = 256;
+use std::fmt;
+use zeroize::Zeroize;
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/ddh-proof";
 
 ///  This is a proof of membership of DDH: (G, xG, yG, xyG)
 /// taken from: [ D. Chaum, T. P. Pedersen. Transferred cash grows in size. In Advances in Cryptology, EUROCRYPT ,volume 658 of Lecture Notes in Computer Science, pages 390 - 407, 1993.]
@@ -13,7 +18,7 @@ const HASH_OUTPUT_BIT_SIZE: usize = 256;
 /// h1 = g1^x, h2 = g2^x
 /// The protocol:
 /// 1: Prover chooses a1 = g1^s1 , a2 = g2^s for random s
-/// 2. prover calculates challenge e = H(g1,h1,g2,h2,a1,a2)
+/// 2. prover calculates challenge e = H(g1,h1,g2,h2,a1,a2) over a transcript of the proof context
 /// 3. prover calculates z  = s + ex,
 /// 4. prover sends pi = {e, a1,a2,z}
 /// 5. verifier checks that g1^z = a1 * h1^e, g2^z = a2 * h2^e
@@ -34,42 +39,70 @@ pub struct DDHStatement {
     pub h2: BigInt,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq)]
 pub struct DDHWitness {
     pub x: BigInt,
 }
 
+impl Zeroize for DDHWitness {
+    fn zeroize(&mut self) {
+        self.x.zeroize_bn();
+    }
+}
+
+impl Drop for DDHWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for DDHWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DDHWitness").field("x", &Redacted).finish()
+    }
+}
+
 pub trait NISigmaProof<T, W, S> {
-    fn prove(w: &W, delta: &S) -> T;
+    fn prove(w: &W, delta: &S, context: &ProofContext) -> T;
+
+    fn verify(&self, delta: &S, context: &ProofContext) -> Result<(), ProofError>;
+}
 
-    fn verify(&self, delta: &S) -> Result<(), ProofError>;
+// e = H(g1, g2, h1, h2, a1, a2) in Z_q
+fn challenge(delta: &DDHStatement, a1: &BigInt, a2: &BigInt, context: &ProofContext) -> BigInt {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigint(b"p", &delta.pp.p);
+    transcript.append_bigint(b"g1", &delta.g1);
+    transcript.append_bigint(b"g2", &delta.g2);
+    transcript.append_bigint(b"h1", &delta.h1);
+    transcript.append_bigint(b"h2", &delta.h2);
+    transcript.append_bigint(b"a1", a1);
+    transcript.append_bigint(b"a2", a2);
+    transcript.challenge_zn(b"e", &delta.pp.q)
 }
 
 impl NISigmaProof<DDHProof, DDHWitness, DDHStatement> for DDHProof {
-    fn prove(w: &DDHWitness, delta: &DDHStatement) -> DDHProof {
+    fn prove(w: &DDHWitness, delta: &DDHStatement, context: &ProofContext) -> DDHProof {
         let mut s = BigInt::sample_below(&delta.pp.q);
         let a1 = BigInt::mod_pow(&delta.g1, &s, &delta.pp.p);
         let a2 = BigInt::mod_pow(&delta.g2, &s, &delta.pp.p);
 
-        let e = hash(
-            &[&delta.g1, &delta.g2, &delta.h1, &delta.h2, &a1, &a2],
-            &delta.pp,
-            HASH_OUTPUT_BIT_SIZE,
-        );
+        let e = challenge(delta, &a1, &a2, context);
 
         let z = &s + &e * &w.x;
         s.zeroize_bn();
         DDHProof { a1, a2, z }
     }
 
-    fn verify(&self, delta: &DDHStatement) -> Result<(), ProofError> {
-        let e = hash(
+    fn verify(&self, delta: &DDHStatement, context: &ProofContext) -> Result<(), ProofError> {
+        validate_group_elements(
             &[
-                &delta.g1, &delta.g2, &delta.h1, &delta.h2, &self.a1, &self.a2,
+                &delta.g1, &delta.h1, &delta.g2, &delta.h2, &self.a1, &self.a2,
             ],
             &delta.pp,
-            HASH_OUTPUT_BIT_SIZE,
-        );
+        )
+        .map_err(ProofError::SchemeError)?;
+        let e = challenge(delta, &self.a1, &self.a2, context);
 
         let z = self.z.modulus(&delta.pp.q);
         let g1_z = BigInt::mod_pow(&delta.g1, &z, &delta.pp.p);
@@ -91,6 +124,9 @@ impl NISigmaProof<DDHProof, DDHWitness, DDHStatement> for DDHProof {
 mod tests {
 
     use crate::utlities::ddh_proof::*;
+    use crate::utlities::transcript::ProofContext;
+    use crate::HomomorphicSchemeError;
+    use crate::ProofError;
     use curv::BigInt;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalKeyPair;
@@ -111,9 +147,31 @@ mod tests {
         let h2 = BigInt::mod_pow(&g2, &r, &pp.p);
         let delta = DDHStatement { pp, g1, h1, g2, h2 };
         let w = DDHWitness { x: r };
-        let proof = DDHProof::prove(&w, &delta);
-        let verify = proof.verify(&delta);
-        assert!(verify.is_ok())
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let proof = DDHProof::prove(&w, &delta, &context);
+        let verify = proof.verify(&delta, &context);
+        assert!(verify.is_ok());
+
+        // the proof is bound to the session and to its prover
+        assert!(proof
+            .verify(&delta, &ProofContext::new(&[2u8; 32], 1))
+            .is_err());
+        assert!(proof
+            .verify(&delta, &ProofContext::new(&[1u8; 32], 2))
+            .is_err());
+
+        // a1 * (p - 1) is outside of the subgroup of order q
+        let p_minus_one = &delta.pp.p - BigInt::one();
+        let bad_proof = DDHProof {
+            a1: BigInt::mod_mul(&proof.a1, &p_minus_one, &delta.pp.p),
+            ..proof
+        };
+        assert_eq!(
+            bad_proof.verify(&delta, &context),
+            Err(ProofError::SchemeError(
+                HomomorphicSchemeError::NotInSubgroup
+            ))
+        );
     }
 
     #[test]
@@ -132,8 +190,8 @@ mod tests {
         let w = DDHWitness {
             x: keypair.sk.x.clone(),
         };
-        let proof = DDHProof::prove(&w, &delta);
-        let verify = proof.verify(&delta);
+        let proof = DDHProof::prove(&w, &delta, &ProofContext::default());
+        let verify = proof.verify(&delta, &ProofContext::default());
         assert!(verify.is_ok())
     }
 
@@ -153,8 +211,8 @@ mod tests {
         let h2 = BigInt::mod_pow(&g2, &(&r + BigInt::one()), &pp.p);
         let delta = DDHStatement { pp, g1, h1, g2, h2 };
         let w = DDHWitness { x: r };
-        let proof = DDHProof::prove(&w, &delta);
-        let verify = proof.verify(&delta);
+        let proof = DDHProof::prove(&w, &delta, &ProofContext::default());
+        let verify = proof.verify(&delta, &ProofContext::default());
         assert!(verify.is_ok())
     }
 }
@@ -1,12 +1,18 @@
-use crate::utlities::hash;
+use crate::utlities::group_element::validate_group_elements;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
 use crate::ProofError;
 use curv::arithmetic::traits::Modulo;
 use curv::arithmetic::traits::Samplable;
 use curv::arithmetic::traits::ZeroizeBN;
 use curv::BigInt;
 use elgamal::ElGamalPP;
+use std::fmt;
+use zeroize::Zeroize;
+
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/dlog-proof";
 
-const HASH_OUTPUT_BIT_SIZE: usize = 256;
 /// This is implementation of Schnorr's identification protocol for elliptic curve groups or a
 /// sigma protocol for Proof of knowledge of the discrete log of an Elliptic-curve point:
 /// C.P. Schnorr. Efficient Identification and Signatures for Smart Cards. In
@@ -23,28 +29,61 @@ pub struct DLogProof {
     pub response: BigInt,
 }
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Witness {
     pub x: BigInt,
 }
 
+impl Zeroize for Witness {
+    fn zeroize(&mut self) {
+        self.x.zeroize_bn();
+    }
+}
+
+impl Drop for Witness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness").field("x", &Redacted).finish()
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Statement {
     pub h: BigInt,
 }
 
 pub trait ProveDLog {
-    fn prove(witness: &Witness, pp: &ElGamalPP) -> DLogProof;
+    fn prove(witness: &Witness, pp: &ElGamalPP, context: &ProofContext) -> DLogProof;
+
+    fn verify(
+        &self,
+        statement: &Statement,
+        pp: &ElGamalPP,
+        context: &ProofContext,
+    ) -> Result<(), ProofError>;
+}
 
-    fn verify(&self, statement: &Statement, pp: &ElGamalPP) -> Result<(), ProofError>;
+// e = H(p, g, h, random_point) in Z_q
+fn challenge(pp: &ElGamalPP, h: &BigInt, random_point: &BigInt, context: &ProofContext) -> BigInt {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigint(b"p", &pp.p);
+    transcript.append_bigint(b"g", &pp.g);
+    transcript.append_bigint(b"h", h);
+    transcript.append_bigint(b"random_point", random_point);
+    transcript.challenge_zn(b"e", &pp.q)
 }
 
 impl ProveDLog for DLogProof {
-    fn prove(w: &Witness, pp: &ElGamalPP) -> DLogProof {
+    fn prove(w: &Witness, pp: &ElGamalPP, context: &ProofContext) -> DLogProof {
         let mut r: BigInt = BigInt::sample_below(&pp.q);
         let random_point = BigInt::mod_pow(&pp.g, &r, &pp.p);
         let pk = BigInt::mod_pow(&pp.g, &w.x, &pp.p);
-        let e = hash(&[&random_point, &pk, &pp.g], &pp, HASH_OUTPUT_BIT_SIZE);
+        let e = challenge(pp, &pk, &random_point, context);
         let response = &r + &(e * &w.x);
         r.zeroize_bn();
         DLogProof {
@@ -53,12 +92,15 @@ impl ProveDLog for DLogProof {
         }
     }
 
-    fn verify(&self, statement: &Statement, pp: &ElGamalPP) -> Result<(), ProofError> {
-        let e = hash(
-            &[&self.random_point, &statement.h, &pp.g],
-            &pp,
-            HASH_OUTPUT_BIT_SIZE,
-        );
+    fn verify(
+        &self,
+        statement: &Statement,
+        pp: &ElGamalPP,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        validate_group_elements(&[&statement.h, &self.random_point], pp)
+            .map_err(ProofError::SchemeError)?;
+        let e = challenge(pp, &statement.h, &self.random_point, context);
 
         let z = self.response.modulus(&pp.q);
         let pk_e = BigInt::mod_pow(&statement.h, &e, &pp.p);
@@ -77,8 +119,7 @@ impl ProveDLog for DLogProof {
 mod tests {
     use crate::utlities::dlog_proof::DLogProof;
     use crate::utlities::dlog_proof::{ProveDLog, Statement, Witness};
-    use crate::utlities::hash;
-    use curv::BigInt;
+    use crate::utlities::transcript::ProofContext;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalKeyPair;
     use elgamal::ElGamalPP;
@@ -90,21 +131,15 @@ mod tests {
         let witness = Witness { x: keypair.sk.x };
         let statement = Statement { h: keypair.pk.h };
 
-        let dlog_proof = DLogProof::prove(&witness, &pp);
-        let verified = dlog_proof.verify(&statement, &pp);
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let dlog_proof = DLogProof::prove(&witness, &pp, &context);
+        let verified = dlog_proof.verify(&statement, &pp, &context);
         match verified {
             Ok(_t) => assert!(true),
             Err(_e) => assert!(false),
         }
-    }
-
-    #[test]
-    fn test_hash() {
-        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
-        let res = hash(&[&BigInt::from(1)], &pp, 256);
-        assert!(
-            (res.bit_length() - pp.q.bit_length()) < 10
-                || (pp.q.bit_length() - res.bit_length()) < 10
-        );
+        assert!(dlog_proof
+            .verify(&statement, &pp, &ProofContext::new(&[1u8; 32], 2))
+            .is_err());
     }
 }
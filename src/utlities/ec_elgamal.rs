@@ -0,0 +1,256 @@
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::Redacted;
+use crate::HomomorphicSchemeError;
+use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::ZeroizeBN;
+use curv::elliptic::curves::secp256_k1::FE;
+use curv::elliptic::curves::secp256_k1::GE;
+use curv::elliptic::curves::traits::ECPoint;
+use curv::elliptic::curves::traits::ECScalar;
+use curv::BigInt;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Exponent ElGamal over secp256k1: Enc(m; r) = (r * G, m * G + r * H).
+/// Ciphertexts and proofs are much smaller than over a 2048 bits field, but plaintexts live in
+/// Z_q for a 256 bits q. It is a scheme for the generic proofs of this module, not a backend of
+/// HMRT: the ModProof of trial division ties each residue to the encrypted share of the
+/// candidate under the same key, and the shares of a candidate of at least 1024 bits do not fit
+/// in Z_q, so the joint key of HMRT stays in an RFC 7919 group.
+/// curv cannot represent the point at infinity, every operation that would output it returns
+/// HomomorphicSchemeError::PointAtInfinity. Random randomness hits it only with negligible
+/// probability.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ECElGamalPublicKey {
+    pub h: GE,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ECElGamalPrivateKey {
+    pub x: FE,
+}
+
+impl Zeroize for ECElGamalPrivateKey {
+    fn zeroize(&mut self) {
+        self.x.zeroize();
+    }
+}
+
+impl Drop for ECElGamalPrivateKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for ECElGamalPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ECElGamalPrivateKey")
+            .field("x", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ECElGamalKeyPair {
+    pub pk: ECElGamalPublicKey,
+    pub sk: ECElGamalPrivateKey,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ECElGamalCiphertext {
+    pub c1: GE,
+    pub c2: GE,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ECExponentElGamal;
+
+impl ECElGamalKeyPair {
+    pub fn generate() -> Self {
+        let x: FE = ECScalar::new_random();
+        let h = &GE::generator() * &x;
+        ECElGamalKeyPair {
+            pk: ECElGamalPublicKey { h },
+            sk: ECElGamalPrivateKey { x },
+        }
+    }
+}
+
+impl ECElGamalPublicKey {
+    /// joint public key for the sum of the private keys
+    pub fn add(&self, other: &ECElGamalPublicKey) -> Result<Self, HomomorphicSchemeError> {
+        Ok(ECElGamalPublicKey {
+            h: add_points(&self.h, &other.h)?,
+        })
+    }
+}
+
+// k mod q as a scalar, None for zero which curv cannot multiply by
+fn to_scalar(k: &BigInt) -> Option<FE> {
+    let k = k.mod_floor(&FE::q());
+    if k == BigInt::zero() {
+        None
+    } else {
+        Some(ECScalar::from(&k))
+    }
+}
+
+fn add_points(a: &GE, b: &GE) -> Result<GE, HomomorphicSchemeError> {
+    // same x coordinate and different points: b = -a
+    if a.x_coor() == b.x_coor() && a != b {
+        return Err(HomomorphicSchemeError::PointAtInfinity);
+    }
+    Ok(a + b)
+}
+
+impl HomomorphicScheme for ECExponentElGamal {
+    type PublicKey = ECElGamalPublicKey;
+    type Ciphertext = ECElGamalCiphertext;
+    type Randomness = BigInt;
+
+    fn plaintext_modulus(_pk: &ECElGamalPublicKey) -> BigInt {
+        FE::q()
+    }
+
+    fn sample_randomness(_pk: &ECElGamalPublicKey) -> BigInt {
+        BigInt::sample_below(&FE::q())
+    }
+
+    fn encrypt_with_randomness(
+        pk: &ECElGamalPublicKey,
+        m: &BigInt,
+        r: &BigInt,
+    ) -> Result<ECElGamalCiphertext, HomomorphicSchemeError> {
+        let r = match to_scalar(r) {
+            Some(r) => r,
+            None => return Err(HomomorphicSchemeError::EncryptionError),
+        };
+        let c1 = &GE::generator() * &r;
+        let h_r = &pk.h * &r;
+        let c2 = match to_scalar(m) {
+            Some(m) => add_points(&(&GE::generator() * &m), &h_r)?,
+            None => h_r,
+        };
+        Ok(ECElGamalCiphertext { c1, c2 })
+    }
+
+    fn add(
+        _pk: &ECElGamalPublicKey,
+        c_a: &ECElGamalCiphertext,
+        c_b: &ECElGamalCiphertext,
+    ) -> Result<ECElGamalCiphertext, HomomorphicSchemeError> {
+        Ok(ECElGamalCiphertext {
+            c1: add_points(&c_a.c1, &c_b.c1)?,
+            c2: add_points(&c_a.c2, &c_b.c2)?,
+        })
+    }
+
+    fn mul(
+        _pk: &ECElGamalPublicKey,
+        c: &ECElGamalCiphertext,
+        k: &BigInt,
+    ) -> Result<ECElGamalCiphertext, HomomorphicSchemeError> {
+        match to_scalar(k) {
+            Some(k) => Ok(ECElGamalCiphertext {
+                c1: &c.c1 * &k,
+                c2: &c.c2 * &k,
+            }),
+            None => Err(HomomorphicSchemeError::PointAtInfinity),
+        }
+    }
+
+    fn add_randomness(_pk: &ECElGamalPublicKey, r_a: &BigInt, r_b: &BigInt) -> BigInt {
+        BigInt::mod_add(r_a, r_b, &FE::q())
+    }
+
+    fn mul_randomness(_pk: &ECElGamalPublicKey, r: &BigInt, k: &BigInt) -> BigInt {
+        BigInt::mod_mul(r, &k.mod_floor(&FE::q()), &FE::q())
+    }
+
+    fn zeroize_randomness(r: &mut BigInt) {
+        r.zeroize_bn();
+    }
+
+    // curv only deserializes points on the curve
+    fn validate_ciphertext(
+        _pk: &ECElGamalPublicKey,
+        _c: &ECElGamalCiphertext,
+    ) -> Result<(), HomomorphicSchemeError> {
+        Ok(())
+    }
+
+    fn public_key_to_hash_input(pk: &ECElGamalPublicKey) -> Vec<BigInt> {
+        vec![pk.h.bytes_compressed_to_big_int()]
+    }
+
+    fn ciphertext_to_hash_input(c: &ECElGamalCiphertext) -> Vec<BigInt> {
+        vec![
+            c.c1.bytes_compressed_to_big_int(),
+            c.c2.bytes_compressed_to_big_int(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::ec_elgamal::ECElGamalKeyPair;
+    use crate::utlities::ec_elgamal::ECExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::HomomorphicSchemeError;
+    use curv::arithmetic::traits::Samplable;
+    use curv::elliptic::curves::secp256_k1::FE;
+    use curv::elliptic::curves::traits::ECScalar;
+    use curv::BigInt;
+
+    #[test]
+    fn test_ec_exponent_elgamal_homomorphism() {
+        let keypair1 = ECElGamalKeyPair::generate();
+        let keypair2 = ECElGamalKeyPair::generate();
+        let joint_pk = keypair1.pk.add(&keypair2.pk).unwrap();
+        let q = FE::q();
+
+        // Enc(a; r) * 3 + Enc(b; s) = Enc(3a + b; 3r + s)
+        let (a, b) = (BigInt::sample(200), BigInt::sample(200));
+        let (r, s) = (BigInt::sample_below(&q), BigInt::sample_below(&q));
+        let c_a = ECExponentElGamal::encrypt_with_randomness(&joint_pk, &a, &r).unwrap();
+        let c_b = ECExponentElGamal::encrypt_with_randomness(&joint_pk, &b, &s).unwrap();
+        let c_3a = ECExponentElGamal::mul(&joint_pk, &c_a, &BigInt::from(3)).unwrap();
+        let c_sum = ECExponentElGamal::add(&joint_pk, &c_3a, &c_b).unwrap();
+
+        let r_3a = ECExponentElGamal::mul_randomness(&joint_pk, &r, &BigInt::from(3));
+        let r_sum = ECExponentElGamal::add_randomness(&joint_pk, &r_3a, &s);
+        let expected = ECExponentElGamal::encrypt_with_randomness(
+            &joint_pk,
+            &(&a * &BigInt::from(3) + &b),
+            &r_sum,
+        )
+        .unwrap();
+        assert_eq!(c_sum, expected);
+    }
+
+    #[test]
+    fn test_ec_exponent_elgamal_encrypt_zero() {
+        let keypair = ECElGamalKeyPair::generate();
+        let q = FE::q();
+        let r = BigInt::sample_below(&q);
+        let c =
+            ECExponentElGamal::encrypt_with_randomness(&keypair.pk, &BigInt::zero(), &r).unwrap();
+        let r_fe: FE = ECScalar::from(&r);
+        assert_eq!(c.c2, &keypair.pk.h * &r_fe);
+
+        assert_eq!(
+            ECExponentElGamal::mul(&keypair.pk, &c, &q).err(),
+            Some(HomomorphicSchemeError::PointAtInfinity)
+        );
+        assert_eq!(
+            ECExponentElGamal::encrypt_with_randomness(
+                &keypair.pk,
+                &BigInt::one(),
+                &BigInt::zero()
+            )
+            .err(),
+            Some(HomomorphicSchemeError::EncryptionError)
+        );
+    }
+}
@@ -1,100 +1,127 @@
-use crate::utlities::hash;
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
 use crate::ProofError;
 use curv::arithmetic::traits::Modulo;
 use curv::arithmetic::traits::Samplable;
 use curv::arithmetic::traits::ZeroizeBN;
 use curv::BigInt;
-use elgamal::ElGamalCiphertext;
-use elgamal::ElGamalPublicKey;
+use std::fmt;
+use zeroize::Zeroize;
 
-/// This is a proof of knowledge that a pair of group elements {D, E}
-/// form a valid homomorphic ElGamal encryption (”in the exponent”) using public key Y .
-/// The implementation was adapted to cyclic group of prime order from:
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/homomorphic-enc-proof";
+
+/// This is a proof of knowledge of the plaintext and randomness of a ciphertext c = Enc(m; r)
+/// under public key pk of an additively homomorphic scheme S, exponent ElGamal by default.
+/// The implementation was adapted from:
 /// https://github.com/ZenGo-X/curv/blob/master/src/cryptographic_primitives/proofs/sigma_correct_homomorphic_elgamal_enc.rs
-/// Specifically, the witness is ω = (x, r), the statement is δ = (g, h, c1, c2).
-/// The relation R outputs 1 if c1 = g^y, c2 = g^m * h^y
+/// Specifically, the witness is ω = (m, r), the statement is δ = (pk, c).
+/// The relation R outputs 1 if c = Enc(m; r)
 /// proof goes as follows:
-/// 1. The prover chooses s1, s2, computes a1 = g^s1, a2 = h^s2, a3 = g^s2 and sends to the verifier t = a1 * a2, a3
-/// 2. The verifier picks a challenge e \in Z_q
-/// 3. The proveer computes z1 = s1 + e*x and  z2 = s2 + e*r
-/// 4. The verifier accepes if g^z1 * h^z2 = t * c2^e and g^z2 = a3 * c1*e
+/// 1. The prover chooses a plaintext s1 and randomness s2, sends A = Enc(s1; s2) to the verifier
+/// 2. The verifier picks a challenge e in Z_M, M the plaintext modulus
+/// 3. The prover computes z1 = s1 + e*m mod M and z2 = s2 + e*r in the randomness space of S
+/// 4. The verifier accepts if Enc(z1; z2) = A + e*c
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct HomoELGamalProof {
-    pub t: BigInt,
-    pub a3: BigInt,
+#[serde(bound = "")]
+pub struct HomoELGamalProof<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub a: S::Ciphertext,
     pub z1: BigInt,
-    pub z2: BigInt,
+    pub z2: S::Randomness,
 }
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct HomoElGamalWitness {
-    pub r: BigInt,
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct HomoElGamalWitness<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub r: S::Randomness,
     pub m: BigInt,
 }
 
+impl<S: HomomorphicScheme> Zeroize for HomoElGamalWitness<S> {
+    fn zeroize(&mut self) {
+        S::zeroize_randomness(&mut self.r);
+        self.m.zeroize_bn();
+    }
+}
+
+impl<S: HomomorphicScheme> Drop for HomoElGamalWitness<S> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<S: HomomorphicScheme> fmt::Debug for HomoElGamalWitness<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HomoElGamalWitness")
+            .field("r", &Redacted)
+            .field("m", &Redacted)
+            .finish()
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct HomoElGamalStatement {
-    pub pk: ElGamalPublicKey,
-    pub ciphertext: ElGamalCiphertext,
+#[serde(bound = "")]
+pub struct HomoElGamalStatement<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub pk: S::PublicKey,
+    pub ciphertext: S::Ciphertext,
 }
 
-impl HomoELGamalProof {
-    pub fn prove(w: &HomoElGamalWitness, delta: &HomoElGamalStatement) -> HomoELGamalProof {
-        let mut s1 = BigInt::sample_below(&delta.pk.pp.q);
-        let mut s2 = BigInt::sample_below(&delta.pk.pp.q);
-        let mut a1 = BigInt::mod_pow(&delta.pk.pp.g, &s1, &delta.pk.pp.p);
-        let mut a2 = BigInt::mod_pow(&delta.pk.h, &s2, &delta.pk.pp.p);
-        let a3 = BigInt::mod_pow(&delta.pk.pp.g, &s2, &delta.pk.pp.p);
-        let t = BigInt::mod_mul(&a1, &a2, &delta.pk.pp.p);
-        let e = hash(
-            &[
-                &t,
-                &a3,
-                &delta.pk.pp.g,
-                &delta.pk.h,
-                &delta.ciphertext.c1,
-                &delta.ciphertext.c2,
-            ],
-            &delta.pk.pp,
-            256, // TODO
-        );
+// e = H(pk, c, A) in Z_M
+fn challenge<S: HomomorphicScheme>(
+    delta: &HomoElGamalStatement<S>,
+    a: &S::Ciphertext,
+    context: &ProofContext,
+) -> BigInt {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigints(b"pk", &S::public_key_to_hash_input(&delta.pk));
+    transcript.append_bigints(b"c", &S::ciphertext_to_hash_input(&delta.ciphertext));
+    transcript.append_bigints(b"A", &S::ciphertext_to_hash_input(a));
+    transcript.challenge_zn(b"e", &S::plaintext_modulus(&delta.pk))
+}
 
-        let z1 = &s1 + &w.m * &e;
-        let z2 = &s2 + &w.r * &e;
+impl<S: HomomorphicScheme> HomoELGamalProof<S> {
+    pub fn prove(
+        w: &HomoElGamalWitness<S>,
+        delta: &HomoElGamalStatement<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        let plaintext_modulus = S::plaintext_modulus(&delta.pk);
+        let mut s1 = BigInt::sample_below(&plaintext_modulus);
+        let s2 = S::sample_randomness(&delta.pk);
+        let a = S::encrypt_with_randomness(&delta.pk, &s1, &s2)
+            .map_err(|_| ProofError::ElGamalProofError)?;
+        let e = challenge(delta, &a, context);
 
-        s1.zeroize_bn();
-        s2.zeroize_bn();
-        a1.zeroize_bn();
-        a2.zeroize_bn();
-        HomoELGamalProof { t, a3, z1, z2 }
-    }
-    pub fn verify(&self, delta: &HomoElGamalStatement) -> Result<(), ProofError> {
-        let e = hash(
-            &[
-                &self.t,
-                &self.a3,
-                &delta.pk.pp.g,
-                &delta.pk.h,
-                &delta.ciphertext.c1,
-                &delta.ciphertext.c2,
-            ],
-            &delta.pk.pp,
-            256,
+        let z1 = BigInt::mod_add(
+            &s1,
+            &BigInt::mod_mul(&w.m, &e, &plaintext_modulus),
+            &plaintext_modulus,
         );
+        let z2 = S::add_randomness(&delta.pk, &s2, &S::mul_randomness(&delta.pk, &w.r, &e));
 
-        let g_z1 = BigInt::mod_pow(&delta.pk.pp.g, &self.z1, &delta.pk.pp.p);
-        let h_z2 = BigInt::mod_pow(&delta.pk.h, &self.z2, &delta.pk.pp.p);
+        s1.zeroize_bn();
+        Ok(HomoELGamalProof { a, z1, z2 })
+    }
 
-        let g_z1_mul_h_z2 = BigInt::mod_mul(&g_z1, &h_z2, &delta.pk.pp.p);
-        let c1_e = BigInt::mod_pow(&delta.ciphertext.c1, &e, &delta.pk.pp.p);
-        let c2_e = BigInt::mod_pow(&delta.ciphertext.c2, &e, &delta.pk.pp.p);
+    pub fn verify(
+        &self,
+        delta: &HomoElGamalStatement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        S::validate_ciphertext(&delta.pk, &delta.ciphertext)
+            .and(S::validate_ciphertext(&delta.pk, &self.a))
+            .map_err(ProofError::SchemeError)?;
+        let e = challenge(delta, &self.a, context);
 
-        let t_c2_e = BigInt::mod_mul(&self.t, &c2_e, &delta.pk.pp.p);
-        let g_z2 = BigInt::mod_pow(&delta.pk.pp.g, &self.z2, &delta.pk.pp.p);
-        let a3_c1_e = BigInt::mod_mul(&self.a3, &c1_e, &delta.pk.pp.p);
+        let enc_z = S::encrypt_with_randomness(&delta.pk, &self.z1, &self.z2)
+            .map_err(ProofError::SchemeError)?;
+        let e_c = S::mul(&delta.pk, &delta.ciphertext, &e).map_err(ProofError::SchemeError)?;
+        let a_e_c = S::add(&delta.pk, &self.a, &e_c).map_err(ProofError::SchemeError)?;
 
-        if g_z1_mul_h_z2 == t_c2_e && g_z2 == a3_c1_e {
+        if enc_z == a_e_c {
             Ok(())
         } else {
             Err(ProofError::ElGamalProofError)
@@ -105,13 +132,20 @@ impl HomoELGamalProof {
 #[cfg(test)]
 mod tests {
 
+    use crate::utlities::ec_elgamal::ECElGamalKeyPair;
+    use crate::utlities::ec_elgamal::ECExponentElGamal;
     use crate::utlities::elgamal_enc_proof::*;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::homomorphic_scheme::PaillierScheme;
+    use crate::utlities::transcript::ProofContext;
     use curv::arithmetic::traits::Samplable;
     use curv::BigInt;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalKeyPair;
     use elgamal::ElGamalPP;
-    use elgamal::ExponentElGamal;
+    use paillier::KeyGeneration;
+    use paillier::Paillier;
 
     #[test]
     fn test_correct_homo_elgamal() {
@@ -119,13 +153,63 @@ mod tests {
         let keypair = ElGamalKeyPair::generate(&pp);
         let m = BigInt::from(11);
         let r = BigInt::sample_below(&pp.q);
-        let c = ExponentElGamal::encrypt_from_predefined_randomness(&m, &keypair.pk, &r).unwrap();
-        let delta = HomoElGamalStatement {
+        let c = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &m, &r).unwrap();
+        let delta = HomoElGamalStatement::<FFDHEExponentElGamal> {
             pk: keypair.pk,
             ciphertext: c,
         };
         let w = HomoElGamalWitness { r, m };
-        let proof = HomoELGamalProof::prove(&w, &delta);
-        assert!(proof.verify(&delta).is_ok())
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let proof = HomoELGamalProof::prove(&w, &delta, &context).unwrap();
+        assert!(proof.verify(&delta, &context).is_ok());
+        assert!(proof
+            .verify(&delta, &ProofContext::new(&[1u8; 32], 2))
+            .is_err());
+    }
+
+    #[test]
+    fn test_correct_homo_enc_paillier() {
+        let (ek, _) = Paillier::keypair().keys();
+        let m = BigInt::sample_below(&ek.n);
+        let r = PaillierScheme::sample_randomness(&ek);
+        let c = PaillierScheme::encrypt_with_randomness(&ek, &m, &r).unwrap();
+        let delta = HomoElGamalStatement::<PaillierScheme> {
+            pk: ek,
+            ciphertext: c,
+        };
+        let w = HomoElGamalWitness { r, m };
+        let proof = HomoELGamalProof::prove(&w, &delta, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&delta, &ProofContext::default()).is_ok())
+    }
+
+    #[test]
+    fn test_wrong_homo_enc_ec_elgamal() {
+        let keypair = ECElGamalKeyPair::generate();
+        let m = BigInt::from(11);
+        let r = ECExponentElGamal::sample_randomness(&keypair.pk);
+        let c = ECExponentElGamal::encrypt_with_randomness(&keypair.pk, &m, &r).unwrap();
+        let delta = HomoElGamalStatement::<ECExponentElGamal> {
+            pk: keypair.pk,
+            ciphertext: c,
+        };
+        let w = HomoElGamalWitness { r, m };
+        let proof = HomoELGamalProof::prove(&w, &delta, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&delta, &ProofContext::default()).is_ok());
+
+        // the witness does not open another ciphertext
+        let other_delta = HomoElGamalStatement::<ECExponentElGamal> {
+            ciphertext: ECExponentElGamal::encrypt_with_randomness(
+                &delta.pk,
+                &BigInt::from(12),
+                &w.r,
+            )
+            .unwrap(),
+            ..delta.clone()
+        };
+        let bad_proof =
+            HomoELGamalProof::prove(&w, &other_delta, &ProofContext::default()).unwrap();
+        assert!(bad_proof
+            .verify(&other_delta, &ProofContext::default())
+            .is_err());
     }
 }
@@ -1,13 +1,18 @@
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::homomorphic_scheme::Masks;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
 use crate::ProofError;
 use bit_vec::BitVec;
-use curv::arithmetic::traits::{Converter, Modulo, Samplable};
-use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
-use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::arithmetic::traits::{Modulo, Samplable, ZeroizeBN};
 use curv::BigInt;
-use elgamal::ElGamalCiphertext;
-use elgamal::ElGamalPublicKey;
-use elgamal::ExponentElGamal;
 use rayon::prelude::*;
+use std::fmt;
+use zeroize::Zeroize;
+
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/equal-secret-proof";
 
 /// This is a non-interactive version of the protocol \pi_eq
 /// from https://eprint.iacr.org/2011/494.pdf page 12 point 5.  The witness is {x,r}, the
@@ -17,83 +22,128 @@ use rayon::prelude::*;
 /// assume computational security, taking 120 repetitions for enough security :
 /// 1) prover computes a vector of random encryptions : c_i = Enc_pk(s_i,r_i).
 /// 2) prover computes h'_i = h^s_i
-/// 3) using FS: prover computes e = Hash(Vec<h'_i>, Vec<c_i>) and takes first 120bits
+/// 3) using FS: prover computes e = Hash(Vec<h'_i>, Vec<c_i>) with 120 bits
 /// 4) if e_bit_i = 0 prover outputs z_i = (s_i,r_i),
 /// 5) if e_bit_i = 1 prover outputs z_i = (x + s_i, r + r_i)
 /// We note that |s_i| = 100 + |x| (same for r_i, r)
 /// 6) verifier computes e
 /// 7) if e_bit_i = 0 verifer checks c_i = Enc_pk(s_i,r_i) , h'_i = h^s_i
 /// 8) if e_bit_i = 1 verifier checks c * c_i = Enc_pk(x + s_i, r + r_i), h'_i * h' = h^(x + s_i)
+/// The cryptosystem is any additively homomorphic scheme S, exponent ElGamal by default. r_i is
+/// sampled by the scheme and r + r_i is computed with its randomness arithmetic.
 
-//TODO: make proof generic for cryptosystem
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct EqProof {
-    pub ciphertext_i_vec: Vec<ElGamalCiphertext>,
+#[serde(bound = "")]
+pub struct EqProof<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub ciphertext_i_vec: Vec<S::Ciphertext>,
     pub h_prime_i_vec: Vec<BigInt>,
-    pub z_vec: Vec<Response>,
+    pub z_vec: Vec<Response<S>>,
 }
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Response {
+#[serde(bound = "")]
+pub struct Response<S: HomomorphicScheme = FFDHEExponentElGamal> {
     z1: BigInt,
-    z2: BigInt,
+    z2: S::Randomness,
 }
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Witness {
-    x: BigInt,
-    r: BigInt,
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Witness<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub x: BigInt,
+    pub r: S::Randomness,
+}
+
+impl<S: HomomorphicScheme> Zeroize for Witness<S> {
+    fn zeroize(&mut self) {
+        self.x.zeroize_bn();
+        S::zeroize_randomness(&mut self.r);
+    }
+}
+
+impl<S: HomomorphicScheme> Drop for Witness<S> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<S: HomomorphicScheme> fmt::Debug for Witness<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("x", &Redacted)
+            .field("r", &Redacted)
+            .finish()
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Statement {
-    pub pk: ElGamalPublicKey,
+#[serde(bound = "")]
+pub struct Statement<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub pk: S::PublicKey,
     pub h: BigInt,
     pub h_prime: BigInt,
     pub n: BigInt, // public parameter for h,h' (modulus n)
-    pub ciphertext: ElGamalCiphertext,
+    pub ciphertext: S::Ciphertext,
     pub sec_param: usize,
-    pub kapa: usize, // size of random sampled s_i,r_i, must be at least 100
+    pub kapa: usize, // size of random sampled s_i, must be at least 100
 }
 
-impl EqProof {
-    pub fn prove(statement: &Statement, witness: &Witness) -> Result<Self, ProofError> {
+// e = H(pk, c, n, h, h', sec_param, kapa, h'_1, c_1, ..., h'_k, c_k), one bit per repetition
+fn challenge<S: HomomorphicScheme>(
+    statement: &Statement<S>,
+    h_prime_i_vec: &[BigInt],
+    ciphertext_i_vec: &[S::Ciphertext],
+    context: &ProofContext,
+) -> BitVec {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigints(b"pk", &S::public_key_to_hash_input(&statement.pk));
+    transcript.append_bigints(b"c", &S::ciphertext_to_hash_input(&statement.ciphertext));
+    transcript.append_bigint(b"n", &statement.n);
+    transcript.append_bigint(b"h", &statement.h);
+    transcript.append_bigint(b"h_prime", &statement.h_prime);
+    transcript.append_u64(b"sec_param", statement.sec_param as u64);
+    transcript.append_u64(b"kapa", statement.kapa as u64);
+    for i in 0..statement.sec_param {
+        transcript.append_bigint(b"h_prime_i", &h_prime_i_vec[i]);
+        transcript.append_bigints(b"c_i", &S::ciphertext_to_hash_input(&ciphertext_i_vec[i]));
+    }
+    BitVec::from_bytes(&transcript.challenge_bytes(b"e", (statement.sec_param + 7) / 8))
+}
+
+impl<S: HomomorphicScheme> EqProof<S> {
+    pub fn prove(
+        statement: &Statement<S>,
+        witness: &Witness<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
         if statement.kapa < 100 {
             return Err(ProofError::EqError);
         }
-        let s_i_vec: Vec<_> = (0..statement.sec_param)
-            .into_par_iter()
-            .map(|_| BigInt::sample(statement.pk.pp.q.bit_length() + statement.kapa))
-            .collect();
-        let r_i_vec: Vec<_> = (0..statement.sec_param)
-            .into_par_iter()
-            .map(|_| BigInt::sample(statement.pk.pp.q.bit_length() + statement.kapa))
-            .collect();
+        let plaintext_bit_length = S::plaintext_modulus(&statement.pk).bit_length();
+        // s_i and r_i mask the witness, Masks wipes them on drop
+        let masks = Masks::<S> {
+            plaintexts: (0..statement.sec_param)
+                .into_par_iter()
+                .map(|_| BigInt::sample(plaintext_bit_length + statement.kapa))
+                .collect(),
+            randomness: (0..statement.sec_param)
+                .into_par_iter()
+                .map(|_| S::sample_randomness(&statement.pk))
+                .collect(),
+        };
+        let (s_i_vec, r_i_vec) = (&masks.plaintexts, &masks.randomness);
 
         let h_prime_i_vec = (0..statement.sec_param)
             .into_par_iter()
             .map(|i| BigInt::mod_pow(&statement.h, &s_i_vec[i], &statement.n))
             .collect::<Vec<BigInt>>();
 
-        let ciphertext_i_vec: Vec<_> = (0..statement.sec_param)
+        let ciphertext_i_vec = (0..statement.sec_param)
             .into_par_iter()
-            .map(|i| {
-                ExponentElGamal::encrypt_from_predefined_randomness(
-                    &(s_i_vec[i]).modulus(&statement.pk.pp.q),
-                    &statement.pk,
-                    &(r_i_vec[i]).modulus(&statement.pk.pp.q),
-                )
-                .unwrap()
-            })
-            .collect();
+            .map(|i| S::encrypt_with_randomness(&statement.pk, &s_i_vec[i], &r_i_vec[i]))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProofError::EqError)?;
 
-        let mut fs_input = vec![&statement.h, &statement.h_prime];
-        for i in 0..statement.sec_param {
-            fs_input.push(&h_prime_i_vec[i]);
-            fs_input.push(&ciphertext_i_vec[i].c2);
-        }
-        let e = HSha256::create_hash(&fs_input);
-        let e_bytes_vec = BigInt::to_vec(&e);
-        let bits_of_e = BitVec::from_bytes(&e_bytes_vec[..]);
+        let bits_of_e = challenge(statement, &h_prime_i_vec, &ciphertext_i_vec, context);
 
         let response_vec: Vec<_> = (0..statement.sec_param)
             .into_par_iter()
@@ -104,7 +154,7 @@ impl EqProof {
                 },
                 true => Response {
                     z1: &witness.x + &s_i_vec[i],
-                    z2: &witness.r + &r_i_vec[i],
+                    z2: S::add_randomness(&statement.pk, &witness.r, &r_i_vec[i]),
                 },
             })
             .collect();
@@ -116,40 +166,66 @@ impl EqProof {
         })
     }
 
-    pub fn verify(&self, statement: &Statement) -> Result<(), ProofError> {
-        let mut fs_input = vec![&statement.h, &statement.h_prime];
-        for i in 0..statement.sec_param {
-            fs_input.push(&self.h_prime_i_vec[i]);
-            fs_input.push(&self.ciphertext_i_vec[i].c2);
+    pub fn verify(
+        &self,
+        statement: &Statement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        if self.ciphertext_i_vec.len() != statement.sec_param
+            || self.h_prime_i_vec.len() != statement.sec_param
+            || self.z_vec.len() != statement.sec_param
+        {
+            return Err(ProofError::EqError);
         }
-        let e = HSha256::create_hash(&fs_input);
-        let e_bytes_vec = BigInt::to_vec(&e);
-        let bits_of_e = BitVec::from_bytes(&e_bytes_vec[..]);
+        let ciphertexts_ok = S::validate_ciphertext(&statement.pk, &statement.ciphertext).is_ok()
+            && self
+                .ciphertext_i_vec
+                .iter()
+                .all(|c_i| S::validate_ciphertext(&statement.pk, c_i).is_ok());
+        // z1 is s_i or x + s_i: a negative exponent of h could have no inverse mod n
+        let z1_bit_bound = S::plaintext_modulus(&statement.pk).bit_length() + statement.kapa + 1;
+        let responses_ok = self
+            .z_vec
+            .iter()
+            .all(|z| z.z1 >= BigInt::zero() && z.z1.bit_length() <= z1_bit_bound);
+        if !ciphertexts_ok || !responses_ok {
+            return Err(ProofError::EqError);
+        }
+        let bits_of_e = challenge(
+            statement,
+            &self.h_prime_i_vec,
+            &self.ciphertext_i_vec,
+            context,
+        );
 
         let checks: Vec<_> = (0..statement.sec_param)
             .into_par_iter()
             .map(|i| match bits_of_e[i] {
                 false => {
-                    ExponentElGamal::encrypt_from_predefined_randomness(
-                        &(self.z_vec[i].z1).modulus(&statement.pk.pp.q),
-                        &statement.pk,
-                        &(self.z_vec[i].z2).modulus(&statement.pk.pp.q),
-                    )
-                    .unwrap()
-                        == self.ciphertext_i_vec[i]
+                    S::encrypt_with_randomness(&statement.pk, &self.z_vec[i].z1, &self.z_vec[i].z2)
+                        .map(|c_i| c_i == self.ciphertext_i_vec[i])
+                        .unwrap_or(false)
                         && BigInt::mod_pow(&statement.h, &self.z_vec[i].z1, &statement.n)
                             == self.h_prime_i_vec[i]
                 }
                 true => {
-                    let c_star = ExponentElGamal::encrypt_from_predefined_randomness(
-                        &(self.z_vec[i].z1).modulus(&statement.pk.pp.q),
+                    let c_star = S::encrypt_with_randomness(
                         &statement.pk,
-                        &(self.z_vec[i].z2).modulus(&statement.pk.pp.q),
-                    )
-                    .unwrap();
+                        &self.z_vec[i].z1,
+                        &self.z_vec[i].z2,
+                    );
+                    let c_c_i = S::add(
+                        &statement.pk,
+                        &statement.ciphertext,
+                        &self.ciphertext_i_vec[i],
+                    );
+
+                    let enc_check = match (c_c_i, c_star) {
+                        (Ok(c_c_i), Ok(c_star)) => c_c_i == c_star,
+                        _ => false,
+                    };
 
-                    ExponentElGamal::add(&statement.ciphertext, &self.ciphertext_i_vec[i]).unwrap()
-                        == c_star
+                    enc_check
                         && BigInt::mod_pow(&statement.h, &self.z_vec[i].z1, &statement.n)
                             == BigInt::mod_mul(
                                 &self.h_prime_i_vec[i],
@@ -160,10 +236,9 @@ impl EqProof {
             })
             .collect();
 
-        if checks.iter().all(|b| *b) {
-            Ok(())
-        } else {
-            Err(ProofError::EqError)
+        match checks.iter().position(|b| !*b) {
+            None => Ok(()),
+            Some(index) => Err(ProofError::EqRepetitionError { index }),
         }
     }
 }
@@ -173,13 +248,18 @@ mod tests {
     use crate::utlities::equal_secret_proof::EqProof;
     use crate::utlities::equal_secret_proof::Statement;
     use crate::utlities::equal_secret_proof::Witness;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::homomorphic_scheme::PaillierScheme;
+    use crate::utlities::transcript::ProofContext;
     use curv::arithmetic::traits::{Modulo, Samplable};
     use curv::BigInt;
     use elgamal::prime::is_prime;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalKeyPair;
     use elgamal::ElGamalPP;
-    use elgamal::ExponentElGamal;
+    use paillier::KeyGeneration;
+    use paillier::Paillier;
 
     #[test]
     fn test_correct_eq_elgamal() {
@@ -199,10 +279,10 @@ mod tests {
         let r = BigInt::sample_below(&pp.q);
         let h_prime = BigInt::mod_pow(&h, &x, &n);
         let ciphertext =
-            ExponentElGamal::encrypt_from_predefined_randomness(&x, &keypair.pk, &r).unwrap();
+            FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &x, &r).unwrap();
         let witness = Witness { x, r };
 
-        let statement = Statement {
+        let statement = Statement::<FFDHEExponentElGamal> {
             pk: keypair.pk.clone(),
             h,
             h_prime,
@@ -212,8 +292,44 @@ mod tests {
             kapa: 100,
         };
 
-        let proof = EqProof::prove(&statement, &witness).unwrap();
-        let verify = proof.verify(&statement);
-        assert!(verify.is_ok())
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let proof = EqProof::prove(&statement, &witness, &context).unwrap();
+        let verify = proof.verify(&statement, &context);
+        assert!(verify.is_ok());
+        assert!(proof
+            .verify(&statement, &ProofContext::new(&[2u8; 32], 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_correct_eq_paillier() {
+        let (ek, _) = Paillier::keypair().keys();
+        let n = ek.n.clone();
+        let h = BigInt::from(3);
+        let x = BigInt::sample_below(&ek.n);
+        let r = PaillierScheme::sample_randomness(&ek);
+        let h_prime = BigInt::mod_pow(&h, &x, &n);
+        let ciphertext = PaillierScheme::encrypt_with_randomness(&ek, &x, &r).unwrap();
+        let witness = Witness { x, r };
+
+        let statement = Statement::<PaillierScheme> {
+            pk: ek,
+            h,
+            h_prime,
+            n,
+            ciphertext,
+            sec_param: 120,
+            kapa: 100,
+        };
+
+        let context = ProofContext::default();
+        let proof = EqProof::prove(&statement, &witness, &context).unwrap();
+        assert!(proof.verify(&statement, &context).is_ok());
+
+        let wrong_statement = Statement::<PaillierScheme> {
+            h_prime: BigInt::mod_mul(&statement.h_prime, &statement.h, &statement.n),
+            ..statement.clone()
+        };
+        assert!(proof.verify(&wrong_statement, &context).is_err());
     }
 }
@@ -0,0 +1,314 @@
+use crate::utlities::ddh_proof::DDHProof;
+use crate::utlities::ddh_proof::DDHStatement;
+use crate::utlities::ddh_proof::DDHWitness;
+use crate::utlities::ddh_proof::NISigmaProof;
+use crate::utlities::group_element::validate_group_elements;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::transcript::ProofContext;
+use crate::HomomorphicSchemeError;
+use crate::ProofError;
+use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::ZeroizeBN;
+use curv::BigInt;
+use elgamal::ElGamalCiphertext;
+use elgamal::ElGamalPrivateKey;
+use elgamal::ElGamalPublicKey;
+use elgamal::ExponentElGamal;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// Additively homomorphic ElGamal "in the exponent" over a group of prime order q, with the
+/// private key shared additively between the parties.
+/// Plaintexts are only recovered through a zero test: a ciphertext (c1, c2) = (g^r, g^m * h^r)
+/// encrypts zero iff c2 equals the product of the decryption shares c1^x_i. This is all the trial
+/// division of HMRT needs, and unlike a full decryption it does not require a discrete log.
+/// Before the zero test a party raises the ciphertext to a secret r, and proves both steps with
+/// a proof of equal discrete logs: the randomization and its decryption share.
+pub trait ExponentElGamalScheme: HomomorphicScheme<Randomness = BigInt> {
+    type PrivateKey: Send + Sync;
+    type DecryptionShare: Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned;
+    type EqualityProof: Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned;
+
+    /// c1^x_i for the share x_i of the private key
+    fn partial_decrypt(c: &Self::Ciphertext, sk: &Self::PrivateKey) -> Self::DecryptionShare;
+
+    /// Combines the decryption shares of all parties and tests whether c encrypts zero
+    fn is_zero(
+        c: &Self::Ciphertext,
+        shares: &[Self::DecryptionShare],
+    ) -> Result<bool, HomomorphicSchemeError>;
+
+    /// Proves that c_random = c^r, both components raised to the same r
+    fn prove_randomization(
+        pk: &Self::PublicKey,
+        c: &Self::Ciphertext,
+        c_random: &Self::Ciphertext,
+        r: &BigInt,
+        context: &ProofContext,
+    ) -> Self::EqualityProof;
+
+    fn verify_randomization(
+        pk: &Self::PublicKey,
+        c: &Self::Ciphertext,
+        c_random: &Self::Ciphertext,
+        proof: &Self::EqualityProof,
+        context: &ProofContext,
+    ) -> Result<(), ProofError>;
+
+    /// Proves that share is the decryption share of c under the private key of pk_i
+    fn prove_partial_decryption(
+        pk_i: &Self::PublicKey,
+        c: &Self::Ciphertext,
+        share: &Self::DecryptionShare,
+        sk: &Self::PrivateKey,
+        context: &ProofContext,
+    ) -> Self::EqualityProof;
+
+    fn verify_partial_decryption(
+        pk_i: &Self::PublicKey,
+        c: &Self::Ciphertext,
+        share: &Self::DecryptionShare,
+        proof: &Self::EqualityProof,
+        context: &ProofContext,
+    ) -> Result<(), ProofError>;
+}
+
+/// Exponent ElGamal over the RFC 7919 finite field groups, a thin wrapper of the elgamal crate
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FFDHEExponentElGamal;
+
+impl HomomorphicScheme for FFDHEExponentElGamal {
+    type PublicKey = ElGamalPublicKey;
+    type Ciphertext = ElGamalCiphertext;
+    type Randomness = BigInt;
+
+    fn plaintext_modulus(pk: &ElGamalPublicKey) -> BigInt {
+        pk.pp.q.clone()
+    }
+
+    fn sample_randomness(pk: &ElGamalPublicKey) -> BigInt {
+        BigInt::sample_below(&pk.pp.q)
+    }
+
+    fn encrypt_with_randomness(
+        pk: &ElGamalPublicKey,
+        m: &BigInt,
+        r: &BigInt,
+    ) -> Result<ElGamalCiphertext, HomomorphicSchemeError> {
+        ExponentElGamal::encrypt_from_predefined_randomness(m, pk, r)
+            .map_err(|_| HomomorphicSchemeError::EncryptionError)
+    }
+
+    fn add(
+        _pk: &ElGamalPublicKey,
+        c_a: &ElGamalCiphertext,
+        c_b: &ElGamalCiphertext,
+    ) -> Result<ElGamalCiphertext, HomomorphicSchemeError> {
+        ExponentElGamal::add(c_a, c_b).map_err(|_| HomomorphicSchemeError::MismatchedGroups)
+    }
+
+    fn mul(
+        pk: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+        k: &BigInt,
+    ) -> Result<ElGamalCiphertext, HomomorphicSchemeError> {
+        Ok(ExponentElGamal::mul(c, &k.mod_floor(&pk.pp.q)))
+    }
+
+    fn add_randomness(pk: &ElGamalPublicKey, r_a: &BigInt, r_b: &BigInt) -> BigInt {
+        BigInt::mod_add(r_a, r_b, &pk.pp.q)
+    }
+
+    fn mul_randomness(pk: &ElGamalPublicKey, r: &BigInt, k: &BigInt) -> BigInt {
+        BigInt::mod_mul(r, &k.mod_floor(&pk.pp.q), &pk.pp.q)
+    }
+
+    fn zeroize_randomness(r: &mut BigInt) {
+        r.zeroize_bn();
+    }
+
+    // the ciphertext carries its own group parameters, which the arithmetic of the elgamal crate
+    // uses as is
+    fn validate_ciphertext(
+        pk: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+    ) -> Result<(), HomomorphicSchemeError> {
+        if c.pp != pk.pp {
+            return Err(HomomorphicSchemeError::InvalidCiphertext);
+        }
+        validate_group_elements(&[&c.c1, &c.c2], &pk.pp)?;
+        Ok(())
+    }
+
+    fn public_key_to_hash_input(pk: &ElGamalPublicKey) -> Vec<BigInt> {
+        vec![pk.h.clone()]
+    }
+
+    fn ciphertext_to_hash_input(c: &ElGamalCiphertext) -> Vec<BigInt> {
+        vec![c.c1.clone(), c.c2.clone()]
+    }
+}
+
+// (c1, c1^r, c2, c2^r)
+fn randomization_statement(
+    pk: &ElGamalPublicKey,
+    c: &ElGamalCiphertext,
+    c_random: &ElGamalCiphertext,
+) -> DDHStatement {
+    DDHStatement {
+        pp: pk.pp.clone(),
+        g1: c.c1.clone(),
+        h1: c_random.c1.clone(),
+        g2: c.c2.clone(),
+        h2: c_random.c2.clone(),
+    }
+}
+
+// (g, h_i = g^x_i, c1, c1^x_i)
+fn partial_decryption_statement(
+    pk_i: &ElGamalPublicKey,
+    c: &ElGamalCiphertext,
+    share: &BigInt,
+) -> DDHStatement {
+    DDHStatement {
+        pp: pk_i.pp.clone(),
+        g1: pk_i.pp.g.clone(),
+        h1: pk_i.h.clone(),
+        g2: c.c1.clone(),
+        h2: share.clone(),
+    }
+}
+
+impl ExponentElGamalScheme for FFDHEExponentElGamal {
+    type PrivateKey = ElGamalPrivateKey;
+    type DecryptionShare = BigInt;
+    type EqualityProof = DDHProof;
+
+    fn partial_decrypt(c: &ElGamalCiphertext, sk: &ElGamalPrivateKey) -> BigInt {
+        BigInt::mod_pow(&c.c1, &sk.x, &c.pp.p)
+    }
+
+    fn is_zero(c: &ElGamalCiphertext, shares: &[BigInt]) -> Result<bool, HomomorphicSchemeError> {
+        if shares.is_empty() {
+            return Err(HomomorphicSchemeError::InvalidDecryptionShares);
+        }
+        let d = shares.iter().fold(BigInt::one(), |acc, share| {
+            BigInt::mod_mul(&acc, share, &c.pp.p)
+        });
+        Ok(d == c.c2.mod_floor(&c.pp.p))
+    }
+
+    fn prove_randomization(
+        pk: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+        c_random: &ElGamalCiphertext,
+        r: &BigInt,
+        context: &ProofContext,
+    ) -> DDHProof {
+        let witness = DDHWitness { x: r.clone() };
+        DDHProof::prove(&witness, &randomization_statement(pk, c, c_random), context)
+    }
+
+    fn verify_randomization(
+        pk: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+        c_random: &ElGamalCiphertext,
+        proof: &DDHProof,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        proof.verify(&randomization_statement(pk, c, c_random), context)
+    }
+
+    fn prove_partial_decryption(
+        pk_i: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+        share: &BigInt,
+        sk: &ElGamalPrivateKey,
+        context: &ProofContext,
+    ) -> DDHProof {
+        let witness = DDHWitness { x: sk.x.clone() };
+        DDHProof::prove(
+            &witness,
+            &partial_decryption_statement(pk_i, c, share),
+            context,
+        )
+    }
+
+    fn verify_partial_decryption(
+        pk_i: &ElGamalPublicKey,
+        c: &ElGamalCiphertext,
+        share: &BigInt,
+        proof: &DDHProof,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        proof.verify(&partial_decryption_statement(pk_i, c, share), context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::exponent_elgamal::ExponentElGamalScheme;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use curv::arithmetic::traits::Modulo;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalKeyPair;
+    use elgamal::ElGamalPP;
+
+    #[test]
+    fn test_ffdhe_zero_test_with_shared_key() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair1 = ElGamalKeyPair::generate(&pp);
+        let keypair2 = ElGamalKeyPair::generate(&pp);
+        let joint_pk = keypair1.pk.add(&keypair2.pk).unwrap();
+
+        let a = BigInt::from(17);
+        let r_a = BigInt::sample_below(&pp.q);
+        let r_b = BigInt::sample_below(&pp.q);
+        let c_a = FFDHEExponentElGamal::encrypt_with_randomness(&joint_pk, &a, &r_a).unwrap();
+        let c_minus_a =
+            FFDHEExponentElGamal::encrypt_with_randomness(&joint_pk, &(&pp.q - &a), &r_b).unwrap();
+        let c_zero = FFDHEExponentElGamal::add(&joint_pk, &c_a, &c_minus_a).unwrap();
+
+        let zero_shares = [
+            FFDHEExponentElGamal::partial_decrypt(&c_zero, &keypair1.sk),
+            FFDHEExponentElGamal::partial_decrypt(&c_zero, &keypair2.sk),
+        ];
+        assert!(FFDHEExponentElGamal::is_zero(&c_zero, &zero_shares).unwrap());
+        // a single share does not decrypt
+        assert!(!FFDHEExponentElGamal::is_zero(&c_zero, &zero_shares[..1]).unwrap());
+
+        let shares = [
+            FFDHEExponentElGamal::partial_decrypt(&c_a, &keypair1.sk),
+            FFDHEExponentElGamal::partial_decrypt(&c_a, &keypair2.sk),
+        ];
+        assert!(!FFDHEExponentElGamal::is_zero(&c_a, &shares).unwrap());
+    }
+
+    #[test]
+    fn test_ffdhe_validate_ciphertext() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let r = BigInt::sample_below(&pp.q);
+        let c = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &BigInt::from(3), &r)
+            .unwrap();
+        assert!(FFDHEExponentElGamal::validate_ciphertext(&keypair.pk, &c).is_ok());
+
+        let mut zero_modulus = c.clone();
+        zero_modulus.pp.p = BigInt::zero();
+        assert!(FFDHEExponentElGamal::validate_ciphertext(&keypair.pk, &zero_modulus).is_err());
+
+        let mut zero_c1 = c.clone();
+        zero_c1.c1 = BigInt::zero();
+        assert!(FFDHEExponentElGamal::validate_ciphertext(&keypair.pk, &zero_c1).is_err());
+
+        // c2 * (p - 1) is in Z_p^* but not in the subgroup of order q
+        let mut small_order_c2 = c;
+        small_order_c2.c2 = BigInt::mod_mul(&small_order_c2.c2, &(&pp.p - BigInt::one()), &pp.p);
+        assert!(FFDHEExponentElGamal::validate_ciphertext(&keypair.pk, &small_order_c2).is_err());
+    }
+}
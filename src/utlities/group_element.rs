@@ -0,0 +1,109 @@
+use crate::utlities::jacobi;
+use crate::HomomorphicSchemeError;
+use curv::arithmetic::traits::Modulo;
+use curv::BigInt;
+use elgamal::ElGamalPP;
+
+/// An element of the subgroup of prime order q of Z_p^* for ElGamal parameters pp, that is
+/// 1 < x < p and x^q = 1 mod p.
+/// Elements received from the counterparty arrive as plain BigInts: converting them with
+/// `from_bigint` before use keeps elements of small order, or values outside of Z_p^*, away from
+/// the exponentiations with our secrets (e.g. the joint ElGamal key or a partial decryption).
+///
+/// Validation happens when a message is verified, not when it is deserialized: messages and
+/// statements keep plain BigInts because the group they belong to (the proposed RFC 7919 group,
+/// or the joint key's `pp`) is only known to the verifier, and serde has no way to pass it to a
+/// `Deserialize` impl. Every verifier that uses a counterparty element therefore starts with
+/// `from_bigint`, `validate_group_elements` or `HomomorphicScheme::validate_ciphertext` and
+/// aborts before touching its secrets; a new verifier has to do the same.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GroupElement {
+    x: BigInt,
+}
+
+impl GroupElement {
+    pub fn from_bigint(x: &BigInt, pp: &ElGamalPP) -> Result<Self, HomomorphicSchemeError> {
+        if x <= &BigInt::one() || x >= &pp.p || !is_in_subgroup(x, pp) {
+            return Err(HomomorphicSchemeError::NotInSubgroup);
+        }
+        Ok(GroupElement { x: x.clone() })
+    }
+
+    pub fn as_bigint(&self) -> &BigInt {
+        &self.x
+    }
+
+    pub fn into_bigint(self) -> BigInt {
+        self.x
+    }
+}
+
+/// Validates all elements at once, fails on the first that is not in the subgroup
+pub fn validate_group_elements(
+    elements: &[&BigInt],
+    pp: &ElGamalPP,
+) -> Result<Vec<GroupElement>, HomomorphicSchemeError> {
+    elements
+        .iter()
+        .map(|x| GroupElement::from_bigint(x, pp))
+        .collect()
+}
+
+// x^q = 1. For a safe prime p = 2q + 1 (all RFC 7919 groups) this is Euler's criterion, and the
+// Jacobi symbol gives the same answer without an exponentiation
+fn is_in_subgroup(x: &BigInt, pp: &ElGamalPP) -> bool {
+    if pp.p == BigInt::from(2) * &pp.q + BigInt::one() {
+        jacobi(x, &pp.p) == 1
+    } else {
+        BigInt::mod_pow(x, &pp.q, &pp.p) == BigInt::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::group_element::validate_group_elements;
+    use crate::utlities::group_element::GroupElement;
+    use curv::arithmetic::traits::Modulo;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalPP;
+
+    #[test]
+    fn test_group_element() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let r = BigInt::sample_below(&pp.q);
+        let x = BigInt::mod_pow(&pp.g, &r, &pp.p);
+        let element = GroupElement::from_bigint(&x, &pp).unwrap();
+        assert_eq!(element.as_bigint(), &x);
+        assert!(GroupElement::from_bigint(&pp.g, &pp).is_ok());
+
+        // the generic check agrees with the Jacobi symbol shortcut
+        assert_eq!(BigInt::mod_pow(&x, &pp.q, &pp.p), BigInt::one());
+    }
+
+    #[test]
+    fn test_bad_group_elements() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let p_minus_one = &pp.p - BigInt::one();
+        // p - 1 has order 2, g * (p - 1) is in Z_p^* but of order 2q
+        let g_times_minus_one = BigInt::mod_mul(&pp.g, &p_minus_one, &pp.p);
+        for x in [
+            BigInt::zero(),
+            BigInt::one(),
+            BigInt::zero() - &pp.g,
+            p_minus_one,
+            g_times_minus_one,
+            pp.p.clone(),
+            &pp.p + &pp.g,
+        ]
+        .iter()
+        {
+            assert!(GroupElement::from_bigint(x, &pp).is_err());
+        }
+
+        let g_square = BigInt::mod_mul(&pp.g, &pp.g, &pp.p);
+        assert!(validate_group_elements(&[&pp.g, &g_square], &pp).is_ok());
+        assert!(validate_group_elements(&[&pp.g, &BigInt::one()], &pp).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::HomomorphicSchemeError;
+use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::ZeroizeBN;
+use curv::BigInt;
+use paillier::traits::EncryptWithChosenRandomness;
+use paillier::EncryptionKey;
+use paillier::Paillier;
+use paillier::Randomness;
+use paillier::RawPlaintext;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// Additively homomorphic encryption, everything the sigma protocols of this module need from a
+/// cryptosystem: encryption with chosen randomness, homomorphic addition and multiplication by
+/// a scalar, and the same operations on randomness so that a prover can open Enc(a) * Enc(b)^k.
+/// Scalars are reduced modulo the plaintext modulus, hence multiplying by -1 negates.
+pub trait HomomorphicScheme {
+    type PublicKey: Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned;
+    type Ciphertext: Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned;
+    type Randomness: Clone + PartialEq + Debug + Send + Sync + Serialize + DeserializeOwned;
+
+    /// plaintexts are taken modulo this value, q for ElGamal and N for Paillier
+    fn plaintext_modulus(pk: &Self::PublicKey) -> BigInt;
+
+    fn sample_randomness(pk: &Self::PublicKey) -> Self::Randomness;
+
+    fn encrypt_with_randomness(
+        pk: &Self::PublicKey,
+        m: &BigInt,
+        r: &Self::Randomness,
+    ) -> Result<Self::Ciphertext, HomomorphicSchemeError>;
+
+    /// Enc(m_a + m_b) from Enc(m_a) and Enc(m_b)
+    fn add(
+        pk: &Self::PublicKey,
+        c_a: &Self::Ciphertext,
+        c_b: &Self::Ciphertext,
+    ) -> Result<Self::Ciphertext, HomomorphicSchemeError>;
+
+    /// Enc(k * m) from Enc(m)
+    fn mul(
+        pk: &Self::PublicKey,
+        c: &Self::Ciphertext,
+        k: &BigInt,
+    ) -> Result<Self::Ciphertext, HomomorphicSchemeError>;
+
+    /// randomness of add(Enc(m_a, r_a), Enc(m_b, r_b))
+    fn add_randomness(
+        pk: &Self::PublicKey,
+        r_a: &Self::Randomness,
+        r_b: &Self::Randomness,
+    ) -> Self::Randomness;
+
+    /// randomness of mul(Enc(m, r), k)
+    fn mul_randomness(pk: &Self::PublicKey, r: &Self::Randomness, k: &BigInt) -> Self::Randomness;
+
+    /// Overwrites randomness that must stay secret, e.g. the randomness of a witness
+    fn zeroize_randomness(r: &mut Self::Randomness);
+
+    /// Checks that a ciphertext received from another party is a ciphertext under pk, so that
+    /// the homomorphic operations on it cannot fail on a malformed value
+    fn validate_ciphertext(
+        pk: &Self::PublicKey,
+        c: &Self::Ciphertext,
+    ) -> Result<(), HomomorphicSchemeError>;
+
+    /// Fiat-Shamir input for a public key
+    fn public_key_to_hash_input(pk: &Self::PublicKey) -> Vec<BigInt>;
+
+    /// Fiat-Shamir input for a ciphertext
+    fn ciphertext_to_hash_input(c: &Self::Ciphertext) -> Vec<BigInt>;
+}
+
+/// Plaintexts and randomness sampled by a prover to mask its witness. They are zeroized on drop,
+/// so also when the proof fails half way.
+pub(crate) struct Masks<S: HomomorphicScheme> {
+    pub plaintexts: Vec<BigInt>,
+    pub randomness: Vec<S::Randomness>,
+}
+
+impl<S: HomomorphicScheme> Drop for Masks<S> {
+    fn drop(&mut self) {
+        self.plaintexts.iter_mut().for_each(|m| m.zeroize_bn());
+        self.randomness.iter_mut().for_each(S::zeroize_randomness);
+    }
+}
+
+/// Paillier encryption (1 + N)^m * r^N mod N^2, randomness is the unit r of Z_N
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaillierScheme;
+
+impl HomomorphicScheme for PaillierScheme {
+    type PublicKey = EncryptionKey;
+    type Ciphertext = BigInt;
+    type Randomness = BigInt;
+
+    fn plaintext_modulus(pk: &EncryptionKey) -> BigInt {
+        pk.n.clone()
+    }
+
+    fn sample_randomness(pk: &EncryptionKey) -> BigInt {
+        BigInt::sample_below(&pk.n)
+    }
+
+    fn encrypt_with_randomness(
+        pk: &EncryptionKey,
+        m: &BigInt,
+        r: &BigInt,
+    ) -> Result<BigInt, HomomorphicSchemeError> {
+        if r.gcd(&pk.n) != BigInt::one() {
+            return Err(HomomorphicSchemeError::EncryptionError);
+        }
+        Ok(Paillier::encrypt_with_chosen_randomness(
+            pk,
+            RawPlaintext::from(m.mod_floor(&pk.n)),
+            &Randomness(r.clone()),
+        )
+        .0
+        .into_owned())
+    }
+
+    fn add(
+        pk: &EncryptionKey,
+        c_a: &BigInt,
+        c_b: &BigInt,
+    ) -> Result<BigInt, HomomorphicSchemeError> {
+        Ok(BigInt::mod_mul(c_a, c_b, &pk.nn))
+    }
+
+    fn mul(pk: &EncryptionKey, c: &BigInt, k: &BigInt) -> Result<BigInt, HomomorphicSchemeError> {
+        Ok(BigInt::mod_pow(c, &k.mod_floor(&pk.n), &pk.nn))
+    }
+
+    fn add_randomness(pk: &EncryptionKey, r_a: &BigInt, r_b: &BigInt) -> BigInt {
+        BigInt::mod_mul(r_a, r_b, &pk.n)
+    }
+
+    fn mul_randomness(pk: &EncryptionKey, r: &BigInt, k: &BigInt) -> BigInt {
+        BigInt::mod_pow(r, &k.mod_floor(&pk.n), &pk.n)
+    }
+
+    fn zeroize_randomness(r: &mut BigInt) {
+        r.zeroize_bn();
+    }
+
+    fn validate_ciphertext(pk: &EncryptionKey, c: &BigInt) -> Result<(), HomomorphicSchemeError> {
+        if c <= &BigInt::zero() || c >= &pk.nn {
+            return Err(HomomorphicSchemeError::InvalidCiphertext);
+        }
+        Ok(())
+    }
+
+    fn public_key_to_hash_input(pk: &EncryptionKey) -> Vec<BigInt> {
+        vec![pk.n.clone()]
+    }
+
+    fn ciphertext_to_hash_input(c: &BigInt) -> Vec<BigInt> {
+        vec![c.clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::homomorphic_scheme::PaillierScheme;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use paillier::KeyGeneration;
+    use paillier::Paillier;
+
+    #[test]
+    fn test_paillier_scheme_randomness_arithmetic() {
+        let (ek, _) = Paillier::keypair().keys();
+        let a = BigInt::sample_below(&ek.n);
+        let b = BigInt::sample_below(&ek.n);
+        let r_a = PaillierScheme::sample_randomness(&ek);
+        let r_b = PaillierScheme::sample_randomness(&ek);
+        let c_a = PaillierScheme::encrypt_with_randomness(&ek, &a, &r_a).unwrap();
+        let c_b = PaillierScheme::encrypt_with_randomness(&ek, &b, &r_b).unwrap();
+
+        // Enc(a) - Enc(b) opens to a - b with randomness r_a * r_b^-1
+        let minus_c_b = PaillierScheme::mul(&ek, &c_b, &-BigInt::one()).unwrap();
+        let c_diff = PaillierScheme::add(&ek, &c_a, &minus_c_b).unwrap();
+        let minus_r_b = PaillierScheme::mul_randomness(&ek, &r_b, &-BigInt::one());
+        let r_diff = PaillierScheme::add_randomness(&ek, &r_a, &minus_r_b);
+        assert_eq!(
+            PaillierScheme::encrypt_with_randomness(&ek, &(&a - &b), &r_diff).unwrap(),
+            c_diff
+        );
+
+        assert!(PaillierScheme::encrypt_with_randomness(&ek, &a, &ek.n).is_err());
+    }
+}
@@ -1,22 +1,72 @@
 use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::BigInt;
-use elgamal::ElGamalPP;
+use std::fmt;
 
+pub mod affine_proof;
 pub mod ddh_proof;
 pub mod dlog_proof;
+pub mod ec_elgamal;
 pub mod elgamal_enc_proof;
 pub mod equal_secret_proof;
+pub mod exponent_elgamal;
+pub mod group_element;
+pub mod homomorphic_scheme;
 pub mod mod_proof;
 pub mod range_proof;
+pub mod rsa_ddh_proof;
+pub mod sealed_share;
+pub mod tight_range_proof;
+pub mod transcript;
 
-// This function implements H: {0,1}* -> Z_q
-fn hash(input: &[&BigInt], pp: &ElGamalPP, hash_output_bitlen: usize) -> BigInt {
+// This function implements H: {0,1}* -> Z_n for an arbitrary modulus n (e.g. an RSA modulus)
+pub fn hash_to_zn(input: &[&BigInt], n: &BigInt) -> BigInt {
     let mut res = HSha256::create_hash(input);
-    while res.bit_length() < 2 * pp.q.bit_length() {
-        res = (&res << hash_output_bitlen) + HSha256::create_hash(&[&res]);
+    while res.bit_length() < 2 * n.bit_length() {
+        res = (&res << 256) + HSha256::create_hash(&[&res]);
+    }
+    res.modulus(n)
+}
+
+// Jacobi symbol (a/n) for an odd positive n, computed with the binary algorithm
+pub fn jacobi(a: &BigInt, n: &BigInt) -> i8 {
+    let zero = BigInt::zero();
+    let two = BigInt::from(2);
+    let three = BigInt::from(3);
+    let four = BigInt::from(4);
+    let eight = BigInt::from(8);
+
+    let mut a = a.mod_floor(n);
+    let mut n = n.clone();
+    let mut t: i8 = 1;
+    while a != zero {
+        while a.mod_floor(&two) == zero {
+            a = a.div_floor(&two);
+            let n_mod_8 = n.mod_floor(&eight);
+            if n_mod_8 == three || n_mod_8 == BigInt::from(5) {
+                t = -t;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a.mod_floor(&four) == three && n.mod_floor(&four) == three {
+            t = -t;
+        }
+        a = a.mod_floor(&n);
+    }
+    if n == BigInt::one() {
+        t
+    } else {
+        0
+    }
+}
+
+// Debug output of a secret field: secrets must never end up in logs
+pub(crate) struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[redacted]")
     }
-    res.modulus(&pp.q)
 }
 
 // BoringSSL's table.
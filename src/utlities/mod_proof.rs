@@ -1,160 +1,253 @@
-use crate::utlities::range_proof::RangeProof;
-use crate::utlities::range_proof::Statement as RangeStatement;
-use crate::utlities::range_proof::Witness as RangeWitness;
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::tight_range_proof::Statement as TightRangeStatement;
+use crate::utlities::tight_range_proof::TightRangeProof;
+use crate::utlities::tight_range_proof::Witness as TightRangeWitness;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::Redacted;
 use crate::ProofError;
 use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::ZeroizeBN;
 use curv::BigInt;
-use elgamal::ElGamalCiphertext;
-use elgamal::ElGamalPublicKey;
-use elgamal::ExponentElGamal;
-
-const SECPARAM: usize = 120;
-const KAPA: usize = 100;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::marker::PhantomData;
+use zeroize::Zeroize;
 
 /// taken from page 13 of [https://eprint.iacr.org/2011/494.pdf] bullet 2:
 /// zk proof that a ciphertexts {c,c'} encrypt plaintexts {a, b = a mod p}
 /// We define c'' = (c * c'^-1 ) ^ p^(-1). c'' encrypts plaintext d
-/// The protocol is composed of two range proofs:
-/// 1) b < p
-/// 2) d < ceil M/p rounded from above where M is the upper bound on the size of alpha
+/// The protocol is composed of two tight range proofs, without slack:
+/// 1) b < p: b is the residue itself, not only congruent to it
+/// 2) d < ceil(M/p) where M is the exclusive upper bound on a, so that a = b + p * d holds over
+/// the integers and not only modulo the plaintext modulus
+/// The range proofs are those of R, the tight range proof by default. For exponent ElGamal over
+/// an RFC 7919 group, `BulletproofRangeProof` gives shorter proofs.
 ///
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct ModProof {
-    pub range_proof1: RangeProof,
-    pub range_proof2: RangeProof,
+#[serde(bound = "")]
+pub struct ModProof<
+    S: HomomorphicScheme = FFDHEExponentElGamal,
+    R: ModRangeProof<S> = TightRangeProof<S>,
+> {
+    pub range_proof1: R,
+    pub range_proof2: R,
+    #[serde(skip)]
+    _scheme: PhantomData<S>,
 }
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct ModWitness {
-    pub r_a: BigInt,
+/// Exact range proof 0 <= x < range behind the two statements of a `ModProof`
+pub trait ModRangeProof<S: HomomorphicScheme>: Serialize + DeserializeOwned {
+    fn prove(
+        witness: &TightRangeWitness<S>,
+        statement: &TightRangeStatement<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError>;
+
+    fn verify(
+        &self,
+        statement: &TightRangeStatement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError>;
+}
+
+impl<S: HomomorphicScheme> ModRangeProof<S> for TightRangeProof<S> {
+    fn prove(
+        witness: &TightRangeWitness<S>,
+        statement: &TightRangeStatement<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        TightRangeProof::prove(witness, statement, context)
+    }
+
+    fn verify(
+        &self,
+        statement: &TightRangeStatement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        TightRangeProof::verify(self, statement, context)
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ModWitness<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub r_a: S::Randomness,
     pub a: BigInt,
-    pub r_b: BigInt,
+    pub r_b: S::Randomness,
     pub b: BigInt,
 }
 
+impl<S: HomomorphicScheme> Zeroize for ModWitness<S> {
+    fn zeroize(&mut self) {
+        S::zeroize_randomness(&mut self.r_a);
+        self.a.zeroize_bn();
+        S::zeroize_randomness(&mut self.r_b);
+        self.b.zeroize_bn();
+    }
+}
+
+impl<S: HomomorphicScheme> Drop for ModWitness<S> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<S: HomomorphicScheme> fmt::Debug for ModWitness<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ModWitness")
+            .field("r_a", &Redacted)
+            .field("a", &Redacted)
+            .field("r_b", &Redacted)
+            .field("b", &Redacted)
+            .finish()
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct ModStatement {
-    pub c: ElGamalCiphertext,
-    pub c_prime: ElGamalCiphertext,
+#[serde(bound = "")]
+pub struct ModStatement<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub c: S::Ciphertext,
+    pub c_prime: S::Ciphertext,
     pub modulus_p: BigInt,
     pub upper_bound_m: BigInt, // This is M
-    pub pk: ElGamalPublicKey,
+    pub pk: S::PublicKey,
 }
 
-impl ModProof {
-    pub fn prove(witness: &ModWitness, statement: &ModStatement) -> Result<Self, ProofError> {
-        let minus_c_prime = ExponentElGamal::mul(&statement.c_prime, &-BigInt::one());
-        let c_minus_c_prime = ExponentElGamal::add(&statement.c, &minus_c_prime).unwrap();
-        let p_inv = statement.modulus_p.invert(&statement.pk.pp.q);
-        if p_inv.is_none() {
-            return Err(ProofError::ModProofError);
-        }
-        let c_double_prime = ExponentElGamal::mul(&c_minus_c_prime, &p_inv.clone().unwrap());
-
-        // TODO: check security / move to tight range proof.
-        let range_1 = BigInt::from(3) * &statement.modulus_p; // we compensate for the "slack" in the current range proof.
-        let mut range_2 =
-            if statement.upper_bound_m.mod_floor(&statement.modulus_p) == BigInt::zero() {
-                statement.upper_bound_m.div_floor(&statement.modulus_p)
-            } else {
-                statement.upper_bound_m.div_floor(&statement.modulus_p) + &statement.modulus_p
-            };
-        range_2 = range_2 * BigInt::from(3);
+// c'' = (c * c'^-1) ^ p^(-1), together with p^-1 modulo the plaintext modulus
+fn double_prime<S: HomomorphicScheme>(
+    statement: &ModStatement<S>,
+) -> Result<(S::Ciphertext, BigInt), ProofError> {
+    let p_inv = match statement
+        .modulus_p
+        .invert(&S::plaintext_modulus(&statement.pk))
+    {
+        Some(p_inv) => p_inv,
+        None => return Err(ProofError::ModProofError),
+    };
+    let minus_c_prime = S::mul(&statement.pk, &statement.c_prime, &-BigInt::one())
+        .map_err(ProofError::SchemeError)?;
+    let c_minus_c_prime =
+        S::add(&statement.pk, &statement.c, &minus_c_prime).map_err(ProofError::SchemeError)?;
+    let c_double_prime =
+        S::mul(&statement.pk, &c_minus_c_prime, &p_inv).map_err(ProofError::SchemeError)?;
+    Ok((c_double_prime, p_inv))
+}
+
+// ceil(M/p), the exclusive bound on d = (a - b) / p for a < M
+fn quotient_range<S: HomomorphicScheme>(statement: &ModStatement<S>) -> BigInt {
+    (&statement.upper_bound_m + &statement.modulus_p - BigInt::one())
+        .div_floor(&statement.modulus_p)
+}
+
+// a failed range proof of statement 1 (c') or 2 (c''), keeping the failing bit
+fn mod_range_proof_error(statement: usize, err: ProofError) -> ProofError {
+    let bit = match err {
+        ProofError::RangeProofBitError { index } => Some(index),
+        _ => None,
+    };
+    ProofError::ModRangeProofError { statement, bit }
+}
 
-        let r_a_mul_minus_r_b = BigInt::mod_sub(&witness.r_a, &witness.r_b, &statement.pk.pp.q);
-        let r_d = BigInt::mod_mul(&r_a_mul_minus_r_b, &p_inv.unwrap(), &statement.pk.pp.q);
+impl<S: HomomorphicScheme, R: ModRangeProof<S>> ModProof<S, R> {
+    pub fn prove(
+        witness: &ModWitness<S>,
+        statement: &ModStatement<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        let (c_double_prime, p_inv) = double_prime(statement)?;
 
-        let d = (BigInt::mod_sub(&witness.a, &witness.b, &statement.pk.pp.q))
+        let minus_r_b = S::mul_randomness(&statement.pk, &witness.r_b, &-BigInt::one());
+        let r_a_minus_r_b = S::add_randomness(&statement.pk, &witness.r_a, &minus_r_b);
+        let r_d = S::mul_randomness(&statement.pk, &r_a_minus_r_b, &p_inv);
+
+        let d = (BigInt::mod_sub(&witness.a, &witness.b, &S::plaintext_modulus(&statement.pk)))
             .div_floor(&statement.modulus_p);
 
-        let range_witness1 = RangeWitness {
+        let range_witness1 = TightRangeWitness::<S> {
             x: witness.b.clone(),
             r: witness.r_b.clone(),
         };
-        let range_witness2 = RangeWitness { x: d, r: r_d };
-        let range_statement1 = RangeStatement {
+        let range_witness2 = TightRangeWitness::<S> { x: d, r: r_d };
+        let range_statement1 = TightRangeStatement::<S> {
             pk: statement.pk.clone(),
-            range: range_1,
+            range: statement.modulus_p.clone(),
             ciphertext: statement.c_prime.clone(),
-            sec_param: SECPARAM,
-            kapa: KAPA,
         };
 
-        let range_statement2 = RangeStatement {
+        let range_statement2 = TightRangeStatement::<S> {
             pk: statement.pk.clone(),
-            range: range_2,
-            ciphertext: c_double_prime.clone(),
-            sec_param: SECPARAM,
-            kapa: KAPA,
+            range: quotient_range(statement),
+            ciphertext: c_double_prime,
         };
 
-        let range_proof1 = RangeProof::prove(&range_witness1, &range_statement1);
-        let range_proof2 = RangeProof::prove(&range_witness2, &range_statement2);
+        let range_proof1 = R::prove(&range_witness1, &range_statement1, context)
+            .map_err(|err| mod_range_proof_error(1, err))?;
+        let range_proof2 = R::prove(&range_witness2, &range_statement2, context)
+            .map_err(|err| mod_range_proof_error(2, err))?;
 
-        match range_proof1.is_ok() && range_proof2.is_ok() {
-            true => Ok(ModProof {
-                range_proof1: range_proof1.unwrap(),
-                range_proof2: range_proof2.unwrap(),
-            }),
-            false => Err(ProofError::RangeProofError),
-        }
+        Ok(ModProof {
+            range_proof1,
+            range_proof2,
+            _scheme: PhantomData,
+        })
     }
 
-    pub fn verify(&self, statement: &ModStatement) -> Result<(), ProofError> {
-        let range_1 = BigInt::from(3) * &statement.modulus_p; // we compensate for the "slack" in the current range proof.
-        let mut range_2 =
-            if statement.upper_bound_m.mod_floor(&statement.modulus_p) == BigInt::zero() {
-                statement.upper_bound_m.div_floor(&statement.modulus_p)
-            } else {
-                statement.upper_bound_m.div_floor(&statement.modulus_p) + &statement.modulus_p
-            };
-        range_2 = range_2 * BigInt::from(3);
-
-        let minus_c_prime = ExponentElGamal::mul(&statement.c_prime, &-BigInt::one());
-        let c_minus_c_prime = ExponentElGamal::add(&statement.c, &minus_c_prime).unwrap();
-        let p_inv = statement.modulus_p.invert(&statement.pk.pp.q);
-        if p_inv.is_none() {
+    pub fn verify(
+        &self,
+        statement: &ModStatement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        if statement.modulus_p <= BigInt::zero() {
             return Err(ProofError::ModProofError);
         }
-        let c_double_prime = ExponentElGamal::mul(&c_minus_c_prime, &p_inv.unwrap());
 
-        let range_statement1 = RangeStatement {
+        S::validate_ciphertext(&statement.pk, &statement.c)
+            .and(S::validate_ciphertext(&statement.pk, &statement.c_prime))
+            .map_err(ProofError::SchemeError)?;
+        let (c_double_prime, _) = double_prime(statement)?;
+
+        let range_statement1 = TightRangeStatement::<S> {
             pk: statement.pk.clone(),
-            range: range_1,
+            range: statement.modulus_p.clone(),
             ciphertext: statement.c_prime.clone(),
-            sec_param: SECPARAM,
-            kapa: KAPA,
         };
 
-        let range_statement2 = RangeStatement {
+        let range_statement2 = TightRangeStatement::<S> {
             pk: statement.pk.clone(),
-            range: range_2,
-            ciphertext: c_double_prime.clone(),
-            sec_param: SECPARAM,
-            kapa: KAPA,
+            range: quotient_range(statement),
+            ciphertext: c_double_prime,
         };
 
-        match self.range_proof1.verify(&range_statement1).is_ok()
-            && self.range_proof2.verify(&range_statement2).is_ok()
-        {
-            true => Ok(()),
-            false => Err(ProofError::ModProofError),
-        }
+        self.range_proof1
+            .verify(&range_statement1, context)
+            .map_err(|err| mod_range_proof_error(1, err))?;
+        self.range_proof2
+            .verify(&range_statement2, context)
+            .map_err(|err| mod_range_proof_error(2, err))
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use crate::protocols::bulletproofs::bulletproof::BulletproofRangeProof;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::homomorphic_scheme::PaillierScheme;
     use crate::utlities::mod_proof::ModProof;
     use crate::utlities::mod_proof::ModStatement;
     use crate::utlities::mod_proof::ModWitness;
+    use crate::utlities::transcript::ProofContext;
+    use crate::ProofError;
     use curv::arithmetic::traits::Samplable;
     use curv::BigInt;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalKeyPair;
     use elgamal::ElGamalPP;
-    use elgamal::ExponentElGamal;
+    use paillier::KeyGeneration;
+    use paillier::Paillier;
 
     #[test]
     pub fn test_mod_proof() {
@@ -167,12 +260,11 @@ mod tests {
             let modulus_p = BigInt::from(71);
             let b = a.mod_floor(&modulus_p);
             let r_b = BigInt::sample_below(&pp.q);
-            let c =
-                ExponentElGamal::encrypt_from_predefined_randomness(&a, &keypair.pk, &r_a).unwrap();
+            let c = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &a, &r_a).unwrap();
             let c_prime =
-                ExponentElGamal::encrypt_from_predefined_randomness(&b, &keypair.pk, &r_b).unwrap();
+                FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &b, &r_b).unwrap();
             let witness = ModWitness { r_a, a, r_b, b };
-            let statement = ModStatement {
+            let statement = ModStatement::<FFDHEExponentElGamal> {
                 c,
                 c_prime,
                 modulus_p,
@@ -180,9 +272,140 @@ mod tests {
                 pk: keypair.pk,
             };
 
-            let proof = ModProof::prove(&witness, &statement).unwrap();
-            let verify = proof.verify(&statement);
+            let proof = ModProof::<FFDHEExponentElGamal>::prove(
+                &witness,
+                &statement,
+                &ProofContext::default(),
+            )
+            .unwrap();
+            let verify = proof.verify(&statement, &ProofContext::default());
             assert!(verify.is_ok());
         }
     }
+
+    #[test]
+    pub fn test_mod_proof_with_bulletproofs() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let share_bit_size: usize = pp.q.bit_length() / 2 - 2;
+        let a = BigInt::sample(share_bit_size);
+        let r_a = BigInt::sample_below(&pp.q);
+        let modulus_p = BigInt::from(71);
+        let b = a.mod_floor(&modulus_p);
+        let r_b = BigInt::sample_below(&pp.q);
+        let c = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &a, &r_a).unwrap();
+        let c_prime = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &b, &r_b).unwrap();
+        let witness = ModWitness { r_a, a, r_b, b };
+        let statement = ModStatement::<FFDHEExponentElGamal> {
+            c,
+            c_prime,
+            modulus_p,
+            upper_bound_m: BigInt::from(2).pow(share_bit_size as u32),
+            pk: keypair.pk,
+        };
+        let context = ProofContext::default();
+
+        let proof = ModProof::<FFDHEExponentElGamal, BulletproofRangeProof>::prove(
+            &witness, &statement, &context,
+        )
+        .unwrap();
+        assert!(proof.verify(&statement, &context).is_ok());
+
+        // the modulus is part of both range statements
+        let mut other_modulus = statement.clone();
+        other_modulus.modulus_p = BigInt::from(73);
+        assert!(proof.verify(&other_modulus, &context).is_err());
+    }
+
+    #[test]
+    pub fn test_mod_proof_residue_out_of_range() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let share_bit_size: usize = pp.q.bit_length() / 2 - 2;
+        let a = BigInt::sample(share_bit_size);
+        let r_a = BigInt::sample_below(&pp.q);
+        let modulus_p = BigInt::from(71);
+        // b = a mod p is congruent to a, but b + p is not the residue
+        let b = a.mod_floor(&modulus_p) + &modulus_p;
+        let r_b = BigInt::sample_below(&pp.q);
+        let c = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &a, &r_a).unwrap();
+        let c_prime = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &b, &r_b).unwrap();
+        let witness = ModWitness { r_a, a, r_b, b };
+        let statement = ModStatement::<FFDHEExponentElGamal> {
+            c,
+            c_prime,
+            modulus_p,
+            upper_bound_m: BigInt::from(2).pow(share_bit_size as u32),
+            pk: keypair.pk,
+        };
+
+        // the error names the range proof of c' that could not be generated
+        assert_eq!(
+            ModProof::<FFDHEExponentElGamal>::prove(&witness, &statement, &ProofContext::default())
+                .unwrap_err(),
+            ProofError::ModRangeProofError {
+                statement: 1,
+                bit: None,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_mod_proof_quotient_out_of_range() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let share_bit_size: usize = pp.q.bit_length() / 2 - 2;
+        let upper_bound_m = BigInt::from(2).pow(share_bit_size as u32);
+        // a >= M: the residue is in range but a = b + p * d needs d > ceil(M/p)
+        let a = BigInt::from(2) * &upper_bound_m + BigInt::sample(share_bit_size);
+        let r_a = BigInt::sample_below(&pp.q);
+        let modulus_p = BigInt::from(71);
+        let b = a.mod_floor(&modulus_p);
+        let r_b = BigInt::sample_below(&pp.q);
+        let c = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &a, &r_a).unwrap();
+        let c_prime = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &b, &r_b).unwrap();
+        let witness = ModWitness { r_a, a, r_b, b };
+        let statement = ModStatement::<FFDHEExponentElGamal> {
+            c,
+            c_prime,
+            modulus_p,
+            upper_bound_m,
+            pk: keypair.pk,
+        };
+
+        assert_eq!(
+            ModProof::<FFDHEExponentElGamal>::prove(&witness, &statement, &ProofContext::default())
+                .unwrap_err(),
+            ProofError::ModRangeProofError {
+                statement: 2,
+                bit: None,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_mod_proof_paillier() {
+        let (ek, _) = Paillier::keypair().keys();
+        let share_bit_size: usize = 1000;
+        let a = BigInt::sample(share_bit_size);
+        let r_a = PaillierScheme::sample_randomness(&ek);
+        let modulus_p = BigInt::from(71);
+        let b = a.mod_floor(&modulus_p);
+        let r_b = PaillierScheme::sample_randomness(&ek);
+        let c = PaillierScheme::encrypt_with_randomness(&ek, &a, &r_a).unwrap();
+        let c_prime = PaillierScheme::encrypt_with_randomness(&ek, &b, &r_b).unwrap();
+        let witness = ModWitness { r_a, a, r_b, b };
+        let statement = ModStatement::<PaillierScheme> {
+            c,
+            c_prime,
+            modulus_p,
+            upper_bound_m: BigInt::from(2).pow(share_bit_size as u32),
+            pk: ek,
+        };
+
+        let proof =
+            ModProof::<PaillierScheme>::prove(&witness, &statement, &ProofContext::default())
+                .unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+    }
 }
@@ -1,139 +1,179 @@
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::homomorphic_scheme::Masks;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
 use crate::ProofError;
 use bit_vec::BitVec;
-use curv::arithmetic::traits::{Converter, Modulo, Samplable};
-use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
-use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::arithmetic::traits::{Modulo, Samplable, ZeroizeBN};
 use curv::BigInt;
-use elgamal::ElGamalCiphertext;
-use elgamal::ElGamalPublicKey;
-use elgamal::ExponentElGamal;
 use rand::prelude::*;
 use rayon::prelude::*;
+use std::fmt;
+use std::iter;
 use std::mem;
+use zeroize::Zeroize;
+
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/range-proof";
 
 /// This range proof is adaptation of the range proof given in
 /// [https://eprint.iacr.org/2017/552.pdf] appendix A, based on the proof by Boudot in
 /// [https://www.iacr.org/archive/eurocrypt2000/1807/18070437-new.pdf]
 /// We assume random oracle model and adjust the non-interactive proof implemented in
 /// [https://github.com/ZenGo-X/zk-paillier/blob/master/src/zkproofs/range_proof_ni.rs]
-/// to exponent ElGamal cryptosystem. In this proof the verifier is given a ciphertext c=Enc(x) and
-/// for a given range q such that x< q/3, the prover convinces the verifier that  0<x<q
+/// to any additively homomorphic scheme S, exponent ElGamal by default. In this proof the verifier
+/// is given a ciphertext c=Enc(x) and for a given range q such that x< q/3, the prover convinces
+/// the verifier that  0<x<q
+/// The proof has a slack of 3, tight_range_proof proves 0 <= x < q exactly.
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct RangeProof {
-    pub encrypted_pairs: EncryptedPairs,
-    pub z_vec: Vec<Response>,
+#[serde(bound = "")]
+pub struct RangeProof<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub encrypted_pairs: EncryptedPairs<S>,
+    pub z_vec: Vec<Response<S>>,
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct EncryptedPairs {
-    pub c1: Vec<ElGamalCiphertext>,
-    pub c2: Vec<ElGamalCiphertext>,
+#[serde(bound = "")]
+pub struct EncryptedPairs<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub c1: Vec<S::Ciphertext>,
+    pub c2: Vec<S::Ciphertext>,
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub enum Response {
+#[serde(bound = "")]
+pub enum Response<S: HomomorphicScheme = FFDHEExponentElGamal> {
     Open {
         w1: BigInt,
-        r1: BigInt,
+        r1: S::Randomness,
         w2: BigInt,
-        r2: BigInt,
+        r2: S::Randomness,
     },
 
     Mask {
         j: u8,
         masked_x: BigInt,
-        masked_r: BigInt,
+        masked_r: S::Randomness,
     },
 }
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Witness {
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Witness<S: HomomorphicScheme = FFDHEExponentElGamal> {
     pub x: BigInt,
-    pub r: BigInt,
+    pub r: S::Randomness,
+}
+
+impl<S: HomomorphicScheme> Zeroize for Witness<S> {
+    fn zeroize(&mut self) {
+        self.x.zeroize_bn();
+        S::zeroize_randomness(&mut self.r);
+    }
+}
+
+impl<S: HomomorphicScheme> Drop for Witness<S> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<S: HomomorphicScheme> fmt::Debug for Witness<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("x", &Redacted)
+            .field("r", &Redacted)
+            .finish()
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct Statement {
-    pub pk: ElGamalPublicKey,
+#[serde(bound = "")]
+pub struct Statement<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub pk: S::PublicKey,
     pub range: BigInt,
-    pub ciphertext: ElGamalCiphertext,
+    pub ciphertext: S::Ciphertext,
     pub sec_param: usize,
     pub kapa: usize,
 }
 
-impl RangeProof {
-    pub fn prove(witness: &Witness, statement: &Statement) -> Result<Self, ProofError> {
+// e = H(pk, range, c, sec_param, kapa, c1_1, c2_1, ..., c1_k, c2_k), one bit per repetition
+fn challenge<S: HomomorphicScheme>(
+    statement: &Statement<S>,
+    encrypted_pairs: &EncryptedPairs<S>,
+    context: &ProofContext,
+) -> BitVec {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigints(b"pk", &S::public_key_to_hash_input(&statement.pk));
+    transcript.append_bigint(b"range", &statement.range);
+    transcript.append_bigints(b"c", &S::ciphertext_to_hash_input(&statement.ciphertext));
+    transcript.append_u64(b"sec_param", statement.sec_param as u64);
+    transcript.append_u64(b"kapa", statement.kapa as u64);
+    for i in 0..statement.sec_param {
+        transcript.append_bigints(b"c1", &S::ciphertext_to_hash_input(&encrypted_pairs.c1[i]));
+        transcript.append_bigints(b"c2", &S::ciphertext_to_hash_input(&encrypted_pairs.c2[i]));
+    }
+    BitVec::from_bytes(&transcript.challenge_bytes(b"e", (statement.sec_param + 7) / 8))
+}
+
+impl<S: HomomorphicScheme> RangeProof<S> {
+    pub fn prove(
+        witness: &Witness<S>,
+        statement: &Statement<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
         let third_range = statement.range.div_floor(&BigInt::from(3));
         let two_third_range = &third_range * BigInt::from(2);
 
-        let mut w1_vec: Vec<_> = (0..statement.sec_param)
-            .into_par_iter()
-            .map(|_| BigInt::sample_range(&third_range, &two_third_range))
-            .collect();
-        let mut w2_vec: Vec<_> = (0..statement.sec_param)
-            .into_par_iter()
-            .map(|i| &w1_vec[i] - &third_range)
-            .collect();
+        // the openings of the commitments stay secret unless challenged, Masks wipes them on drop
+        let mut masks1 = Masks::<S> {
+            plaintexts: (0..statement.sec_param)
+                .into_par_iter()
+                .map(|_| BigInt::sample_range(&third_range, &two_third_range))
+                .collect(),
+            randomness: (0..statement.sec_param)
+                .into_par_iter()
+                .map(|_| S::sample_randomness(&statement.pk))
+                .collect(),
+        };
+        let mut masks2 = Masks::<S> {
+            plaintexts: (0..statement.sec_param)
+                .into_par_iter()
+                .map(|i| &masks1.plaintexts[i] - &third_range)
+                .collect(),
+            randomness: (0..statement.sec_param)
+                .into_par_iter()
+                .map(|_| S::sample_randomness(&statement.pk))
+                .collect(),
+        };
 
         for i in 0..statement.sec_param {
             if random() {
-                mem::swap(&mut w2_vec[i], &mut w1_vec[i]);
+                mem::swap(&mut masks2.plaintexts[i], &mut masks1.plaintexts[i]);
             }
         }
+        let (w1_vec, r1_vec) = (&masks1.plaintexts, &masks1.randomness);
+        let (w2_vec, r2_vec) = (&masks2.plaintexts, &masks2.randomness);
 
-        let r1_vec: Vec<_> = (0..statement.sec_param)
-            .into_par_iter()
-            .map(|_| BigInt::sample(&statement.pk.pp.q.bit_length() + statement.kapa))
-            .collect();
-        let r2_vec: Vec<_> = (0..statement.sec_param)
-            .into_par_iter()
-            .map(|_| BigInt::sample(&statement.pk.pp.q.bit_length() + statement.kapa))
-            .collect();
-
-        let c1_vec: Vec<_> = w1_vec
+        let c1_vec = w1_vec
             .par_iter()
-            .zip(&r1_vec)
-            .map(|(wi, ri)| {
-                ExponentElGamal::encrypt_from_predefined_randomness(
-                    &wi,
-                    &statement.pk,
-                    &ri.modulus(&statement.pk.pp.q),
-                )
-                .unwrap()
-            })
-            .collect();
+            .zip(r1_vec)
+            .map(|(wi, ri)| S::encrypt_with_randomness(&statement.pk, wi, ri))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProofError::RangeProofError)?;
 
-        let c2_vec: Vec<_> = w2_vec
+        let c2_vec = w2_vec
             .par_iter()
-            .zip(&r2_vec)
-            .map(|(wi, ri)| {
-                ExponentElGamal::encrypt_from_predefined_randomness(
-                    &wi,
-                    &statement.pk,
-                    &ri.modulus(&statement.pk.pp.q),
-                )
-                .unwrap()
-            })
-            .collect();
-
-        let mut fs_input = vec![
-            &statement.pk.h,
-            &statement.ciphertext.c1,
-            &statement.ciphertext.c2,
-        ];
-        for i in 0..statement.sec_param {
-            fs_input.push(&c1_vec[i].c1);
-            fs_input.push(&c2_vec[i].c1);
-        }
-        let e = HSha256::create_hash(&fs_input);
-        let e_bytes_vec = BigInt::to_vec(&e);
-        let bits_of_e = BitVec::from_bytes(&e_bytes_vec[..]);
+            .zip(r2_vec)
+            .map(|(wi, ri)| S::encrypt_with_randomness(&statement.pk, wi, ri))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProofError::RangeProofError)?;
 
         let encrypted_pairs = EncryptedPairs {
             c1: c1_vec,
             c2: c2_vec,
         };
+        let bits_of_e = challenge(statement, &encrypted_pairs, context);
 
         let responses: Vec<_> = (0..statement.sec_param)
             .into_par_iter()
@@ -152,13 +192,13 @@ impl RangeProof {
                     Response::Mask {
                         j: 1,
                         masked_x: &witness.x + &w1_vec[i],
-                        masked_r: BigInt::mod_add(&witness.r, &r1_vec[i], &statement.pk.pp.q),
+                        masked_r: S::add_randomness(&statement.pk, &witness.r, &r1_vec[i]),
                     }
                 } else {
                     Response::Mask {
                         j: 2,
                         masked_x: &witness.x + &w2_vec[i],
-                        masked_r: BigInt::mod_add(&witness.r, &r2_vec[i], &statement.pk.pp.q),
+                        masked_r: S::add_randomness(&statement.pk, &witness.r, &r2_vec[i]),
                     }
                 }
             })
@@ -170,22 +210,26 @@ impl RangeProof {
         })
     }
 
-    pub fn verify(&self, statement: &Statement) -> Result<(), ProofError> {
+    pub fn verify(
+        &self,
+        statement: &Statement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        if self.encrypted_pairs.c1.len() != statement.sec_param
+            || self.encrypted_pairs.c2.len() != statement.sec_param
+            || self.z_vec.len() != statement.sec_param
+        {
+            return Err(ProofError::RangeProofError);
+        }
+        iter::once(&statement.ciphertext)
+            .chain(self.encrypted_pairs.c1.iter())
+            .chain(self.encrypted_pairs.c2.iter())
+            .try_for_each(|c| S::validate_ciphertext(&statement.pk, c))
+            .map_err(ProofError::SchemeError)?;
         let third_range = statement.range.div_floor(&BigInt::from(3));
         let two_third_range = &third_range * BigInt::from(2);
 
-        let mut fs_input = vec![
-            &statement.pk.h,
-            &statement.ciphertext.c1,
-            &statement.ciphertext.c2,
-        ];
-        for i in 0..statement.sec_param {
-            fs_input.push(&self.encrypted_pairs.c1[i].c1);
-            fs_input.push(&self.encrypted_pairs.c2[i].c1);
-        }
-        let e = HSha256::create_hash(&fs_input);
-        let e_bytes_vec = BigInt::to_vec(&e);
-        let bits_of_e = BitVec::from_bytes(&e_bytes_vec[..]);
+        let bits_of_e = challenge(statement, &self.encrypted_pairs, context);
 
         let verifications: Vec<bool> = (0..statement.sec_param)
             .into_par_iter()
@@ -196,31 +240,29 @@ impl RangeProof {
                     (false, Response::Open { w1, r1, w2, r2 }) => {
                         let mut res = true;
 
-                        let expected_c1i = ExponentElGamal::encrypt_from_predefined_randomness(
-                            &w1,
-                            &statement.pk,
-                            &r1.modulus(&statement.pk.pp.q),
-                        )
-                        .unwrap();
-                        let expected_c2i = ExponentElGamal::encrypt_from_predefined_randomness(
-                            &w2,
-                            &statement.pk,
-                            &r2.modulus(&statement.pk.pp.q),
-                        )
-                        .unwrap();
-
-                        if expected_c1i != self.encrypted_pairs.c1[i] {
-                            res = false;
-                        }
-                        if expected_c2i != self.encrypted_pairs.c2[i] {
-                            res = false;
+                        match (
+                            S::encrypt_with_randomness(&statement.pk, w1, r1),
+                            S::encrypt_with_randomness(&statement.pk, w2, r2),
+                        ) {
+                            (Ok(expected_c1i), Ok(expected_c2i)) => {
+                                if expected_c1i != self.encrypted_pairs.c1[i] {
+                                    res = false;
+                                }
+                                if expected_c2i != self.encrypted_pairs.c2[i] {
+                                    res = false;
+                                }
+                            }
+                            _ => res = false,
                         }
 
-                        let flag =
-                            (w2.le(&third_range) && w1.ge(&third_range) && w1.le(&two_third_range))
+                        let flag = w1 >= &BigInt::zero()
+                            && w2 >= &BigInt::zero()
+                            && ((w2.le(&third_range)
+                                && w1.ge(&third_range)
+                                && w1.le(&two_third_range))
                                 || (w1.le(&third_range)
                                     && w2.ge(&third_range)
-                                    && w2.le(&two_third_range));
+                                    && w2.le(&two_third_range)));
 
                         if !flag {
                             res = false;
@@ -240,21 +282,27 @@ impl RangeProof {
                         let mut res = true;
 
                         let c = if *j == 1 {
-                            ExponentElGamal::add(&self.encrypted_pairs.c1[i], &statement.ciphertext)
-                                .unwrap()
+                            S::add(
+                                &statement.pk,
+                                &self.encrypted_pairs.c1[i],
+                                &statement.ciphertext,
+                            )
                         } else {
-                            ExponentElGamal::add(&self.encrypted_pairs.c2[i], &statement.ciphertext)
-                                .unwrap()
+                            S::add(
+                                &statement.pk,
+                                &self.encrypted_pairs.c2[i],
+                                &statement.ciphertext,
+                            )
                         };
 
-                        let enc_zi = ExponentElGamal::encrypt_from_predefined_randomness(
-                            &masked_x,
-                            &statement.pk,
-                            &masked_r,
-                        )
-                        .unwrap();
-                        if c != enc_zi {
-                            res = false;
+                        let enc_zi = S::encrypt_with_randomness(&statement.pk, masked_x, masked_r);
+                        match (c, enc_zi) {
+                            (Ok(c), Ok(enc_zi)) => {
+                                if c != enc_zi {
+                                    res = false;
+                                }
+                            }
+                            _ => res = false,
                         }
                         if *masked_x < third_range || *masked_x > two_third_range {
                             res = false;
@@ -268,25 +316,32 @@ impl RangeProof {
             })
             .collect();
 
-        if verifications.iter().all(|b| *b) {
-            Ok(())
-        } else {
-            Err(ProofError::RangeProofError)
+        match verifications.iter().position(|b| !*b) {
+            None => Ok(()),
+            Some(index) => Err(ProofError::RangeProofRepetitionError { index }),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::utlities::ec_elgamal::ECElGamalKeyPair;
+    use crate::utlities::ec_elgamal::ECExponentElGamal;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::homomorphic_scheme::PaillierScheme;
     use crate::utlities::range_proof::RangeProof;
     use crate::utlities::range_proof::Statement;
     use crate::utlities::range_proof::Witness;
+    use crate::utlities::transcript::ProofContext;
+    use crate::ProofError;
     use curv::arithmetic::traits::Samplable;
     use curv::BigInt;
     use elgamal::rfc7919_groups::SupportedGroups;
     use elgamal::ElGamalKeyPair;
     use elgamal::ElGamalPP;
-    use elgamal::ExponentElGamal;
+    use paillier::KeyGeneration;
+    use paillier::Paillier;
 
     #[test]
     fn test_range_proof() {
@@ -295,10 +350,10 @@ mod tests {
         let x = BigInt::from(BigInt::from(2).pow(18));
         let r = BigInt::sample_below(&pp.q);
         let ciphertext =
-            ExponentElGamal::encrypt_from_predefined_randomness(&x, &keypair.pk, &r).unwrap();
+            FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &x, &r).unwrap();
         let range = BigInt::from(BigInt::from(2).pow(20));
         let witness = Witness { x, r };
-        let statement = Statement {
+        let statement = Statement::<FFDHEExponentElGamal> {
             pk: keypair.pk,
             range,
             ciphertext,
@@ -306,9 +361,37 @@ mod tests {
             kapa: 100,
         };
 
-        let proof = RangeProof::prove(&witness, &statement).unwrap();
-        let verify = proof.verify(&statement);
-        assert!(verify.is_ok())
+        let proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        let verify = proof.verify(&statement, &ProofContext::default());
+        assert!(verify.is_ok());
+        assert!(proof
+            .verify(&statement, &ProofContext::new(&[1u8; 32], 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_range_proof_failing_repetition() {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let x = BigInt::from(BigInt::from(2).pow(18));
+        let r = BigInt::sample_below(&pp.q);
+        let ciphertext =
+            FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &x, &r).unwrap();
+        let witness = Witness { x, r };
+        let statement = Statement::<FFDHEExponentElGamal> {
+            pk: keypair.pk,
+            range: BigInt::from(BigInt::from(2).pow(20)),
+            ciphertext,
+            sec_param: 120,
+            kapa: 100,
+        };
+
+        let mut proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        proof.z_vec[5] = proof.z_vec[6].clone();
+        assert_eq!(
+            proof.verify(&statement, &ProofContext::default()),
+            Err(ProofError::RangeProofRepetitionError { index: 5 })
+        );
     }
 
     /// here we use x = q/2>q/3 and therefore proof wll fail
@@ -320,10 +403,10 @@ mod tests {
         let x = BigInt::from(BigInt::from(2).pow(19));
         let r = BigInt::sample_below(&pp.q);
         let ciphertext =
-            ExponentElGamal::encrypt_from_predefined_randomness(&x, &keypair.pk, &r).unwrap();
+            FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, &x, &r).unwrap();
         let range = BigInt::from(BigInt::from(2).pow(20));
         let witness = Witness { x, r };
-        let statement = Statement {
+        let statement = Statement::<FFDHEExponentElGamal> {
             pk: keypair.pk,
             range,
             ciphertext,
@@ -331,8 +414,60 @@ mod tests {
             kapa: 100,
         };
 
-        let proof = RangeProof::prove(&witness, &statement).unwrap();
-        let verify = proof.verify(&statement);
+        let proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        let verify = proof.verify(&statement, &ProofContext::default());
         assert!(verify.is_ok())
     }
+
+    #[test]
+    fn test_range_proof_paillier() {
+        let (ek, _) = Paillier::keypair().keys();
+        let x = BigInt::from(BigInt::from(2).pow(18));
+        let r = PaillierScheme::sample_randomness(&ek);
+        let ciphertext = PaillierScheme::encrypt_with_randomness(&ek, &x, &r).unwrap();
+        let witness = Witness { x, r };
+        let statement = Statement::<PaillierScheme> {
+            pk: ek,
+            range: BigInt::from(BigInt::from(2).pow(20)),
+            ciphertext,
+            sec_param: 120,
+            kapa: 100,
+        };
+
+        let proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+    }
+
+    #[test]
+    fn test_range_proof_ec_elgamal() {
+        let keypair = ECElGamalKeyPair::generate();
+        let x = BigInt::from(BigInt::from(2).pow(18));
+        let r = ECExponentElGamal::sample_randomness(&keypair.pk);
+        let ciphertext = ECExponentElGamal::encrypt_with_randomness(&keypair.pk, &x, &r).unwrap();
+        let witness = Witness { x, r };
+        let statement = Statement::<ECExponentElGamal> {
+            pk: keypair.pk,
+            range: BigInt::from(BigInt::from(2).pow(20)),
+            ciphertext,
+            sec_param: 120,
+            kapa: 100,
+        };
+
+        let proof = RangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+
+        // a proof for another ciphertext does not verify
+        let other_statement = Statement::<ECExponentElGamal> {
+            ciphertext: ECExponentElGamal::encrypt_with_randomness(
+                &statement.pk,
+                &BigInt::from(5),
+                &BigInt::sample_below(&BigInt::from(2).pow(200)),
+            )
+            .unwrap(),
+            ..statement.clone()
+        };
+        assert!(proof
+            .verify(&other_statement, &ProofContext::default())
+            .is_err());
+    }
 }
@@ -0,0 +1,156 @@
+use crate::utlities::ddh_proof::NISigmaProof;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
+use crate::ProofError;
+use curv::arithmetic::traits::Modulo;
+use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::ZeroizeBN;
+use curv::BigInt;
+use std::fmt;
+use zeroize::Zeroize;
+
+const CHALLENGE_BIT_LENGTH: usize = 256;
+const STAT_SEC_PARAM: usize = 80;
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/rsa-ddh-proof";
+
+/// Equality of discrete logs in Z_N^* for an RSA modulus N of unknown order, used to prove
+/// correct partial exponentiation with an RSA key share (see Shoup, Practical Threshold Signatures,
+/// EUROCRYPT 2000). The statement is (N, g1, h1, g2, h2), the witness is x. The relation outputs 1 if:
+/// h1 = g1^x mod N, h2 = g2^x mod N
+/// The protocol is the one of DDHProof, except that the group order is unknown. Therefore
+/// s is sampled from a range |N| + |e| + STAT_SEC_PARAM bits larger than x and z = s + ex is
+/// computed over the integers.
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RSADDHProof {
+    pub a1: BigInt,
+    pub a2: BigInt,
+    pub z: BigInt,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RSADDHStatement {
+    pub n: BigInt,
+    pub g1: BigInt,
+    pub h1: BigInt,
+    pub g2: BigInt,
+    pub h2: BigInt,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct RSADDHWitness {
+    pub x: BigInt,
+}
+
+impl Zeroize for RSADDHWitness {
+    fn zeroize(&mut self) {
+        self.x.zeroize_bn();
+    }
+}
+
+impl Drop for RSADDHWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl fmt::Debug for RSADDHWitness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RSADDHWitness")
+            .field("x", &Redacted)
+            .finish()
+    }
+}
+
+// e = H(N, g1, g2, h1, h2, a1, a2) of CHALLENGE_BIT_LENGTH bits
+fn challenge(delta: &RSADDHStatement, a1: &BigInt, a2: &BigInt, context: &ProofContext) -> BigInt {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigint(b"n", &delta.n);
+    transcript.append_bigint(b"g1", &delta.g1);
+    transcript.append_bigint(b"g2", &delta.g2);
+    transcript.append_bigint(b"h1", &delta.h1);
+    transcript.append_bigint(b"h2", &delta.h2);
+    transcript.append_bigint(b"a1", a1);
+    transcript.append_bigint(b"a2", a2);
+    transcript.challenge_bits(b"e", CHALLENGE_BIT_LENGTH)
+}
+
+impl NISigmaProof<RSADDHProof, RSADDHWitness, RSADDHStatement> for RSADDHProof {
+    fn prove(w: &RSADDHWitness, delta: &RSADDHStatement, context: &ProofContext) -> RSADDHProof {
+        let mut s = BigInt::sample(delta.n.bit_length() + CHALLENGE_BIT_LENGTH + STAT_SEC_PARAM);
+        let a1 = BigInt::mod_pow(&delta.g1, &s, &delta.n);
+        let a2 = BigInt::mod_pow(&delta.g2, &s, &delta.n);
+
+        let e = challenge(delta, &a1, &a2, context);
+
+        let z = &s + &e * &w.x;
+        s.zeroize_bn();
+        RSADDHProof { a1, a2, z }
+    }
+
+    fn verify(&self, delta: &RSADDHStatement, context: &ProofContext) -> Result<(), ProofError> {
+        let z_bound = delta.n.bit_length() + CHALLENGE_BIT_LENGTH + STAT_SEC_PARAM + 1;
+        if self.z < BigInt::zero() || self.z.bit_length() > z_bound {
+            return Err(ProofError::DHProofError);
+        }
+
+        let e = challenge(delta, &self.a1, &self.a2, context);
+
+        let g1_z = BigInt::mod_pow(&delta.g1, &self.z, &delta.n);
+        let g2_z = BigInt::mod_pow(&delta.g2, &self.z, &delta.n);
+        let h1_e = BigInt::mod_pow(&delta.h1, &e, &delta.n);
+        let h2_e = BigInt::mod_pow(&delta.h2, &e, &delta.n);
+        let a1_plus_h1_e = BigInt::mod_mul(&self.a1, &h1_e, &delta.n);
+        let a2_plus_h2_e = BigInt::mod_mul(&self.a2, &h2_e, &delta.n);
+
+        if g1_z == a1_plus_h1_e && g2_z == a2_plus_h2_e {
+            Ok(())
+        } else {
+            Err(ProofError::DHProofError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::utlities::ddh_proof::NISigmaProof;
+    use crate::utlities::rsa_ddh_proof::*;
+    use crate::utlities::transcript::ProofContext;
+    use curv::BigInt;
+
+    // the proof does not depend on the factorization of N, any odd modulus will do
+    fn sample_statement(x: &BigInt, x_prime: &BigInt) -> RSADDHStatement {
+        let n = BigInt::sample(2048) * BigInt::from(2) + BigInt::one();
+        let g1 = BigInt::sample_below(&n);
+        let g2 = BigInt::sample_below(&n);
+        let h1 = BigInt::mod_pow(&g1, x, &n);
+        let h2 = BigInt::mod_pow(&g2, x_prime, &n);
+        RSADDHStatement { n, g1, h1, g2, h2 }
+    }
+
+    #[test]
+    fn test_rsa_ddh_proof() {
+        let x = BigInt::sample(2048);
+        let delta = sample_statement(&x, &x);
+        let w = RSADDHWitness { x };
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let proof = RSADDHProof::prove(&w, &delta, &context);
+        let verify = proof.verify(&delta, &context);
+        assert!(verify.is_ok());
+        assert!(proof.verify(&delta, &ProofContext::default()).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bad_rsa_ddh_proof() {
+        let x = BigInt::sample(2048);
+        // we use x' = x+1
+        let delta = sample_statement(&x, &(&x + BigInt::one()));
+        let w = RSADDHWitness { x };
+        let proof = RSADDHProof::prove(&w, &delta, &ProofContext::default());
+        let verify = proof.verify(&delta, &ProofContext::default());
+        assert!(verify.is_ok())
+    }
+}
@@ -0,0 +1,321 @@
+use crate::SealedShareError;
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::Aead;
+use aes_gcm::aead::NewAead;
+use aes_gcm::aead::Payload;
+use aes_gcm::Aes256Gcm;
+use argon2::Config;
+use argon2::ThreadMode;
+use argon2::Variant;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zeroize::Zeroize;
+
+/// Version of the envelope written by `seal`
+pub const SEALED_SHARE_VERSION: u8 = 1;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+// Argon2id parameters for new envelopes, 64 MiB of memory and 3 passes. Opening reads the
+// parameters from the envelope so they can be raised without breaking stored shares
+const ARGON2_MEM_COST: u32 = 65536;
+const ARGON2_TIME_COST: u32 = 3;
+const ARGON2_LANES: u32 = 4;
+
+// Window of Argon2id parameters accepted by `open`. The kdf of an envelope is only authenticated
+// after the key is derived, so without a bound a tampered file could ask for terabytes of memory
+// or years of passes before the AEAD check rejects it
+const ARGON2_MEM_COST_RANGE: (u32, u32) = (8192, 1_048_576);
+const ARGON2_TIME_COST_RANGE: (u32, u32) = (1, 16);
+const ARGON2_LANES_RANGE: (u32, u32) = (1, 16);
+
+/// Secret protecting a sealed share: a password, stretched with Argon2id, or a 256 bit key
+/// used as is (e.g. from a KMS)
+pub enum StorageKey<'a> {
+    Password(&'a [u8]),
+    Key(&'a [u8; KEY_LEN]),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum KeyDerivation {
+    /// the envelope was sealed with a raw key
+    None,
+    Argon2id {
+        salt: Vec<u8>,
+        mem_cost: u32,
+        time_cost: u32,
+        lanes: u32,
+    },
+}
+
+/// Versioned envelope for storing secret state at rest: the json serialization of the value,
+/// encrypted with AES-256-GCM. The header (version, content and kdf) is authenticated as
+/// associated data, so a share cannot be opened as another type or with weakened KDF
+/// parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SealedShare {
+    pub version: u8,
+    /// what the envelope holds, e.g. PartyOneKeySetup
+    pub content: String,
+    pub kdf: KeyDerivation,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u8,
+    content: &'a str,
+    kdf: &'a KeyDerivation,
+}
+
+pub fn seal<T: Serialize>(
+    value: &T,
+    content: &str,
+    key: &StorageKey,
+) -> Result<SealedShare, SealedShareError> {
+    let mut rng = rand::thread_rng();
+    let kdf = match key {
+        StorageKey::Password(_) => {
+            let mut salt = vec![0u8; SALT_LEN];
+            rng.fill_bytes(&mut salt);
+            KeyDerivation::Argon2id {
+                salt,
+                mem_cost: ARGON2_MEM_COST,
+                time_cost: ARGON2_TIME_COST,
+                lanes: ARGON2_LANES,
+            }
+        }
+        StorageKey::Key(_) => KeyDerivation::None,
+    };
+    let mut nonce = vec![0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let aad = header(SEALED_SHARE_VERSION, content, &kdf)?;
+    let mut plaintext = serde_json::to_vec(value).map_err(|_| SealedShareError::EncodingError)?;
+    let mut encryption_key = derive_key(key, &kdf)?;
+    let ciphertext = Aes256Gcm::new(GenericArray::from_slice(&encryption_key)).encrypt(
+        GenericArray::from_slice(&nonce),
+        Payload {
+            msg: &plaintext,
+            aad: &aad,
+        },
+    );
+    plaintext.zeroize();
+    encryption_key.zeroize();
+
+    Ok(SealedShare {
+        version: SEALED_SHARE_VERSION,
+        content: content.to_string(),
+        kdf,
+        nonce,
+        ciphertext: ciphertext.map_err(|_| SealedShareError::EncodingError)?,
+    })
+}
+
+pub fn open<T: DeserializeOwned>(
+    sealed: &SealedShare,
+    content: &str,
+    key: &StorageKey,
+) -> Result<T, SealedShareError> {
+    if sealed.version != SEALED_SHARE_VERSION {
+        return Err(SealedShareError::UnsupportedVersion(sealed.version));
+    }
+    if sealed.content != content {
+        return Err(SealedShareError::WrongContent);
+    }
+    if sealed.nonce.len() != NONCE_LEN {
+        return Err(SealedShareError::EncodingError);
+    }
+
+    let aad = header(sealed.version, &sealed.content, &sealed.kdf)?;
+    let mut encryption_key = derive_key(key, &sealed.kdf)?;
+    let plaintext = Aes256Gcm::new(GenericArray::from_slice(&encryption_key)).decrypt(
+        GenericArray::from_slice(&sealed.nonce),
+        Payload {
+            msg: &sealed.ciphertext,
+            aad: &aad,
+        },
+    );
+    encryption_key.zeroize();
+    let mut plaintext = plaintext.map_err(|_| SealedShareError::DecryptionFailed)?;
+    let value = serde_json::from_slice(&plaintext).map_err(|_| SealedShareError::EncodingError);
+    plaintext.zeroize();
+    value
+}
+
+fn header(version: u8, content: &str, kdf: &KeyDerivation) -> Result<Vec<u8>, SealedShareError> {
+    serde_json::to_vec(&Header {
+        version,
+        content,
+        kdf,
+    })
+    .map_err(|_| SealedShareError::EncodingError)
+}
+
+fn derive_key(key: &StorageKey, kdf: &KeyDerivation) -> Result<Vec<u8>, SealedShareError> {
+    match (key, kdf) {
+        (StorageKey::Key(key), KeyDerivation::None) => Ok(key.to_vec()),
+        (
+            StorageKey::Password(password),
+            KeyDerivation::Argon2id {
+                salt,
+                mem_cost,
+                time_cost,
+                lanes,
+            },
+        ) => {
+            let in_range = |x: u32, (min, max): (u32, u32)| min <= x && x <= max;
+            if !in_range(*mem_cost, ARGON2_MEM_COST_RANGE)
+                || !in_range(*time_cost, ARGON2_TIME_COST_RANGE)
+                || !in_range(*lanes, ARGON2_LANES_RANGE)
+            {
+                return Err(SealedShareError::KdfParametersOutOfRange);
+            }
+            let config = Config {
+                variant: Variant::Argon2id,
+                mem_cost: *mem_cost,
+                time_cost: *time_cost,
+                lanes: *lanes,
+                thread_mode: ThreadMode::Parallel,
+                hash_length: KEY_LEN as u32,
+                ..Config::default()
+            };
+            argon2::hash_raw(password, salt, &config)
+                .map_err(|_| SealedShareError::KeyDerivationError)
+        }
+        _ => Err(SealedShareError::KeyTypeMismatch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::sealed_share::open;
+    use crate::utlities::sealed_share::seal;
+    use crate::utlities::sealed_share::KeyDerivation;
+    use crate::utlities::sealed_share::StorageKey;
+    use crate::SealedShareError;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+
+    #[test]
+    fn test_seal_and_open() {
+        let secret = BigInt::sample(2048);
+        let key = [7u8; 32];
+        let sealed = seal(&secret, "BigInt", &StorageKey::Key(&key)).unwrap();
+        assert_eq!(sealed.kdf, KeyDerivation::None);
+        let opened: BigInt = open(&sealed, "BigInt", &StorageKey::Key(&key)).unwrap();
+        assert_eq!(opened, secret);
+
+        let sealed = seal(&secret, "BigInt", &StorageKey::Password(b"hunter2")).unwrap();
+        let json = serde_json::to_string(&sealed).unwrap();
+        assert!(!json.contains(&secret.to_str_radix(16)));
+        let sealed = serde_json::from_str(&json).unwrap();
+        let opened: BigInt = open(&sealed, "BigInt", &StorageKey::Password(b"hunter2")).unwrap();
+        assert_eq!(opened, secret);
+    }
+
+    #[test]
+    fn test_open_with_wrong_key() {
+        let secret = BigInt::sample(2048);
+        let sealed = seal(&secret, "BigInt", &StorageKey::Password(b"hunter2")).unwrap();
+        assert_eq!(
+            open::<BigInt>(&sealed, "BigInt", &StorageKey::Password(b"hunter3")).unwrap_err(),
+            SealedShareError::DecryptionFailed
+        );
+        assert_eq!(
+            open::<BigInt>(&sealed, "BigInt", &StorageKey::Key(&[7u8; 32])).unwrap_err(),
+            SealedShareError::KeyTypeMismatch
+        );
+
+        let sealed = seal(&secret, "BigInt", &StorageKey::Key(&[7u8; 32])).unwrap();
+        assert_eq!(
+            open::<BigInt>(&sealed, "BigInt", &StorageKey::Key(&[8u8; 32])).unwrap_err(),
+            SealedShareError::DecryptionFailed
+        );
+    }
+
+    #[test]
+    fn test_open_tampered_envelope() {
+        let secret = BigInt::sample(2048);
+        let key = StorageKey::Key(&[7u8; 32]);
+        let sealed = seal(&secret, "BigInt", &key).unwrap();
+        assert_eq!(
+            open::<BigInt>(&sealed, "PartyOneKeySetup", &key).unwrap_err(),
+            SealedShareError::WrongContent
+        );
+
+        let mut bad_version = sealed.clone();
+        bad_version.version = 2;
+        assert_eq!(
+            open::<BigInt>(&bad_version, "BigInt", &key).unwrap_err(),
+            SealedShareError::UnsupportedVersion(2)
+        );
+
+        // the content label is authenticated
+        let mut relabeled = sealed.clone();
+        relabeled.content = "Other".to_string();
+        assert_eq!(
+            open::<BigInt>(&relabeled, "Other", &key).unwrap_err(),
+            SealedShareError::DecryptionFailed
+        );
+
+        let mut flipped = sealed;
+        flipped.ciphertext[0] ^= 1;
+        assert_eq!(
+            open::<BigInt>(&flipped, "BigInt", &key).unwrap_err(),
+            SealedShareError::DecryptionFailed
+        );
+    }
+
+    #[test]
+    fn test_open_with_kdf_parameters_out_of_range() {
+        let secret = BigInt::sample(2048);
+        let password = StorageKey::Password(b"hunter2");
+        let sealed = seal(&secret, "BigInt", &password).unwrap();
+        let with_kdf = |f: &dyn Fn(&mut u32, &mut u32, &mut u32)| {
+            let mut tampered = sealed.clone();
+            if let KeyDerivation::Argon2id {
+                mem_cost,
+                time_cost,
+                lanes,
+                ..
+            } = &mut tampered.kdf
+            {
+                f(mem_cost, time_cost, lanes);
+            }
+            tampered
+        };
+
+        // rejected before Argon2id runs, a u32::MAX memory cost would otherwise try to allocate
+        // 4 TiB
+        for tampered in [
+            with_kdf(&|mem_cost, _, _| *mem_cost = u32::MAX),
+            with_kdf(&|mem_cost, _, _| *mem_cost = 8),
+            with_kdf(&|_, time_cost, _| *time_cost = u32::MAX),
+            with_kdf(&|_, time_cost, _| *time_cost = 0),
+            with_kdf(&|_, _, lanes| *lanes = 1 << 20),
+            with_kdf(&|_, _, lanes| *lanes = 0),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                open::<BigInt>(tampered, "BigInt", &password).unwrap_err(),
+                SealedShareError::KdfParametersOutOfRange
+            );
+        }
+
+        // parameters inside the window are derived, and then fail authentication
+        let weaker = with_kdf(&|mem_cost, time_cost, _| {
+            *mem_cost = 8192;
+            *time_cost = 1;
+        });
+        assert_eq!(
+            open::<BigInt>(&weaker, "BigInt", &password).unwrap_err(),
+            SealedShareError::DecryptionFailed
+        );
+    }
+}
@@ -0,0 +1,562 @@
+use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+use crate::utlities::homomorphic_scheme::Masks;
+use crate::utlities::transcript::ProofContext;
+use crate::utlities::transcript::Transcript;
+use crate::utlities::Redacted;
+use crate::HomomorphicSchemeError;
+use crate::ProofError;
+use curv::arithmetic::traits::{Modulo, Samplable, ZeroizeBN};
+use curv::BigInt;
+use rayon::prelude::*;
+use std::fmt;
+use std::iter;
+use zeroize::Zeroize;
+
+/// Exact range proof: given a ciphertext c = Enc(x) of an additively homomorphic scheme S,
+/// exponent ElGamal by default, the prover convinces the verifier that 0 <= x < range. Unlike the
+/// cut-and-choose proof of range_proof there is no slack, for range = 2^k it proves exactly that
+/// x has k bits.
+///
+/// x is decomposed as x = sum b_i * G_i with bits b_i and the weights
+/// G_i = floor((range - 1 + 2^i) / 2^(i+1)) for i < k = |range - 1|. The subset sums of the
+/// weights are exactly [0, range - 1], for range = 2^k the weights are 2^(k-1), ..., 2, 1.
+/// The prover sends c_i = Enc(b_i * G_i) for i >= 1, the verifier derives c_0 as c minus all of
+/// them, and each c_i comes with an OR-proof that it encrypts 0 or G_i, as in
+/// [https://link.springer.com/chapter/10.1007/3-540-48658-5_19]. Branch m of the OR-proof is a
+/// sigma protocol for knowledge of w with c_i = Enc(m, w):
+/// 1. prover sends a = Enc(0, s)
+/// 2. verifier sends a challenge e
+/// 3. prover sends z = s + e * w in the randomness space of S
+/// 4. verifier checks Enc(e * m, z) = a + e * c_i
+/// The prover simulates the other branch and the challenges of both branches add up to the
+/// Fiat-Shamir challenge modulo 2^CHALLENGE_BITS.
+/// The proof has k OR-proofs, it is larger than the cut-and-choose proof for ranges of more than
+/// 2 * sec_param bits.
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct TightRangeProof<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    /// Enc(b_i * G_i) for 1 <= i < k, Enc(b_0 * G_0) is derived from the statement
+    pub ciphertexts: Vec<S::Ciphertext>,
+    pub bit_proofs: Vec<BitProof<S>>,
+}
+
+/// OR-proof that c_i encrypts 0 or G_i
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BitProof<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub a_zero: S::Ciphertext,
+    pub a_weight: S::Ciphertext,
+    /// challenge of the zero branch, the weight branch gets e - e_zero mod 2^CHALLENGE_BITS
+    pub e_zero: BigInt,
+    pub z_zero: S::Randomness,
+    pub z_weight: S::Randomness,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Witness<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub x: BigInt,
+    pub r: S::Randomness,
+}
+
+impl<S: HomomorphicScheme> Zeroize for Witness<S> {
+    fn zeroize(&mut self) {
+        self.x.zeroize_bn();
+        S::zeroize_randomness(&mut self.r);
+    }
+}
+
+impl<S: HomomorphicScheme> Drop for Witness<S> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<S: HomomorphicScheme> fmt::Debug for Witness<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Witness")
+            .field("x", &Redacted)
+            .field("r", &Redacted)
+            .finish()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Statement<S: HomomorphicScheme = FFDHEExponentElGamal> {
+    pub pk: S::PublicKey,
+    /// exclusive upper bound on x, at least 2 and at most the plaintext modulus so that the sum
+    /// of the weights does not wrap around
+    pub range: BigInt,
+    pub ciphertext: S::Ciphertext,
+}
+
+const CHALLENGE_BITS: usize = 128;
+const DOMAIN_SEPARATOR: &[u8] = b"vice-city/tight-range-proof";
+
+// weights G_i of the decomposition of [0, range - 1], largest first
+fn weights(range: &BigInt) -> Vec<BigInt> {
+    let h = range - BigInt::one();
+    (0..h.bit_length())
+        .map(|i| (&h + (BigInt::one() << i)) >> (i + 1))
+        .collect()
+}
+
+// b_i * G_i taking every weight that still fits, None if x is not in [0, range - 1]
+fn decompose(x: &BigInt, weights: &[BigInt]) -> Option<Vec<BigInt>> {
+    let mut rest = x.clone();
+    let parts = weights
+        .iter()
+        .map(|g| {
+            if &rest >= g {
+                rest = &rest - g;
+                g.clone()
+            } else {
+                BigInt::zero()
+            }
+        })
+        .collect();
+    let is_decomposed = rest == BigInt::zero();
+    rest.zeroize_bn();
+    if is_decomposed {
+        Some(parts)
+    } else {
+        None
+    }
+}
+
+// c_0 = c - (c_1 + ... + c_{k-1})
+fn first_ciphertext<S: HomomorphicScheme>(
+    statement: &Statement<S>,
+    ciphertexts: &[S::Ciphertext],
+) -> Result<S::Ciphertext, HomomorphicSchemeError> {
+    match ciphertexts.split_first() {
+        None => Ok(statement.ciphertext.clone()),
+        Some((first, rest)) => {
+            let sum = rest
+                .iter()
+                .try_fold(first.clone(), |acc, c| S::add(&statement.pk, &acc, c))?;
+            let minus_sum = S::mul(&statement.pk, &sum, &-BigInt::one())?;
+            S::add(&statement.pk, &statement.ciphertext, &minus_sum)
+        }
+    }
+}
+
+// randomness of first_ciphertext, computed the same way
+fn first_randomness<S: HomomorphicScheme>(
+    pk: &S::PublicKey,
+    r: &S::Randomness,
+    randomness: &[S::Randomness],
+) -> S::Randomness {
+    match randomness.split_first() {
+        None => r.clone(),
+        Some((first, rest)) => {
+            let mut sum = rest
+                .iter()
+                .fold(first.clone(), |acc, r_i| S::add_randomness(pk, &acc, r_i));
+            let mut minus_sum = S::mul_randomness(pk, &sum, &-BigInt::one());
+            let r_0 = S::add_randomness(pk, r, &minus_sum);
+            S::zeroize_randomness(&mut sum);
+            S::zeroize_randomness(&mut minus_sum);
+            r_0
+        }
+    }
+}
+
+// e = H(pk, range, c, c_1, ..., c_{k-1}, a_zero_0, a_weight_0, ..., a_zero_{k-1}, a_weight_{k-1})
+fn challenge<S: HomomorphicScheme>(
+    statement: &Statement<S>,
+    ciphertexts: &[S::Ciphertext],
+    first_messages: &[(S::Ciphertext, S::Ciphertext)],
+    context: &ProofContext,
+) -> BigInt {
+    let mut transcript = Transcript::new(DOMAIN_SEPARATOR, context);
+    transcript.append_bigints(b"pk", &S::public_key_to_hash_input(&statement.pk));
+    transcript.append_bigint(b"range", &statement.range);
+    transcript.append_bigints(b"c", &S::ciphertext_to_hash_input(&statement.ciphertext));
+    for c in ciphertexts {
+        transcript.append_bigints(b"c_i", &S::ciphertext_to_hash_input(c));
+    }
+    for (a_zero, a_weight) in first_messages {
+        transcript.append_bigints(b"a_zero", &S::ciphertext_to_hash_input(a_zero));
+        transcript.append_bigints(b"a_weight", &S::ciphertext_to_hash_input(a_weight));
+    }
+    transcript.challenge_bits(b"e", CHALLENGE_BITS)
+}
+
+fn challenge_modulus() -> BigInt {
+    BigInt::one() << CHALLENGE_BITS
+}
+
+// checks Enc(e * m, z) = a + e * c
+fn verify_branch<S: HomomorphicScheme>(
+    pk: &S::PublicKey,
+    c: &S::Ciphertext,
+    m: &BigInt,
+    a: &S::Ciphertext,
+    e: &BigInt,
+    z: &S::Randomness,
+) -> bool {
+    let e_m = BigInt::mod_mul(e, m, &S::plaintext_modulus(pk));
+    match (
+        S::encrypt_with_randomness(pk, &e_m, z),
+        S::mul(pk, c, e).and_then(|e_c| S::add(pk, a, &e_c)),
+    ) {
+        (Ok(lhs), Ok(rhs)) => lhs == rhs,
+        _ => false,
+    }
+}
+
+impl<S: HomomorphicScheme> TightRangeProof<S> {
+    pub fn prove(
+        witness: &Witness<S>,
+        statement: &Statement<S>,
+        context: &ProofContext,
+    ) -> Result<Self, ProofError> {
+        let pk = &statement.pk;
+        if statement.range <= BigInt::one() || statement.range > S::plaintext_modulus(pk) {
+            return Err(ProofError::RangeProofError);
+        }
+        let weights = weights(&statement.range);
+        let k = weights.len();
+
+        // the bits and the randomness of every branch stay secret, Masks wipes them on drop
+        let mut bits = Masks::<S> {
+            plaintexts: decompose(&witness.x, &weights).ok_or(ProofError::RangeProofError)?,
+            randomness: (1..k).map(|_| S::sample_randomness(pk)).collect(),
+        };
+        let ciphertexts = (1..k)
+            .into_par_iter()
+            .map(|i| S::encrypt_with_randomness(pk, &bits.plaintexts[i], &bits.randomness[i - 1]))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProofError::RangeProofError)?;
+        let c_0 =
+            first_ciphertext(statement, &ciphertexts).map_err(|_| ProofError::RangeProofError)?;
+        let r_0 = first_randomness::<S>(pk, &witness.r, &bits.randomness);
+        bits.randomness.insert(0, r_0);
+        let bit_ciphertexts: Vec<&S::Ciphertext> =
+            iter::once(&c_0).chain(ciphertexts.iter()).collect();
+
+        // s for the real branch, z for the simulated one
+        let branches = Masks::<S> {
+            plaintexts: (0..k).map(|_| BigInt::sample(CHALLENGE_BITS)).collect(),
+            randomness: (0..2 * k).map(|_| S::sample_randomness(pk)).collect(),
+        };
+        let (s_vec, z_sim_vec) = branches.randomness.split_at(k);
+        let e_sim_vec = &branches.plaintexts;
+
+        let first_messages = (0..k)
+            .into_par_iter()
+            .map(|i| -> Result<_, HomomorphicSchemeError> {
+                let is_one = bits.plaintexts[i] != BigInt::zero();
+                let m_sim = if is_one {
+                    BigInt::zero()
+                } else {
+                    weights[i].clone()
+                };
+                let e_m_sim = BigInt::mod_mul(&e_sim_vec[i], &m_sim, &S::plaintext_modulus(pk));
+                let a_real = S::encrypt_with_randomness(pk, &BigInt::zero(), &s_vec[i])?;
+                let a_sim =
+                    S::encrypt_with_randomness(pk, &e_m_sim, &z_sim_vec[i]).and_then(|enc| {
+                        S::mul(pk, bit_ciphertexts[i], &(BigInt::zero() - &e_sim_vec[i]))
+                            .and_then(|minus_e_c| S::add(pk, &enc, &minus_e_c))
+                    })?;
+                if is_one {
+                    Ok((a_sim, a_real))
+                } else {
+                    Ok((a_real, a_sim))
+                }
+            })
+            .collect::<Result<Vec<(S::Ciphertext, S::Ciphertext)>, _>>()
+            .map_err(|_| ProofError::RangeProofError)?;
+
+        let e = challenge(statement, &ciphertexts, &first_messages, context);
+
+        let bit_proofs = first_messages
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, (a_zero, a_weight))| {
+                let w = &bits.randomness[i];
+                let e_sim = &e_sim_vec[i];
+                let e_real = BigInt::mod_sub(&e, e_sim, &challenge_modulus());
+                let z_real = S::add_randomness(pk, &s_vec[i], &S::mul_randomness(pk, w, &e_real));
+                // the simulated a was computed with -e_sim * c_i, the verifier adds e_sim * c_i:
+                // their sum encrypts zero under randomness the prover knows
+                let w_sum = S::add_randomness(
+                    pk,
+                    &S::mul_randomness(pk, w, &(BigInt::zero() - e_sim)),
+                    &S::mul_randomness(pk, w, e_sim),
+                );
+                let z_sim = S::add_randomness(pk, &z_sim_vec[i], &w_sum);
+                if bits.plaintexts[i] != BigInt::zero() {
+                    BitProof {
+                        a_zero,
+                        a_weight,
+                        e_zero: e_sim.clone(),
+                        z_zero: z_sim,
+                        z_weight: z_real,
+                    }
+                } else {
+                    BitProof {
+                        a_zero,
+                        a_weight,
+                        e_zero: e_real,
+                        z_zero: z_real,
+                        z_weight: z_sim,
+                    }
+                }
+            })
+            .collect();
+
+        Ok(TightRangeProof {
+            ciphertexts,
+            bit_proofs,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        statement: &Statement<S>,
+        context: &ProofContext,
+    ) -> Result<(), ProofError> {
+        let pk = &statement.pk;
+        if statement.range <= BigInt::one() || statement.range > S::plaintext_modulus(pk) {
+            return Err(ProofError::RangeProofError);
+        }
+        let weights = weights(&statement.range);
+        let k = weights.len();
+        if self.ciphertexts.len() + 1 != k || self.bit_proofs.len() != k {
+            return Err(ProofError::RangeProofError);
+        }
+        iter::once(&statement.ciphertext)
+            .chain(self.ciphertexts.iter())
+            .chain(self.bit_proofs.iter().map(|proof| &proof.a_zero))
+            .chain(self.bit_proofs.iter().map(|proof| &proof.a_weight))
+            .try_for_each(|c| S::validate_ciphertext(pk, c))
+            .map_err(ProofError::SchemeError)?;
+        let c_0 =
+            first_ciphertext(statement, &self.ciphertexts).map_err(ProofError::SchemeError)?;
+        let bit_ciphertexts: Vec<&S::Ciphertext> =
+            iter::once(&c_0).chain(self.ciphertexts.iter()).collect();
+
+        let first_messages: Vec<_> = self
+            .bit_proofs
+            .iter()
+            .map(|proof| (proof.a_zero.clone(), proof.a_weight.clone()))
+            .collect();
+        let e = challenge(statement, &self.ciphertexts, &first_messages, context);
+        let challenge_modulus = challenge_modulus();
+
+        let verifications: Vec<bool> = (0..k)
+            .into_par_iter()
+            .map(|i| {
+                let proof = &self.bit_proofs[i];
+                if proof.e_zero < BigInt::zero() || proof.e_zero >= challenge_modulus {
+                    return false;
+                }
+                let e_weight = BigInt::mod_sub(&e, &proof.e_zero, &challenge_modulus);
+                verify_branch::<S>(
+                    pk,
+                    bit_ciphertexts[i],
+                    &BigInt::zero(),
+                    &proof.a_zero,
+                    &proof.e_zero,
+                    &proof.z_zero,
+                ) && verify_branch::<S>(
+                    pk,
+                    bit_ciphertexts[i],
+                    &weights[i],
+                    &proof.a_weight,
+                    &e_weight,
+                    &proof.z_weight,
+                )
+            })
+            .collect();
+
+        match verifications.iter().position(|b| !*b) {
+            None => Ok(()),
+            Some(index) => Err(ProofError::RangeProofBitError { index }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::ec_elgamal::ECElGamalKeyPair;
+    use crate::utlities::ec_elgamal::ECExponentElGamal;
+    use crate::utlities::exponent_elgamal::FFDHEExponentElGamal;
+    use crate::utlities::homomorphic_scheme::HomomorphicScheme;
+    use crate::utlities::homomorphic_scheme::PaillierScheme;
+    use crate::utlities::tight_range_proof::decompose;
+    use crate::utlities::tight_range_proof::weights;
+    use crate::utlities::tight_range_proof::Statement;
+    use crate::utlities::tight_range_proof::TightRangeProof;
+    use crate::utlities::tight_range_proof::Witness;
+    use crate::utlities::transcript::ProofContext;
+    use crate::ProofError;
+    use curv::arithmetic::traits::Samplable;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalKeyPair;
+    use elgamal::ElGamalPP;
+    use paillier::KeyGeneration;
+    use paillier::Paillier;
+
+    fn ffdhe_statement(
+        x: &BigInt,
+        range: &BigInt,
+    ) -> (
+        Witness<FFDHEExponentElGamal>,
+        Statement<FFDHEExponentElGamal>,
+    ) {
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let keypair = ElGamalKeyPair::generate(&pp);
+        let r = BigInt::sample_below(&pp.q);
+        let ciphertext = FFDHEExponentElGamal::encrypt_with_randomness(&keypair.pk, x, &r).unwrap();
+        (
+            Witness { x: x.clone(), r },
+            Statement {
+                pk: keypair.pk,
+                range: range.clone(),
+                ciphertext,
+            },
+        )
+    }
+
+    #[test]
+    fn test_weights() {
+        let powers: Vec<_> = [16, 8, 4, 2, 1].iter().map(|g| BigInt::from(*g)).collect();
+        assert_eq!(weights(&BigInt::from(32)), powers);
+        for range in 2..200 {
+            let range = BigInt::from(range);
+            let weights = weights(&range);
+            for x in 0..200 {
+                let x = BigInt::from(x);
+                let parts = decompose(&x, &weights);
+                assert_eq!(parts.is_some(), x < range);
+                if let Some(parts) = parts {
+                    assert_eq!(parts.iter().fold(BigInt::zero(), |acc, b| acc + b), x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tight_range_proof() {
+        let range = BigInt::from(2).pow(20);
+        for x in &[
+            BigInt::zero(),
+            BigInt::sample_below(&range),
+            &range - BigInt::one(),
+        ] {
+            let (witness, statement) = ffdhe_statement(x, &range);
+            let proof =
+                TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+            assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+        }
+
+        // a range that is not a power of 2
+        let range = BigInt::from(71);
+        let (witness, statement) = ffdhe_statement(&BigInt::from(70), &range);
+        let proof = TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+        assert!(proof
+            .verify(&statement, &ProofContext::new(&[1u8; 32], 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_tight_range_proof_no_slack() {
+        // x = range, just out of the range, cannot be proven
+        let range = BigInt::from(2).pow(20);
+        let (witness, statement) = ffdhe_statement(&range, &range);
+        assert_eq!(
+            TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap_err(),
+            ProofError::RangeProofError
+        );
+
+        // a proof for x < 2^20 does not verify for a smaller range
+        let (witness, statement) = ffdhe_statement(&BigInt::from(5), &range);
+        let proof = TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        let smaller_statement = Statement {
+            range: BigInt::from(2).pow(19),
+            ..statement.clone()
+        };
+        assert!(proof
+            .verify(&smaller_statement, &ProofContext::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_tight_range_proof_failing_bit() {
+        let range = BigInt::from(2).pow(20);
+        let (witness, statement) = ffdhe_statement(&BigInt::from(12345), &range);
+        let mut proof =
+            TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        proof.bit_proofs[5].z_zero = proof.bit_proofs[6].z_zero.clone();
+        assert_eq!(
+            proof.verify(&statement, &ProofContext::default()),
+            Err(ProofError::RangeProofBitError { index: 5 })
+        );
+
+        let mut proof =
+            TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        proof.ciphertexts.pop();
+        assert_eq!(
+            proof.verify(&statement, &ProofContext::default()),
+            Err(ProofError::RangeProofError)
+        );
+    }
+
+    #[test]
+    fn test_tight_range_proof_paillier() {
+        let (ek, _) = Paillier::keypair().keys();
+        let range = BigInt::from(2).pow(20);
+        let x = BigInt::sample_below(&range);
+        let r = PaillierScheme::sample_randomness(&ek);
+        let ciphertext = PaillierScheme::encrypt_with_randomness(&ek, &x, &r).unwrap();
+        let witness = Witness { x, r };
+        let statement = Statement::<PaillierScheme> {
+            pk: ek,
+            range,
+            ciphertext,
+        };
+
+        let proof = TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+    }
+
+    #[test]
+    fn test_tight_range_proof_ec_elgamal() {
+        let keypair = ECElGamalKeyPair::generate();
+        let range = BigInt::from(2).pow(20);
+        let x = BigInt::sample_below(&range);
+        let r = ECExponentElGamal::sample_randomness(&keypair.pk);
+        let ciphertext = ECExponentElGamal::encrypt_with_randomness(&keypair.pk, &x, &r).unwrap();
+        let witness = Witness { x, r };
+        let statement = Statement::<ECExponentElGamal> {
+            pk: keypair.pk,
+            range,
+            ciphertext,
+        };
+
+        let proof = TightRangeProof::prove(&witness, &statement, &ProofContext::default()).unwrap();
+        assert!(proof.verify(&statement, &ProofContext::default()).is_ok());
+
+        // a proof for another ciphertext does not verify
+        let other_statement = Statement::<ECExponentElGamal> {
+            ciphertext: ECExponentElGamal::encrypt_with_randomness(
+                &statement.pk,
+                &BigInt::from(5),
+                &ECExponentElGamal::sample_randomness(&statement.pk),
+            )
+            .unwrap(),
+            ..statement.clone()
+        };
+        assert!(proof
+            .verify(&other_statement, &ProofContext::default())
+            .is_err());
+    }
+}
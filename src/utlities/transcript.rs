@@ -0,0 +1,157 @@
+use curv::arithmetic::traits::Converter;
+use curv::BigInt;
+use sha2::Digest;
+use sha2::Sha256;
+
+// identifies the transcript construction, changing it invalidates all proofs
+const TRANSCRIPT_VERSION: &[u8] = b"vice-city/transcript/v1";
+
+/// Where a proof is created: the session it belongs to and the party that proves. A proof made
+/// for one context does not verify in another, so it cannot be replayed in another session or
+/// returned to its prover as the other party's proof.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofContext {
+    pub session_id: Vec<u8>,
+    pub party_id: u64,
+}
+
+impl ProofContext {
+    pub fn new(session_id: &[u8], party_id: u64) -> Self {
+        ProofContext {
+            session_id: session_id.to_vec(),
+            party_id,
+        }
+    }
+}
+
+/// Fiat-Shamir transcript: a running SHA-256 over length prefixed, labeled messages.
+/// Every transcript starts with the domain separator of its proof and the proof context, and
+/// every challenge is appended to the transcript once drawn, so that later challenges depend on
+/// all previous rounds.
+#[derive(Clone)]
+pub struct Transcript {
+    hasher: Sha256,
+}
+
+impl Transcript {
+    pub fn new(domain_separator: &'static [u8], context: &ProofContext) -> Self {
+        let mut transcript = Transcript {
+            hasher: Sha256::new(),
+        };
+        transcript.append_message(b"version", TRANSCRIPT_VERSION);
+        transcript.append_message(b"dom-sep", domain_separator);
+        transcript.append_message(b"session-id", &context.session_id);
+        transcript.append_u64(b"party-id", context.party_id);
+        transcript
+    }
+
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.hasher.input(&(label.len() as u64).to_be_bytes());
+        self.hasher.input(label);
+        self.hasher.input(&(message.len() as u64).to_be_bytes());
+        self.hasher.input(message);
+    }
+
+    pub fn append_u64(&mut self, label: &'static [u8], x: u64) {
+        self.append_message(label, &x.to_be_bytes());
+    }
+
+    // a sign byte and the big endian magnitude, empty for zero
+    pub fn append_bigint(&mut self, label: &'static [u8], x: &BigInt) {
+        let (sign, magnitude) = if x < &BigInt::zero() {
+            (1u8, BigInt::zero() - x)
+        } else {
+            (0u8, x.clone())
+        };
+        let mut bytes = vec![sign];
+        if magnitude != BigInt::zero() {
+            bytes.extend(BigInt::to_vec(&magnitude));
+        }
+        self.append_message(label, &bytes);
+    }
+
+    pub fn append_bigints(&mut self, label: &'static [u8], values: &[BigInt]) {
+        self.append_u64(label, values.len() as u64);
+        for x in values {
+            self.append_bigint(label, x);
+        }
+    }
+
+    /// len uniform bytes, e.g. the bits selecting the openings of a cut-and-choose proof
+    pub fn challenge_bytes(&mut self, label: &'static [u8], len: usize) -> Vec<u8> {
+        self.append_message(b"challenge", label);
+        self.append_u64(b"challenge-len", len as u64);
+        let seed = self.hasher.clone().result();
+        let mut output = Vec::with_capacity(len + 32);
+        let mut counter: u64 = 0;
+        while output.len() < len {
+            let block = Sha256::new()
+                .chain(&seed)
+                .chain(&counter.to_be_bytes())
+                .result();
+            output.extend_from_slice(&block);
+            counter += 1;
+        }
+        output.truncate(len);
+        self.append_message(label, &output);
+        output
+    }
+
+    /// a challenge of bit_length bits
+    pub fn challenge_bits(&mut self, label: &'static [u8], bit_length: usize) -> BigInt {
+        let bytes = self.challenge_bytes(label, (bit_length + 7) / 8);
+        BigInt::from(&bytes[..]) >> (8 * bytes.len() - bit_length)
+    }
+
+    /// a challenge in Z_n, statistically close to uniform
+    pub fn challenge_zn(&mut self, label: &'static [u8], n: &BigInt) -> BigInt {
+        self.challenge_bits(label, 2 * n.bit_length()).modulus(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utlities::transcript::ProofContext;
+    use crate::utlities::transcript::Transcript;
+    use curv::BigInt;
+    use elgamal::rfc7919_groups::SupportedGroups;
+    use elgamal::ElGamalPP;
+
+    #[test]
+    fn test_transcript_challenges() {
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let mut transcript = Transcript::new(b"test", &context);
+        transcript.append_bigint(b"x", &BigInt::from(42));
+        let mut other = transcript.clone();
+        assert_eq!(
+            transcript.challenge_bits(b"e", 256),
+            other.challenge_bits(b"e", 256)
+        );
+        // a challenge binds the previous one
+        let e = transcript.challenge_bits(b"e", 256);
+        assert_ne!(e, other.challenge_bits(b"f", 256));
+        assert!(e.bit_length() <= 256);
+
+        let pp = ElGamalPP::generate_from_rfc7919(SupportedGroups::FFDHE2048);
+        let e = transcript.challenge_zn(b"e", &pp.q);
+        assert!(e < pp.q && pp.q.bit_length() - e.bit_length() < 10);
+        assert_eq!(transcript.challenge_bytes(b"bits", 15).len(), 15);
+    }
+
+    #[test]
+    fn test_transcript_domain_separation() {
+        let challenge = |domain: &'static [u8], context: &ProofContext, x: &BigInt| {
+            let mut transcript = Transcript::new(domain, context);
+            transcript.append_bigint(b"x", x);
+            transcript.challenge_bits(b"e", 256)
+        };
+        let context = ProofContext::new(&[1u8; 32], 1);
+        let x = BigInt::from(42);
+        let e = challenge(b"test", &context, &x);
+        assert_eq!(e, challenge(b"test", &context, &x));
+        assert_ne!(e, challenge(b"other", &context, &x));
+        assert_ne!(e, challenge(b"test", &ProofContext::new(&[2u8; 32], 1), &x));
+        assert_ne!(e, challenge(b"test", &ProofContext::new(&[1u8; 32], 2), &x));
+        assert_ne!(e, challenge(b"test", &context, &-x));
+    }
+}